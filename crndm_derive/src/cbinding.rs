@@ -6,7 +6,7 @@ use syn::spanned::Spanned;
 use syn::*;
 use syn::punctuated::Punctuated;
 use std::collections::HashMap;
-use std::lazy::SyncLazy;
+use std::sync::LazyLock;
 use std::sync::Mutex;
 use std::io::*;
 use std::fs::{File,create_dir_all,read_to_string};
@@ -36,11 +36,11 @@ pub struct Attributes {
     concurrent: bool
 }
 
-pub static mut TYPES: SyncLazy<Mutex<HashMap<TypeName, Contents>>> = SyncLazy::new(|| {
+pub static TYPES: LazyLock<Mutex<HashMap<TypeName, Contents>>> = LazyLock::new(|| {
     Mutex::new(HashMap::new())
 });
 
-pub static mut POOLS: SyncLazy<Mutex<HashMap<TypeName, Contents>>> = SyncLazy::new(|| {
+pub static POOLS: LazyLock<Mutex<HashMap<TypeName, Contents>>> = LazyLock::new(|| {
     Mutex::new(HashMap::new())
 });
 
@@ -50,34 +50,29 @@ fn check_type(ty: &Type, pool_type: &Ident, gen_idents: &Vec<Ident>, warn: bool)
         Type::Array(a) => check_type(a.elem.as_ref(), pool_type, gen_idents, warn),
         Type::Tuple(t) => {
             for e in &t.elems {
-                check_type(&e, pool_type, gen_idents, warn);
+                check_type(e, pool_type, gen_idents, warn);
             }
         }
         Type::Path(p) => {
             if let Some(id) = p.path.get_ident() {
-                if warn {
-                    if gen_idents.contains(id) && id != pool_type {
+                if warn
+                    && gen_idents.contains(id) && id != pool_type {
                         emit_warning! {
                             id.span(), "direct use of template parameter is not safe";
                             help = "consider using corundum::gen::ByteArray<{}, {}>", id, pool_type;
                             help = "use `#[attrs(allow_generics)]` to disable this warning"
                         }
                     }
-                }
             } else if let Some(last) = p.path.segments.last() {
                 if last.ident == "ByteArray" {
                     if let PathArguments::AngleBracketed(args) = &last.arguments {
                         for arg in &args.args {
-                            if let GenericArgument::Type(t) = arg {
-                                if let Type::Path(p) = t {
-                                    if let Some(id) = p.path.get_ident() {
-                                        if !gen_idents.contains(id) {
-                                            emit_error!(arg.span(), "not a generic parameter")
-                                        } else {
-                                            return;
-                                        }
-                                    } else {
+                            if let GenericArgument::Type(Type::Path(p)) = arg {
+                                if let Some(id) = p.path.get_ident() {
+                                    if !gen_idents.contains(id) {
                                         emit_error!(arg.span(), "not a generic parameter")
+                                    } else {
+                                        return;
                                     }
                                 } else {
                                     emit_error!(arg.span(), "not a generic parameter")
@@ -276,11 +271,11 @@ pub fn derive_cbindgen(input: TokenStream) -> TokenStream {
 
     let mut expanded = vec![];
     let mut includes = "".to_owned();
-    let mut all_types = unsafe { match TYPES.lock() {
+    let mut all_types = match TYPES.lock() {
         Ok(g) => g,
         Err(p) => p.into_inner()
-    } };
-    let mut entry = all_types.entry(name_str.clone()).or_insert(Contents::default());
+    };
+    let entry = all_types.entry(name_str.clone()).or_insert(Contents::default());
     entry.generics = generics.iter().map(|v| v.to_string()).collect();
     let new_sizes: Vec<Ident>  = generics.iter().map(|v| format_ident!("{}_size", v.to_string())).collect();
 
@@ -411,10 +406,10 @@ name = new_name,
 pool = pool,
 size_list = size_list,
 size_list_arg = size_list_arg,
-fn_new = fn_new.to_string(),
-fn_open = fn_open.to_string(),
-fn_drop = fn_drop.to_string(),
-root_name = __m.to_string()
+fn_new = fn_new,
+fn_open = fn_open,
+fn_drop = fn_drop,
+root_name = __m
         ));
     }
 
@@ -561,8 +556,8 @@ fn refine_path(m: &TokenStream2, p: &mut Path, tmpl: &Vec<String>, ty_tmpl: &Ide
             PathArguments::AngleBracketed(args) => {
                 for g in &mut args.args {
                     match g {
-                        GenericArgument::Type(ty) => { check_generics(m, ty, &tmpl, ty_tmpl, gen, if s.ident != "Gen" && check == 1 { 1 } else { 0 }, modify, has_generics, ident); }
-                        GenericArgument::Binding(b) => { check_generics(m, &mut b.ty, &tmpl, ty_tmpl, gen, check, modify, has_generics, ident); }
+                        GenericArgument::Type(ty) => { check_generics(m, ty, tmpl, ty_tmpl, gen, if s.ident != "Gen" && check == 1 { 1 } else { 0 }, modify, has_generics, ident); }
+                        GenericArgument::Binding(b) => { check_generics(m, &mut b.ty, tmpl, ty_tmpl, gen, check, modify, has_generics, ident); }
                         _ => ()
                     }
                 }
@@ -580,7 +575,7 @@ fn refine_path(m: &TokenStream2, p: &mut Path, tmpl: &Vec<String>, ty_tmpl: &Ide
 
 fn check_generics(m: &TokenStream2, ty: &mut Type, tmpl: &Vec<String>, ty_tmpl: &Ident, gen: &Vec<String>, check: i32, modify: bool, has_generics: &mut Option<&mut bool>, ident: &Ident) -> bool {
     let res = match ty {
-        Type::Array(a) => check_generics(m, &mut *a.elem, tmpl, ty_tmpl, gen, check, modify, has_generics, ident),
+        Type::Array(a) => check_generics(m, &mut a.elem, tmpl, ty_tmpl, gen, check, modify, has_generics, ident),
         Type::BareFn(f) => {
             for i in &mut f.inputs {
                 if check_generics(m, &mut i.ty, tmpl, ty_tmpl, gen, 2, modify, has_generics, &format_ident!("j")) {
@@ -595,8 +590,8 @@ fn check_generics(m: &TokenStream2, ty: &mut Type, tmpl: &Vec<String>, ty_tmpl:
             }
             false
         },
-        Type::Group(g) => check_generics(m, &mut *g.elem, tmpl, ty_tmpl, gen, check, modify, has_generics, ident),
-        Type::Paren(ty) => check_generics(m, &mut *ty.elem, tmpl, ty_tmpl, gen, check, modify, has_generics, ident),
+        Type::Group(g) => check_generics(m, &mut g.elem, tmpl, ty_tmpl, gen, check, modify, has_generics, ident),
+        Type::Paren(ty) => check_generics(m, &mut ty.elem, tmpl, ty_tmpl, gen, check, modify, has_generics, ident),
         Type::Path(p) => {
             // if let Some(last) = p.path.segments.last() {
             //     if last.ident == "Box" {
@@ -655,7 +650,7 @@ fn check_generics(m: &TokenStream2, ty: &mut Type, tmpl: &Vec<String>, ty_tmpl:
         }
         // tmpl.contains(&p.path.get_ident().expect(&format!("{}", line!())).ident.to_string()),
         Type::Ptr(p) => {
-            if check_generics(m, &mut *p.elem, tmpl, ty_tmpl, gen, if check == 2 { 0 } else { check }, modify, has_generics, ident) {
+            if check_generics(m, &mut p.elem, tmpl, ty_tmpl, gen, if check == 2 { 0 } else { check }, modify, has_generics, ident) {
                 // update(ty);
                 // *ty = parse2(quote!(corundum::gen::Gen)).expect(&format!("{}", line!()));
                 // if modify {
@@ -666,7 +661,7 @@ fn check_generics(m: &TokenStream2, ty: &mut Type, tmpl: &Vec<String>, ty_tmpl:
             false
         },
         Type::Reference(r) =>  {
-            if check_generics(m, &mut *r.elem, tmpl, ty_tmpl, gen, if check == 2 { 0 } else { check }, modify, has_generics, ident) {
+            if check_generics(m, &mut r.elem, tmpl, ty_tmpl, gen, if check == 2 { 0 } else { check }, modify, has_generics, ident) {
                 // update(ty);
                 // *ty = parse2(quote!(corundum::gen::Gen)).expect(&format!("{}", line!()));
                 // if modify {
@@ -676,7 +671,7 @@ fn check_generics(m: &TokenStream2, ty: &mut Type, tmpl: &Vec<String>, ty_tmpl:
             }
             false
         },
-        Type::Slice(s) => check_generics(m, &mut *s.elem, tmpl, ty_tmpl, gen, check, modify, has_generics, ident),
+        Type::Slice(s) => check_generics(m, &mut s.elem, tmpl, ty_tmpl, gen, check, modify, has_generics, ident),
         Type::Tuple(t) => t.elems.iter_mut().any(|t| check_generics(m, t, tmpl, ty_tmpl, gen, check, modify, has_generics, ident)),
         Type::Verbatim(v) => {
             let name = v.to_string();
@@ -742,7 +737,7 @@ pub fn cbindgen(_attr: TokenStream, item: TokenStream) -> TokenStream {
                                             if let Some(ident) = tr.path.get_ident() {
                                                 if ident == "MemPool" {
                                                     if let Type::Path(p) = &t.bounded_ty {
-                                                        pool_type = p.path.get_ident().map(|v| v.clone());
+                                                        pool_type = p.path.get_ident().cloned();
                                                     }
                                                 }
                                             }
@@ -766,10 +761,10 @@ pub fn cbindgen(_attr: TokenStream, item: TokenStream) -> TokenStream {
             let new_name = format_ident!("__{}", name);
             let small_name = name.to_string().to_lowercase();
     
-            let mut types = unsafe { match TYPES.lock() {
+            let mut types = match TYPES.lock() {
                 Ok(g) => g,
                 Err(p) => p.into_inner()
-            }};
+            };
     
             let entry = types.entry(name.to_string()).or_insert(Contents::default());
 
@@ -837,11 +832,11 @@ pub fn cbindgen(_attr: TokenStream, item: TokenStream) -> TokenStream {
                         {
                             if is_constructor {
                                 let mut i = spc.sig.inputs.iter_mut();
-                                if let Some(a) = i.nth(0) {
+                                if let Some(a) = i.next() {
                                     if let FnArg::Typed(PatType { pat, ty, .. }) = a {
                                         if let Pat::Ident(PatIdent { ident, .. }) = &**pat {
                                             let mut has_generics = false;
-                                            check_generics(&quote!(), &mut *ty, &gen, &pool_type, &entry.generics, 1, false, &mut Some(&mut has_generics), &ident);
+                                            check_generics(&quote!(), &mut *ty, &gen, &pool_type, &entry.generics, 1, false, &mut Some(&mut has_generics), ident);
                                             args.push((has_generics, ident.to_string()));
                                         }
                                     }
@@ -850,11 +845,11 @@ pub fn cbindgen(_attr: TokenStream, item: TokenStream) -> TokenStream {
                             }
                             let mut i = spc.sig.inputs.iter_mut();
                             if i.next().is_some() {
-                                while let Some(a) = i.next() {
+                                for a in i {
                                     if let FnArg::Typed(PatType { pat, ty, .. }) = a {
                                         if let Pat::Ident(PatIdent { ident, .. }) = &**pat {
                                             let mut has_generics = false;
-                                            check_generics(&quote!(), &mut *ty, &gen, &pool_type, &entry.generics, 1, false, &mut Some(&mut has_generics), &ident);
+                                            check_generics(&quote!(), &mut *ty, &gen, &pool_type, &entry.generics, 1, false, &mut Some(&mut has_generics), ident);
                                             args.push((has_generics, ident.to_string()));
                                         }
                                     }
@@ -869,16 +864,14 @@ pub fn cbindgen(_attr: TokenStream, item: TokenStream) -> TokenStream {
                             check_generics(&quote!(), ty, &gen, &pool_type, &entry.generics, 1, false, &mut Some(&mut output_has_generics), &spc.sig.ident);
                         }
                         let mut last_arg_is_journal = false;
-                        if let Some(last) = spc.sig.inputs.last() {
-                            if let FnArg::Typed(ty) = last {
-                                if let Type::Reference(r) = &*ty.ty {
-                                    if let Type::Path(p) = &*r.elem {
-                                        if let Some(last) = p.path.segments.last() {
-                                            if last.ident == "Journal" {
-                                                let args = &last.arguments;
-                                                let j = quote!(Journal#args);
-                                                last_arg_is_journal = j.to_string() == quote!(Journal<#pool_type>).to_string();
-                                            }
+                        if let Some(FnArg::Typed(ty)) = spc.sig.inputs.last() {
+                            if let Type::Reference(r) = &*ty.ty {
+                                if let Type::Path(p) = &*r.elem {
+                                    if let Some(last) = p.path.segments.last() {
+                                        if last.ident == "Journal" {
+                                            let args = &last.arguments;
+                                            let j = quote!(Journal#args);
+                                            last_arg_is_journal = j.to_string() == quote!(Journal<#pool_type>).to_string();
                                         }
                                     }
                                 }
@@ -892,21 +885,19 @@ pub fn cbindgen(_attr: TokenStream, item: TokenStream) -> TokenStream {
                             continue;
                         }
                         let mut is_const = true;
-                        if let Some(first) = spc.sig.inputs.first() {
-                            if let FnArg::Receiver(rc) = first {
-                                if is_constructor {
-                                    emit_error! {
-                                        spc.sig.span(), "ambiguous constructor";
-                                        note = "a constructor cannot have a receiver (`self` argument)"
-                                    }
-                                    continue;
+                        if let Some(FnArg::Receiver(rc)) = spc.sig.inputs.first() {
+                            if is_constructor {
+                                emit_error! {
+                                    spc.sig.span(), "ambiguous constructor";
+                                    note = "a constructor cannot have a receiver (`self` argument)"
                                 }
-                                is_const = rc.mutability.is_none();
-                                // if rc.mutability.is_some() {
-                                //     emit_error!(rc.span(), "mutable receiver is not allowed");
-                                //     continue;
-                                // }
+                                continue;
                             }
+                            is_const = rc.mutability.is_none();
+                            // if rc.mutability.is_some() {
+                            //     emit_error!(rc.span(), "mutable receiver is not allowed");
+                            //     continue;
+                            // }
                         }
                         spc.sig.inputs = inputs;
                         
@@ -1047,10 +1038,7 @@ pub fn cbindgen(_attr: TokenStream, item: TokenStream) -> TokenStream {
 
 fn parse_c_fn(sig: &str, name: &str) -> (String, Vec<String>) {
     fn is_word(c: char) -> bool {
-        (c >= 'a' && c <= 'z') ||
-        (c >= 'A' && c <= 'Z') ||
-        (c >= '0' && c <= '9') ||
-        c == '_'
+        c.is_ascii_alphanumeric() || c == '_'
     }
 
     let mut par = 0; // (...)
@@ -1103,13 +1091,12 @@ fn parse_c_fn(sig: &str, name: &str) -> (String, Vec<String>) {
                 "]" => br -= 1,
                 "<" => lt += 1,
                 ">" => lt -= 1,
-                "," => {
-                    if par == 1 && br == 0 && lt == 0 {
+                ","
+                    if par == 1 && br == 0 && lt == 0 => {
                         args.push(token.clone());
                         token.clear();
                         continue;
                     }
-                }
                 _ => ()
             }
             token += &(t + " ");
@@ -1153,20 +1140,24 @@ pub fn export(dir: PathBuf, span: proc_macro2::Span, overwrite: bool, warning: b
         }
     }
 
-    let mut pools = unsafe { match POOLS.lock() {
+    let mut pools = match POOLS.lock() {
         Ok(g) => g,
         Err(p) => p.into_inner()
-    } };
-    let mut types = unsafe { match TYPES.lock() {
+    };
+    let mut types = match TYPES.lock() {
         Ok(g) => g,
         Err(p) => p.into_inner()
-    } };
+    };
 
+    let no_mangle_re = Regex::new(r"\#\[no_mangle\].*").expect(&format!("{}", line!()));
+    let gen_arg_re = Regex::new(r"\bGen\b\s*<\s*(\w+)\s*>").expect(&format!("{}", line!()));
+    let gen_re = Regex::new(r"\bGen\b").expect(&format!("{}", line!()));
+    let pool_re = Regex::new(r"\b_P\b").expect(&format!("{}", line!()));
     for (ty, cnt) in &mut *types {
         let alias = cnt.alias.clone();
         let fwd_decl = cnt.decl.clone();
         for p in &cnt.pools {
-            if let Some(pool) = pools.get_mut(&*p) {
+            if let Some(pool) = pools.get_mut(p) {
                 let alias = alias.replace("{pool}", p);
                 pool.contents = pool.contents.replace("// forward declarations",
                     &format!("// forward declarations\n{}", fwd_decl));
@@ -1181,11 +1172,9 @@ pub fn export(dir: PathBuf, span: proc_macro2::Span, overwrite: bool, warning: b
         let mut cbindfile = "".to_owned();
         // let mut funcs = vec!();
         for (_, _, f, _, _, _, _, _, _) in &mut cnt.funcs {
-            let re = Regex::new(&format!(r"\#\[no_mangle\].*")).expect(&format!("{}", line!()));
-            if re.find(f).is_some() {
-                let re = Regex::new(&format!(r"\bGen\b\s*<\s*(\w+)\s*>")).expect(&format!("{}", line!()));
-                *f = re.replace_all(f, "&$1").to_string();
-                cbindfile += &f;
+            if no_mangle_re.find(f).is_some() {
+                *f = gen_arg_re.replace_all(f, "&$1").to_string();
+                cbindfile += f;
                 cbindfile += "\n";
             }
         }
@@ -1286,9 +1275,9 @@ pub fn export(dir: PathBuf, span: proc_macro2::Span, overwrite: bool, warning: b
                     // eprintln!("type: {:?}", cnt.generics);
                     // eprintln!("func {}: {:?}", name, tmp);
                     let diff = tmp.len() - cnt.generics.len();
-                    for i in diff .. tmp.len() {
-                        let re = Regex::new(&format!(r"\b{}\b", tmp[i])).expect(&format!("{}", line!()));
-                        append = re.replace_all(&append, &cnt.generics[i-diff]).to_string();
+                    for (t, g) in tmp[diff..].iter().zip(&cnt.generics) {
+                        let re = Regex::new(&format!(r"\b{}\b", t)).expect(&format!("{}", line!()));
+                        append = re.replace_all(&append, g).to_string();
                     }
                     let fn_tmp = &tmp.as_slice()[0..diff];
                     if !fn_tmp.is_empty() {
@@ -1321,8 +1310,7 @@ pub fn export(dir: PathBuf, span: proc_macro2::Span, overwrite: bool, warning: b
         for (p, contents) in &mut cnt.traits {
             for (f, args, sig, tmp, _, ret, ret_gen, is_cons, is_const) in &mut cnt.funcs {
                 let (cret, cargs) = parse_c_fn(sig, f);
-                let re = Regex::new(r"\bGen\b").expect(&format!("{}", line!()));
-                let cast = if *ret_gen && re.find(&cret).is_none() {
+                let cast = if *ret_gen && gen_re.find(&cret).is_none() {
                     format!("({})", cret)
                 } else {
                     "".to_owned()
@@ -1331,7 +1319,7 @@ pub fn export(dir: PathBuf, span: proc_macro2::Span, overwrite: bool, warning: b
                 for i in 0..args.len() {
                     let (gen, n) = &args[i];
                     arglist.push(if *gen {
-                        if re.find(&cargs[i]).is_some() {
+                        if gen_re.find(&cargs[i]).is_some() {
                             n.to_owned()
                         } else {
                             let mut res = format!("({}){}", cargs[i].replace(&format!(" {} ", n), " "), n);
@@ -1349,8 +1337,7 @@ pub fn export(dir: PathBuf, span: proc_macro2::Span, overwrite: bool, warning: b
                 };
                 let args = arglist.join(", ");
                 let old_sig = sig.clone();
-                let re = Regex::new(r"\b_P\b").expect(&format!("{}", line!()));
-                *sig = re.replace_all(sig, p as &str).to_string();
+                *sig = pool_re.replace_all(sig, p as &str).to_string();
                 if *is_cons {
                     *contents = contents.replace("    // specialized methods",
                         &format!("    // specialized methods\n    {}static {} {{\n        {}\n    }}",
@@ -1529,10 +1516,10 @@ pub fn carbide(input: TokenStream) -> TokenStream {
         #(#recurse,)*
     };
 
-    let mut all_pools = unsafe { match POOLS.lock() {
+    let mut all_pools = match POOLS.lock() {
         Ok(g) => g,
         Err(p) => p.into_inner()
-    } };
+    };
     let mut expanded = vec![];
     for m in &mods {
         let flags = &m.1;
@@ -1946,25 +1933,25 @@ public:
     }}
 }};",
 pool = m,
-pool_alloc = fn_alloc.to_string(),
-pool_allocated = fn_allocated.to_string(),
-pool_dealloc = fn_dealloc.to_string(),
-pool_valid = fn_valid.to_string(),
-pool_print_info = fn_print_info.to_string(),
-pool_log = fn_log.to_string(),
-pool_used = fn_used.to_string(),
+pool_alloc = fn_alloc,
+pool_allocated = fn_allocated,
+pool_dealloc = fn_dealloc,
+pool_valid = fn_valid,
+pool_print_info = fn_print_info,
+pool_log = fn_log,
+pool_used = fn_used,
 pool_journal = fn_journal.to_string(),
-pool_txn_running = fn_txn_running.to_string(),
-pool_open = fn_open.to_string(),
-pool_close = fn_close.to_string(),
-pool_base = fn_base.to_string(),
-pool_gen = fn_gen.to_string(),
-pool_txn_begin = fn_txn_begin.to_string(),
-pool_txn_commit = fn_txn_commit.to_string(),
-pool_txn_rollback = fn_txn_rollback.to_string(),
-pool_named_open = named_open.to_string(),
-pool_named_data_pointer = named_data_pointer.to_string(),
-pool_named_logged_pointer = named_logged_pointer.to_string(),
+pool_txn_running = fn_txn_running,
+pool_open = fn_open,
+pool_close = fn_close,
+pool_base = fn_base,
+pool_gen = fn_gen,
+pool_txn_begin = fn_txn_begin,
+pool_txn_commit = fn_txn_commit,
+pool_txn_rollback = fn_txn_rollback,
+pool_named_open = named_open,
+pool_named_data_pointer = named_data_pointer,
+pool_named_logged_pointer = named_logged_pointer,
 root_name = root_name.to_string(),
 );
         entry.contents = contents;
@@ -1,6 +1,7 @@
-#![feature(once_cell)]
-#![feature(type_name_of_val)]
-#![feature(proc_macro_span)]
+#![allow(clippy::expect_fun_call)]
+#![allow(clippy::too_many_arguments)]
+#![allow(clippy::type_complexity)]
+
 
 use proc_macro2::Group;
 use syn::parse::Parser;
@@ -19,6 +20,7 @@ extern crate proc_macro_error;
 
 mod pclone;
 mod root;
+mod trace;
 mod cbinding;
 
 #[proc_macro_error]
@@ -28,11 +30,17 @@ pub fn derive_pclone(input: TokenStream) -> TokenStream {
 }
 
 #[proc_macro_error]
-#[proc_macro_derive(Root, attributes(pools))]
+#[proc_macro_derive(Root, attributes(pools, schema, migrate))]
 pub fn derive_root(input: TokenStream) -> TokenStream {
     root::derive_root(input)
 }
 
+#[proc_macro_error]
+#[proc_macro_derive(Trace, attributes(pools))]
+pub fn derive_trace(input: TokenStream) -> TokenStream {
+    trace::derive_trace(input)
+}
+
 #[proc_macro_error]
 #[proc_macro_derive(Export, attributes(mods,attrs))]
 pub fn derive_cbindgen(input: TokenStream) -> TokenStream {
@@ -49,7 +57,7 @@ fn list(attrs: &Vec<Attribute>, name: &str) -> Vec<proc_macro2::TokenStream> {
     let mut ret = vec![];
     for attr in attrs {
         for segment in attr.path.segments.iter() {
-            if segment.ident.to_string() == String::from(name) {
+            if segment.ident == name {
                 if let Ok(g) = parse2::<Group>(attr.tokens.clone()) {
                     let parser = Punctuated::<Path, Token![,]>::parse_terminated;
                     if let Ok(list) = parser.parse2(g.stream()) {
@@ -17,7 +17,7 @@ pub fn derive_pclone(input: TokenStream) -> TokenStream {
     for p in &pools {
 
         // Add a bound `T: PClone` to every type parameter T.
-        let generics = add_trait_bounds(input.generics.clone(), &pools, &p);
+        let generics = add_trait_bounds(input.generics.clone(), &pools, p);
         let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
 
         // Generate an expression to sum up the heap size of each field.
@@ -42,7 +42,7 @@ pub fn derive_pclone(input: TokenStream) -> TokenStream {
 }
 
 // Add a bound `T: PClone` to every type parameter T.
-fn add_trait_bounds(mut generics: Generics, pool: &Vec<TokenStream2>, p: &TokenStream2) -> Generics {
+fn add_trait_bounds(mut generics: Generics, pool: &[TokenStream2], p: &TokenStream2) -> Generics {
     for param in &mut generics.params {
         if let GenericParam::Type(ref mut type_param) = *param {
             let ident = type_param.ident.clone();
@@ -94,7 +94,7 @@ fn pclone_all_fields(ident: &Ident, data: &Data) -> TokenStream2 {
             }
         }
         Data::Enum(DataEnum { ref variants, .. }) => {
-            let res = variants.iter().map(|ref v| {
+            let res = variants.iter().map(|v| {
                 let variant = v.ident.clone();
                 match v.fields {
                     Fields::Unit => quote! {
@@ -13,16 +13,43 @@ pub fn derive_root(input: TokenStream) -> TokenStream {
     // Used in the quasi-quotation below as `#name`.
     let name = input.ident;
 
+    // Generate the schema and the migration path, if specified.
+    let schema = schema(&input.attrs, &name);
+    let migrate = crate::list(&input.attrs, "migrate");
+    if migrate.len() > 1 {
+        abort_call_site!("only one predecessor can be specified in `migrate`");
+    }
+    if !migrate.is_empty() && schema.is_none() {
+        abort_call_site!("`migrate` requires a versioned `schema`");
+    }
+
     let mut expanded = vec![];
     for p in &pools {
 
         // Add a bound `T: RootObj` to every type parameter T.
-        let generics = add_trait_bounds(input.generics.clone(), &pools, &p);
+        let generics = add_trait_bounds(input.generics.clone(), &pools, p);
         let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
 
         // Generate an expression to sum up the heap size of each field.
         let sum = root_all_fields(&name, &input.data);
 
+        let schema = schema.as_ref().map(|s| quote! {
+            #[inline]
+            fn schema() -> corundum::Schema {
+                #s
+            }
+        });
+        let upgrade = migrate.iter().map(|from| quote! {
+            #[inline]
+            unsafe fn upgrade(
+                from: corundum::Schema,
+                off: u64,
+                j: &corundum::stm::Journal<#p>
+            ) -> Option<Self> {
+                corundum::upgrade_root::<#from, Self, #p>(from, off, j)
+            }
+        });
+
         expanded.push(quote! {
             #[automatically_derived]
             #[allow(unused_qualifications)]
@@ -31,6 +58,8 @@ pub fn derive_root(input: TokenStream) -> TokenStream {
                 fn init(j: &corundum::stm::Journal<#p>) -> Self {
                     #sum
                 }
+                #schema
+                #(#upgrade)*
             }
         });
     }
@@ -41,8 +70,48 @@ pub fn derive_root(input: TokenStream) -> TokenStream {
     TokenStream::from(expanded)
 }
 
+// Generate an expression for the schema given in `#[schema(...)]`. It accepts
+// `version` and an optional `name` which is the type name by default.
+fn schema(attrs: &[Attribute], ident: &Ident) -> Option<TokenStream2> {
+    let attr = attrs.iter().find(|a| a.path.is_ident("schema"))?;
+    let mut name = ident.to_string();
+    let mut version = None;
+    match attr.parse_meta() {
+        Ok(Meta::List(list)) => {
+            for item in list.nested {
+                match item {
+                    NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("name") => {
+                        if let Lit::Str(s) = &nv.lit {
+                            name = s.value();
+                        } else {
+                            abort!(nv.lit.span(), "expected a string");
+                        }
+                    }
+                    NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("version") => {
+                        if let Lit::Int(i) = &nv.lit {
+                            version = Some(i.base10_parse::<u32>().unwrap_or_else(|e| {
+                                abort!(i.span(), "{}", e)
+                            }));
+                        } else {
+                            abort!(nv.lit.span(), "expected an integer");
+                        }
+                    }
+                    item => abort!(item.span(), "invalid option";
+                        note = "available options are 'name' and 'version'"
+                    )
+                }
+            }
+        }
+        _ => abort!(attr.span(), "expected `#[schema(version = ...)]`")
+    }
+    let version = version.unwrap_or_else(|| {
+        abort!(attr.span(), "schema version is not specified")
+    });
+    Some(quote! { corundum::Schema::named(#name, #version) })
+}
+
 // Add a bound `T: RootObj` to every type parameter T.
-fn add_trait_bounds(mut generics: Generics, pool: &Vec<TokenStream2>, p: &TokenStream2) -> Generics {
+fn add_trait_bounds(mut generics: Generics, pool: &[TokenStream2], p: &TokenStream2) -> Generics {
     for param in &mut generics.params {
         if let GenericParam::Type(ref mut type_param) = *param {
             let ident = type_param.ident.clone();
@@ -77,7 +146,7 @@ fn root_all_fields(ident: &Ident, data: &Data) -> TokenStream2 {
                     // Expands to an expression like
                     //
                     //     0 + self.0.heap_size() + self.1.heap_size() + self.2.heap_size()
-                    let recurse = fields.unnamed.iter().enumerate().map(|(_, f)| {
+                    let recurse = fields.unnamed.iter().map(|f| {
                         quote_spanned! {f.span()=>
                             corundum::RootObj::init(j)
                         }
@@ -93,7 +162,7 @@ fn root_all_fields(ident: &Ident, data: &Data) -> TokenStream2 {
             }
         }
         Data::Enum(DataEnum { ref variants, .. }) => {
-            let res = variants.iter().map(|ref v| {
+            let res = variants.iter().map(|v| {
                 let variant = v.ident.clone();
                 match v.fields {
                     Fields::Unit => quote! {
@@ -120,7 +189,7 @@ fn root_all_fields(ident: &Ident, data: &Data) -> TokenStream2 {
                                 #name: #varname
                             }
                         });
-                        let clones = fields.named.iter().enumerate().map(|(_i,f)| {
+                        let clones = fields.named.iter().map(|f| {
                             let name = &f.ident;
                             quote_spanned! {f.span()=>
                                 #name: corundum::RootObj::init(j)
@@ -0,0 +1,144 @@
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{quote, quote_spanned, format_ident};
+use syn::spanned::Spanned;
+use syn::*;
+
+pub fn derive_trace(input: TokenStream) -> TokenStream {
+    // Parse the input tokens into a syntax tree.
+    let input = parse_macro_input!(input as DeriveInput);
+
+    let pools = crate::list(&input.attrs, "pools");
+
+    // Used in the quasi-quotation below as `#name`.
+    let name = input.ident;
+
+    let mut expanded = vec![];
+    for p in &pools {
+
+        // Add a bound `T: Trace` to every type parameter T.
+        let generics = add_trait_bounds(input.generics.clone(), &pools, p);
+        let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+        // Generate a statement to trace each field.
+        let body = trace_all_fields(&name, &input.data, p);
+
+        expanded.push(quote! {
+            #[automatically_derived]
+            #[allow(unused_qualifications)]
+            impl#impl_generics corundum::Trace<#p> for #name #ty_generics #where_clause {
+                #[inline]
+                fn trace<__V: corundum::Visitor>(&self, v: &mut __V) {
+                    #body
+                }
+            }
+        });
+    }
+
+    let expanded = quote! { #(#expanded)* };
+
+    // Hand the output tokens back to the compiler.
+    TokenStream::from(expanded)
+}
+
+// Add a bound `T: Trace` to every type parameter T.
+fn add_trait_bounds(mut generics: Generics, pool: &[TokenStream2], p: &TokenStream2) -> Generics {
+    for param in &mut generics.params {
+        if let GenericParam::Type(ref mut type_param) = *param {
+            let ident = type_param.ident.clone();
+            let me = ident.to_string();
+            if !pool.iter().any(|p| p.to_string() == me) {
+                type_param.bounds.push(parse_quote!(corundum::Trace<#p>));
+            }
+        }
+    }
+    generics
+}
+
+// Generate a statement to trace each field.
+fn trace_all_fields(ident: &Ident, data: &Data, p: &TokenStream2) -> TokenStream2 {
+    match *data {
+        Data::Struct(ref data) => {
+            match data.fields {
+                Fields::Named(ref fields) => {
+                    let recurse = fields.named.iter().map(|f| {
+                        let name = &f.ident;
+                        let ty = &f.ty;
+                        quote_spanned! {f.span()=>
+                            <#ty as corundum::Trace<#p>>::trace(&self.#name, v)
+                        }
+                    });
+                    quote! {
+                        #(#recurse;)*
+                    }
+                }
+                Fields::Unnamed(ref fields) => {
+                    let recurse = fields.unnamed.iter().enumerate().map(|(i, f)| {
+                        let index = Index::from(i);
+                        let ty = &f.ty;
+                        quote_spanned! {f.span()=>
+                            <#ty as corundum::Trace<#p>>::trace(&self.#index, v)
+                        }
+                    });
+                    quote! {
+                        #(#recurse;)*
+                    }
+                }
+                Fields::Unit => {
+                    // Unit structs do not have any persistent pointer.
+                    quote!()
+                }
+            }
+        }
+        Data::Enum(DataEnum { ref variants, .. }) => {
+            let res = variants.iter().map(|v| {
+                let variant = v.ident.clone();
+                match v.fields {
+                    Fields::Unit => quote! {
+                        #ident::#variant => {}
+                    },
+                    Fields::Unnamed(ref fields) => {
+                        let recurse = fields.unnamed.iter().enumerate().map(|(i, f)| {
+                            let varname = format_ident!("__self_{}", i);
+                            quote_spanned! {f.span()=>
+                                #varname
+                            }
+                        });
+                        let traces = recurse.clone();
+                        quote! {
+                            #ident::#variant(#(#recurse,)*) => {
+                                #(<_ as corundum::Trace<#p>>::trace(#traces, v);)*
+                            }
+                        }
+                    },
+                    Fields::Named(ref fields) => {
+                        let recurse = fields.named.iter().enumerate().map(|(i,f)| {
+                            let name = &f.ident;
+                            let varname = format_ident!("__self_{}", i);
+                            quote_spanned! {f.span()=>
+                                #name: #varname
+                            }
+                        });
+                        let traces = fields.named.iter().enumerate().map(|(i,f)| {
+                            let varname = format_ident!("__self_{}", i);
+                            quote_spanned! {f.span()=>
+                                <_ as corundum::Trace<#p>>::trace(#varname, v)
+                            }
+                        });
+                        quote! {
+                            #ident::#variant{#(#recurse,)*} => {
+                                #(#traces;)*
+                            }
+                        }
+                    }
+                }
+            });
+            quote! {
+                match self {
+                    #(#res,)*
+                }
+            }
+        }
+        Data::Union(_) => panic!("Union types cannot derive Trace"),
+    }
+}
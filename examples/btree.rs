@@ -85,7 +85,7 @@ impl BTree {
         None
     }
 
-    fn foreach<F: Copy + FnOnce(&BTreeNode) -> ()>(node: &Ptr, f: F) {
+    fn foreach<F: Copy + FnOnce(&BTreeNode)>(node: &Ptr, f: F) {
         if let Some(node) = node {
             Self::foreach(&node.slots[0], f);
             f(node);
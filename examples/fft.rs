@@ -40,6 +40,7 @@ pub fn fft(filename: &str) -> Vec<Complex<f64>> {
     // alternate between buf_a and buf_b to avoid allocating a new vector each time:
     let mut buf_b = buf_a.clone();
 
+    #[allow(clippy::needless_range_loop)]
     fn ft(a: &[Complex<f64>], c: &mut [Complex<f64>], n: usize, is: usize) {
         for k in 0..n {
             let mut s = Complex::new(0.0, 0.0);
@@ -58,7 +59,7 @@ pub fn fft(filename: &str) -> Vec<Complex<f64>> {
 fn show(label: &str, buf: &[Complex<f64>]) {
     println!("{}", label);
     let string = buf
-        .into_iter()
+        .iter()
         .map(|x| format!("{:.4}{:+.4}i", x.re, x.im))
         .collect::<Vec<_>>()
         .join(", ");
@@ -70,7 +71,7 @@ pub fn fft_persistent(filename: &str) -> Vec<Complex<f64>> {
 
     type P = Allocator;
 
-    struct FFT {
+    struct Fft {
         a: PRefCell<PVec<PCell<Complex<f64>>>>,
         c: PRefCell<PVec<PCell<Complex<f64>>>>,
         n: PCell<usize>,
@@ -81,7 +82,7 @@ pub fn fft_persistent(filename: &str) -> Vec<Complex<f64>> {
         filled: PCell<bool>,
     }
 
-    impl RootObj<P> for FFT {
+    impl RootObj<P> for Fft {
         fn init(_j: &Journal) -> Self {
             Self {
                 a: PRefCell::new(PVec::new()),
@@ -96,7 +97,7 @@ pub fn fft_persistent(filename: &str) -> Vec<Complex<f64>> {
         }
     }
 
-    impl FFT {
+    impl Fft {
         pub fn fill(&self, input: &[Complex<f64>], n: usize, step: usize) {
             P::transaction(|j| {
                 let mut buf_a = self.a.borrow_mut(j);
@@ -180,7 +181,7 @@ pub fn fft_persistent(filename: &str) -> Vec<Complex<f64>> {
         }
     }
 
-    let root = P::open::<FFT>("fft.pool", O_CFNE).unwrap();
+    let root = P::open::<Fft>("fft.pool", O_CFNE).unwrap();
 
     root.fft(filename)
 }
@@ -189,12 +190,10 @@ fn main() {
     use std::env;
 
     let args: Vec<String> = env::args().collect();
-    if args.len() > 1 {
-        if args[1] == "p" {
-            let output = fft_persistent("fft.in");
-            show("fft output:", &output);
-            return;
-        }
+    if args.len() > 1 && args[1] == "p" {
+        let output = fft_persistent("fft.in");
+        show("fft output:", &output);
+        return;
     }
 
     let output = fft("fft.in");
@@ -58,7 +58,7 @@ fn main() {
 
     fn calc(n1: f64, n2: f64, stop: f64) -> (f64, f64) {
         if n1 >= stop {
-            return (n2, n1);
+            (n2, n1)
         } else {
             calc(n1 + n2, n1, stop)
         }
@@ -15,7 +15,7 @@ pub struct HashMap<K: PSafe, V: PSafe> {
 }
 
 impl<K: PSafe, V: PSafe + Copy> HashMap<K, V> {
-    pub fn foreach<F: FnMut(&K, V) -> ()>(&self, mut f: F) {
+    pub fn foreach<F: FnMut(&K, V)>(&self, mut f: F) {
         for i in 0..BUCKETS_MAX {
             for e in &*self.buckets[i].borrow() {
                 let e = e.borrow();
@@ -119,7 +119,7 @@ impl<K: PSafe + Display, V: PSafe + Display + Copy> Display for HashMap<K, V> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
         let mut vec = vec![];
         self.foreach(|word, freq| {
-            vec.push((word.to_string(), freq.clone()));
+            vec.push((word.to_string(), freq));
         });
         vec.sort_by(|x, y| x.0.cmp(&y.0));
         for (word, freq) in vec {
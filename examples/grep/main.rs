@@ -142,7 +142,7 @@ fn main() {
 
             let mut files = vec![];
             let f = BufReader::new(
-                File::open(&filename).expect(&format!("cannot open `{}`", &filename)),
+                File::open(&filename).unwrap_or_else(|_| panic!("cannot open `{}`", filename)),
             );
 
             for line in f.lines() {
@@ -1,4 +1,3 @@
-#![feature(maybe_uninit_uninit_array)]
 #![allow(dead_code)]
 
 mod hashmap;
@@ -75,9 +74,9 @@ fn perform<T: 'static + Map<u64, u64> + RootObj<P> + PSafe>(path: &str) {
 
     print!("$ ");
     stdout().flush().unwrap();
-    while let Ok(_) = stdin().read_line(&mut buf) {
+    while stdin().read_line(&mut buf).is_ok() {
         if buf.is_empty() { break }
-        match buf.remove(0) as char {
+        match buf.remove(0) {
             'i' => str_insert(&*map, &mut buf),
             'c' => str_check(&*map, &mut buf),
             'r' => str_remove(&*map, &mut buf),
@@ -101,8 +100,8 @@ fn vperform<T: Map<u64, u64> + Default>() {
 
     print!("$ ");
     stdout().flush().unwrap();
-    while let Ok(_) = stdin().read_line(&mut buf) {
-        match buf.remove(0) as char {
+    while stdin().read_line(&mut buf).is_ok() {
+        match buf.remove(0) {
             'i' => str_insert(&map, &mut buf),
             'c' => str_check(&map, &mut buf),
             'r' => str_remove(&map, &mut buf),
@@ -111,17 +111,18 @@ impl<V: PSafe + Default + Copy> Node<V> {
                 }
             }
 
-            if i != self.n && self.items[i].key != 0 {
-                if f(&self.items[i].key, &self.items[i].val) {
-                    return true;
-                }
+            if i != self.n
+                && self.items[i].key != 0
+                && f(&self.items[i].key, &self.items[i].val)
+            {
+                return true;
             }
         }
         false
     }
 
     #[inline]
-    unsafe fn lookup<'a>(&self, key: u64) -> bool {
+    unsafe fn lookup(&self, key: u64) -> bool {
         for i in 0 .. self.n + 1 {
             if i != self.n && self.items[i].key == key {
                 return true;
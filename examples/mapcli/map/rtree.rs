@@ -10,6 +10,8 @@ pub struct RTree<K, V> {
 }
 
 impl<K, V> RTree<K, V> {
+    #[allow(clippy::mut_from_ref)]
+    #[allow(invalid_reference_casting)]
     fn self_mut(&self) -> &mut Self {
         unsafe { &mut *(self as *const Self as *mut Self) }
     }
@@ -38,7 +40,7 @@ where
         true
     }
     fn lookup(&self, key: K) -> bool {
-        self.btree.get(&key).is_some()
+        self.btree.contains_key(&key)
     }
 }
 
@@ -1,6 +1,8 @@
 #[cfg(target_arch = "x86_64")]
+#[allow(clippy::needless_range_loop)]
 mod run {
 
+    use std::arch::asm;
     use corundum::measure;
     use corundum::default::{*, Journal};
     use corundum::stat::*;
@@ -28,7 +30,7 @@ mod run {
         };
     }
     
-    fn main() {
+    pub fn main() {
         use std::env;
         use std::vec::Vec as StdVec;
     
@@ -282,7 +284,7 @@ mod run {
             let layout = std::alloc::Layout::from_size_align(*s * 8, 4).unwrap();
             measure!(format!("malloc({})", *s * 8), cnt, {
                 for _ in 0..cnt {
-                    unsafe{ std::alloc::alloc(layout); }
+                    unsafe{ let _ = std::alloc::alloc(layout); }
                 }
             });
         }
@@ -6,7 +6,6 @@
 
 #![allow(dead_code)]
 #![allow(incomplete_features)]
-#![feature(type_name_of_val)]
 
 use std::mem::MaybeUninit;
 use corundum::default::*;
@@ -105,18 +104,18 @@ pub fn main() {
 
     let root = P::open::<KvStore<i32>>(&args[1], O_CFNE | O_1GB).unwrap();
 
-    if args[2] == String::from("get") && args.len() == 4 {
-        println!("{:?}", root.get(&*args[3]))
-    } else if args[2] == String::from("put") && args.len() == 5 {
-        root.put(&*args[3], args[4].parse().unwrap())
+    if args[2] == "get" && args.len() == 4 {
+        println!("{:?}", root.get(&args[3]))
+    } else if args[2] == "put" && args.len() == 5 {
+        root.put(&args[3], args[4].parse().unwrap())
     }
-    if args[2] == String::from("burst")
-        && (args[3] == String::from("put") || args[3] == String::from("putget"))
+    if args[2] == "burst"
+        && (args[3] == "put" || args[3] == "putget")
         && args.len() == 5
     {
         for i in 0..args[4].parse().unwrap() {
             let key = format!("key{}", i);
-            root.put(&*key, i);
+            root.put(&key, i);
             if i == 500 {
                 // To see what happens when it crashes in the middle of burst
                 // put, uncomment the following line:
@@ -124,13 +123,13 @@ pub fn main() {
             }
         }
     }
-    if args[2] == String::from("burst")
-        && (args[3] == String::from("get") || args[3] == String::from("putget"))
+    if args[2] == "burst"
+        && (args[3] == "get" || args[3] == "putget")
         && args.len() == 5
     {
         for i in 0..args[4].parse().unwrap() {
             let key = format!("key{}", i);
-            root.get(&*key);
+            root.get(&key);
         }
     }
 }
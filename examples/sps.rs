@@ -31,10 +31,7 @@ impl RootObj<P> for Root {
 
 impl Root {
     fn verify(&self) -> bool {
-        let mut vec = std::vec::Vec::<bool>::with_capacity(ARRAY_SIZE);
-        for _ in 0..ARRAY_SIZE {
-            vec.push(false);
-        }
+        let mut vec = [false; ARRAY_SIZE];
         for i in 0..ARRAY_SIZE {
             let v = self.vec[i].get();
             if vec[v] {
@@ -20,7 +20,7 @@ pub trait Prog {
     fn exec(&self, args: Vec<String>) -> bool;
     fn help();
 
-    fn next(args: &Vec<String>, i: &mut usize) -> Option<String> {
+    fn next(args: &[String], i: &mut usize) -> Option<String> {
         if *i < args.len() {
             *i += 1;
             Some(args[*i - 1].clone())
@@ -29,8 +29,8 @@ pub trait Prog {
         }
     }
 
-    fn repeat(&self, args: &Vec<String>, i: usize, mut n: usize) -> bool {
-        let mut v = args[i..].to_vec().clone();
+    fn repeat(&self, args: &[String], i: usize, mut n: usize) -> bool {
+        let mut v = args[i..].to_vec();
         v.insert(0, "nop".to_string());
         v.insert(0, "nop".to_string());
         while n > 1 {
@@ -72,9 +72,9 @@ fn main() {
         } else if tp == "plist" {
             perform::<plist::List<i32>>(args)
         } else if tp == "vbst" {
-            perform::<vbst::BST<i32>>(args)
+            perform::<vbst::Bst<i32>>(args)
         } else if tp == "pbst" {
-            perform::<pbst::BST<i64>>(args)
+            perform::<pbst::Bst<i64>>(args)
         } else if tp == "vhash" {
             perform::<vhash::HashMap<i32, i32>>(args)
         } else if tp == "phash" {
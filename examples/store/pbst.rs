@@ -138,11 +138,11 @@ impl<T: Display + PartialEq + NVData> Display for Node<T> {
     }
 }
 
-pub struct BST<T: NVData> {
+pub struct Bst<T: NVData> {
     root: Link<T>,
 }
 
-impl<T: PartialOrd + NVData> BST<T> {
+impl<T: PartialOrd + NVData> Bst<T> {
     pub fn insert(&self, data: T, j: &Journal) {
         let loc = Node::<T>::loc(self.root.pclone(j), &data, j);
         let mut loc = loc.borrow_mut(j);
@@ -172,23 +172,23 @@ impl<T: PartialOrd + NVData> BST<T> {
     }
 }
 
-impl<T: PartialEq + Display + NVData> BST<T> {
+impl<T: PartialEq + Display + NVData> Bst<T> {
     pub fn print(&self, look: &Option<T>) -> String {
         if let Some(root) = &*self.root.borrow() {
-            format!("{}", root.print("".to_string(), look))
+            root.print("".to_string(), look)
         } else {
             "Empty".to_string()
         }
     }
 }
 
-impl<T: Display + PartialEq + NVData> Display for BST<T> {
+impl<T: Display + PartialEq + NVData> Display for Bst<T> {
     fn fmt(&self, fmt: &mut Formatter<'_>) -> Result<(), Error> {
         write!(fmt, "{}", self.print(&None))
     }
 }
 
-impl<T: NVData> RootObj<P> for BST<T> {
+impl<T: NVData> RootObj<P> for Bst<T> {
     fn init(j: &Journal) -> Self {
         Self {
             root: Prc::new(PRefCell::new(None), j),
@@ -196,7 +196,7 @@ impl<T: NVData> RootObj<P> for BST<T> {
     }
 }
 
-impl<T: Display + Debug + NVData + PartialOrd + FromStr> Prog for BST<T>
+impl<T: Display + Debug + NVData + PartialOrd + FromStr> Prog for Bst<T>
 where
     <T as FromStr>::Err: Debug,
 {
@@ -227,7 +227,7 @@ where
                         if let Some(n) = Self::next(&args, &mut i) {
                             let n: T = n
                                 .parse()
-                                .expect(&format!("Expected a(n) {}", std::any::type_name::<T>()));
+                                .unwrap_or_else(|_| panic!("Expected a(n) {}", std::any::type_name::<T>()));
                             P::transaction(|j| self.insert(n, j)).unwrap()
                         } else {
                             return false;
@@ -236,7 +236,7 @@ where
                         if let Some(n) = Self::next(&args, &mut i) {
                             let n: T = n
                                 .parse()
-                                .expect(&format!("Expected a(n) {}", std::any::type_name::<T>()));
+                                .unwrap_or_else(|_| panic!("Expected a(n) {}", std::any::type_name::<T>()));
                             P::transaction(|j| self.remove(n, j)).unwrap()
                         } else {
                             return false;
@@ -245,7 +245,7 @@ where
                         if let Some(n) = Self::next(&args, &mut i) {
                             let n: T = n
                                 .parse()
-                                .expect(&format!("Expected a(n) {}", std::any::type_name::<T>()));
+                                .unwrap_or_else(|_| panic!("Expected a(n) {}", std::any::type_name::<T>()));
                             println!("{:?}", self.search(n))
                         } else {
                             return false;
@@ -260,8 +260,6 @@ where
                         }
                     } else if op == "print" {
                         println!("{}", self)
-                    } else if op == "help" {
-                        Self::help()
                     }
                 } else {
                     return true;
@@ -181,7 +181,7 @@ where
                         if let Some(n) = Self::next(&args, &mut i) {
                             let n: T = n
                                 .parse()
-                                .expect(&format!("Expected a(n) {}", std::any::type_name::<T>()));
+                                .unwrap_or_else(|_| panic!("Expected a(n) {}", std::any::type_name::<T>()));
                             P::transaction(|j| self.push_back(n, j)).unwrap();
                         } else {
                             return false;
@@ -190,7 +190,7 @@ where
                         if let Some(n) = Self::next(&args, &mut i) {
                             let n: T = n
                                 .parse()
-                                .expect(&format!("Expected a(n) {}", std::any::type_name::<T>()));
+                                .unwrap_or_else(|_| panic!("Expected a(n) {}", std::any::type_name::<T>()));
                             P::transaction(|j| self.push_front(n, j)).unwrap();
                         } else {
                             return false;
@@ -207,8 +207,6 @@ where
                         }
                     } else if op == "print" {
                         println!("{}", self)
-                    } else if op == "help" {
-                        Self::help()
                     }
                 } else {
                     return true;
@@ -135,11 +135,11 @@ impl<T: Display + PartialEq> Display for Node<T> {
     }
 }
 
-pub struct BST<T> {
+pub struct Bst<T> {
     root: Link<T>,
 }
 
-impl<T: PartialOrd + Clone> BST<T> {
+impl<T: PartialOrd + Clone> Bst<T> {
     pub fn insert(&self, data: T) {
         let loc = Node::<T>::loc(self.root.clone(), &data);
         let mut loc = loc.borrow_mut();
@@ -169,23 +169,23 @@ impl<T: PartialOrd + Clone> BST<T> {
     }
 }
 
-impl<T: PartialEq + Display> BST<T> {
+impl<T: PartialEq + Display> Bst<T> {
     pub fn print(&self, look: &Option<T>) -> String {
         if let Some(root) = &*self.root.borrow() {
-            format!("{}", root.print("".to_string(), look))
+            root.print("".to_string(), look)
         } else {
             "Empty".to_string()
         }
     }
 }
 
-impl<T: Display + PartialEq> Display for BST<T> {
+impl<T: Display + PartialEq> Display for Bst<T> {
     fn fmt(&self, fmt: &mut Formatter<'_>) -> Result<(), Error> {
         write!(fmt, "{}", self.print(&None))
     }
 }
 
-impl<T: Display + Debug + Clone + PartialOrd + FromStr> Prog for BST<T>
+impl<T: Display + Debug + Clone + PartialOrd + FromStr> Prog for Bst<T>
 where
     <T as FromStr>::Err: Debug,
 {
@@ -218,7 +218,7 @@ where
                         if let Some(n) = Self::next(&args, &mut i) {
                             let n: T = n
                                 .parse()
-                                .expect(&format!("Expected a(n) {}", std::any::type_name::<T>()));
+                                .unwrap_or_else(|_| panic!("Expected a(n) {}", std::any::type_name::<T>()));
                             self.insert(n)
                         } else {
                             return false;
@@ -227,7 +227,7 @@ where
                         if let Some(n) = Self::next(&args, &mut i) {
                             let n: T = n
                                 .parse()
-                                .expect(&format!("Expected a(n) {}", std::any::type_name::<T>()));
+                                .unwrap_or_else(|_| panic!("Expected a(n) {}", std::any::type_name::<T>()));
                             self.remove(n)
                         } else {
                             return false;
@@ -236,7 +236,7 @@ where
                         if let Some(n) = Self::next(&args, &mut i) {
                             let n: T = n
                                 .parse()
-                                .expect(&format!("Expected a(n) {}", std::any::type_name::<T>()));
+                                .unwrap_or_else(|_| panic!("Expected a(n) {}", std::any::type_name::<T>()));
                             println!("{}", self.print(&self.search(n)))
                         } else {
                             return false;
@@ -251,8 +251,6 @@ where
                         }
                     } else if op == "print" {
                         println!("{}", self)
-                    } else if op == "help" {
-                        Self::help()
                     }
                 } else {
                     return true;
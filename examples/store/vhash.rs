@@ -121,9 +121,9 @@ where
                         if let Some(n) = Self::next(&args, &mut i) {
                             let key: K = n
                                 .parse()
-                                .expect(&format!("Expected a(n) {}", std::any::type_name::<K>()));
+                                .unwrap_or_else(|_| panic!("Expected a(n) {}", std::any::type_name::<K>()));
                             if let Some(n) = Self::next(&args, &mut i) {
-                                let val: V = n.parse().expect(&format!(
+                                let val: V = n.parse().unwrap_or_else(|_| panic!(
                                     "Expected a(n) {}",
                                     std::any::type_name::<V>()
                                 ));
@@ -138,7 +138,7 @@ where
                         if let Some(n) = Self::next(&args, &mut i) {
                             let key: K = n
                                 .parse()
-                                .expect(&format!("Expected a(n) {}", std::any::type_name::<K>()));
+                                .unwrap_or_else(|_| panic!("Expected a(n) {}", std::any::type_name::<K>()));
                             println!("{:?}", self.get(key))
                         } else {
                             return false;
@@ -151,8 +151,6 @@ where
                         }
                     } else if op == "print" {
                         println!("{:#?}", self)
-                    } else if op == "help" {
-                        Self::help()
                     }
                 } else {
                     return true;
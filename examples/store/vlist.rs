@@ -158,7 +158,7 @@ where
                         if let Some(n) = Self::next(&args, &mut i) {
                             let n: T = n
                                 .parse()
-                                .expect(&format!("Expected a(n) {}", std::any::type_name::<T>()));
+                                .unwrap_or_else(|_| panic!("Expected a(n) {}", std::any::type_name::<T>()));
                             self.push_back(n)
                         } else {
                             return false;
@@ -167,7 +167,7 @@ where
                         if let Some(n) = Self::next(&args, &mut i) {
                             let n: T = n
                                 .parse()
-                                .expect(&format!("Expected a(n) {}", std::any::type_name::<T>()));
+                                .unwrap_or_else(|_| panic!("Expected a(n) {}", std::any::type_name::<T>()));
                             self.push_front(n)
                         } else {
                             return false;
@@ -184,8 +184,6 @@ where
                         }
                     } else if op == "print" {
                         println!("{}", self)
-                    } else if op == "help" {
-                        Self::help()
                     }
                 } else {
                     return true;
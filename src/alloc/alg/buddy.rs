@@ -38,11 +38,7 @@ fn off_to_option(p: u64) -> Option<u64> {
 
 #[inline]
 fn option_to_pptr(p: Option<u64>) -> u64 {
-    if let Some(p) = p {
-        p
-    } else {
-        u64::MAX
-    }
+    p.unwrap_or(u64::MAX)
 }
 
 #[repr(C)]
@@ -150,7 +146,7 @@ impl<A: MemPool> BuddyAlg<A> {
     }
 
     #[inline]
-    fn in_range<'a>(off: u64) -> bool {
+    fn in_range(off: u64) -> bool {
         (off < u64::MAX - A::start()) && (off + A::start() < A::end())
     }
 
@@ -167,7 +163,7 @@ impl<A: MemPool> BuddyAlg<A> {
     }
 
     #[inline]
-    fn lock(&mut self) {
+    pub(crate) fn lock(&mut self) {
         unsafe { 
             // debug_assert!(self.aux.empty(), "locked before: aux is not empty");
 
@@ -176,19 +172,19 @@ impl<A: MemPool> BuddyAlg<A> {
 
             #[cfg(any(feature = "no_pthread", windows))] {
                 let tid = std::thread::current().id().as_u64().get();
-                while std::intrinsics::atomic_cxchg_acqrel(&mut self.mutex, 0, tid).0 != tid {}
+                while crate::sync::spin_cxchg(&mut self.mutex, tid) != tid {}
             }
         }
     }
 
     #[inline]
-    fn unlock(&mut self) {
+    pub(crate) fn unlock(&mut self) {
         unsafe { 
             #[cfg(not(any(feature = "no_pthread", windows)))]
             libc::pthread_mutex_unlock(&mut self.mutex.0); 
 
             #[cfg(any(feature = "no_pthread", windows))]
-            std::intrinsics::atomic_store_relaxed(&mut self.mutex, 0);
+            crate::sync::spin_release(&mut self.mutex);
         }
     }
 
@@ -232,7 +228,7 @@ impl<A: MemPool> BuddyAlg<A> {
         self.aux.clear();
         self.log64.foreach(|(off, data)| unsafe {
             let n = Self::buddy(off);
-            std::intrinsics::atomic_store_relaxed(&mut n.next, data);
+            std::ptr::write_volatile(&mut n.next, data);
         });
         self.log64.clear();
         self.available = self.available_log;
@@ -320,12 +316,28 @@ impl<A: MemPool> BuddyAlg<A> {
     /// Otherwise, `u64::MAX` is returned.
     pub unsafe fn alloc_impl(&mut self, len: usize, perform: bool) -> u64 {
         self.lock();
+        if let Some(off) = self.alloc_block(len) {
+            self.aux.sync_all();
+            if perform {
+                self.perform();
+            }
+            off
+        } else {
+            self.discard();
+            u64::MAX
+        }
+    }
+
+    /// Generates required changes to the metadata for allocating a new memory
+    /// block with the size `len` without locking the allocator. The caller
+    /// owns the lock and is responsible for performing or discarding the
+    /// changes.
+    pub(crate) unsafe fn alloc_block(&mut self, len: usize) -> Option<u64> {
         let idx = get_idx(len);
         let len = 1 << idx;
 
         if len > self.available {
-            self.discard();
-            u64::MAX
+            None
         } else {
             match self.find_free_memory(idx, false) {
                 Some(off) => {
@@ -334,11 +346,6 @@ impl<A: MemPool> BuddyAlg<A> {
 
                     self.available_log = self.available - len;
 
-                    self.aux.sync_all();
-                    if perform {
-                        self.perform();
-                    }
-
                     #[cfg(feature = "stat_footprint")]
                     {
                         let usage = self.size - self.available_log;
@@ -347,7 +354,7 @@ impl<A: MemPool> BuddyAlg<A> {
                         }
                     }
 
-                    off
+                    Some(off)
                 }
                 None => {
                     eprintln!(
@@ -355,8 +362,7 @@ impl<A: MemPool> BuddyAlg<A> {
                         len,
                         self.available()
                     );
-                    self.discard();
-                    u64::MAX
+                    None
                 }
             }
         }
@@ -369,6 +375,19 @@ impl<A: MemPool> BuddyAlg<A> {
     /// argument.
     pub unsafe fn dealloc_impl(&mut self, off: u64, len: usize, perform: bool) {
         self.lock();
+        self.free_block(off, len);
+
+        self.aux.sync_all();
+        if perform {
+            self.perform();
+        }
+    }
+
+    /// Generates required changes to the metadata for reclaiming the memory
+    /// block at offset `off` with the size of `len` without locking the
+    /// allocator. The caller owns the lock and is responsible for performing
+    /// or discarding the changes.
+    pub(crate) unsafe fn free_block(&mut self, off: u64, len: usize) {
         let idx = get_idx(len);
         let len = 1 << idx;
 
@@ -377,11 +396,97 @@ impl<A: MemPool> BuddyAlg<A> {
 
         self.available_log = self.available;
         self.free_impl(off, len);
+    }
 
+    #[inline]
+    /// Keeps the available space unchanged when the auxiliary buffer is
+    /// drained. It is used by the operations which only change the meta-data
+    /// of the layers built on top of the free lists.
+    pub(crate) fn keep_available(&mut self) {
+        self.available_log = self.available;
+    }
+
+    #[inline]
+    /// Flushes the auxiliary buffer
+    pub(crate) fn sync_aux(&self) {
         self.aux.sync_all();
-        if perform {
-            self.perform();
+    }
+
+    /// Adds the range `start..end`, which lies right after the pool and is
+    /// not in use, to the free lists, and increases the zone size accordingly.
+    /// The 64-bit word at offset `size_off` is set to the end of the added
+    /// blocks in the same failure-atomic section as the free lists. So, a crash
+    /// either keeps the previous size, or reaches the new one.
+    ///
+    /// The range is split into aligned power-of-two blocks. The new blocks of
+    /// each list are chained together through their own memory first, which
+    /// is unreachable until they are linked. Then, only the links from the
+    /// tails of the lists go through the auxiliary buffer, which needs at most
+    /// one entry per list. The new blocks are not merged with their free
+    /// buddies in the existing range.
+    pub unsafe fn extend(&mut self, start: u64, end: u64, size_off: u64) {
+        self.lock();
+        let mut first = [u64::MAX; 64];
+        let mut last = [u64::MAX; 64];
+        let mut added = 0;
+        let mut off = (start + 7) & !7;
+        while off + 8 <= end {
+            let mut len = 1u64 << off.trailing_zeros().min(63);
+            while off + len > end {
+                len >>= 1;
+            }
+            let idx = get_idx(len as usize);
+            let b = read_addr::<Buddy>(A::start() + off);
+            b.next = u64::MAX;
+            persist_obj(b, false);
+            if let Some(p) = off_to_option(last[idx]) {
+                let p = read_addr::<Buddy>(A::start() + p);
+                p.next = off;
+                persist_obj(p, false);
+            } else {
+                first[idx] = off;
+            }
+            last[idx] = off;
+            added += len as usize;
+            off += len;
+        }
+        if added == 0 {
+            self.unlock();
+            return;
         }
+
+        let top = (0..64).rev().find(|i| !is_none(first[*i])).unwrap();
+        if top > self.last_idx {
+            // Free lists beyond `last_idx` are empty; extending the range
+            // before the operation is harmless if it crashes
+            self.last_idx = top;
+            persist_obj(&self.last_idx, false);
+        }
+        sfence();
+
+        for (idx, first) in first.iter().enumerate() {
+            if let Some(f) = off_to_option(*first) {
+                let mut tail = None;
+                let mut curr = self.buddies[idx];
+                while let Some(b) = off_to_option(curr) {
+                    tail = Some(b);
+                    curr = Self::buddy(b).next;
+                }
+                match tail {
+                    Some(t) => self.aux_push(t, f),
+                    None => self.aux_push(Self::get_off(&self.buddies[idx]), f),
+                }
+            }
+        }
+        self.available_log = self.available + added;
+
+        let zone_size = &*(&self.size as *const usize as *const u64);
+        self.log(Self::get_off(zone_size), (self.size + added) as u64);
+        self.log(size_off, off);
+
+        self.aux.sync_all();
+        self.log64.sync_all();
+        self.perform();
     }
 
     #[inline]
@@ -484,6 +589,18 @@ impl<A: MemPool> BuddyAlg<A> {
     /// 
     /// [`DropOnFailure`]: ../alloc/trait.MemPool.html#method.drop_on_failure
     pub fn recover(&mut self) {
+        let self_mut = self as *mut Self;
+        self.recover_with(|off, len| unsafe {
+            (*self_mut).dealloc_impl(off, len, false);
+        });
+    }
+
+    /// Starts the recovery procedure in which the allocations in the
+    /// [`DropOnFailure`] logs are reclaimed using `dealloc`. The `dealloc`
+    /// function should only generate the changes in the auxiliary buffer.
+    ///
+    /// [`DropOnFailure`]: ../alloc/trait.MemPool.html#method.drop_on_failure
+    pub(crate) fn recover_with<F: FnMut(u64, usize)>(&mut self, mut dealloc: F) {
         #[cfg(not(any(feature = "no_pthread", windows)))] unsafe {
         crate::sync::init_lock(&mut self.mutex.0, &mut self.mutex.1);
         }
@@ -528,7 +645,7 @@ impl<A: MemPool> BuddyAlg<A> {
                 unsafe {
                     let self_mut = self as *mut Self;
                     self.drop_log.drain_atomic(|(off, len)| {
-                        (*self_mut).dealloc_impl(off, len, false);
+                        dealloc(off, len);
                     }, || {
                         (*self_mut).drain_aux();
                         (*self_mut).discard();
@@ -561,7 +678,7 @@ impl<A: MemPool> BuddyAlg<A> {
         }
         if info_level > 2 {
             if !self.aux.is_empty() {
-                res += &format!("\nOperation Logs:\n");
+                res += "\nOperation Logs:\n";
                 self.aux.foreach(|(off, next)| {
                     let n = Self::buddy(off);
                     res += &format!("  aux @({:x}) {:x} -> {:x}\n", off, n.next, next);
@@ -569,7 +686,7 @@ impl<A: MemPool> BuddyAlg<A> {
             }
     
             if !self.log64.is_empty() {
-                res += &format!("\nRedo Logs:\n");
+                res += "\nRedo Logs:\n";
                 self.log64.foreach(|(off, next)| {
                     let n = Self::buddy(off);
                     res += &format!("  log @({:x}) {:x} -> {:x}\n", off, n.next, next);
@@ -577,7 +694,7 @@ impl<A: MemPool> BuddyAlg<A> {
             }
     
             if !self.drop_log.is_empty() {
-                res += &format!("\nDrop Logss:\n");
+                res += "\nDrop Logs:\n";
                 self.drop_log.foreach(|(off, len)| {
                     res += &format!("  drop ({:x}; {})\n", off, len);
                 });
@@ -648,6 +765,60 @@ impl<A: MemPool> BuddyAlg<A> {
         true
     }
 
+    /// Returns the range of memory which is managed by a zone initialized
+    /// with `init(base, size)`
+    pub fn span(base: u64, size: usize) -> (u64, usize) {
+        let mut idx = get_idx(size);
+        if 1 << idx > size {
+            idx -= 1;
+        }
+        (base, 1 << idx)
+    }
+
+    /// Returns the range taken from the allocator for a block of size `len`
+    /// at offset `off`
+    #[inline]
+    pub fn footprint(&self, off: u64, len: usize) -> (u64, usize) {
+        (off, 1 << get_idx(len))
+    }
+
+    /// Breaks an allocated range into the largest aligned blocks that it
+    /// contains, so that they can be reclaimed one by one
+    pub fn split(&self, off: u64, len: usize) -> Vec<(u64, usize)> {
+        let mut res = vec![];
+        let end = off + len as u64;
+        let mut off = off;
+        while off + 8 <= end {
+            let mut len = 1u64 << off.trailing_zeros().min(63);
+            while off + len > end {
+                len >>= 1;
+            }
+            res.push((off, len as usize));
+            off += len;
+        }
+        res
+    }
+
+    /// Returns the offsets and the sizes of the free blocks. A list is not
+    /// followed further than the number of blocks that fit in the zone so
+    /// that a cyclic link does not hang it.
+    pub fn free_blocks(&self) -> Vec<(u64, usize)> {
+        let mut res = vec![];
+        for idx in 3..self.last_idx + 1 {
+            let mut curr = self.buddies[idx];
+            let mut n = 0;
+            while let Some(b) = off_to_option(curr) {
+                if n > self.size >> idx || !Self::in_range(b) {
+                    break;
+                }
+                res.push((b, 1 << idx));
+                curr = Self::buddy(b).next;
+                n += 1;
+            }
+        }
+        res
+    }
+
     /// Prints the free lists
     pub fn print(&self) {
         println!();
@@ -723,9 +894,10 @@ impl<T, A: MemPool> Zones<T, A> {
 
     #[inline]
     /// Returns a mutable reference to the zone object associated with the
-    /// given offset
+    /// given offset. The memory beyond the last zone's quota (e.g., after
+    /// growing the pool) belongs to the last zone.
     pub fn from_off(&self, off: u64) -> (&mut T, usize) {
-        let i = off as usize / self.quota;
+        let i = (off as usize / self.quota).min(self.count - 1);
         let off = self.base + i * mem::size_of::<T>();
         (Self::read(off as u64), i)
     }
@@ -746,6 +918,7 @@ impl<T, A: MemPool> IndexMut<usize> for Zones<T, A> {
 }
 
 #[cfg(test)]
+#[allow(clippy::items_after_test_module)]
 mod test {
     use crate::RootObj;
     use crate::default::*;
@@ -757,6 +930,7 @@ mod test {
         use rand::distributions::Alphanumeric;
         use rand::Rng;
 
+        #[allow(clippy::type_complexity)]
         struct Root {
             vec: PRefCell<PVec<Parc<(i32, PMutex<PString>)>>>
         }
@@ -827,6 +1001,196 @@ mod test {
 
         println!("{} -> {}", u, P::used());
     }
+
+    #[test]
+    fn buddy_grow_test() {
+        let _pool = P::open_no_root("grow.pool", O_CF).unwrap();
+
+        // Journals may remain pinned after the first transaction
+        P::transaction(|_| {}).unwrap();
+        let size = P::size();
+        let avail = P::available();
+
+        P::grow(size + 4 * 1024 * 1024).unwrap();
+        assert_eq!(P::size(), size + 4 * 1024 * 1024);
+        assert_eq!(P::available(), avail + 4 * 1024 * 1024);
+        assert!(P::verify());
+        assert!(P::grow(size).is_err());
+
+        P::transaction(|j| {
+            let b = Pbox::<[u8; 3 * 1024 * 1024]>::new_uninit(j);
+            assert!(P::contains(b.as_ref() as *const _ as u64));
+        }).unwrap();
+        #[cfg(not(feature = "pin_journals"))]
+        assert_eq!(P::available(), avail + 4 * 1024 * 1024);
+    }
+
+    #[test]
+    fn buddy_grow_reserve_test() {
+        crate::pool!(reserved, R);
+        type R = reserved::R;
+
+        R::set_growth_reserve(1024 * 1024);
+        let _pool = R::open_no_root("grow_reserve.pool", O_CF).unwrap();
+        let size = R::size();
+        assert!(R::grow(size + 2 * 1024 * 1024).is_err());
+        assert_eq!(R::size(), size);
+        R::grow(size + 512 * 1024).unwrap();
+        assert_eq!(R::size(), size + 512 * 1024);
+        assert!(R::verify());
+    }
+
+    #[test]
+    fn buddy_instances_test() {
+        let shards: Vec<_> = (0..3).map(|i| {
+            P::open_instance::<PCell<i32>>(&format!("shard{}.pool", i), O_CF).unwrap()
+        }).collect();
+        assert!(P::open_instance::<PCell<i32>>("shard0.pool", 0).is_err());
+
+        for (i, shard) in shards.iter().enumerate() {
+            shard.transaction(move |root, j| root.set(i as i32 * 10, j)).unwrap();
+        }
+        for (i, shard) in shards.iter().enumerate() {
+            assert_ne!(shard.id(), 0);
+            assert_eq!(shard.enter(|root| root.get()), i as i32 * 10);
+        }
+        assert_eq!(P::instance(), 0);
+
+        drop(shards);
+        let shard = P::open_instance::<PCell<i32>>("shard2.pool", 0).unwrap();
+        assert_eq!(shard.enter(|root| root.get()), 20);
+    }
+
+    #[test]
+    fn buddy_instances_cross_test() {
+        use std::panic::{catch_unwind, AssertUnwindSafe};
+
+        crate::pool!(sharded, S);
+        type S = sharded::S;
+
+        let root = S::open::<sharded::Parc<i32>>("sharded.pool", O_CF).unwrap();
+        let shard = S::open_instance::<sharded::Parc<i32>>("sharded1.pool", O_CF).unwrap();
+
+        // The root object of the default instance cannot be reached from the
+        // shard even if it is moved there
+        let default = root.clone();
+        assert!(catch_unwind(AssertUnwindSafe(|| {
+            shard.enter(move |_| **default)
+        })).is_err());
+        assert_eq!(**root, 0);
+
+        // Nor the pointers of the default instance through a leaked reference
+        let leaked: &'static sharded::Parc<i32> = Box::leak(Box::new(root.clone()));
+        assert!(catch_unwind(AssertUnwindSafe(|| {
+            shard.enter(move |_| **leaked)
+        })).is_err());
+        assert_eq!(shard.enter(|root| **root), 0);
+    }
+
+    #[test]
+    fn buddy_migrate_test() {
+        use crate::{Schema, Migrate, upgrade_root};
+
+        struct RootV1 {
+            count: PCell<i32>,
+        }
+        struct RootV2 {
+            count: PCell<i64>,
+            items: PRefCell<PVec<PString>>,
+        }
+        struct RootV3 {
+            items: PRefCell<PVec<PString>>,
+            total: PCell<u64>,
+        }
+
+        impl RootObj<P> for RootV1 {
+            fn init(_: &Journal) -> Self {
+                Self { count: PCell::new(0) }
+            }
+            fn schema() -> Schema {
+                Schema::named("Root", 1)
+            }
+        }
+        impl RootObj<P> for RootV2 {
+            fn init(_: &Journal) -> Self {
+                Self { count: PCell::new(0), items: PRefCell::new(PVec::new()) }
+            }
+            fn schema() -> Schema {
+                Schema::named("Root", 2)
+            }
+            unsafe fn upgrade(from: Schema, off: u64, j: &Journal) -> Option<Self> {
+                upgrade_root::<RootV1, Self, P>(from, off, j)
+            }
+        }
+        impl RootObj<P> for RootV3 {
+            fn init(_: &Journal) -> Self {
+                Self { items: PRefCell::new(PVec::new()), total: PCell::new(0) }
+            }
+            fn schema() -> Schema {
+                Schema::named("Root", 3)
+            }
+            unsafe fn upgrade(from: Schema, off: u64, j: &Journal) -> Option<Self> {
+                upgrade_root::<RootV2, Self, P>(from, off, j)
+            }
+        }
+        impl Migrate<RootV1, P> for RootV2 {
+            fn migrate(old: RootV1, j: &Journal) -> Self {
+                let mut items = PVec::new();
+                items.push("first".to_pstring(j), j);
+                Self { count: PCell::new(old.count.get() as i64), items: PRefCell::new(items) }
+            }
+        }
+        impl Migrate<RootV2, P> for RootV3 {
+            fn migrate(old: RootV2, _: &Journal) -> Self {
+                Self { total: PCell::new(old.count.get() as u64), items: old.items }
+            }
+        }
+
+        {
+            let root = P::open::<RootV1>("migrate.pool", O_CF).unwrap();
+            P::transaction(|j| root.count.set(7, j)).unwrap();
+        }
+        {
+            let root = P::open::<RootV3>("migrate.pool", 0).unwrap();
+            assert_eq!(root.total.get(), 7);
+            assert_eq!(root.items.borrow()[0].as_str(), "first");
+        }
+        let used = {
+            let root = P::open::<RootV3>("migrate.pool", 0).unwrap();
+            assert_eq!(root.total.get(), 7);
+            P::used()
+        };
+        assert!(P::open::<RootV2>("migrate.pool", 0).is_err());
+        let _root = P::open::<RootV3>("migrate.pool", 0).unwrap();
+        assert_eq!(P::used(), used);
+    }
+
+    #[test]
+    fn buddy_legacy_format_test() {
+        use std::collections::hash_map::DefaultHasher;
+        use std::convert::TryInto;
+        use std::hash::{Hash, Hasher};
+
+        let size = {
+            let root = P::open::<PCell<i32>>("legacy.pool", O_CF).unwrap();
+            P::transaction(|j| root.set(42, j)).unwrap();
+            P::size()
+        };
+
+        // The images created before the root objects were versioned have the
+        // hash of the header type name as the magic number, the size of the
+        // pool at offset 48, and nothing in the upper half of the flags
+        let mut s = DefaultHasher::new();
+        "corundum::default::BuddyAllocInner".hash(&mut s);
+        let image = std::fs::read("legacy.pool").unwrap();
+        let word = |image: &[u8], i: usize| u64::from_ne_bytes(image[i..i + 8].try_into().unwrap());
+        assert_eq!(word(&image, 0), s.finish());
+        assert_eq!(word(&image, 48), size as u64);
+        assert_eq!(word(&image, 8), crate::alloc::FLAG_HAS_ROOT);
+
+        let root = P::open::<PCell<i32>>("legacy.pool", 0).unwrap();
+        assert_eq!(root.get(), 42);
+    }
 }
 
 #[cfg(feature = "verbose")]
@@ -939,10 +1303,15 @@ macro_rules! __cfg_delete_history {
 /// * `Prc<T>` = [`corundum::prc::Prc`]`<T, `[`Allocator`]`>`
 /// * `Parc<T>` = [`corundum::sync::Parc`]`<T, `[`Allocator`]`>`
 /// * `PMutex<T>` = [`corundum::sync::PMutex`]`<T, `[`Allocator`]`>`
+/// * `PRwLock<T>` = [`corundum::sync::PRwLock`]`<T, `[`Allocator`]`>`
+/// * `PCondvar` = [`corundum::sync::PCondvar`]`<`[`Allocator`]`>`
+/// * `PAtomicU64` = [`corundum::sync::PAtomicU64`]`<`[`Allocator`]`>`
+/// * `PAtomicPtr<T>` = [`corundum::sync::PAtomicPtr`]`<T, `[`Allocator`]`>`
 /// * `PCell<T>` = [`corundum::cell::PCell`]`<T, `[`Allocator`]`>`
 /// * `PRefCell<T>` = [`corundum::cell::PRefCell`]`<T, `[`Allocator`]`>`
 /// * `VCell<T>` = [`corundum::cell::VCell`]`<T, `[`Allocator`]`>`
 /// * `TCell<T>` = [`corundum::cell::TCell`]`<T, `[`Allocator`]`>`
+/// * `VersionedCell<T>` = [`corundum::cell::VersionedCell`]`<T, `[`Allocator`]`>`
 /// * `PVec<T>` = [`corundum::vec::Vec`]`<T, `[`Allocator`]`>`
 /// * `PString` = [`corundum::str::String`]`<`[`Allocator`]`>`
 ///
@@ -988,22 +1357,57 @@ macro_rules! __cfg_delete_history {
 /// }).unwrap();
 /// ```
 /// 
+/// A pool type can also open up to `MAX_INSTANCES - 1` more image files at
+/// runtime using [`open_instance()`], e.g., for sharding data across several
+/// files without declaring a pool type per file.
+/// 
+/// The on-media layout of the pool image file can be specified as the third
+/// argument. By default, the pool uses [`NativeLayout`]. The following pool
+/// type creates image files with a PMDK-compatible header (see [`libpmem`]).
+/// 
+/// ```
+/// corundum::pool!(pmem, P, corundum::libpmem::PmdkHeaderLayout);
+/// ```
+/// 
+/// The allocation algorithm can be specified as the fourth argument. It is
+/// either [`BuddyAlg`] (default) or [`SlabAlg`] which serves small objects
+/// from size-class slabs to reduce the internal fragmentation.
+/// 
+/// ```
+/// corundum::pool!(slab, P, corundum::NativeLayout, SlabAlg);
+/// ```
+/// 
 /// [`Allocator`]: ./alloc/default/struct.Allocator.html
+/// [`NativeLayout`]: ./struct.NativeLayout.html
+/// [`libpmem`]: ./libpmem/index.html
+/// [`BuddyAlg`]: ./struct.BuddyAlg.html
+/// [`SlabAlg`]: ./struct.SlabAlg.html
+/// [`open_instance()`]: ./trait.MemPoolTraits.html#method.open_instance
 /// [`corundum::boxed::Pbox`]: ./boxed/struct.Pbox.html
 /// [`corundum::prc::Prc`]: ./prc/struct.Prc.html
 /// [`corundum::sync::Parc`]: ./sync/struct.Parc.html
 /// [`corundum::sync::PMutex`]: ./sync/struct.PMutex.html
+/// [`corundum::sync::PRwLock`]: ./sync/struct.PRwLock.html
+/// [`corundum::sync::PCondvar`]: ./sync/struct.PCondvar.html
+/// [`corundum::sync::PAtomicU64`]: ./sync/struct.PAtomicU64.html
+/// [`corundum::sync::PAtomicPtr`]: ./sync/struct.PAtomicPtr.html
 /// [`corundum::cell::PCell`]: ./cell/struct.PCell.html
 /// [`corundum::cell::PRefCell`]: ./cell/struct.PRefCell.html
 /// [`corundum::cell::VCell`]: ./cell/struct.VCell.html
 /// [`corundum::cell::TCell`]: ./cell/struct.TCell.html
+/// [`corundum::cell::VersionedCell`]: ./cell/struct.VersionedCell.html
 /// [`corundum::vec::Vec`]: ./vec/struct.Vec.html
 /// [`corundum::str::String`]: ./str/struct.String.html
 macro_rules! pool {
     ($mod:ident, $name:ident) => {
+        $crate::pool!($mod, $name, $crate::NativeLayout);
+    };
+    ($mod:ident, $name:ident, $layout:ty) => {
+        $crate::pool!($mod, $name, $layout, BuddyAlg);
+    };
+    ($mod:ident, $name:ident, $layout:ty, $alg:ident) => {
         /// The default allocator module
         pub mod $mod {
-            use memmap::*;
             use std::collections::hash_map::DefaultHasher;
             use std::collections::{HashMap,HashSet};
             use std::fs::OpenOptions;
@@ -1011,13 +1415,14 @@ macro_rules! pool {
             use std::mem;
             use std::ops::Range;
             use std::path::{Path, PathBuf};
-            use std::sync::atomic::{AtomicBool, Ordering};
+            use std::sync::atomic::{AtomicBool, AtomicPtr, AtomicU32, AtomicU64, AtomicUsize, Ordering};
             use std::sync::{Arc, Mutex};
             use std::thread::ThreadId;
             use $crate::ll::*;
             use $crate::result::Result;
             use $crate::utils::read;
             use $crate::*;
+            #[allow(unused_imports)]
             pub use $crate::{
                 PSafe, 
                 TxInSafe, 
@@ -1026,8 +1431,11 @@ macro_rules! pool {
                 AssertTxInSafe, 
                 VSafe, 
                 transaction, 
+                transaction_with, 
+                read_transaction, 
                 open_flags, 
                 PClone, 
+                Trace,
                 Root,
                 RootObj,
                 ToPString,
@@ -1036,10 +1444,6 @@ macro_rules! pool {
                 MemPool
             };
     
-            static mut BUDDY_START: u64 = 0;
-            static mut BUDDY_VALID_START: u64 = 0;
-            static mut BUDDY_END: u64 = 0;
-    
             #[repr(C)]
             struct BuddyAllocInner {
                 magic_number: u64,
@@ -1050,32 +1454,34 @@ macro_rules! pool {
                 root_type_id: u64,
                 journals: u64,
                 size: usize,
-                zone: Zones<BuddyAlg<$name>, $name>
+                zone: Zones<$alg<$name>, $name>
             }
     
             struct VData {
                 filename: String,
                 journals: HashMap<ThreadId, (u64, i32)>,
                 check_double_free: HashSet<u64>,
-                mmap: MmapMut,
+                mmap: PoolMap,
+                offset: usize,
+                read_only: bool,
             }
     
             impl VData {
-                fn new(mmap: MmapMut, filename: &str) -> Self {
+                fn new(mmap: PoolMap, filename: &str, offset: usize, read_only: bool) -> Self {
                     Self {
                         filename: filename.to_string(),
                         journals: HashMap::new(),
                         check_double_free: HashSet::new(),
                         mmap,
+                        offset,
+                        read_only,
                     }
                 }
             }
+
     
             impl BuddyAllocInner {
                 fn init(&mut self, size: usize) {
-                    let id = std::any::type_name::<Self>();
-                    let mut s = DefaultHasher::new();
-                    id.hash(&mut s);
                     self.flags = 0;
                     self.gen = 1;
                     self.tx_gen = 0;
@@ -1084,13 +1490,8 @@ macro_rules! pool {
                     self.journals = u64::MAX;
                     self.size = size;
     
-                    type T = BuddyAlg<$name>;
-                    let cpus = if let Some(val) = std::env::var_os("CPUS") {
-                        val.into_string().unwrap().parse::<usize>().unwrap()
-                    } else {
-                        num_cpus::get()
-                    };
-                    assert_ne!(cpus, 0);
+                    type T = $alg<$name>;
+                    let cpus = Self::cpus();
                     let quota = size / cpus;
                     self.zone = Zones::new(cpus, mem::size_of::<Self>(), quota);
                     for i in 0..cpus {
@@ -1103,9 +1504,66 @@ macro_rules! pool {
                             true,
                         );
                     }
-                    self.magic_number = s.finish();
+                    self.magic_number = Self::magic();
+                }
+
+                fn magic() -> u64 {
+                    let mut s = DefaultHasher::new();
+                    std::any::type_name::<Self>().hash(&mut s);
+                    s.finish()
+                }
+
+                /// Returns the magic number of the pool type generated in
+                /// module `pool` (e.g., `corundum::default`). It is the same
+                /// as [`magic()`](#method.magic) of that pool type.
+                fn magic_of(pool: &str) -> u64 {
+                    let mut s = DefaultHasher::new();
+                    format!("{}::BuddyAllocInner", pool).as_str().hash(&mut s);
+                    s.finish()
+                }
+
+                /// Checks if the header fields are within the bounds of an
+                /// allocator region of `len` bytes
+                fn is_sane(&self, len: usize) -> bool {
+                    let min = mem::size_of::<Self>() + mem::size_of::<$alg<$name>>();
+                    self.size >= min && self.size <= len &&
+                    (self.journals == u64::MAX || self.journals < self.size as u64)
+                }
+
+                /// Returns the schema version of the root object. It is kept
+                /// in the upper half of `flags` which is zero in the images
+                /// created before the root objects were versioned.
+                fn root_version(&self) -> u32 {
+                    (self.flags >> 32) as u32
+                }
+
+                fn set_root_version(&mut self, version: u32) {
+                    self.flags = (self.flags & 0xffff_ffff) | ((version as u64) << 32);
                 }
     
+                /// Returns true if `off..off+len` is within the root object
+                /// fields of the header. They are logged when the root object
+                /// is migrated, so that the header is updated atomically with
+                /// the migration transaction.
+                fn is_root_meta(off: u64, len: usize) -> bool {
+                    let end = off + len as u64;
+                    let within = |start: usize, size: usize| {
+                        off >= start as u64 && end <= (start + size) as u64
+                    };
+                    within(mem::offset_of!(Self, flags), mem::size_of::<u64>())
+                        || within(mem::offset_of!(Self, root_obj), 2 * mem::size_of::<u64>())
+                }
+
+                fn cpus() -> usize {
+                    let cpus = if let Some(val) = std::env::var_os("CPUS") {
+                        val.into_string().unwrap().parse::<usize>().unwrap()
+                    } else {
+                        num_cpus::get()
+                    };
+                    assert_ne!(cpus, 0);
+                    cpus
+                }
+
                 fn as_bytes(&self) -> &[u8] {
                     let ptr: *const Self = self;
                     let ptr = ptr as *const u8;
@@ -1135,83 +1593,220 @@ macro_rules! pool {
                 }
             }
     
-            static mut BUDDY_INNER: Option<*mut BuddyAllocInner> = None;
-            static mut OPEN: AtomicBool = AtomicBool::new(false);
-            static mut MAX_GEN: u32 = 0;
-            static mut VDATA: LazyCell<Arc<Mutex<Option<VData>>>> = 
-                LazyCell::new(|| Arc::new(Mutex::new(None)));
+            /// Maximum number of instances of the pool which can be open at
+            /// the same time, including the default instance
+            pub const MAX_INSTANCES: usize = 64;
+
+            /// Volatile information of an instance of the pool
+            struct Slot {
+                start: AtomicU64,
+                valid_start: AtomicU64,
+                end: AtomicU64,
+                inner: AtomicPtr<BuddyAllocInner>,
+                open: AtomicBool,
+                reserved: AtomicBool,
+                redo: AtomicBool,
+                vdata: Mutex<Option<VData>>,
+            }
+
+            impl Slot {
+                #[allow(clippy::declare_interior_mutable_const)]
+                const EMPTY: Slot = Slot {
+                    start: AtomicU64::new(0),
+                    valid_start: AtomicU64::new(0),
+                    end: AtomicU64::new(0),
+                    inner: AtomicPtr::new(std::ptr::null_mut()),
+                    open: AtomicBool::new(false),
+                    reserved: AtomicBool::new(false),
+                    redo: AtomicBool::new(false),
+                    vdata: Mutex::new(None),
+                };
+
+                #[inline]
+                fn inner(&self) -> Option<*mut BuddyAllocInner> {
+                    let inner = self.inner.load(Ordering::Acquire);
+                    if inner.is_null() {
+                        None
+                    } else {
+                        Some(inner)
+                    }
+                }
+
+                #[inline]
+                fn contains(&self, addr: u64) -> bool {
+                    addr >= self.valid_start.load(Ordering::Acquire)
+                        && addr < self.end.load(Ordering::Acquire)
+                }
+            }
+
+            static SLOTS: [Slot; MAX_INSTANCES] = [Slot::EMPTY; MAX_INSTANCES];
+            static MAX_GEN: AtomicU32 = AtomicU32::new(0);
+
+            /// Number of the open instances other than the default one
+            static INSTANCES: AtomicUsize = AtomicUsize::new(0);
+
+            /// Size of the address range reserved past the end of the pool
+            static RESERVE: AtomicUsize = AtomicUsize::new($crate::GROWTH_RESERVE);
+
+            thread_local! {
+                /// The instance that the current thread works with
+                static CURRENT: std::cell::Cell<usize> = std::cell::Cell::new(0);
+            }
+
+            #[inline]
+            fn slot() -> &'static Slot {
+                &SLOTS[CURRENT.with(|c| c.get())]
+            }
     
             impl $name {
+                /// Sets the size of the address range reserved past the end
+                /// of the pool when it is opened, which bounds how much it
+                /// can [`grow`](#method.grow) while it is open. It is
+                /// `GROWTH_RESERVE` by default, and it applies to the pools
+                /// opened afterwards.
+                pub fn set_growth_reserve(size: usize) {
+                    RESERVE.store(size, Ordering::Release);
+                }
+
                 fn running_transaction() -> bool {
-                    let vdata = match unsafe { VDATA.lock() } {
+                    let vdata = match slot().vdata.lock() {
                         Ok(g) => g,
                         Err(p) => p.into_inner()
                     };
                     if let Some(vdata) = &*vdata {
-                        !vdata.journals.is_empty()
+                        // Pinned journals stay in the list after their
+                        // transactions are complete
+                        vdata.journals.values().any(|(_, depth)| *depth != 0)
                     } else {
                         false
                     }
                 }
+
+                /// Traverses the object graph from the root object, and
+                /// returns the checker along with the ranges managed by the
+                /// zones
+                fn checker<U: PSafe + RootObj<Self> + Trace<Self>>()
+                    -> Result<(Checker<Self>, Vec<(u64, usize)>)> {
+                    if Self::running_transaction() {
+                        return Err($crate::Error::TransactionRunning);
+                    }
+                    static_inner!(slot().inner(), inner, {
+                        type T = $alg<$name>;
+                        let count = inner.zone.count();
+                        let quota = inner.zone.quota();
+                        let mut free = vec![];
+                        let mut managed = vec![];
+                        for i in 0..count {
+                            free.extend(inner.zone[i].free_blocks());
+                            managed.push(T::span((quota * i) as u64, quota));
+                        }
+
+                        // The range added by growing the pool belongs to the
+                        // last zone, and ends at the current pool size
+                        let ext = inner.zone[count - 1].size() - managed[count - 1].1;
+                        if ext > 0 {
+                            managed.push((inner.size as u64 - ext as u64, ext));
+                        }
+
+                        let mut checker = Checker::<Self>::new(free);
+                        checker.reserve(0, mem::size_of::<BuddyAllocInner>()
+                            + mem::size_of::<T>() * count);
+                        checker.reserve_journals()?;
+                        if inner.has_root() {
+                            if U::schema().id != inner.root_type_id {
+                                return Err($crate::Error::IncompatibleRoot);
+                            }
+                            checker.trace::<U>(inner.root_obj)?;
+                        }
+                        Ok((checker, managed))
+                    })
+                }
     
                 /// Opens a memory pool file and returns an instance of
                 /// [`Allocator`](#) if success. The pool remains open as long
                 /// as the instance lives.
+                ///
+                /// If `magic` is `None`, the pool type is not checked. Otherwise,
+                /// the magic number of the image should be equal to it. The
+                /// header is always checked to be within the bounds of the
+                /// file. If `read_only` is true, the file is mapped privately,
+                /// so that the changes are not written back to it.
                 #[track_caller]
-                pub fn open_impl(filename: &str, no_check: bool) -> Result<PoolGuard<Self>> {
-                    let metadata = std::fs::metadata(filename);
-                    if let Err(e) = &metadata {
-                        Err(format!("{}", e))
+                pub fn open_impl(filename: &str, magic: Option<u64>, read_only: bool) -> Result<PoolGuard<Self>> {
+                    let metadata = match std::fs::metadata(filename) {
+                        Ok(metadata) => metadata,
+                        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                            return Err($crate::Error::NotFound(filename.to_string()));
+                        }
+                        Err(e) => return Err(e.into())
+                    };
+                    if !metadata.is_file() {
+                        Err($crate::Error::InvalidPool(format!("`{}` is not a file", filename)))
                     } else {
-                        let metadata = metadata.unwrap();
-                        assert!(metadata.is_file());
                         if metadata.len() < 8 {
-                            Err("Invalid pool file".to_string())
+                            Err($crate::Error::InvalidPool("Invalid pool file".to_string()))
                         } else {
                             let path = PathBuf::from(filename);
                             let file = OpenOptions::new()
                                 .read(true)
-                                .write(true)
-                                .create(true)
-                                .open(&path)
-                                .unwrap();
-    
-                            let mut mmap =
-                                unsafe { memmap::MmapOptions::new().map_mut(&file).unwrap() };
-    
-                            let raw_offset = mmap.get_mut(0).unwrap();
-    
-                            let id = std::any::type_name::<BuddyAllocInner>();
-                            let mut s = DefaultHasher::new();
-                            id.hash(&mut s);
-                            let id = s.finish();
+                                .write(!read_only)
+                                .create(!read_only)
+                                .open(&path)?;
+    
+                            let reserve = RESERVE.load(Ordering::Acquire);
+                            let mut mmap = PoolMap::map(&file, read_only, reserve)?;
+    
+                            let (offset, _) = match unsafe {
+                                <$layout as PoolLayout>::open(mmap.as_ptr(), mmap.len())
+                            } {
+                                Ok(region) => region,
+                                Err(e) => return Err(e)
+                            };
+                            if offset + mem::size_of::<BuddyAllocInner>() > mmap.len() {
+                                return Err($crate::Error::InvalidPool(
+                                    "Invalid pool file".to_string()));
+                            }
+                            let raw_offset = match mmap.get_mut(offset) {
+                                Some(raw_offset) => raw_offset,
+                                None => return Err($crate::Error::InvalidPool(
+                                    "Invalid pool file".to_string()))
+                            };
     
                             let inner = unsafe {
                                 read::<BuddyAllocInner>(raw_offset)
                             };
-                            if !no_check {
-                                assert_eq!(
-                                    inner.magic_number, id,
-                                    "Invalid magic number for the pool image file"
-                                );
+                            if let Some(magic) = magic {
+                                if inner.magic_number != magic {
+                                    return Err($crate::Error::InvalidPool(
+                                        "Invalid magic number for the pool image file".to_string()));
+                                }
+                            }
+                            if !inner.is_sane((metadata.len() as usize).saturating_sub(offset)) {
+                                return Err($crate::Error::InvalidPool(
+                                    "The header of the pool image file is corrupted".to_string()));
                             }
     
                             let base = raw_offset as *mut _ as u64;
-                            unsafe {
-                                inner.gen = MAX_GEN.max(inner.gen + 1);
+                            {
+                                // Every instance gets a distinct generation
+                                let gen = inner.gen;
+                                let prev = MAX_GEN.fetch_update(Ordering::AcqRel, Ordering::Acquire,
+                                    |max| Some(max.max(gen) + 1)).unwrap();
+                                inner.gen = prev.max(gen) + 1;
                                 inner.tx_gen = 0;
-                                MAX_GEN = inner.gen;
-                                BUDDY_START = base;
-                                BUDDY_VALID_START = base
+                                let slot = slot();
+                                slot.start.store(base, Ordering::Release);
+                                slot.valid_start.store(base
                                     + mem::size_of::<BuddyAllocInner>() as u64
-                                    + mem::size_of::<BuddyAlg<Self>>() as u64;
-                                BUDDY_END = BUDDY_START + inner.size as u64 + 1;
-                                BUDDY_INNER = Some(inner);
-                                let mut vdata = match VDATA.lock() {
+                                    + mem::size_of::<$alg<Self>>() as u64, Ordering::Release);
+                                slot.end.store(base + inner.size as u64 + 1, Ordering::Release);
+                                slot.inner.store(inner, Ordering::Release);
+                                register_pool(base, base + inner.size as u64, Self::name());
+                                let mut vdata = match slot.vdata.lock() {
                                     Ok(g) => g,
                                     Err(p) => p.into_inner()
                                 };
-                                *vdata = Some(VData::new(mmap, filename));
+                                *vdata = Some(VData::new(mmap, filename, offset, read_only));
                             }
     
                             Ok(PoolGuard::<Self>::new())
@@ -1234,42 +1829,53 @@ macro_rules! pool {
                             .write(true)
                             .create(true)
                             .open(filename);
-                        if let Err(e) = &file {
-                            Err(format!("{}", e))
+                        if let Err(e) = file {
+                            Err(e.into())
                         } else {
                             let file = file.unwrap();
-                            let mut len = file.metadata().unwrap().len() as usize;
+                            let mut len = file.metadata()?.len() as usize;
                             if len < 8 {
                                 len = 10 * 1024 * 1024;
-                                file.set_len(len as u64).unwrap();
+                                file.set_len(len as u64)?;
                             }
     
-                            let mut mmap = memmap::MmapOptions::new().map_mut(&file).unwrap();
-                            let begin = mmap.get_mut(0).unwrap();
+                            let mut mmap = memmap::MmapOptions::new().map_mut(&file)?;
+                            let (offset, len) = <$layout as PoolLayout>::format(
+                                mmap.as_mut_ptr(), len, stringify!($mod))?;
+                            let begin = mmap.get_mut(offset).unwrap();
                             std::ptr::write_bytes(begin, 0xff, 8);
-                            BUDDY_START = begin as *const _ as u64;
-                            BUDDY_END = u64::MAX;
+                            slot().start.store(begin as *const _ as u64, Ordering::Release);
+                            slot().end.store(u64::MAX, Ordering::Release);
     
                             let inner = read::<BuddyAllocInner>(begin);
                             inner.init(len);
-                            mmap.flush().unwrap();
+                            mmap.flush()?;
                             Ok(())
                         }
                     } else {
-                        Err("Image file does not exist".to_string())
+                        Err($crate::Error::NotFound(filename.to_string()))
                     }
                 }
     
                 #[inline]
                 #[track_caller]
                 fn gen() -> u32 {
-                    static_inner!(BUDDY_INNER, inner, { inner.gen })
+                    static_inner!(slot().inner(), inner, { inner.gen })
                 }
     
+                #[inline]
+                fn log_mode() -> $crate::stm::LogMode {
+                    if slot().redo.load(Ordering::Acquire) {
+                        $crate::stm::LogMode::Redo
+                    } else {
+                        $crate::stm::LogMode::Undo
+                    }
+                }
+
                 #[inline]
                 #[track_caller]
                 fn tx_gen() -> u32 {
-                    static_inner!(BUDDY_INNER, inner, {
+                    static_inner!(slot().inner(), inner, {
                         inner.tx_gen += 1;
                         inner.tx_gen
                     })
@@ -1277,13 +1883,13 @@ macro_rules! pool {
     
                 #[track_caller]
                 fn size() -> usize {
-                    static_inner!(BUDDY_INNER, inner, { inner.size })
+                    static_inner!(slot().inner(), inner, { inner.size })
                 }
     
                 #[inline]
                 #[track_caller]
                 fn available() -> usize {
-                    static_inner!(BUDDY_INNER, inner, {
+                    static_inner!(slot().inner(), inner, {
                         let mut sum = 0;
                         for i in 0..inner.zone.count() {
                             sum += inner.zone[i].available();
@@ -1294,7 +1900,7 @@ macro_rules! pool {
     
                 #[track_caller]
                 fn used() -> usize {
-                    static_inner!(BUDDY_INNER, inner, {
+                    static_inner!(slot().inner(), inner, {
                         let mut sum = 0;
                         for i in 0..inner.zone.count() {
                             sum += inner.zone[i].used();
@@ -1303,19 +1909,77 @@ macro_rules! pool {
                     })
                 }
     
+                #[track_caller]
+                fn grow(new_size: usize) -> Result<()> {
+                    if Self::running_transaction() {
+                        return Err($crate::Error::TransactionRunning);
+                    }
+                    let mut vdata = match slot().vdata.lock() {
+                        Ok(g) => g,
+                        Err(p) => p.into_inner()
+                    };
+                    let vdata = if let Some(vdata) = &mut *vdata {
+                        vdata
+                    } else {
+                        return Err($crate::Error::NotOpen);
+                    };
+                    if vdata.read_only {
+                        return Err($crate::Error::Unsupported(
+                            "The pool is open only to read info".to_string()));
+                    }
+                    static_inner!(slot().inner(), inner, {
+                        if new_size <= inner.size {
+                            return Err($crate::Error::InvalidSize(format!(
+                                "The new size ({}) should be larger than the current size ({})",
+                                new_size, inner.size)));
+                        }
+
+                        <$layout as PoolLayout>::grow(vdata.mmap.as_mut_ptr(), new_size)?;
+
+                        // Extending the file and the mapping is idempotent; the
+                        // pool size does not change until the new blocks are
+                        // added to the free lists.
+                        let file_size = vdata.offset + new_size;
+                        let file = OpenOptions::new()
+                            .read(true)
+                            .write(true)
+                            .open(&vdata.filename)
+                            ?;
+                        let len = file.metadata()?.len();
+                        if len < file_size as u64 {
+                            file.set_len(file_size as u64)?;
+                        }
+                        if vdata.mmap.len() < file_size {
+                            vdata.mmap.extend(&file, file_size)?;
+                        }
+
+                        // Add the new range to the last zone along with the new
+                        // pool size in a single failure-atomic section. The
+                        // valid range is widened only after that, so the new
+                        // range is never seen before it is in the free lists.
+                        let z = inner.zone.count() - 1;
+                        let size_off = Self::off_unchecked(&inner.size);
+                        inner.zone[z].extend(inner.size as u64, new_size as u64, size_off);
+                        slot().end.store(Self::start() + inner.size as u64 + 1, Ordering::Release);
+                        register_pool(Self::start(), Self::start() + inner.size as u64, Self::name());
+                        Ok(())
+                    })
+                }
+
                 #[inline]
                 fn rng() -> Range<u64> {
-                    unsafe { BUDDY_VALID_START..BUDDY_END }
+                    let slot = slot();
+                    slot.valid_start.load(Ordering::Acquire)..slot.end.load(Ordering::Acquire)
                 }
     
                 #[inline]
                 fn start() -> u64 {
-                    unsafe { BUDDY_START }
+                    slot().start.load(Ordering::Acquire)
                 }
     
                 #[inline]
                 fn end() -> u64 {
-                    unsafe { BUDDY_END }
+                    slot().end.load(Ordering::Acquire)
                 }
     
                 #[allow(unused_unsafe)]
@@ -1323,13 +1987,14 @@ macro_rules! pool {
                 unsafe fn pre_alloc(size: usize) -> (*mut u8, u64, usize, usize) {
                     let _perf = $crate::__cfg_stat_perf!($crate::stat::Measure::<Self>::Alloc(std::time::Instant::now()));
     
-                    static_inner!(BUDDY_INNER, inner, {
+                    static_inner!(slot().inner(), inner, {
                         let cpu = cpu();
                         let cnt = inner.zone.count();
                         for i in 0..cnt {
                             let z = (cpu+i)%cnt;
                             let a = inner.zone[z].alloc_impl(size, false);
                             if a != u64::MAX {
+                                $crate::__trace_event!(Self, "alloc", off = a, len = size, zone = z);
                                 return (Self::get_mut_unchecked(a), a, size, z);
                             }
                         }
@@ -1346,9 +2011,10 @@ macro_rules! pool {
                 unsafe fn pre_dealloc(ptr: *mut u8, size: usize) -> usize {
                     let _perf = $crate::__cfg_stat_perf!($crate::stat::Measure::<Self>::Dealloc(std::time::Instant::now()));
     
-                    static_inner!(BUDDY_INNER, inner, {
+                    static_inner!(slot().inner(), inner, {
                         let off = Self::off(ptr).expect("invalid pointer");
                         let (zone,zidx) = inner.zone.from_off(off);
+                        $crate::__trace_event!(Self, "dealloc", off = off, len = size, zone = zidx);
                         $crate::__cfg_check_access_violation!({
                             if zone.is_allocated(off, size) {
                                 zone.dealloc_impl(off, size, false);
@@ -1366,7 +2032,7 @@ macro_rules! pool {
                 #[allow(unused_unsafe)]
                 #[track_caller]
                 unsafe fn log64(off: u64, val: u64, z: usize) {
-                    static_inner!(BUDDY_INNER, inner, {
+                    static_inner!(slot().inner(), inner, {
                         inner.zone[z].log(off, val);
                     })
                 }
@@ -1375,7 +2041,7 @@ macro_rules! pool {
                 #[allow(unused_unsafe)]
                 #[track_caller]
                 unsafe fn drop_on_failure(off: u64, len: usize, z: usize) {
-                    static_inner!(BUDDY_INNER, inner, {
+                    static_inner!(slot().inner(), inner, {
                         inner.zone[z].drop_on_failure(off, len);
                     })
                 }
@@ -1383,8 +2049,8 @@ macro_rules! pool {
                 #[inline]
                 #[track_caller]
                 fn zone(off: u64) -> usize {
-                    static_inner!(BUDDY_INNER, inner, {
-                        off as usize / inner.zone.quota()
+                    static_inner!(slot().inner(), inner, {
+                        (off as usize / inner.zone.quota()).min(inner.zone.count() - 1)
                     })
                 }
     
@@ -1392,7 +2058,7 @@ macro_rules! pool {
                 #[allow(unused_unsafe)]
                 #[track_caller]
                 unsafe fn prepare(z: usize) {
-                    static_inner!(BUDDY_INNER, inner, {
+                    static_inner!(slot().inner(), inner, {
                         inner.zone[z].prepare();
                     })
                 }
@@ -1401,7 +2067,7 @@ macro_rules! pool {
                 #[allow(unused_unsafe)]
                 #[track_caller]
                 unsafe fn perform(z: usize) {
-                    static_inner!(BUDDY_INNER, inner, {
+                    static_inner!(slot().inner(), inner, {
                         inner.zone[z].perform();
                     })
                 }
@@ -1410,7 +2076,7 @@ macro_rules! pool {
                 #[allow(unused_unsafe)]
                 #[track_caller]
                 unsafe fn discard(z: usize) {
-                    static_inner!(BUDDY_INNER, inner, {
+                    static_inner!(slot().inner(), inner, {
                         inner.zone[z].discard();
                     })
                 }
@@ -1419,7 +2085,7 @@ macro_rules! pool {
                 #[allow(unused_unsafe)]
                 #[track_caller]
                 fn allocated(off: u64, _len: usize) -> bool {
-                    static_inner!(BUDDY_INNER, _inner, {
+                    static_inner!(slot().inner(), _inner, {
                         if off >= Self::end() {
                             false
                         } else if Self::contains(off + Self::start()) {
@@ -1427,7 +2093,7 @@ macro_rules! pool {
                                 { _inner.zone.from_off(off).0.is_allocated(off, _len) },
                                 { true })
                         } else {
-                            false
+                            BuddyAllocInner::is_root_meta(off, _len)
                         }
                     })
                 }
@@ -1436,7 +2102,7 @@ macro_rules! pool {
                 #[allow(unused_unsafe)]
                 #[track_caller]
                 fn verify() -> bool {
-                    static_inner!(BUDDY_INNER, inner, {
+                    static_inner!(slot().inner(), inner, {
                         for i in 0..inner.zone.count() {
                             if !inner.zone[i].verify() {
                                 return false;
@@ -1450,7 +2116,7 @@ macro_rules! pool {
                 #[allow(unused_unsafe)]
                 #[track_caller]
                 unsafe fn journals_head() -> &'static u64 {
-                    static_inner!(BUDDY_INNER, inner, {
+                    static_inner!(slot().inner(), inner, {
                         &inner.journals
                     })
                 }
@@ -1458,11 +2124,11 @@ macro_rules! pool {
                 #[allow(unused_unsafe)]
                 #[track_caller]
                 unsafe fn drop_journal(journal: &mut Journal) {
-                    let _vdata = match VDATA.lock() {
+                    let _vdata = match slot().vdata.lock() {
                         Ok(g) => g,
                         Err(p) => p.into_inner()
                     };
-                    static_inner!(BUDDY_INNER, inner, {
+                    static_inner!(slot().inner(), inner, {
                         let off = Self::off(journal).unwrap();
                     
                         $crate::__cfg_pin_journals!({
@@ -1486,7 +2152,7 @@ macro_rules! pool {
                 #[allow(unused_unsafe)]
                 #[track_caller]
                 unsafe fn journals<T, F: Fn(&mut HashMap<ThreadId, (u64, i32)>)->T>(f: F)->T{
-                    let mut vdata = match VDATA.lock() {
+                    let mut vdata = match slot().vdata.lock() {
                         Ok(g) => g,
                         Err(p) => p.into_inner()
                     };
@@ -1498,7 +2164,7 @@ macro_rules! pool {
                 }
 
                 unsafe fn dealloc_history() -> *mut HashSet<u64> {
-                    let mut vdata = match VDATA.lock() {
+                    let mut vdata = match slot().vdata.lock() {
                         Ok(g) => g,
                         Err(p) => p.into_inner()
                     };
@@ -1510,8 +2176,8 @@ macro_rules! pool {
                 }
     
                 #[allow(unused_unsafe,unused_braces)]
-                unsafe fn recover() {
-                    static_inner!(BUDDY_INNER, inner, {
+                unsafe fn recover() -> Result<()> {
+                    static_inner!(slot().inner(), inner, {
                         let info_level = std::env::var("RECOVERY_INFO")
                             .unwrap_or("0".to_string())
                             .parse::<u32>()
@@ -1541,7 +2207,7 @@ macro_rules! pool {
                         #[allow(unused_mut,unused_variables)]
                         let mut check_double_free = __cfg_delete_history!({
                             std::collections::HashSet::<u64>::new()
-                        }, { () });
+                        }, { });
                         
     
                         while let Ok(logs) = Self::deref_mut::<Journal>(inner.journals) {
@@ -1557,9 +2223,9 @@ macro_rules! pool {
                             });
     
                             __cfg_delete_history!({
-                                logs.recover(&mut check_double_free);
+                                logs.recover(&mut check_double_free)?;
                             }, {
-                                logs.recover();
+                                logs.recover()?;
                             });
     
                             $crate::__cfg_check_allocator_cyclic_links!({
@@ -1580,6 +2246,7 @@ macro_rules! pool {
                                 Self::drop_journal(logs);
                             });
                         }
+                        Ok(())
                     })
                 }
     
@@ -1590,17 +2257,11 @@ macro_rules! pool {
                     flags: u32,
                 ) -> Result<RootCell<'a, U, Self>> {
                     let slf = Self::open_no_root(path, flags)?;
-                    static_inner!(BUDDY_INNER, inner, {
-                        // Replace it with std::any::TypeId::of::<U>() when it
-                        // is available in the future for non-'static types
-                        let id = format!("{} ({})", std::any::type_name::<U>(),
-                            mem::size_of::<U>());
-                        let mut s = DefaultHasher::new();
-                        id.hash(&mut s);
-                        let id = s.finish();
+                    static_inner!(slot().inner(), inner, {
+                        let schema = U::schema();
                         if !inner.has_root() {
                             if mem::size_of::<U>() == 0 {
-                                Err("root type cannot be a ZST".to_string())
+                                Err($crate::Error::ZeroSizedRoot)
                             } else {
                                 let root_off = Self::transaction(move |j| {
                                     let ptr = Self::new(U::init(j), j);
@@ -1610,18 +2271,50 @@ macro_rules! pool {
                                 let ptr = Self::get_unchecked(root_off);
                                 inner.flags |= FLAG_HAS_ROOT;
                                 inner.root_obj = root_off;
-                                inner.root_type_id = id;
+                                inner.root_type_id = schema.id;
+                                inner.set_root_version(schema.version);
                                 persist_obj(inner, true);
                                 Ok(RootCell::new(ptr, Arc::new(slf)))
                             }
                         } else {
-                            if inner.root_type_id == id {
+                            let stored = Schema {
+                                id: inner.root_type_id,
+                                version: inner.root_version()
+                            };
+                            if stored == schema {
                                 Ok(RootCell::new(
                                     Self::deref::<U>(inner.root_obj)?,
                                     Arc::new(slf),
                                 ))
+                            } else if stored.version < schema.version {
+                                // The header is updated in the same transaction
+                                // so that the old root object is either
+                                // entirely migrated or left untouched
+                                let mut inner = AssertTxInSafe(inner);
+                                let root_off = Self::transaction(move |j| {
+                                    U::upgrade(stored, inner.root_obj, j).map(|root| {
+                                        let ptr = Self::new(root, j);
+                                        let root_off = Self::off_unchecked(ptr);
+                                        stm::Log::create(&inner.root_obj, j, stm::Notifier::None);
+                                        stm::Log::create(&inner.root_type_id, j, stm::Notifier::None);
+                                        stm::Log::create(&inner.flags, j, stm::Notifier::None);
+                                        inner.root_obj = root_off;
+                                        inner.root_type_id = schema.id;
+                                        inner.set_root_version(schema.version);
+                                        persist_obj(&**inner, true);
+                                        root_off
+                                    })
+                                })?;
+                                if let Some(root_off) = root_off {
+                                    Ok(RootCell::new(
+                                        Self::deref::<U>(root_off)?,
+                                        Arc::new(slf),
+                                    ))
+                                } else {
+                                    Err($crate::Error::NoMigrationPath(stored.version))
+                                }
                             } else {
-                                Err("Incompatible root type".to_string())
+                                Err($crate::Error::IncompatibleRoot)
                             }
                         }
                     })
@@ -1629,73 +2322,309 @@ macro_rules! pool {
     
                 #[inline]
                 fn is_open() -> bool {
-                    unsafe { BUDDY_INNER.is_some() }
+                    slot().inner().is_some()
                 }
     
                 #[allow(unused_unsafe)]
                 #[track_caller]
                 fn open_no_root(path: &str, flags: u32) -> Result<PoolGuard<Self>> {
                     unsafe {
-                        while OPEN.compare_exchange(false, true, Ordering::AcqRel, Ordering::Relaxed).is_err() {}
+                        while slot().open.compare_exchange(false, true, Ordering::AcqRel, Ordering::Relaxed).is_err() {}
                         if !Self::running_transaction() {
                             if flags == open_flags::O_READINFO {
-                                Self::open_impl(path, true)
-                            } else if let Ok(_) = Self::apply_flags(path, flags) {
-                                let res = Self::open_impl(path, false);
+                                let res = Self::open_impl(path, None, true);
+                                if res.is_err() {
+                                    slot().open.store(false, Ordering::Release);
+                                }
+                                res
+                            } else if flags == open_flags::O_REPAIR {
+                                let res = Self::open_impl(path, Some(BuddyAllocInner::magic()), false);
+                                if res.is_ok() {
+                                    Self::recover()?;
+                                } else {
+                                    slot().open.store(false, Ordering::Release);
+                                }
+                                res
+                            } else if let Err(e) = Self::apply_flags(path, flags) {
+                                slot().open.store(false, Ordering::Release);
+                                Err(e)
+                            } else {
+                                let res = Self::open_impl(path, Some(BuddyAllocInner::magic()), false);
                                 if res.is_ok() {
-                                    Self::recover();
+                                    Self::recover()?;
+                                    slot().redo.store(flags & open_flags::O_REDO != 0, Ordering::Release);
+                                } else {
+                                    slot().open.store(false, Ordering::Release);
                                 }
                                 res
+                            }
+                        } else {
+                            slot().open.store(false, Ordering::Release);
+                            Err($crate::Error::UncommittedTransaction)
+                        }
+                    }
+                }
+
+                #[allow(unused_unsafe)]
+                #[track_caller]
+                fn open_repair(path: &str, pool: &str) -> Result<PoolGuard<Self>> {
+                    unsafe {
+                        while slot().open.compare_exchange(false, true, Ordering::AcqRel, Ordering::Relaxed).is_err() {}
+                        if !Self::running_transaction() {
+                            let res = Self::open_impl(path, Some(BuddyAllocInner::magic_of(pool)), false);
+                            if res.is_ok() {
+                                Self::recover()?;
                             } else {
-                                OPEN.store(false, Ordering::Release);
-                                Err("Could not open file".to_string())
+                                slot().open.store(false, Ordering::Release);
                             }
+                            res
                         } else {
-                            OPEN.store(false, Ordering::Release);
-                            Err("An uncommitted transaction exists in the pool"
-                                .to_string())
+                            slot().open.store(false, Ordering::Release);
+                            Err($crate::Error::UncommittedTransaction)
                         }
                     }
                 }
     
                 #[allow(unused_unsafe)]
                 unsafe fn close() -> Result<()> {
-                    if OPEN.load(Ordering::Acquire) {
-                        let mut vdata = match VDATA.lock() {
+                    let slot = slot();
+                    if slot.open.load(Ordering::Acquire) {
+                        let mut vdata = match slot.vdata.lock() {
                             Ok(g) => g,
                             Err(p) => p.into_inner()
                         };
                         *vdata = None;
-                        BUDDY_INNER = None;
-                        OPEN.store(false, Ordering::Release);
+                        unregister_pool(slot.start.load(Ordering::Acquire));
+                        slot.inner.store(std::ptr::null_mut(), Ordering::Release);
+                        slot.open.store(false, Ordering::Release);
+                        if Self::instance() != 0 {
+                            INSTANCES.fetch_sub(1, Ordering::AcqRel);
+                        }
+                        slot.reserved.store(false, Ordering::Release);
+                        slot.redo.store(false, Ordering::Release);
                         Ok(())
                     } else {
-                        Err("Pool was already closed".to_string())
+                        Err($crate::Error::NotOpen)
+                    }
+                }
+
+                #[inline]
+                fn instance() -> usize {
+                    CURRENT.with(|c| c.get())
+                }
+
+                fn instance_of(addr: u64) -> Option<usize> {
+                    let id = Self::instance();
+                    if SLOTS[id].contains(addr) {
+                        Some(id)
+                    } else if INSTANCES.load(Ordering::Acquire) == 0 && id == 0 {
+                        None
+                    } else {
+                        SLOTS.iter().position(|s| s.inner().is_some() && s.contains(addr))
                     }
                 }
+
+                #[inline]
+                #[track_caller]
+                unsafe fn set_instance(id: usize) -> usize {
+                    assert!(id < MAX_INSTANCES, "Invalid instance {} (max = {})", id, MAX_INSTANCES - 1);
+                    CURRENT.with(|c| c.replace(id))
+                }
+
+                #[track_caller]
+                fn open_instance<'a, U: 'a + PSafe + RootObj<Self>>(
+                    path: &str,
+                    flags: u32,
+                ) -> Result<PoolInstance<'a, U, Self>> {
+                    // Mapping a file twice gives two independent allocators
+                    // over the same memory
+                    if let Ok(file) = std::fs::canonicalize(path) {
+                        for id in 0..MAX_INSTANCES {
+                            let vdata = match SLOTS[id].vdata.lock() {
+                                Ok(g) => g,
+                                Err(p) => p.into_inner()
+                            };
+                            if let Some(vdata) = &*vdata {
+                                if std::fs::canonicalize(&vdata.filename).ok().as_ref() == Some(&file) {
+                                    return Err($crate::Error::AlreadyOpen(path.to_string()));
+                                }
+                            }
+                        }
+                    }
+                    for id in 1..MAX_INSTANCES {
+                        let slot = &SLOTS[id];
+                        if slot.reserved.compare_exchange(false, true, Ordering::AcqRel, Ordering::Relaxed).is_ok() {
+                            return match unsafe { Self::with_instance_unchecked(id, || Self::open::<U>(path, flags)) } {
+                                Ok(root) => {
+                                    INSTANCES.fetch_add(1, Ordering::AcqRel);
+                                    Ok(PoolInstance::new(id, root))
+                                }
+                                Err(e) => {
+                                    slot.reserved.store(false, Ordering::Release);
+                                    Err(e)
+                                }
+                            };
+                        }
+                    }
+                    Err($crate::Error::TooManyInstances {
+                        pool: Self::name(),
+                        max: MAX_INSTANCES - 1,
+                    })
+                }
     
+                #[track_caller]
+                fn compact<U: PSafe + RootObj<Self> + Trace<Self>>(path: &str) -> Result<(usize, usize)> {
+                    let (graph, type_id, gen, old_size) = {
+                        let _root = Self::open::<U>(path, 0)?;
+                        static_inner!(slot().inner(), inner, {
+                            let mut graph = LiveGraph::<Self>::collect::<U>(inner.root_obj)?;
+                            graph.snapshot();
+                            (graph, (inner.root_type_id, inner.root_version()), inner.gen, inner.size)
+                        })
+                    };
+
+                    // The compacted image is built in a temporary file which
+                    // replaces the original one only if it is complete
+                    let file_size = std::fs::metadata(path)?.len() as usize;
+                    let tmp = format!("{}.compact", path);
+                    let cpus = BuddyAllocInner::cpus();
+                    let header = mem::size_of::<BuddyAllocInner>()
+                        + mem::size_of::<$alg<Self>>() * cpus;
+                    let mut new_size = 0;
+                    let mut size = (graph.volume() + header).next_power_of_two()
+                        .max(header.next_power_of_two() * 2 * cpus);
+                    loop {
+                        if size >= file_size {
+                            let _ = std::fs::remove_file(&tmp);
+                            return Err($crate::Error::InvalidSize(
+                                "The pool cannot be compacted any further".to_string()));
+                        }
+                        let file = OpenOptions::new()
+                            .write(true)
+                            .create(true)
+                            .truncate(true)
+                            .open(&tmp)
+                            ?;
+                        file.set_len(size as u64)?;
+
+                        // Formatting overwrites the volatile state of the pool
+                        // type; so, no other pool of this type may be open
+                        unsafe {
+                            while slot().open.compare_exchange(false, true, Ordering::AcqRel, Ordering::Relaxed).is_err() {}
+                            let res = Self::format(&tmp);
+                            slot().open.store(false, Ordering::Release);
+                            res?;
+                        }
+
+                        let guard = Self::open_no_root(&tmp, 0)?;
+                        let done = static_inner!(slot().inner(), inner, {
+                            if let Some(root) = graph.relocate() {
+                                new_size = inner.size;
+                                inner.flags |= FLAG_HAS_ROOT;
+                                inner.root_obj = root;
+                                inner.root_type_id = type_id.0;
+                                inner.set_root_version(type_id.1);
+
+                                // Volatile cells from the original pool should
+                                // not be valid in the new one
+                                inner.gen = inner.gen.max(gen + 1);
+                                MAX_GEN.fetch_max(inner.gen, Ordering::AcqRel);
+                                persist_obj(inner, true);
+
+                                let vdata = match slot().vdata.lock() {
+                                    Ok(g) => g,
+                                    Err(p) => p.into_inner()
+                                };
+                                if let Some(vdata) = &*vdata {
+                                    vdata.mmap.flush()?;
+                                }
+                                true
+                            } else {
+                                false
+                            }
+                        });
+                        drop(guard);
+                        if done {
+                            break;
+                        }
+                        size <<= 1;
+                    }
+                    std::fs::rename(&tmp, path)?;
+                    Ok((old_size, new_size))
+                }
+
+                #[track_caller]
+                fn check_pool<U: PSafe + RootObj<Self> + Trace<Self>>() -> Result<Report> {
+                    let (checker, managed) = Self::checker::<U>()?;
+                    static_inner!(slot().inner(), inner, {
+                        Ok(checker.report(&managed, |off, len| {
+                            inner.zone.from_off(off).0.footprint(off, len)
+                        }))
+                    })
+                }
+
+                fn collect_garbage<U: PSafe + RootObj<Self> + Trace<Self>>() -> Result<usize> {
+                    let (checker, managed) = Self::checker::<U>()?;
+                    static_inner!(slot().inner(), inner, {
+                        let zone = &inner.zone;
+                        checker.collect(&managed,
+                            |off, len| zone.from_off(off).0.footprint(off, len),
+                            |off, len| zone.from_off(off).0.split(off, len))
+                    })
+                }
+
                 fn stat_footprint() -> usize {
                     $crate::__cfg_stat_footprint!({
-                        static_inner!(BUDDY_INNER, inner, { inner.zone.stat_footprint() })
+                        static_inner!(slot().inner(), inner, { inner.zone.stat_footprint() })
                     }, {
                         unimplemented!()
                     })
                 }
     
                 fn print_info() {
+                    static_inner!(slot().inner(), inner, {
+                        println!("{:=^80}", " Pool Header ");
+                        println!("      Magic: {:#018x}", inner.magic_number);
+                        println!("      Flags: {:#x}", inner.flags & 0xffff_ffff);
+                        println!(" Generation: {}", inner.gen);
+                        if inner.has_root() {
+                            println!("       Root: @{:x} (type id = {:#018x}, version = {})",
+                                inner.root_obj, inner.root_type_id, inner.root_version());
+                        } else {
+                            println!("       Root: None");
+                        }
+                        println!("      Zones: {}", inner.zone.count());
+                    });
+
                     println!("{:=^80}", " All Zones ");
                     println!("      Total: {} bytes", Self::size());
                     println!("       Used: {} bytes", Self::used());
                     println!("  Available: {} bytes", Self::available());
     
-                    static_inner!(BUDDY_INNER, inner, { 
+                    static_inner!(slot().inner(), inner, { 
                         for i in 0..inner.zone.count() {
                             println!("{:=^80}", format!(" Persistent Memory Zone #{} ", i));
                             println!("       Total      {}", inner.zone[i].size());
                             println!("        Used      {}", inner.zone[i].used());
                             println!("   Available      {}", inner.zone[i].available());
+                            print!("{}", inner.zone[i].recovery_info(2));
                             inner.zone[i].print();
                         }
+
+                        let mut curr = inner.journals;
+                        if curr == u64::MAX {
+                            println!("{:=^80}", " No Journals ");
+                        }
+                        let mut visited = std::collections::HashSet::new();
+                        while let Ok(j) = Self::deref::<Journal>(curr) {
+                            if !visited.insert(curr) {
+                                println!("{:=^80}", format!(" Cyclic Link to @({:x}) ", curr));
+                                break;
+                            }
+                            println!("{:=^80}", format!(" Journal @({:x}) ", curr));
+                            print!("{}", j.recovery_info(2));
+                            curr = j.next_off();
+                        }
                     })
                 }
             }
@@ -1715,6 +2644,22 @@ macro_rules! pool {
             /// Compact form of [`PMutex`](../../sync/struct.PMutex.html)
             /// `<T,`[`Allocator`](./struct.Allocator.html)`>`.
             pub type PMutex<T> = $crate::sync::PMutex<T, $name>;
+
+            /// Compact form of [`PRwLock`](../../sync/struct.PRwLock.html)
+            /// `<T,`[`Allocator`](./struct.Allocator.html)`>`.
+            pub type PRwLock<T> = $crate::sync::PRwLock<T, $name>;
+
+            /// Compact form of [`PCondvar`](../../sync/struct.PCondvar.html)
+            /// `<`[`Allocator`](./struct.Allocator.html)`>`.
+            pub type PCondvar = $crate::sync::PCondvar<$name>;
+
+            /// Compact form of [`PAtomicU64`](../../sync/struct.PAtomicU64.html)
+            /// `<`[`Allocator`](./struct.Allocator.html)`>`.
+            pub type PAtomicU64 = $crate::sync::PAtomicU64<$name>;
+
+            /// Compact form of [`PAtomicPtr`](../../sync/struct.PAtomicPtr.html)
+            /// `<T,`[`Allocator`](./struct.Allocator.html)`>`.
+            pub type PAtomicPtr<T> = $crate::sync::PAtomicPtr<T, $name>;
     
             /// Compact form of [`PCell`](../../cell/struct.PCell.html)
             /// `<T,`[`Allocator`](./struct.Allocator.html)`>`.
@@ -1744,9 +2689,17 @@ macro_rules! pool {
             /// `<T,`[`Allocator`](./struct.Allocator.html)`>`.
             pub type TCell<T> = $crate::TCell<T, $name>;
     
+            /// Compact form of [`VersionedCell`](../../cell/struct.VersionedCell.html)
+            /// `<T,`[`Allocator`](./struct.Allocator.html)`>`.
+            pub type VersionedCell<T> = $crate::VersionedCell<T, $name>;
+    
             /// Compact form of [`Vec`](../../vec/struct.Vec.html)
             /// `<T,`[`Allocator`](./struct.Allocator.html)`>`.
             pub type PVec<T> = $crate::vec::Vec<T, $name>;
+
+            /// Compact form of [`VecDeque`](../../vec_deque/struct.VecDeque.html)
+            /// `<T,`[`Allocator`](./struct.Allocator.html)`>`.
+            pub type PVecDeque<T> = $crate::vec_deque::VecDeque<T, $name>;
     
             /// Compact form of [`String`](../../str/struct.String.html)
             /// `<`[`Allocator`](./struct.Allocator.html)`>`.
@@ -1782,6 +2735,208 @@ macro_rules! pool {
     };
 }
 
+/// Default size of the virtual address range reserved past the end of a pool
+/// when it is opened. A pool can grow by up to this size while it is open.
+/// Each pool type can change it with `set_growth_reserve()`.
+pub const GROWTH_RESERVE: usize = 1 << 40;
+
+/// A mapping of a pool file into a reserved range of the virtual address
+/// space
+///
+/// The whole range is reserved when the pool is opened, and the file is mapped
+/// at its beginning. So, the mapping can be extended without moving it, and
+/// the existing references to the pool remain valid.
+#[cfg(unix)]
+pub struct PoolMap {
+    base: *mut u8,
+    len: usize,
+    reserved: usize,
+    read_only: bool,
+}
+
+#[cfg(unix)]
+unsafe impl Send for PoolMap {}
+
+#[cfg(unix)]
+unsafe impl Sync for PoolMap {}
+
+#[cfg(unix)]
+impl PoolMap {
+    /// Reserves the address range, with `reserve` more bytes past the end of
+    /// the file, and maps `file` into it. If `read_only` is true, the file is
+    /// mapped privately, so that the changes are not written back to it.
+    pub fn map(file: &std::fs::File, read_only: bool, reserve: usize) -> crate::result::Result<Self> {
+        let len = file.metadata()?.len() as usize;
+        let page = page_size();
+        let reserved = len.div_ceil(page) * page + reserve;
+        let base = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                reserved,
+                libc::PROT_NONE,
+                libc::MAP_PRIVATE | libc::MAP_ANONYMOUS | libc::MAP_NORESERVE,
+                -1,
+                0,
+            )
+        };
+        if base == libc::MAP_FAILED {
+            return Err(crate::Error::Io(std::io::Error::last_os_error()));
+        }
+        let mut map = Self { base: base as *mut u8, len: 0, reserved, read_only };
+        map.map_file(file, len)?;
+        Ok(map)
+    }
+
+    /// Maps the first `len` bytes of `file` at the beginning of the reserved
+    /// range, replacing the previous mapping
+    fn map_file(&mut self, file: &std::fs::File, len: usize) -> crate::result::Result<()> {
+        use std::os::unix::io::AsRawFd;
+
+        let flags = if self.read_only { libc::MAP_PRIVATE } else { libc::MAP_SHARED };
+        let res = unsafe {
+            libc::mmap(
+                self.base as *mut libc::c_void,
+                len,
+                libc::PROT_READ | libc::PROT_WRITE,
+                flags | libc::MAP_FIXED,
+                file.as_raw_fd(),
+                0,
+            )
+        };
+        if res == libc::MAP_FAILED {
+            Err(crate::Error::Io(std::io::Error::last_os_error()))
+        } else {
+            self.len = len;
+            Ok(())
+        }
+    }
+
+    /// Extends the mapping to the first `new_len` bytes of `file` in place
+    pub fn extend(&mut self, file: &std::fs::File, new_len: usize) -> crate::result::Result<()> {
+        if self.read_only {
+            Err(crate::Error::Unsupported("The mapping is read-only".to_string()))
+        } else if new_len > self.reserved {
+            Err(crate::Error::InvalidSize(format!(
+                "The mapping cannot grow beyond the reserved address range ({} bytes)",
+                self.reserved)))
+        } else {
+            self.map_file(file, new_len)
+        }
+    }
+
+    /// Returns the length of the mapped range
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns a raw pointer to the beginning of the mapping
+    #[inline]
+    pub fn as_ptr(&self) -> *const u8 {
+        self.base
+    }
+
+    /// Returns a mutable raw pointer to the beginning of the mapping
+    #[inline]
+    pub fn as_mut_ptr(&mut self) -> *mut u8 {
+        self.base
+    }
+
+    /// Returns a mutable reference to the byte at `offset`, if it is mapped
+    #[inline]
+    pub fn get_mut(&mut self, offset: usize) -> Option<&mut u8> {
+        if offset < self.len {
+            Some(unsafe { &mut *self.base.add(offset) })
+        } else {
+            None
+        }
+    }
+
+    /// Flushes the mapped range to the file
+    pub fn flush(&self) -> std::io::Result<()> {
+        if self.read_only {
+            return Ok(());
+        }
+        let res = unsafe {
+            libc::msync(self.base as *mut libc::c_void, self.len, libc::MS_SYNC)
+        };
+        if res != 0 {
+            Err(std::io::Error::last_os_error())
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[cfg(unix)]
+impl Drop for PoolMap {
+    fn drop(&mut self) {
+        unsafe { libc::munmap(self.base as *mut libc::c_void, self.reserved); }
+    }
+}
+
+#[cfg(unix)]
+fn page_size() -> usize {
+    unsafe { libc::sysconf(libc::_SC_PAGESIZE) as usize }
+}
+
+/// A mapping of a pool file which cannot be extended on this platform
+#[cfg(not(unix))]
+pub struct PoolMap {
+    mmap: memmap::MmapMut,
+}
+
+#[cfg(not(unix))]
+impl PoolMap {
+    /// Maps `file`. If `read_only` is true, the file is mapped privately, so
+    /// that the changes are not written back to it. No range is reserved.
+    pub fn map(file: &std::fs::File, read_only: bool, _reserve: usize) -> crate::result::Result<Self> {
+        let mmap = unsafe {
+            if read_only {
+                memmap::MmapOptions::new().map_copy(file)?
+            } else {
+                memmap::MmapOptions::new().map_mut(file)?
+            }
+        };
+        Ok(Self { mmap })
+    }
+
+    /// Extending the mapping is not supported on this platform
+    pub fn extend(&mut self, _file: &std::fs::File, _new_len: usize) -> crate::result::Result<()> {
+        Err(crate::Error::Unsupported(
+            "Extending memory mappings is not supported on this platform".to_string()))
+    }
+
+    /// Returns the length of the mapped range
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.mmap.len()
+    }
+
+    /// Returns a raw pointer to the beginning of the mapping
+    #[inline]
+    pub fn as_ptr(&self) -> *const u8 {
+        self.mmap.as_ptr()
+    }
+
+    /// Returns a mutable raw pointer to the beginning of the mapping
+    #[inline]
+    pub fn as_mut_ptr(&mut self) -> *mut u8 {
+        self.mmap.as_mut_ptr()
+    }
+
+    /// Returns a mutable reference to the byte at `offset`, if it is mapped
+    #[inline]
+    pub fn get_mut(&mut self, offset: usize) -> Option<&mut u8> {
+        self.mmap.get_mut(offset)
+    }
+
+    /// Flushes the mapped range to the file
+    pub fn flush(&self) -> std::io::Result<()> {
+        self.mmap.flush()
+    }
+}
+
 #[cfg(feature = "verbose")]
 pub fn debug_alloc<A: MemPool>(addr: u64, len: usize, pre: usize, post: usize) {
     crate::log!(A, Green, "", "PRE: {:<6}  ({:>6x}:{:<6x}) = {:<6} POST = {:<6}",
@@ -1,2 +1,655 @@
+//! PMDK-compatible pool header
+//!
+//! This module implements a subset of the on-media layout of [libpmemobj]
+//! pools (pool format version 6, PMDK 1.5 and later): the pool header, the
+//! pool descriptor, the lanes, and the heap headers. It does not implement
+//! the allocator of libpmemobj. It can be used in two ways:
+//!
+//!  * As a [`PoolLayout`] of a pool type declared with the [`pool!`] macro.
+//!    The image file then carries a PMDK-compatible header whose root object
+//!    is Corundum's allocator region, and the application code remains the
+//!    same:
+//!
+//!    ```
+//!    corundum::pool!(pmem, P, corundum::libpmem::PmdkHeaderLayout);
+//!    ```
+//!
+//!  * For inspecting pool files written by PMDK-based tools using
+//!    [`inspect()`].
+//!
+//! # Compatible Subset
+//!
+//! The following structures are written and verified as libpmemobj does:
+//!
+//!  * The 4 KiB pool header (`struct pool_hdr`) with the `PMEMOBJ` signature,
+//!    the 2K-checksum feature, architecture flags, and Fletcher64 checksum.
+//!    Shutdown state (SDS) and multi-part pool sets are not supported.
+//!  * The 2 KiB pool descriptor including the layout name, lanes and heap
+//!    offsets, root object offset and size, and the heap size.
+//!  * 1024 lanes with empty external redo, internal redo, and undo logs.
+//!  * The heap header and the first zone. The allocator region occupies all
+//!    chunks of the first zone as a single huge allocation with a compact
+//!    allocation header, which is also the root object of the pool. So, the
+//!    pool size is limited to a single zone (almost 16 GiB).
+//!
+//! Objects are allocated inside the region by the allocation algorithm of
+//! the pool type (e.g. [`BuddyAlg`]), not by the libpmemobj allocator. PMDK
+//! tools see them as the contents of the root object, and cannot allocate in
+//! the region. Runs of small objects written by PMDK are recognized by
+//! [`inspect()`], but Corundum cannot allocate memory in pools that it has
+//! not formatted.
+//!
+//! [libpmemobj]: https://pmem.io/pmdk/libpmemobj/
+//! [`PoolLayout`]: ../trait.PoolLayout.html
+//! [`pool!`]: ../../macro.pool.html
+//! [`BuddyAlg`]: ../struct.BuddyAlg.html
+//! [`inspect()`]: ./fn.inspect.html
 
-// To be implemented
\ No newline at end of file
+use super::PoolLayout;
+use crate::result::Result;
+use crate::Error;
+use std::fs::OpenOptions;
+use std::mem;
+
+/// Signature of libpmemobj pools
+pub const OBJ_HDR_SIG: &[u8; 8] = b"PMEMOBJ\0";
+
+/// Supported major version of the libpmemobj pool format
+pub const OBJ_FORMAT_MAJOR: u32 = 6;
+
+/// Maximum length of the layout name, including the null terminator
+pub const PMEMOBJ_MAX_LAYOUT: usize = 1024;
+
+/// Size of a heap chunk
+pub const CHUNKSIZE: usize = 256 * 1024;
+
+/// Maximum number of chunks in a zone
+pub const MAX_CHUNK: usize = u16::MAX as usize - 7;
+
+const POOL_HDR_CSUM_2K_OFF: usize = 2048;
+const POOL_HDR_CSUM_4K_OFF: usize = 4088;
+
+const POOL_FEAT_SINGLEHDR: u32 = 0x0001;
+const POOL_FEAT_CKSUM_2K: u32 = 0x0002;
+const POOL_FEAT_SDS: u32 = 0x0004;
+const POOL_FEAT_INCOMPAT_VALID: u32 = POOL_FEAT_SINGLEHDR | POOL_FEAT_CKSUM_2K | POOL_FEAT_SDS;
+
+const OBJ_DSC_P_SIZE: usize = 2048;
+const OBJ_LANES_OFFSET: usize = 8192;
+const OBJ_NLANES: usize = 1024;
+
+const LANE_TOTAL_SIZE: usize = 3072;
+const LANE_REDO_EXTERNAL_SIZE: usize = 640;
+const LANE_REDO_INTERNAL_SIZE: usize = 192;
+const LANE_UNDO_SIZE: usize = 2048;
+
+const HEAP_SIGNATURE: &[u8; 16] = b"MEMORY_HEAP_HDR\0";
+const HEAP_MAJOR: u64 = 1;
+const HEAP_MINOR: u64 = 0;
+const ZONE_HEADER_MAGIC: u32 = 0xC3F0A2D2;
+
+const CHUNK_TYPE_FOOTER: u16 = 1;
+const CHUNK_TYPE_FREE: u16 = 2;
+const CHUNK_TYPE_USED: u16 = 3;
+const CHUNK_TYPE_RUN: u16 = 4;
+const CHUNK_TYPE_RUN_DATA: u16 = 5;
+const CHUNK_FLAG_COMPACT_HEADER: u16 = 0x0001;
+
+const ALLOC_HDR_SIZE_SHIFT: u64 = 48;
+const ALLOC_HDR_FLAGS_MASK: u64 = (1 << ALLOC_HDR_SIZE_SHIFT) - 1;
+
+#[repr(C)]
+#[allow(dead_code)]
+#[derive(Clone, Copy, Debug, Default)]
+struct Features {
+    compat: u32,
+    incompat: u32,
+    ro_compat: u32,
+}
+
+#[repr(C)]
+#[allow(dead_code)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct ArchFlags {
+    alignment_desc: u64,
+    machine_class: u8,
+    data: u8,
+    reserved: [u8; 4],
+    machine: u16,
+}
+
+/// Pool header (`struct pool_hdr`)
+#[repr(C)]
+#[allow(dead_code)]
+struct PoolHdr {
+    signature: [u8; 8],
+    major: u32,
+    features: Features,
+    poolset_uuid: [u8; 16],
+    uuid: [u8; 16],
+    prev_part_uuid: [u8; 16],
+    next_part_uuid: [u8; 16],
+    prev_repl_uuid: [u8; 16],
+    next_repl_uuid: [u8; 16],
+    crtime: u64,
+    arch_flags: ArchFlags,
+    unused: [u8; 1904],
+    unused2: [u8; 1976],
+    sds: [u8; 64],
+    checksum: u64,
+}
+
+/// Persistent part of the pool descriptor (`struct pmemobjpool`)
+#[repr(C)]
+#[allow(dead_code)]
+struct ObjDescriptor {
+    layout: [u8; PMEMOBJ_MAX_LAYOUT],
+    lanes_offset: u64,
+    nlanes: u64,
+    heap_offset: u64,
+    unused3: u64,
+    unused: [u8; OBJ_DSC_P_SIZE - PMEMOBJ_MAX_LAYOUT - 40],
+    checksum: u64,
+
+    root_offset: u64,
+    run_id: u64,
+    root_size: u64,
+    conversion_flags: u64,
+    heap_size: u64,
+    heap_curr_allocated: u64,
+    pmem_reserved: [u8; 496],
+}
+
+/// Header of a unified log in a lane (`struct ulog`)
+#[repr(C)]
+#[allow(dead_code)]
+struct ULog {
+    checksum: u64,
+    next: u64,
+    capacity: u64,
+    gen_num: u64,
+    flags: u64,
+    unused: [u64; 3],
+}
+
+#[repr(C)]
+#[allow(dead_code)]
+struct HeapHeader {
+    signature: [u8; 16],
+    major: u64,
+    minor: u64,
+    unused: u64,
+    chunksize: u64,
+    chunks_per_zone: u64,
+    reserved: [u8; 960],
+    checksum: u64,
+}
+
+#[repr(C)]
+#[allow(dead_code)]
+struct ZoneHeader {
+    magic: u32,
+    size_idx: u32,
+    reserved: [u8; 56],
+}
+
+#[repr(C)]
+#[allow(dead_code)]
+#[derive(Clone, Copy)]
+struct ChunkHeader {
+    kind: u16,
+    flags: u16,
+    size_idx: u32,
+}
+
+#[repr(C)]
+#[allow(dead_code)]
+struct AllocHeaderCompact {
+    size: u64,
+    extra: u64,
+}
+
+const ZONE_META_SIZE: usize = mem::size_of::<ZoneHeader>()
+    + mem::size_of::<ChunkHeader>() * MAX_CHUNK;
+const ZONE_MAX_SIZE: usize = ZONE_META_SIZE + CHUNKSIZE * MAX_CHUNK;
+
+/// Computes the Fletcher64 checksum of `data` as libpmemobj does
+/// (`util_checksum_compute`). The 8 bytes at `csum_off` and all bytes from
+/// `skip_off` on are considered zero.
+pub fn checksum(data: &[u8], csum_off: usize, skip_off: usize) -> u64 {
+    assert_eq!(data.len() % 4, 0, "checksum length should be a multiple of 4");
+    let mut lo: u32 = 0;
+    let mut hi: u32 = 0;
+    for (i, w) in data.chunks_exact(4).enumerate() {
+        let off = i * 4;
+        if off < skip_off && !(csum_off..csum_off + 8).contains(&off) {
+            lo = lo.wrapping_add(u32::from_le_bytes([w[0], w[1], w[2], w[3]]));
+        }
+        hi = hi.wrapping_add(lo);
+    }
+    (hi as u64) << 32 | lo as u64
+}
+
+/// Returns the architecture flags of the running machine
+fn arch_flags() -> ArchFlags {
+    // Alignments of char, short, int, long, long long, size_t, off_t, float,
+    // double, long double, and void*, minus one, in 4-bit fields
+    let aligns = [
+        mem::align_of::<libc::c_char>(),
+        mem::align_of::<libc::c_short>(),
+        mem::align_of::<libc::c_int>(),
+        mem::align_of::<libc::c_long>(),
+        mem::align_of::<libc::c_longlong>(),
+        mem::align_of::<libc::size_t>(),
+        mem::align_of::<libc::off_t>(),
+        mem::align_of::<libc::c_float>(),
+        mem::align_of::<libc::c_double>(),
+        16,
+        mem::align_of::<*const u8>(),
+    ];
+    let mut alignment_desc = 0u64;
+    for (i, a) in aligns.iter().enumerate() {
+        alignment_desc |= ((*a as u64 - 1) & 0xf) << (4 * i);
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    let machine = 62;
+    #[cfg(target_arch = "aarch64")]
+    let machine = 183;
+    #[cfg(target_arch = "powerpc64")]
+    let machine = 21;
+    #[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64", target_arch = "powerpc64")))]
+    let machine = 0;
+
+    ArchFlags {
+        alignment_desc,
+        machine_class: if cfg!(target_pointer_width = "64") { 2 } else { 1 },
+        data: if cfg!(target_endian = "little") { 1 } else { 2 },
+        reserved: [0; 4],
+        machine,
+    }
+}
+
+#[inline]
+unsafe fn at<'a, T>(base: *mut u8, off: usize) -> &'a mut T {
+    &mut *(base.add(off) as *mut T)
+}
+
+#[inline]
+unsafe fn get<'a, T>(base: *const u8, off: usize) -> &'a T {
+    &*(base.add(off) as *const T)
+}
+
+#[inline]
+unsafe fn bytes<'a, T>(x: &T) -> &'a [u8] {
+    std::slice::from_raw_parts(x as *const T as *const u8, mem::size_of::<T>())
+}
+
+fn heap_offset() -> usize {
+    let off = OBJ_LANES_OFFSET + OBJ_NLANES * LANE_TOTAL_SIZE;
+    (off + 4095) & !4095
+}
+
+fn new_uuid() -> [u8; 16] {
+    let mut uuid: [u8; 16] = rand::random();
+    uuid[6] = (uuid[6] & 0x0f) | 0x40;
+    uuid[8] = (uuid[8] & 0x3f) | 0x80;
+    uuid
+}
+
+/// Verifies the pool header and the pool descriptor, and returns them
+unsafe fn verify<'a>(base: *const u8, len: usize) -> Result<(&'a PoolHdr, &'a ObjDescriptor)> {
+    if len < mem::size_of::<PoolHdr>() + mem::size_of::<ObjDescriptor>() {
+        return Err(Error::InvalidPool("Invalid pool file".to_string()));
+    }
+    let hdr = get::<PoolHdr>(base, 0);
+    if &hdr.signature != OBJ_HDR_SIG {
+        return Err(Error::InvalidPool("Not a libpmemobj pool (invalid signature)".to_string()));
+    }
+    if hdr.major != OBJ_FORMAT_MAJOR {
+        return Err(Error::InvalidPool(format!("Unsupported libpmemobj pool format version {}", hdr.major)));
+    }
+    if hdr.features.incompat & !POOL_FEAT_INCOMPAT_VALID != 0 {
+        return Err(Error::InvalidPool(format!("Unsupported pool features ({:#x})", hdr.features.incompat)));
+    }
+    let skip = if hdr.features.incompat & POOL_FEAT_CKSUM_2K != 0 {
+        POOL_HDR_CSUM_2K_OFF
+    } else {
+        POOL_HDR_CSUM_4K_OFF
+    };
+    if checksum(bytes(hdr), POOL_HDR_CSUM_4K_OFF, skip) != hdr.checksum {
+        return Err(Error::InvalidPool("Invalid pool header checksum".to_string()));
+    }
+    let arch = arch_flags();
+    if (hdr.arch_flags.machine_class, hdr.arch_flags.data, hdr.arch_flags.machine)
+        != (arch.machine_class, arch.data, arch.machine) {
+        return Err(Error::InvalidPool("The pool is created on an incompatible architecture".to_string()));
+    }
+
+    let dsc = get::<ObjDescriptor>(base, mem::size_of::<PoolHdr>());
+    let dsc_bytes = &bytes(dsc)[..OBJ_DSC_P_SIZE];
+    if checksum(dsc_bytes, OBJ_DSC_P_SIZE - 8, OBJ_DSC_P_SIZE) != dsc.checksum {
+        return Err(Error::InvalidPool("Invalid pool descriptor checksum".to_string()));
+    }
+    if dsc.heap_offset as usize + mem::size_of::<HeapHeader>() > len {
+        return Err(Error::InvalidPool("Invalid heap offset".to_string()));
+    }
+    Ok((hdr, dsc))
+}
+
+/// A pool layout with a PMDK-compatible header
+///
+/// The allocator region is the root object of a libpmemobj pool, and it
+/// spans all chunks of the first zone of the heap. Only the headers follow
+/// libpmemobj; the objects are allocated by Corundum. See the [module-level
+/// documentation](./index.html) for the details.
+pub struct PmdkHeaderLayout;
+
+impl PoolLayout for PmdkHeaderLayout {
+    unsafe fn format(base: *mut u8, len: usize, name: &str) -> Result<(usize, usize)> {
+        if name.len() >= PMEMOBJ_MAX_LAYOUT {
+            return Err(Error::Unsupported("Layout name is too long".to_string()));
+        }
+        let heap_off = heap_offset();
+        let chunks_off = heap_off + mem::size_of::<HeapHeader>() + ZONE_META_SIZE;
+        if len < chunks_off + CHUNKSIZE {
+            return Err(Error::InvalidSize(format!("The pool should be at least {} bytes",
+                chunks_off + CHUNKSIZE)));
+        }
+        if len > heap_off + mem::size_of::<HeapHeader>() + ZONE_MAX_SIZE {
+            return Err(Error::InvalidSize(format!("The pool should be at most {} bytes",
+                heap_off + mem::size_of::<HeapHeader>() + ZONE_MAX_SIZE)));
+        }
+        let nchunks = (len - chunks_off) / CHUNKSIZE;
+
+        // Meta-data is cleared first, so that an interrupted format is not
+        // recognized as a valid pool
+        std::ptr::write_bytes(base, 0, chunks_off);
+
+        // Lanes with empty logs
+        for i in 0..OBJ_NLANES {
+            let lane = OBJ_LANES_OFFSET + i * LANE_TOTAL_SIZE;
+            let mut off = lane;
+            for cap in &[LANE_REDO_EXTERNAL_SIZE, LANE_REDO_INTERNAL_SIZE, LANE_UNDO_SIZE] {
+                at::<ULog>(base, off).capacity = *cap as u64;
+                off += mem::size_of::<ULog>() + cap;
+            }
+        }
+
+        // Heap header and the first zone
+        let heap = at::<HeapHeader>(base, heap_off);
+        heap.signature = *HEAP_SIGNATURE;
+        heap.major = HEAP_MAJOR;
+        heap.minor = HEAP_MINOR;
+        heap.chunksize = CHUNKSIZE as u64;
+        heap.chunks_per_zone = MAX_CHUNK as u64;
+        heap.checksum = checksum(bytes(heap), mem::size_of::<HeapHeader>() - 8,
+            mem::size_of::<HeapHeader>());
+
+        let zone_off = heap_off + mem::size_of::<HeapHeader>();
+        let zone = at::<ZoneHeader>(base, zone_off);
+        zone.magic = ZONE_HEADER_MAGIC;
+        zone.size_idx = nchunks as u32;
+        let chunk_hdr = |i: usize| at::<ChunkHeader>(base,
+            zone_off + mem::size_of::<ZoneHeader>() + i * mem::size_of::<ChunkHeader>());
+        *chunk_hdr(0) = ChunkHeader {
+            kind: CHUNK_TYPE_USED,
+            flags: CHUNK_FLAG_COMPACT_HEADER,
+            size_idx: nchunks as u32,
+        };
+        if nchunks > 1 {
+            *chunk_hdr(nchunks - 1) = ChunkHeader {
+                kind: CHUNK_TYPE_FOOTER,
+                flags: 0,
+                size_idx: nchunks as u32,
+            };
+        }
+
+        // The whole zone is a single huge allocation used as the root object
+        let alloc = at::<AllocHeaderCompact>(base, chunks_off);
+        alloc.size = (nchunks * CHUNKSIZE) as u64;
+        alloc.extra = 0;
+        let root_off = chunks_off + mem::size_of::<AllocHeaderCompact>();
+        let root_size = nchunks * CHUNKSIZE - mem::size_of::<AllocHeaderCompact>();
+
+        let dsc = at::<ObjDescriptor>(base, mem::size_of::<PoolHdr>());
+        dsc.layout[..name.len()].copy_from_slice(name.as_bytes());
+        dsc.lanes_offset = OBJ_LANES_OFFSET as u64;
+        dsc.nlanes = OBJ_NLANES as u64;
+        dsc.heap_offset = heap_off as u64;
+        dsc.checksum = checksum(&bytes(dsc)[..OBJ_DSC_P_SIZE], OBJ_DSC_P_SIZE - 8,
+            OBJ_DSC_P_SIZE);
+        dsc.root_offset = root_off as u64;
+        dsc.root_size = root_size as u64;
+        dsc.heap_size = (len - heap_off) as u64;
+        dsc.heap_curr_allocated = (nchunks * CHUNKSIZE) as u64;
+
+        // The pool header is written last
+        let hdr = at::<PoolHdr>(base, 0);
+        let uuid = new_uuid();
+        hdr.major = OBJ_FORMAT_MAJOR;
+        hdr.features = Features {
+            compat: 0,
+            incompat: POOL_FEAT_CKSUM_2K,
+            ro_compat: 0,
+        };
+        hdr.poolset_uuid = new_uuid();
+        hdr.uuid = uuid;
+        hdr.prev_part_uuid = uuid;
+        hdr.next_part_uuid = uuid;
+        hdr.prev_repl_uuid = uuid;
+        hdr.next_repl_uuid = uuid;
+        hdr.crtime = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        hdr.arch_flags = arch_flags();
+        hdr.signature = *OBJ_HDR_SIG;
+        hdr.checksum = checksum(bytes(hdr), POOL_HDR_CSUM_4K_OFF, POOL_HDR_CSUM_2K_OFF);
+
+        Ok((root_off, root_size))
+    }
+
+    unsafe fn open(base: *const u8, len: usize) -> Result<(usize, usize)> {
+        let (_, dsc) = verify(base, len)?;
+        let heap_off = dsc.heap_offset as usize;
+        let chunks_off = heap_off + mem::size_of::<HeapHeader>() + ZONE_META_SIZE;
+        let zone = get::<ZoneHeader>(base, heap_off + mem::size_of::<HeapHeader>());
+        let first = get::<ChunkHeader>(base,
+            heap_off + mem::size_of::<HeapHeader>() + mem::size_of::<ZoneHeader>());
+        let root_off = dsc.root_offset as usize;
+        let root_size = dsc.root_size as usize;
+        if zone.magic != ZONE_HEADER_MAGIC
+            || first.kind != CHUNK_TYPE_USED
+            || first.flags & CHUNK_FLAG_COMPACT_HEADER == 0
+            || first.size_idx != zone.size_idx
+            || root_off != chunks_off + mem::size_of::<AllocHeaderCompact>()
+            || root_off + root_size > len {
+            return Err(Error::InvalidPool("The root object of the pool is not a Corundum heap".to_string()));
+        }
+        Ok((root_off, root_size))
+    }
+
+    unsafe fn grow(_base: *mut u8, _new_len: usize) -> Result<()> {
+        Err(Error::Unsupported("Growing libpmemobj pools is not supported".to_string()))
+    }
+}
+
+/// Chunk usage of a heap zone
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ZoneInfo {
+    /// Number of chunks in the zone
+    pub chunks: usize,
+
+    /// Number of chunks used by huge allocations
+    pub used: usize,
+
+    /// Number of free chunks
+    pub free: usize,
+
+    /// Number of chunks containing runs of small allocations
+    pub run: usize,
+}
+
+/// Information of a libpmemobj pool
+#[derive(Clone, Debug)]
+pub struct PoolInfo {
+    /// Layout name given at the pool creation
+    pub layout: String,
+
+    /// Major version of the pool format
+    pub major: u32,
+
+    /// Pool UUID
+    pub uuid: [u8; 16],
+
+    /// Creation time in seconds since the Unix epoch
+    pub crtime: u64,
+
+    /// Offset of the heap
+    pub heap_offset: u64,
+
+    /// Size of the heap
+    pub heap_size: u64,
+
+    /// Offset of the root object, or 0 if there is no root object
+    pub root_offset: u64,
+
+    /// Size of the root object
+    pub root_size: u64,
+
+    /// Chunk usage of the initialized zones
+    pub zones: Vec<ZoneInfo>,
+}
+
+/// Reads the header and the heap layout of a libpmemobj pool file
+///
+/// It works with pools created by PMDK as well as pools formatted by
+/// [`PmdkHeaderLayout`]. The file is mapped read-only and is not modified.
+///
+/// # Examples
+///
+/// ```
+/// corundum::pool!(pmem, P, corundum::libpmem::PmdkHeaderLayout);
+/// use pmem::*;
+/// use corundum::open_flags::*;
+///
+/// let _ = P::open::<i32>("inspect.pool", O_CF).unwrap();
+///
+/// let info = corundum::libpmem::inspect("inspect.pool").unwrap();
+/// assert_eq!(info.layout, "pmem");
+/// ```
+///
+/// [`PmdkHeaderLayout`]: ./struct.PmdkHeaderLayout.html
+pub fn inspect(path: &str) -> Result<PoolInfo> {
+    let file = OpenOptions::new()
+        .read(true)
+        .open(path)
+        ?;
+    let mmap = unsafe { memmap::MmapOptions::new().map(&file) }
+        ?;
+    let base = mmap.as_ptr();
+    let len = mmap.len();
+
+    unsafe {
+        let (hdr, dsc) = verify(base, len)?;
+        let heap_off = dsc.heap_offset as usize;
+        let heap = get::<HeapHeader>(base, heap_off);
+        if &heap.signature != HEAP_SIGNATURE
+            || checksum(bytes(heap), mem::size_of::<HeapHeader>() - 8,
+                mem::size_of::<HeapHeader>()) != heap.checksum {
+            return Err(Error::InvalidPool("Invalid heap header".to_string()));
+        }
+
+        // Zones are initialized lazily; the first zone without the magic
+        // number ends the walk
+        let heap_end = (heap_off + dsc.heap_size as usize).min(len);
+        let mut zones = vec![];
+        let mut zone_off = heap_off + mem::size_of::<HeapHeader>();
+        while zone_off + ZONE_META_SIZE <= heap_end {
+            let zone = get::<ZoneHeader>(base, zone_off);
+            if zone.magic != ZONE_HEADER_MAGIC {
+                break;
+            }
+            let chunks = (zone.size_idx as usize).min(MAX_CHUNK);
+            let mut info = ZoneInfo { chunks, ..Default::default() };
+            let mut i = 0;
+            while i < chunks {
+                let c = *get::<ChunkHeader>(base,
+                    zone_off + mem::size_of::<ZoneHeader>() + i * mem::size_of::<ChunkHeader>());
+                let n = (c.size_idx as usize).max(1).min(chunks - i);
+                match c.kind {
+                    CHUNK_TYPE_USED => info.used += n,
+                    CHUNK_TYPE_FREE => info.free += n,
+                    CHUNK_TYPE_RUN | CHUNK_TYPE_RUN_DATA => info.run += n,
+                    _ => return Err(Error::InvalidPool(format!("Invalid chunk header at zone #{}, chunk #{}",
+                        zones.len(), i))),
+                }
+                i += n;
+            }
+            zones.push(info);
+            zone_off += ZONE_MAX_SIZE;
+        }
+
+        let root_offset = dsc.root_offset;
+        if root_offset != 0 {
+            let alloc = get::<AllocHeaderCompact>(base,
+                root_offset as usize - mem::size_of::<AllocHeaderCompact>());
+            if (alloc.size & ALLOC_HDR_FLAGS_MASK) < dsc.root_size {
+                return Err(Error::InvalidPool("Invalid root object header".to_string()));
+            }
+        }
+
+        let layout = dsc.layout.iter().position(|c| *c == 0).unwrap_or(PMEMOBJ_MAX_LAYOUT);
+        Ok(PoolInfo {
+            layout: String::from_utf8_lossy(&dsc.layout[..layout]).to_string(),
+            major: hdr.major,
+            uuid: hdr.uuid,
+            crtime: hdr.crtime,
+            heap_offset: dsc.heap_offset,
+            heap_size: dsc.heap_size,
+            root_offset,
+            root_size: dsc.root_size,
+            zones,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::open_flags::*;
+
+    crate::pool!(pmemobj, P, crate::libpmem::PmdkHeaderLayout);
+    use pmemobj::*;
+
+    #[test]
+    fn libpmem_layout_test() {
+        assert_eq!(mem::size_of::<PoolHdr>(), 4096);
+        assert_eq!(mem::size_of::<ObjDescriptor>(), OBJ_DSC_P_SIZE + 544);
+        assert_eq!(mem::size_of::<HeapHeader>(), 1024);
+        assert_eq!(mem::size_of::<ULog>() * 3 + LANE_REDO_EXTERNAL_SIZE
+            + LANE_REDO_INTERNAL_SIZE + LANE_UNDO_SIZE, LANE_TOTAL_SIZE);
+
+        {
+            let root = P::open::<PRefCell<PVec<PString>>>("libpmem.pool", O_CF).unwrap();
+            P::transaction(|j| {
+                let mut v = root.borrow_mut(j);
+                for i in 0..10 {
+                    v.push(format!("item {}", i).to_pstring(j), j);
+                }
+            }).unwrap();
+        }
+
+        let info = inspect("libpmem.pool").unwrap();
+        assert_eq!(info.layout, "pmemobj");
+        assert_eq!(info.major, OBJ_FORMAT_MAJOR);
+        assert_eq!(info.zones.len(), 1);
+        assert_eq!(info.zones[0].used, info.zones[0].chunks);
+        assert_eq!(info.root_size as usize, info.zones[0].chunks * CHUNKSIZE - 16);
+
+        let root = P::open::<PRefCell<PVec<PString>>>("libpmem.pool", 0).unwrap();
+        assert_eq!(P::size(), info.root_size as usize);
+        assert_eq!(root.borrow()[9].as_str(), "item 9");
+    }
+}
@@ -1,2 +1,55 @@
 pub mod buddy;
+pub mod slab;
 pub mod libpmem;
+
+use crate::result::Result;
+
+/// On-media layout of a pool image file
+///
+/// A layout determines where the allocator region lives in the image file.
+/// The pool header and the allocator meta-data are always placed at the
+/// beginning of the region, and all offsets are relative to it. The
+/// [`pool!`] macro uses [`NativeLayout`] unless another layout is specified.
+///
+/// [`pool!`]: ../macro.pool.html
+pub trait PoolLayout {
+    /// Formats the layout of the image file mapped at `base` with `len`
+    /// bytes, and returns the offset and the size of the allocator region
+    ///
+    /// # Safety
+    ///
+    /// `base` should point to a writable mapping of at least `len` bytes.
+    unsafe fn format(base: *mut u8, len: usize, name: &str) -> Result<(usize, usize)>;
+
+    /// Verifies the layout of the image file mapped at `base` with `len`
+    /// bytes, and returns the offset and the size of the allocator region
+    ///
+    /// # Safety
+    ///
+    /// `base` should point to a readable mapping of at least `len` bytes.
+    unsafe fn open(base: *const u8, len: usize) -> Result<(usize, usize)>;
+
+    /// Updates the layout meta-data before the allocator region grows to
+    /// `new_len` bytes. It fails if the layout does not allow growing.
+    ///
+    /// # Safety
+    ///
+    /// `base` should point to a writable mapping covering the region.
+    unsafe fn grow(_base: *mut u8, _new_len: usize) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Corundum's own layout in which the allocator region spans the whole image
+/// file
+pub struct NativeLayout;
+
+impl PoolLayout for NativeLayout {
+    unsafe fn format(_base: *mut u8, len: usize, _name: &str) -> Result<(usize, usize)> {
+        Ok((0, len))
+    }
+
+    unsafe fn open(_base: *const u8, len: usize) -> Result<(usize, usize)> {
+        Ok((0, len))
+    }
+}
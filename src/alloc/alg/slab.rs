@@ -0,0 +1,577 @@
+use crate::alloc::*;
+use crate::utils::*;
+use std::marker::PhantomData;
+use std::mem;
+
+/// Size of a slab page in bytes
+pub const SLAB_SIZE: usize = 16 * 1024;
+
+/// Object sizes served by the slab allocator
+///
+/// The power-of-two sizes are missing as buddy blocks fit them perfectly.
+pub const SLAB_CLASSES: [usize; 16] = [
+    24, 40, 48, 56, 80, 96, 112, 160, 192, 224, 320, 384, 448, 640, 768, 896,
+];
+
+/// The highest alignment guaranteed for slab objects
+const SLAB_ALIGN: usize = 64;
+
+const NONE: u64 = u64::MAX;
+
+#[repr(C)]
+/// Slab page header
+///
+/// It is placed at the beginning of each slab page. A slab page is a buddy
+/// block of size [`SLAB_SIZE`] which is aligned to its size, so the header
+/// of an object is found by masking its offset.
+///
+/// [`SLAB_SIZE`]: ./constant.SLAB_SIZE.html
+struct Slab {
+    /// Next slab in the list of partially-used slabs of the same class
+    next: u64,
+
+    /// Previous slab in the list of partially-used slabs of the same class
+    prev: u64,
+
+    /// Offset of the first free object in this slab
+    free: u64,
+
+    /// Page offset of the first object which has never been allocated
+    bump: u64,
+
+    /// Number of allocated objects
+    used: u64,
+
+    /// Size class index
+    class: u64,
+
+    _pad: [u64; 2],
+}
+
+const HEADER: usize = mem::size_of::<Slab>();
+
+/// Returns the index of the size class serving objects of size `len`
+///
+/// Objects go to the buddy allocator if the size class saves no space over
+/// the buddy block, or if the object may require an alignment higher than
+/// what slab pages offer. The alignment requirement of an object is inferred
+/// from its size, as the size is always a multiple of it.
+pub fn size_class(len: usize) -> Option<usize> {
+    if len == 0 {
+        None
+    } else {
+        let align = 1 << len.trailing_zeros();
+        let block = 1 << get_idx(len);
+        if align > SLAB_ALIGN {
+            None
+        } else {
+            SLAB_CLASSES
+                .iter()
+                .position(|&c| c >= len && c < block && c % align == 0)
+        }
+    }
+}
+
+#[repr(C)]
+/// Slab Allocation Algorithm
+///
+/// It serves small objects from slab pages of [`SLAB_SIZE`] bytes each of
+/// which is divided into objects of a single size class (see
+/// [`SLAB_CLASSES`]). The slab pages and the rest of the objects are
+/// allocated from a [`BuddyAlg`]. So, a 72-byte object takes 80 bytes instead
+/// of a 128-byte buddy block.
+///
+/// The slabs with at least one free object are kept in a doubly-linked list
+/// per size class. Free objects of a slab form a singly-linked list through
+/// their first 8 bytes, and the objects after `bump` are free without being
+/// in the list. A slab is returned to the buddy allocator once it becomes
+/// empty.
+///
+/// All changes to the slab meta-data go through the auxiliary buffer of the
+/// underlying `BuddyAlg`. So, they follow the same crash-consistency protocol
+/// (`log64`, `drop_on_failure`, and `perform`), and are materialized along
+/// with the changes to the free lists in a single failure-atomic section.
+///
+/// To use it in a pool, pass it to [`pool!`] as the allocation algorithm:
+///
+/// ```
+/// corundum::pool!(slab, P, corundum::NativeLayout, SlabAlg);
+/// ```
+///
+/// [`SLAB_SIZE`]: ./constant.SLAB_SIZE.html
+/// [`SLAB_CLASSES`]: ./constant.SLAB_CLASSES.html
+/// [`BuddyAlg`]: ./struct.BuddyAlg.html
+/// [`pool!`]: ../macro.pool.html
+pub struct SlabAlg<A: MemPool> {
+    /// The underlying buddy allocator
+    buddy: BuddyAlg<A>,
+
+    /// Lists of partially-used slabs
+    partial: [u64; SLAB_CLASSES.len()],
+
+    // Marker
+    phantom: PhantomData<A>,
+}
+
+impl<A: MemPool> SlabAlg<A> {
+    /// Pool Initialization with a given device size
+    ///
+    /// The beginning of the zone is aligned to [`SLAB_SIZE`] so that all slab
+    /// pages are aligned to their size.
+    ///
+    /// [`SLAB_SIZE`]: ./constant.SLAB_SIZE.html
+    pub fn init(&mut self, base: u64, size: usize) {
+        let pad = (SLAB_SIZE as u64 - base % SLAB_SIZE as u64) % SLAB_SIZE as u64;
+        self.buddy.init(base + pad, size - pad as usize);
+        self.partial = [NONE; SLAB_CLASSES.len()];
+    }
+
+    #[inline]
+    fn slab<'a>(off: u64) -> &'a mut Slab {
+        unsafe { read_addr(A::start() + off) }
+    }
+
+    #[inline]
+    fn word<'a>(off: u64) -> &'a mut u64 {
+        unsafe { read_addr(A::start() + off) }
+    }
+
+    #[inline]
+    fn get_off(b: &u64) -> u64 {
+        let off = b as *const _ as u64;
+        off - A::start()
+    }
+
+    #[inline]
+    fn page(off: u64) -> u64 {
+        off & !(SLAB_SIZE as u64 - 1)
+    }
+
+    #[inline]
+    fn is_full(s: &Slab) -> bool {
+        s.free == NONE && s.bump as usize + SLAB_CLASSES[s.class as usize] > SLAB_SIZE
+    }
+
+    #[inline]
+    /// Adds a new low-level 64-bit log entry
+    pub unsafe fn log(&mut self, off: u64, data: u64) {
+        self.buddy.log(off, data);
+    }
+
+    #[inline]
+    /// Adds a new low-level `DropOnFailure` log entry
+    pub unsafe fn drop_on_failure(&mut self, off: u64, len: usize) {
+        self.buddy.drop_on_failure(off, len);
+    }
+
+    #[inline(always)]
+    /// Begins a failure-atomic section
+    pub unsafe fn prepare(&mut self) {
+        self.buddy.prepare();
+    }
+
+    #[inline]
+    /// Materializes the changes in the auxiliary list and clears the drop log
+    /// records
+    pub unsafe fn perform(&mut self) {
+        self.buddy.perform();
+    }
+
+    #[inline]
+    /// Discards the changes in the auxiliary buffer
+    pub fn discard(&mut self) {
+        self.buddy.discard();
+    }
+
+    /// Removes slab `s` from the list of partially-used slabs
+    unsafe fn unlink(&mut self, s: u64) {
+        let slab = Self::slab(s);
+        if slab.prev == NONE {
+            let head = &self.partial[slab.class as usize];
+            self.buddy.aux_push(Self::get_off(head), slab.next);
+        } else {
+            let prev = Self::slab(slab.prev);
+            self.buddy.aux_push(Self::get_off(&prev.next), slab.next);
+        }
+        if slab.next != NONE {
+            let next = Self::slab(slab.next);
+            self.buddy.aux_push(Self::get_off(&next.prev), slab.prev);
+        }
+        self.buddy.aux_push(Self::get_off(&slab.next), NONE);
+        self.buddy.aux_push(Self::get_off(&slab.prev), NONE);
+    }
+
+    /// Adds slab `s` to the head of the list of partially-used slabs
+    unsafe fn link(&mut self, s: u64) {
+        let slab = Self::slab(s);
+        let head = self.partial[slab.class as usize];
+        if head != NONE {
+            self.buddy.aux_push(Self::get_off(&Self::slab(head).prev), s);
+        }
+        self.buddy.aux_push(Self::get_off(&slab.next), head);
+        self.buddy.aux_push(Self::get_off(&slab.prev), NONE);
+        self.buddy.aux_push(Self::get_off(&self.partial[slab.class as usize]), s);
+    }
+
+    /// Generates required changes to the metadata for allocating an object of
+    /// size class `class`
+    unsafe fn alloc_obj(&mut self, class: usize) -> Option<u64> {
+        let len = SLAB_CLASSES[class] as u64;
+        let s = self.partial[class];
+        if s == NONE {
+            // Fresh slab pages are initialized through the auxiliary buffer
+            // as their first bytes belong to the buddy free lists until the
+            // changes are performed
+            let s = self.buddy.alloc_block(SLAB_SIZE)?;
+            debug_assert_eq!(Self::page(s), s, "slab @{} is not aligned", s);
+            let slab = Self::slab(s);
+            self.buddy.aux_push(Self::get_off(&slab.next), NONE);
+            self.buddy.aux_push(Self::get_off(&slab.prev), NONE);
+            self.buddy.aux_push(Self::get_off(&slab.free), NONE);
+            self.buddy.aux_push(Self::get_off(&slab.bump), HEADER as u64 + len);
+            self.buddy.aux_push(Self::get_off(&slab.used), 1);
+            self.buddy.aux_push(Self::get_off(&slab.class), class as u64);
+            self.buddy.aux_push(Self::get_off(&self.partial[class]), s);
+            Some(s + HEADER as u64)
+        } else {
+            self.buddy.keep_available();
+            let slab = Self::slab(s);
+            let (off, free, bump) = if slab.free != NONE {
+                (slab.free, *Self::word(slab.free), slab.bump)
+            } else {
+                (s + slab.bump, NONE, slab.bump + len)
+            };
+            self.buddy.aux_push(Self::get_off(&slab.free), free);
+            self.buddy.aux_push(Self::get_off(&slab.bump), bump);
+            self.buddy.aux_push(Self::get_off(&slab.used), slab.used + 1);
+            if free == NONE && bump + len > SLAB_SIZE as u64 {
+                self.unlink(s);
+            }
+            Some(off)
+        }
+    }
+
+    /// Generates required changes to the metadata for reclaiming the object
+    /// at offset `off`
+    unsafe fn free_obj(&mut self, off: u64) {
+        let s = Self::page(off);
+        let slab = Self::slab(s);
+        debug_assert_ne!(slab.used, 0, "Double free for @{}", off);
+        if slab.used == 1 {
+            // An empty slab is not full, as it has at least two objects
+            self.unlink(s);
+            self.buddy.free_block(s, SLAB_SIZE);
+        } else {
+            self.buddy.keep_available();
+            if Self::is_full(slab) {
+                self.link(s);
+            }
+            self.buddy.aux_push(off, slab.free);
+            self.buddy.aux_push(Self::get_off(&slab.free), off);
+            self.buddy.aux_push(Self::get_off(&slab.used), slab.used - 1);
+        }
+    }
+
+    #[inline]
+    /// Generates required changes to the metadata for allocating a new memory
+    /// block with the size `len`, and materialize them according to the
+    /// `perform` argument. Small objects are allocated from slabs, and the
+    /// rest from the underlying buddy allocator. If successful, it returns the
+    /// offset of the available free block. Otherwise, `u64::MAX` is returned.
+    pub unsafe fn alloc_impl(&mut self, len: usize, perform: bool) -> u64 {
+        if let Some(class) = size_class(len) {
+            self.buddy.lock();
+            if let Some(off) = self.alloc_obj(class) {
+                self.buddy.sync_aux();
+                if perform {
+                    self.perform();
+                }
+                off
+            } else {
+                self.discard();
+                u64::MAX
+            }
+        } else {
+            self.buddy.alloc_impl(len, perform)
+        }
+    }
+
+    #[inline]
+    /// Generates required changes to the metadata for reclaiming the memory
+    /// block at offset `off` with the size of `len`, and materialize them
+    /// according to the `perform` argument.
+    pub unsafe fn dealloc_impl(&mut self, off: u64, len: usize, perform: bool) {
+        if size_class(len).is_some() {
+            self.buddy.lock();
+            self.free_obj(off);
+            self.buddy.sync_aux();
+            if perform {
+                self.perform();
+            }
+        } else {
+            self.buddy.dealloc_impl(off, len, perform);
+        }
+    }
+
+    /// Adds a new range of memory to the underlying buddy allocator (see
+    /// [`BuddyAlg::extend`](./struct.BuddyAlg.html#method.extend))
+    pub unsafe fn extend(&mut self, start: u64, end: u64, size_off: u64) {
+        self.buddy.extend(start, end, size_off);
+    }
+
+    #[inline]
+    /// Determines if the given address range is allocated
+    pub fn is_allocated(&mut self, off: u64, len: usize) -> bool {
+        if size_class(len).is_some() {
+            let s = Self::page(off);
+            if !self.buddy.is_allocated(s, SLAB_SIZE) {
+                return false;
+            }
+            let slab = Self::slab(s);
+            if off < s + HEADER as u64 || off >= s + slab.bump {
+                return false;
+            }
+            let mut curr = slab.free;
+            while curr != NONE {
+                if curr == off {
+                    return false;
+                }
+                curr = *Self::word(curr);
+            }
+            true
+        } else {
+            self.buddy.is_allocated(off, len)
+        }
+    }
+
+    #[inline]
+    /// Starts the recovery procedure (see
+    /// [`BuddyAlg::recover`](./struct.BuddyAlg.html#method.recover)). The
+    /// allocations in the `DropOnFailure` logs are reclaimed from slabs if
+    /// they belong to a size class.
+    pub fn recover(&mut self) {
+        let self_mut = self as *mut Self;
+        self.buddy.recover_with(|off, len| unsafe {
+            (*self_mut).dealloc_impl(off, len, false);
+        });
+    }
+
+    pub fn recovery_info(&self, info_level: u32) -> String {
+        self.buddy.recovery_info(info_level)
+    }
+
+    #[inline]
+    /// Returns the pool size
+    pub fn size(&self) -> usize {
+        self.buddy.size()
+    }
+
+    #[inline]
+    /// Returns the total available space in the pool. The free objects in
+    /// the slabs are not included.
+    pub fn available(&self) -> usize {
+        self.buddy.available()
+    }
+
+    #[inline]
+    /// Returns the total number of bytes used from the pool including the
+    /// slab pages
+    pub fn used(&self) -> usize {
+        self.buddy.used()
+    }
+
+    #[cfg(feature = "stat_footprint")]
+    /// Returns the total number of bytes written to the pool. It may exceed the
+    /// pool size as it does not subtract the reclaimed space after being used.
+    pub fn stat_footprint(&self) -> usize {
+        self.buddy.stat_footprint()
+    }
+
+    pub fn check(&self, f: &str) {
+        self.buddy.check(f);
+        for class in 0..SLAB_CLASSES.len() {
+            let mut curr = self.partial[class];
+            while curr != NONE {
+                let slab = Self::slab(curr);
+                assert_eq!(slab.class, class as u64, "Invalid slab class in checking {}", f);
+                assert_ne!(slab.next, curr, "Cyclic slab link in checking {}", f);
+                curr = slab.next;
+            }
+        }
+    }
+
+    pub fn verify(&mut self) -> bool {
+        if !self.buddy.verify() {
+            return false;
+        }
+        if std::env::var("VERIFY").is_err() { return true; }
+        for class in 0..SLAB_CLASSES.len() {
+            let mut curr = self.partial[class];
+            let mut prev = NONE;
+            while curr != NONE {
+                if !A::contains(curr + A::start()) {
+                    eprintln!("Verification Failed: Invalid slab address 0x{:x} (class={})", curr, class);
+                    return false;
+                }
+                let slab = Self::slab(curr);
+                if slab.prev != prev || slab.class != class as u64 || Self::is_full(slab) {
+                    eprintln!("Verification Failed: Invalid slab @0x{:x} (class={})", curr, class);
+                    return false;
+                }
+                prev = curr;
+                curr = slab.next;
+            }
+        }
+        true
+    }
+
+    /// Returns the range of memory which is managed by a zone initialized
+    /// with `init(base, size)`
+    pub fn span(base: u64, size: usize) -> (u64, usize) {
+        let pad = (SLAB_SIZE as u64 - base % SLAB_SIZE as u64) % SLAB_SIZE as u64;
+        BuddyAlg::<A>::span(base + pad, size - pad as usize)
+    }
+
+    #[inline]
+    /// Returns the range taken from the allocator for a block of size `len`
+    /// at offset `off`. Slab objects take their entire slab page, as the
+    /// allocated objects of a full slab are not distinguishable from the free
+    /// ones.
+    pub fn footprint(&self, off: u64, len: usize) -> (u64, usize) {
+        if size_class(len).is_some() {
+            (Self::page(off), SLAB_SIZE)
+        } else {
+            self.buddy.footprint(off, len)
+        }
+    }
+
+    /// Breaks an allocated range into the largest aligned blocks that it
+    /// contains (see [`BuddyAlg::split`]). The blocks as large as a slab page
+    /// are left out, as a slab page is not distinguishable from an object.
+    ///
+    /// [`BuddyAlg::split`]: ./struct.BuddyAlg.html#method.split
+    pub fn split(&self, off: u64, len: usize) -> Vec<(u64, usize)> {
+        let mut res = self.buddy.split(off, len);
+        res.retain(|(_, len)| *len < SLAB_SIZE);
+        res
+    }
+
+    /// Returns the offsets and the sizes of the free blocks, including the
+    /// free objects of the partially-used slabs
+    pub fn free_blocks(&self) -> Vec<(u64, usize)> {
+        let mut res = self.buddy.free_blocks();
+        for (class, &len) in SLAB_CLASSES.iter().enumerate() {
+            let mut curr = self.partial[class];
+            let mut n = 0;
+            while curr != NONE && n <= self.size() / SLAB_SIZE {
+                if !A::contains(curr + A::start()) {
+                    break;
+                }
+                let slab = Self::slab(curr);
+                if (slab.bump as usize) < SLAB_SIZE {
+                    res.push((curr + slab.bump, SLAB_SIZE - slab.bump as usize));
+                }
+                let mut obj = slab.free;
+                let mut m = 0;
+                while obj != NONE && Self::page(obj) == curr && m <= SLAB_SIZE / len {
+                    res.push((obj, len));
+                    obj = *Self::word(obj);
+                    m += 1;
+                }
+                curr = slab.next;
+                n += 1;
+            }
+        }
+        res
+    }
+
+    /// Prints the free lists and the partially-used slabs
+    pub fn print(&self) {
+        self.buddy.print();
+        for (class, &len) in SLAB_CLASSES.iter().enumerate() {
+            let mut curr = self.partial[class];
+            if curr != NONE {
+                print!("{:>12} [s{:>1}] ", len, class);
+                while curr != NONE {
+                    let slab = Self::slab(curr);
+                    print!("({}:{}/{})", curr, slab.used,
+                        (SLAB_SIZE - HEADER) / len);
+                    curr = slab.next;
+                }
+                println!();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn slab_size_class_test() {
+        assert_eq!(size_class(72).map(|c| SLAB_CLASSES[c]), Some(80));
+        assert_eq!(size_class(96).map(|c| SLAB_CLASSES[c]), Some(96));
+        assert_eq!(size_class(8), None);
+        assert_eq!(size_class(64), None);
+        assert_eq!(size_class(384), None);
+        assert_eq!(size_class(2000), None);
+        for len in 1..1024 {
+            if let Some(c) = size_class(len) {
+                let align = 1 << len.trailing_zeros();
+                assert!(SLAB_CLASSES[c] >= len);
+                assert_eq!((HEADER + SLAB_CLASSES[c]) % align, 0);
+            }
+        }
+    }
+
+    #[test]
+    fn slab_alg_test() {
+        use crate::open_flags::*;
+
+        crate::pool!(slab_pool, P, crate::NativeLayout, SlabAlg);
+        use slab_pool::P;
+
+        let _pool = P::open_no_root("slab.pool", O_CF).unwrap();
+        let used = P::used();
+        let per_slab = (SLAB_SIZE - HEADER) / 768;
+        unsafe {
+            let mut objs = vec![];
+            for i in 0..per_slab * 2 + 1 {
+                let (p, off, _) = P::alloc(700);
+                assert!(!p.is_null());
+                *(p as *mut u64) = i as u64;
+                objs.push((p, off));
+            }
+
+            // A 700-byte object takes 768 bytes instead of 1024 bytes
+            assert_eq!(P::used() - used, 3 * SLAB_SIZE);
+            assert!(P::verify());
+
+            for (i, (p, _)) in objs.iter().enumerate() {
+                assert_eq!(*(*p as *mut u64), i as u64);
+            }
+
+            // The last slab becomes empty, and freed objects are reused before
+            // taking a new slab
+            for (p, _) in objs.iter().step_by(2) {
+                P::dealloc(*p, 700);
+            }
+            assert_eq!(P::used() - used, 2 * SLAB_SIZE);
+            for i in (0..objs.len()).step_by(2) {
+                let (p, off, _) = P::alloc(700);
+                assert!(!p.is_null());
+                objs[i] = (p, off);
+            }
+            assert_eq!(P::used() - used, 3 * SLAB_SIZE);
+            assert!(P::verify());
+
+            // Empty slabs go back to the buddy allocator
+            for (p, _) in objs {
+                P::dealloc(p, 700);
+            }
+            assert_eq!(P::used(), used);
+            assert!(P::verify());
+        }
+    }
+}
@@ -0,0 +1,592 @@
+//! Pool integrity checking
+
+use crate::alloc::{MemPool, Trace, Visitor};
+use crate::result::Result;
+use crate::Error;
+use crate::stm::{Journal, Log, Notifier};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::marker::PhantomData;
+use std::mem;
+
+/// A problem found by [`check_pool`]
+///
+/// [`check_pool`]: ./trait.MemPoolTraits.html#method.check_pool
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Issue {
+    /// A reachable pointer points to a block which is not allocated
+    NotAllocated {
+        /// Offset of the block
+        off: u64,
+
+        /// Size of the block
+        len: usize,
+    },
+
+    /// A block with a single owner (e.g. the pointee of a [`Pbox`] or the
+    /// buffer of a [`Vec`]) is reachable through more than one pointer
+    ///
+    /// [`Pbox`]: ../struct.Pbox.html
+    /// [`Vec`]: ../vec/struct.Vec.html
+    Aliased {
+        /// Offset of the block
+        off: u64,
+
+        /// Number of pointers to the block
+        refs: usize,
+    },
+
+    /// The counters of a [`Prc`] or a [`Parc`] allocation do not match the
+    /// number of references to it
+    ///
+    /// [`Prc`]: ../prc/struct.Prc.html
+    /// [`Parc`]: ../sync/struct.Parc.html
+    Counters {
+        /// Offset of the allocation
+        off: u64,
+
+        /// The stored strong and weak counters
+        stored: (usize, usize),
+
+        /// The expected strong and weak counters
+        expected: (usize, usize),
+    },
+
+    /// A range of allocated memory is not reachable from the root object. It
+    /// may contain several blocks.
+    Leak {
+        /// Offset of the range
+        off: u64,
+
+        /// Size of the range
+        len: usize,
+    },
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Issue::NotAllocated { off, len } => {
+                write!(f, "Block @{:x} ({} bytes) is reachable but not allocated", off, len)
+            }
+            Issue::Aliased { off, refs } => {
+                write!(f, "Owned block @{:x} is reachable through {} pointers", off, refs)
+            }
+            Issue::Counters { off, stored, expected } => write!(f,
+                "Counters of @{:x} are (strong = {}, weak = {}), expected (strong = {}, weak = {})",
+                off, stored.0, stored.1, expected.0, expected.1),
+            Issue::Leak { off, len } => {
+                write!(f, "Range @{:x} ({} bytes) is allocated but not reachable", off, len)
+            }
+        }
+    }
+}
+
+/// The result of [`check_pool`]
+///
+/// [`check_pool`]: ./trait.MemPoolTraits.html#method.check_pool
+#[derive(Debug, Default)]
+pub struct Report {
+    /// Number of blocks reachable from the root object
+    pub blocks: usize,
+
+    /// The problems found
+    pub issues: Vec<Issue>,
+}
+
+impl Report {
+    /// Returns `true` if no problem is found
+    pub fn is_ok(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    /// Returns the leaked ranges
+    pub fn leaks(&self) -> impl Iterator<Item = (u64, usize)> + '_ {
+        self.issues.iter().filter_map(|i| match i {
+            Issue::Leak { off, len } => Some((*off, *len)),
+            _ => None,
+        })
+    }
+
+    /// Returns the total size of the leaked ranges in bytes
+    pub fn leaked(&self) -> usize {
+        self.leaks().map(|(_, len)| len).sum()
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Reachable blocks: {}", self.blocks)?;
+        for issue in &self.issues {
+            writeln!(f, "{}", issue)?;
+        }
+        if self.is_ok() {
+            writeln!(f, "No issues found")
+        } else {
+            writeln!(f, "{} issue(s) found", self.issues.len())
+        }
+    }
+}
+
+/// A reachable block
+struct Block {
+    len: usize,
+
+    /// Whether it is allocated
+    allocated: bool,
+
+    /// Whether its contents are traversed
+    traversed: bool,
+
+    /// Number of owning pointers
+    owned: usize,
+
+    /// Number of strong and weak reference-counted pointers
+    refs: (usize, usize),
+
+    /// The stored strong and weak counters
+    counters: Option<(usize, usize)>,
+
+    /// Offsets of the strong and weak counters
+    fields: (u64, u64),
+}
+
+impl Block {
+    /// Keeps the stored counters if the block is allocated and they are not
+    /// read yet
+    fn read_counters<'a, A: MemPool, F: FnOnce() -> (&'a usize, &'a usize)>(
+        &mut self,
+        counters: F,
+    ) {
+        if self.allocated && self.counters.is_none() {
+            let (strong, weak) = counters();
+            self.counters = Some((*strong, *weak));
+            self.fields = unsafe { (A::off_unchecked(strong), A::off_unchecked(weak)) };
+        }
+    }
+
+    /// Returns the expected strong and weak counters
+    fn expected(&self) -> (usize, usize) {
+        (self.refs.0, self.refs.1 + (self.refs.0 > 0) as usize)
+    }
+}
+
+/// The object graph checker
+///
+/// It is a [`Visitor`] which counts the pointers to every reachable block,
+/// and never traverses a block which is not allocated. Once the graph is
+/// traversed, the blocks which are neither reachable, reserved, nor free are
+/// reported as leaks, unless a raw pointer refers to them.
+pub struct Checker<A: MemPool> {
+    /// Free blocks sorted by their offsets, along with their sizes
+    free: BTreeMap<u64, usize>,
+
+    /// Reachable blocks
+    blocks: BTreeMap<u64, Block>,
+
+    /// Blocks in use by the pool itself
+    reserved: Vec<(u64, usize)>,
+
+    /// Offsets that the raw pointers refer to
+    raw: Vec<u64>,
+
+    phantom: PhantomData<A>,
+}
+
+impl<A: MemPool> Visitor for Checker<A> {
+    fn visit(&mut self, off: &u64, len: usize) -> bool {
+        match self.block(*off, len) {
+            Some(b) => {
+                b.owned += 1;
+                b.allocated && !mem::replace(&mut b.traversed, true)
+            }
+            None => false,
+        }
+    }
+
+    fn visit_strong<'a, F: FnOnce() -> (&'a usize, &'a usize)>(
+        &mut self,
+        off: &u64,
+        len: usize,
+        counters: F,
+    ) -> bool {
+        match self.block(*off, len) {
+            Some(b) => {
+                b.refs.0 += 1;
+                b.read_counters::<A, F>(counters);
+                b.allocated && !mem::replace(&mut b.traversed, true)
+            }
+            None => false,
+        }
+    }
+
+    fn visit_weak<'a, F: FnOnce() -> (&'a usize, &'a usize)>(
+        &mut self,
+        off: &u64,
+        len: usize,
+        counters: F,
+    ) {
+        if let Some(b) = self.block(*off, len) {
+            b.refs.1 += 1;
+            b.read_counters::<A, F>(counters);
+        }
+    }
+
+    fn visit_raw(&mut self, off: &u64, _len: usize) {
+        if *off != u64::MAX {
+            self.raw.push(*off);
+        }
+    }
+}
+
+impl<A: MemPool> Checker<A> {
+    /// Creates a new checker given the free blocks of the pool
+    pub fn new(free: Vec<(u64, usize)>) -> Self {
+        Self {
+            free: free.into_iter().collect(),
+            blocks: BTreeMap::new(),
+            reserved: vec![],
+            raw: vec![],
+            phantom: PhantomData,
+        }
+    }
+
+    /// Marks a block as in use by the pool itself (e.g. the metadata)
+    pub fn reserve(&mut self, off: u64, len: usize) {
+        self.reserved.push((off, len));
+    }
+
+    /// Marks the journals and their logs as in use
+    ///
+    /// It fails if a journal in the pool is not committed or not cleared, as
+    /// its logs may still change the object graph.
+    ///
+    /// # Safety
+    ///
+    /// No transaction should be running.
+    pub unsafe fn reserve_journals(&mut self) -> Result<()> {
+        let mut blocks = vec![];
+        let mut curr = *A::journals_head();
+        while let Ok(j) = A::deref::<Journal<A>>(curr) {
+            if blocks.iter().any(|(off, _)| *off == curr) {
+                break;
+            }
+            if j.is_pending() {
+                return Err(Error::UncommittedTransaction);
+            }
+            blocks.push((curr, mem::size_of::<Journal<A>>()));
+            j.blocks(&mut blocks);
+            curr = j.next_off();
+        }
+        self.reserved.extend(blocks);
+        Ok(())
+    }
+
+    /// Traverses the object graph starting from the root object of type `U`
+    /// at offset `root`
+    pub fn trace<U: Trace<A>>(&mut self, root: u64) -> Result<()> {
+        if self.visit(&root, mem::size_of::<U>()) {
+            unsafe { A::deref::<U>(root)?.trace(self); }
+        }
+        Ok(())
+    }
+
+    /// Returns the reachable block at `off`, and adds it if it is visited for
+    /// the first time
+    fn block(&mut self, off: u64, len: usize) -> Option<&mut Block> {
+        if off == u64::MAX || len == 0 {
+            return None;
+        }
+        if !self.blocks.contains_key(&off) {
+            let allocated = self.is_allocated(off, len);
+            self.blocks.insert(off, Block {
+                len,
+                allocated,
+                traversed: false,
+                owned: 0,
+                refs: (0, 0),
+                counters: None,
+                fields: (u64::MAX, u64::MAX),
+            });
+        }
+        self.blocks.get_mut(&off)
+    }
+
+    /// Determines if the range is allocated and does not overlap a free block
+    fn is_allocated(&self, off: u64, len: usize) -> bool {
+        let end = off + len as u64;
+        A::allocated(off, len) && match self.free.range(..end).next_back() {
+            Some((b, l)) => b + *l as u64 <= off,
+            None => true,
+        }
+    }
+
+    /// Finalizes the check and generates the report
+    ///
+    /// `managed` contains the ranges of memory which are managed by the
+    /// allocator, and `footprint` maps a block to the range that the allocator
+    /// dedicated to it.
+    pub fn report<F: Fn(u64, usize) -> (u64, usize)>(self, managed: &[(u64, usize)],
+        footprint: F) -> Report {
+        let mut report = Report { blocks: self.blocks.len(), issues: vec![] };
+
+        // Used and free ranges sorted by their offsets
+        let mut covered: Vec<(u64, u64)> = self.free.iter()
+            .map(|(off, len)| (*off, *off + *len as u64))
+            .collect();
+        for (off, len) in &self.reserved {
+            let (off, len) = footprint(*off, *len);
+            covered.push((off, off + len as u64));
+        }
+        for (off, b) in &self.blocks {
+            if !b.allocated {
+                report.issues.push(Issue::NotAllocated { off: *off, len: b.len });
+                continue;
+            }
+            let (s, len) = footprint(*off, b.len);
+            covered.push((s, s + len as u64));
+
+            let refs = b.owned + b.refs.0 + b.refs.1;
+            if b.owned > 0 && refs > 1 {
+                report.issues.push(Issue::Aliased { off: *off, refs });
+            }
+            if let Some(stored) = b.counters {
+                let expected = b.expected();
+                if stored != expected {
+                    report.issues.push(Issue::Counters { off: *off, stored, expected });
+                }
+            }
+        }
+        covered.sort_unstable();
+        let mut merged: Vec<(u64, u64)> = Vec::with_capacity(covered.len());
+        for (s, e) in covered {
+            match merged.last_mut() {
+                Some(last) if s <= last.1 => last.1 = last.1.max(e),
+                _ => merged.push((s, e)),
+            }
+        }
+
+        for (start, len) in managed {
+            let end = *start + *len as u64;
+            let mut cursor = *start;
+            let first = merged.partition_point(|(_, e)| *e <= *start);
+            for (s, e) in &merged[first..] {
+                if *s >= end {
+                    break;
+                }
+                if *s > cursor {
+                    report.issues.push(Issue::Leak { off: cursor, len: (*s - cursor) as usize });
+                }
+                cursor = *e;
+            }
+            if cursor < end {
+                report.issues.push(Issue::Leak { off: cursor, len: (end - cursor) as usize });
+            }
+        }
+
+        // A raw pointer does not tell where its pointee starts, so the entire
+        // range around it is kept
+        let raw = &self.raw;
+        report.issues.retain(|i| match i {
+            Issue::Leak { off, len } => !raw.iter().any(|r| *r >= *off && *r < *off + *len as u64),
+            _ => true,
+        });
+        report
+    }
+
+    /// Reclaims the leaked memory, and repairs the counters of the reachable
+    /// reference-counted allocations, in a single transaction
+    ///
+    /// Leaked blocks are not dropped, as their types are unknown. So, the
+    /// counters of the reachable allocations that they refer to are set to
+    /// the number of the reachable references. `split` breaks a leaked range
+    /// into the blocks which can be reclaimed safely. The rest of the
+    /// arguments are the same as in [`report`](#method.report). It returns
+    /// the number of reclaimed bytes.
+    pub fn collect<F, S>(self, managed: &[(u64, usize)], footprint: F, split: S) -> Result<usize>
+    where
+        F: Fn(u64, usize) -> (u64, usize),
+        S: Fn(u64, usize) -> Vec<(u64, usize)>,
+    {
+        let mut fixes = vec![];
+        for b in self.blocks.values() {
+            if let Some((strong, weak)) = b.counters {
+                let expected = b.expected();
+                if strong != expected.0 {
+                    fixes.push((b.fields.0, expected.0));
+                }
+                if weak != expected.1 {
+                    fixes.push((b.fields.1, expected.1));
+                }
+            }
+        }
+        let report = self.report(managed, footprint);
+        if let Some(issue) = report.issues.iter().find(|i|
+            matches!(i, Issue::NotAllocated { .. } | Issue::Aliased { .. })
+        ) {
+            return Err(Error::InvalidPool(format!("{}", issue)));
+        }
+        let mut blocks = vec![];
+        for (off, len) in report.leaks() {
+            blocks.extend(split(off, len));
+        }
+
+        A::transaction(move |j| unsafe {
+            for (off, val) in fixes {
+                let field = A::get_mut_unchecked::<usize>(off);
+                Log::create(&*field, j, Notifier::None);
+                *field = val;
+            }
+            let mut reclaimed = 0;
+            for (off, len) in blocks {
+                Log::drop_on_commit(off, len, j);
+                reclaimed += len;
+            }
+            reclaimed
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::open_flags::*;
+    use crate::{RootObj, Trace, Visitor};
+    use super::Issue;
+
+    crate::pool!(graph, P);
+    use graph::*;
+
+    crate::pool!(guard, Q);
+    use guard::Q;
+
+    struct Node {
+        val: i32,
+        next: PRefCell<Option<Prc<Node>>>,
+        prev: PRefCell<Option<prc::PWeak<Node>>>,
+    }
+
+    impl Node {
+        fn new(val: i32, j: &Journal) -> Prc<Node> {
+            Prc::new(Node {
+                val,
+                next: PRefCell::new(None),
+                prev: PRefCell::new(None),
+            }, j)
+        }
+    }
+
+    impl Trace<P> for Node {
+        fn trace<V: Visitor>(&self, v: &mut V) {
+            self.next.trace(v);
+            self.prev.trace(v);
+        }
+    }
+
+    struct Root {
+        nodes: PRefCell<PVec<Prc<Node>>>,
+        data: PRefCell<Option<Pbox<[u64; 64]>>>,
+    }
+
+    impl RootObj<P> for Root {
+        fn init(_: &Journal) -> Self {
+            Self {
+                nodes: PRefCell::new(PVec::new()),
+                data: PRefCell::new(None),
+            }
+        }
+    }
+
+    impl Trace<P> for Root {
+        fn trace<V: Visitor>(&self, v: &mut V) {
+            self.nodes.trace(v);
+            self.data.trace(v);
+        }
+    }
+
+    #[test]
+    fn check_pool_test() {
+        let root = P::open::<Root>("check.pool", O_CF).unwrap();
+        P::transaction(|j| {
+            // A doubly-linked list of three nodes
+            let mut nodes = root.nodes.borrow_mut(j);
+            let mut prev: Option<Prc<Node>> = None;
+            for i in 0..3 {
+                let n = Node::new(i, j);
+                if let Some(p) = prev {
+                    *p.next.borrow_mut(j) = Some(n.pclone(j));
+                    *n.prev.borrow_mut(j) = Some(Prc::downgrade(&p, j));
+                } else {
+                    nodes.push(n.pclone(j), j);
+                }
+                prev = Some(n);
+            }
+            *root.data.borrow_mut(j) = Some(Pbox::new([1; 64], j));
+        }).unwrap();
+
+        let report = P::check_pool::<Root>().unwrap();
+        assert!(report.is_ok(), "{}", report);
+        assert_eq!(report.blocks, 6);
+
+        // Two nodes form a cycle which is not reachable anymore, and one of
+        // them holds a weak reference to a reachable node
+        P::transaction(|j| {
+            let a = Node::new(10, j);
+            let b = Node::new(11, j);
+            *a.next.borrow_mut(j) = Some(b.pclone(j));
+            *b.next.borrow_mut(j) = Some(a.pclone(j));
+            *a.prev.borrow_mut(j) = Some(Prc::downgrade(&root.nodes.borrow()[0], j));
+        }).unwrap();
+
+        let report = P::check_pool::<Root>().unwrap();
+        assert!(!report.is_ok());
+        assert!(report.leaked() > 0);
+        let counters: Vec<_> = report.issues.iter()
+            .filter(|i| matches!(i, Issue::Counters { .. }))
+            .collect();
+        assert_eq!(counters.len(), 1, "{}", report);
+        if let Issue::Counters { stored, expected, .. } = counters[0] {
+            assert_eq!(*stored, (1, 3));
+            assert_eq!(*expected, (1, 2));
+        }
+        assert!(report.issues.iter().all(|i|
+            matches!(i, Issue::Counters { .. } | Issue::Leak { .. })
+        ), "{}", report);
+
+        // Reclaims the cycle and repairs the counters of the first node
+        let used = P::used();
+        assert!(P::collect_garbage::<Root>().unwrap() > 0);
+        assert!(P::used() < used);
+        let report = P::check_pool::<Root>().unwrap();
+        assert!(report.is_ok(), "{}", report);
+        assert_eq!(report.blocks, 6);
+        assert_eq!(P::collect_garbage::<Root>().unwrap(), 0);
+    }
+
+    #[test]
+    fn collect_garbage_guard_test() {
+        use crate::Error;
+        use std::sync::atomic::Ordering;
+
+        type Root = guard::PAtomicPtr<u64>;
+        let root = Q::open::<Root>("check_guard.pool", O_CF).unwrap();
+
+        // The pointee of a raw pointer is not owned, but it is not a leak
+        Q::transaction(|j| {
+            root.store(guard::Pbox::into_raw(guard::Pbox::new(7u64, j)), Ordering::Release);
+        }).unwrap();
+        assert_eq!(Q::collect_garbage::<Root>().unwrap(), 0);
+        assert_eq!(unsafe { *root.load(Ordering::Acquire) }, 7);
+
+        // Dropping the pointee outside a transaction leaves an uncommitted
+        // journal behind, which still refers to it
+        let p = root.swap(std::ptr::null_mut(), Ordering::AcqRel);
+        drop(unsafe { guard::Pbox::from_raw(p) });
+        assert!(matches!(Q::collect_garbage::<Root>(), Err(Error::UncommittedTransaction)));
+
+        // The next transaction commits it
+        Q::transaction(|_| {}).unwrap();
+        assert_eq!(Q::collect_garbage::<Root>().unwrap(), 0);
+        let report = Q::check_pool::<Root>().unwrap();
+        assert!(report.is_ok(), "{}", report);
+    }
+}
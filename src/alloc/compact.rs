@@ -0,0 +1,368 @@
+//! Offline pool compaction
+
+use crate::alloc::{MemPool, Trace, Visitor};
+use crate::result::Result;
+use crate::Error;
+use std::collections::btree_map::Entry;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::marker::PhantomData;
+use std::mem;
+
+/// The live object graph of a pool
+///
+/// It keeps the live memory blocks reachable from the root object as well as
+/// the location of all persistent pointers to them. The compaction procedure
+/// collects the graph from the original pool, moves the blocks to a new pool,
+/// and then rewrites the pointers according to the new locations.
+pub struct LiveGraph<A: MemPool> {
+    /// Live blocks sorted by their offsets, along with their sizes
+    blocks: BTreeMap<u64, usize>,
+
+    /// Offsets of the persistent pointer fields
+    fields: Vec<u64>,
+
+    /// Live blocks which are only reached through weak pointers so far, and
+    /// are not traversed yet
+    weak_only: HashSet<u64>,
+
+    /// Offset of the root object
+    root: u64,
+
+    /// Contents of the live blocks
+    data: HashMap<u64, Vec<u8>>,
+
+    phantom: PhantomData<A>,
+}
+
+impl<A: MemPool> Visitor for LiveGraph<A> {
+    fn visit(&mut self, off: &u64, len: usize) -> bool {
+        if *off == u64::MAX || len == 0 {
+            return false;
+        }
+        self.fields.push(unsafe { A::off_unchecked(off) });
+        self.blocks.insert(*off, len).is_none() || self.weak_only.remove(off)
+    }
+
+    fn visit_weak<'a, F: FnOnce() -> (&'a usize, &'a usize)>(
+        &mut self,
+        off: &u64,
+        len: usize,
+        _: F,
+    ) {
+        if *off == u64::MAX || len == 0 {
+            return;
+        }
+        self.fields.push(unsafe { A::off_unchecked(off) });
+        if self.blocks.insert(*off, len).is_none() {
+            self.weak_only.insert(*off);
+        }
+    }
+
+    /// Keeps the pointee of a raw pointer like that of a weak pointer, so
+    /// that the pointer is rewritten after relocation. The contents of the
+    /// pointee are not traversed, unless an owning pointer reaches it too.
+    fn visit_raw(&mut self, off: &u64, len: usize) {
+        if *off == u64::MAX || len == 0 {
+            return;
+        }
+        self.fields.push(unsafe { A::off_unchecked(off) });
+        match self.blocks.entry(*off) {
+            Entry::Vacant(e) => {
+                e.insert(len);
+                self.weak_only.insert(*off);
+            }
+            Entry::Occupied(mut e) => {
+                if *e.get() < len {
+                    e.insert(len);
+                }
+            }
+        }
+    }
+}
+
+impl<A: MemPool> LiveGraph<A> {
+    /// Collects the live object graph starting from the root object of type
+    /// `U` at offset `root`
+    pub fn collect<U: Trace<A>>(root: u64) -> Result<Self> {
+        let mut graph = Self {
+            blocks: BTreeMap::new(),
+            fields: vec![],
+            weak_only: HashSet::new(),
+            root,
+            data: HashMap::new(),
+            phantom: PhantomData,
+        };
+        graph.blocks.insert(root, mem::size_of::<U>());
+        unsafe { A::deref::<U>(root)?.trace(&mut graph); }
+        graph.fields.sort();
+        graph.fields.dedup();
+        graph.merge_interior();
+
+        for (off, len) in &graph.blocks {
+            if !A::allocated(*off, *len) {
+                return Err(Error::InvalidPool(
+                    format!("Block @{} ({} bytes) is reachable but not allocated", off, len)));
+            }
+        }
+        for f in &graph.fields {
+            if graph.container(*f).is_none() {
+                return Err(Error::InvalidPool(format!("Pointer @{} is not in a live block", f)));
+            }
+        }
+        Ok(graph)
+    }
+
+    /// Merges the blocks which are reached through interior pointers into
+    /// their enclosing blocks. Allocations never overlap, so an overlapping
+    /// block is a part of the one before it.
+    fn merge_interior(&mut self) {
+        let mut merged = BTreeMap::new();
+        let mut last: Option<(u64, u64)> = None;
+        for (off, len) in &self.blocks {
+            let end = *off + *len as u64;
+            last = match last {
+                Some((b, e)) if *off < e => Some((b, e.max(end))),
+                Some((b, e)) => {
+                    merged.insert(b, (e - b) as usize);
+                    Some((*off, end))
+                }
+                None => Some((*off, end)),
+            };
+        }
+        if let Some((b, e)) = last {
+            merged.insert(b, (e - b) as usize);
+        }
+        self.blocks = merged;
+    }
+
+    /// Returns the offset and the size of the live block containing `off`
+    fn container(&self, off: u64) -> Option<(u64, usize)> {
+        let (b, len) = self.blocks.range(..=off).next_back()?;
+        if off < *b + *len as u64 {
+            Some((*b, *len))
+        } else {
+            None
+        }
+    }
+
+    /// Copies the contents of the live blocks to the volatile memory so that
+    /// the pool can be closed
+    pub fn snapshot(&mut self) {
+        for (off, len) in &self.blocks {
+            let bytes = unsafe { A::deref_slice_unchecked::<u8>(*off, *len) };
+            self.data.insert(*off, bytes.to_vec());
+        }
+    }
+
+    /// Returns the number of live blocks
+    pub fn len(&self) -> usize {
+        self.blocks.len()
+    }
+
+    /// Returns the total size of the live blocks in bytes, considering the
+    /// allocation granularity
+    pub fn volume(&self) -> usize {
+        self.blocks.values().map(|len| (*len).max(8).next_power_of_two()).sum()
+    }
+
+    /// Returns the offset of the root object in the original pool
+    pub fn root(&self) -> u64 {
+        self.root
+    }
+
+    /// Allocates the live blocks in the pool in the descending order of their
+    /// sizes, copies the contents from the snapshot, and rewrites the
+    /// persistent pointers. It returns the new offset of the root object, or
+    /// `None` if there is not enough space in the pool.
+    ///
+    /// # Safety
+    ///
+    /// The open pool should be a freshly formatted one, and the graph should
+    /// be [`snapshot`](#method.snapshot)ed before.
+    pub unsafe fn relocate(&self) -> Option<u64> {
+        let mut order: Vec<(u64, usize)> = self.blocks.iter().map(|(o, l)| (*o, *l)).collect();
+        order.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+
+        let mut map = HashMap::<u64, u64>::new();
+        for (off, len) in order {
+            let (_, new, _, z) = A::pre_alloc(len);
+            if new == u64::MAX {
+                return None;
+            }
+            A::perform(z);
+            let bytes = &self.data[&off];
+            let dst = A::deref_slice_unchecked_mut::<u8>(new, len);
+            dst.copy_from_slice(bytes);
+            map.insert(off, new);
+        }
+
+        for f in &self.fields {
+            let (b, _) = self.container(*f).unwrap();
+            let field = map[&b] + (*f - b);
+            let target = A::get_mut_unchecked::<u64>(field);
+            let (tb, _) = self.container(*target).unwrap();
+            *target = map[&tb] + (*target - tb);
+        }
+
+        for (off, len) in &self.blocks {
+            crate::ll::persist(A::get_unchecked::<u8>(map[off]), *len, false);
+        }
+        crate::ll::sfence();
+
+        Some(map[&self.root])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::default::*;
+    use crate::open_flags::*;
+    use crate::Visitor;
+    type P = Allocator;
+
+    struct Root {
+        items: PRefCell<PVec<Prc<PString>>>,
+        big: PRefCell<Option<Pbox<[u8; 4096]>>>,
+    }
+
+    impl RootObj<P> for Root {
+        fn init(_: &Journal) -> Self {
+            Self {
+                items: PRefCell::new(PVec::new()),
+                big: PRefCell::new(None),
+            }
+        }
+    }
+
+    impl Trace<P> for Root {
+        fn trace<V: Visitor>(&self, v: &mut V) {
+            self.items.trace(v);
+            self.big.trace(v);
+        }
+    }
+
+    #[test]
+    fn compact_test() {
+        {
+            let root = P::open::<Root>("compact.pool", O_1GB | O_CF).unwrap();
+            P::transaction(|j| {
+                let mut items = root.items.borrow_mut(j);
+                for i in 0..100 {
+                    let s = Prc::new(format!("item {}", i).to_pstring(j), j);
+                    items.push(s.pclone(j), j);
+                    items.push(s, j);
+                }
+                *root.big.borrow_mut(j) = Some(Pbox::new([7; 4096], j));
+            }).unwrap();
+        }
+
+        let (before, after) = P::compact::<Root>("compact.pool").unwrap();
+        assert!(after < before);
+
+        let root = P::open::<Root>("compact.pool", 0).unwrap();
+        assert_eq!(P::size(), after);
+        let items = root.items.borrow();
+        assert_eq!(items.len(), 200);
+        for i in 0..100 {
+            assert_eq!(items[2 * i].as_str(), format!("item {}", i));
+            assert!(Prc::ptr_eq(&items[2 * i], &items[2 * i + 1]));
+        }
+        assert_eq!(root.big.borrow().as_ref().unwrap()[4095], 7);
+    }
+
+    struct Slices {
+        whole: PRefCell<Option<Pbox<[u64; 8]>>>,
+        tail: PCell<u64>,
+    }
+
+    impl RootObj<P> for Slices {
+        fn init(_: &Journal) -> Self {
+            Self {
+                whole: PRefCell::new(None),
+                tail: PCell::new(u64::MAX),
+            }
+        }
+    }
+
+    impl Trace<P> for Slices {
+        fn trace<V: Visitor>(&self, v: &mut V) {
+            // The interior pointer is visited before its enclosing block
+            v.visit(unsafe { self.tail.as_mut() }, 4 * 8);
+            self.whole.trace(v);
+        }
+    }
+
+    #[test]
+    fn compact_interior_test() {
+        {
+            let root = P::open::<Slices>("compact_interior.pool", O_1GB | O_CF).unwrap();
+            P::transaction(|j| {
+                let whole = Pbox::new([0, 1, 2, 3, 4, 5, 6, 7], j);
+                root.tail.set(unsafe { P::off_unchecked(&whole[4]) }, j);
+                *root.whole.borrow_mut(j) = Some(whole);
+            }).unwrap();
+        }
+
+        P::compact::<Slices>("compact_interior.pool").unwrap();
+
+        let root = P::open::<Slices>("compact_interior.pool", 0).unwrap();
+        let whole = root.whole.borrow();
+        let whole = whole.as_ref().unwrap();
+        assert_eq!(root.tail.get(), unsafe { P::off_unchecked(&whole[4]) });
+        assert_eq!(unsafe { P::deref_slice_unchecked::<u64>(root.tail.get(), 4) }, [4, 5, 6, 7]);
+    }
+
+    struct Raw {
+        whole: PRefCell<Option<Pbox<[u64; 8]>>>,
+        inner: PAtomicPtr<u64>,
+        alone: PAtomicPtr<u64>,
+    }
+
+    impl RootObj<P> for Raw {
+        fn init(_: &Journal) -> Self {
+            Self {
+                whole: PRefCell::new(None),
+                inner: PAtomicPtr::null(),
+                alone: PAtomicPtr::null(),
+            }
+        }
+    }
+
+    impl Trace<P> for Raw {
+        fn trace<V: Visitor>(&self, v: &mut V) {
+            self.inner.trace(v);
+            self.alone.trace(v);
+            self.whole.trace(v);
+        }
+    }
+
+    #[test]
+    fn compact_raw_test() {
+        use std::sync::atomic::Ordering;
+
+        {
+            let root = P::open::<Raw>("compact_raw.pool", O_1GB | O_CF).unwrap();
+            P::transaction(|j| {
+                // A garbage block to be compacted away
+                let _ = Pbox::new([0u8; 4096], j);
+                let whole = Pbox::new([0, 1, 2, 3, 4, 5, 6, 7], j);
+                root.inner.store(&whole[2] as *const _ as *mut _, Ordering::Release);
+                root.alone.store(Pbox::into_raw(Pbox::new(7u64, j)), Ordering::Release);
+                *root.whole.borrow_mut(j) = Some(whole);
+            }).unwrap();
+        }
+
+        P::compact::<Raw>("compact_raw.pool").unwrap();
+
+        // The raw pointers follow their pointees, whether or not an owning
+        // pointer reaches them
+        let root = P::open::<Raw>("compact_raw.pool", 0).unwrap();
+        let whole = root.whole.borrow();
+        let whole = whole.as_ref().unwrap();
+        assert_eq!(root.inner.load(Ordering::Acquire), &whole[2] as *const _ as *mut _);
+        assert_eq!(unsafe { *root.inner.load(Ordering::Acquire) }, 2);
+        assert_eq!(unsafe { *root.alone.load(Ordering::Acquire) }, 7);
+        let report = P::check_pool::<Raw>().unwrap();
+        assert!(report.is_ok(), "{}", report);
+    }
+}
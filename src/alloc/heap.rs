@@ -41,6 +41,7 @@ unsafe impl MemPoolTraits for Heap {
         let off = x as u64;
         log!(Self, Green, "", "PRE: {:<6}  ({:>6x}:{:<6x}) = {:<6} POST = {:<6}",
             0, off, off + size as u64 - 1, size, 0);
+        crate::__trace_event!(Self, "alloc", off = off, len = size, zone = 0);
         (x, off, size, 0)
     }
 
@@ -50,6 +51,7 @@ unsafe impl MemPoolTraits for Heap {
         let _end = _start + size as u64;
         log!(Self, Red, "DEALLOC", "PRE: {:<6}  ({:>6x}:{:<6x}) = {:<6} POST = {:<6}",
             0, _start, _end, _end - _start + 1, 0);
+        crate::__trace_event!(Self, "dealloc", off = _start, len = size, zone = 0);
         dealloc(ptr, Layout::from_size_align_unchecked(size, 1));
         0
     }
@@ -102,7 +104,7 @@ unsafe impl MemPoolTraits for Heap {
     ) -> Result<RootCell<'a, U, Self>> {
         let slf = Self::open_no_root(path, flags)?;
         if std::mem::size_of::<U>() == 0 {
-            Err("root type cannot be a ZST".to_string())
+            Err(crate::Error::ZeroSizedRoot)
         } else {
             unsafe {
                 let root_off = Self::transaction(move |j| {
@@ -132,7 +134,9 @@ unsafe impl MemPoolTraits for Heap {
         usize::MAX - 1
     }
 
-    unsafe fn recover() {}
+    unsafe fn recover() -> Result<()> {
+        Ok(())
+    }
 
     unsafe fn drop_journal(journal: &mut Journal) {
         let tid = std::thread::current().id();
@@ -173,6 +177,22 @@ pub type Parc<T> = crate::sync::Parc<T, Heap>;
 /// `<T,`[`Heap`](./struct.Heap.html)`>`.
 pub type PMutex<T> = crate::sync::PMutex<T, Heap>;
 
+/// Compact form of [`PRwLock`](../../sync/struct.PRwLock.html)
+/// `<T,`[`Heap`](./struct.Heap.html)`>`.
+pub type PRwLock<T> = crate::sync::PRwLock<T, Heap>;
+
+/// Compact form of [`PCondvar`](../../sync/struct.PCondvar.html)
+/// `<`[`Heap`](./struct.Heap.html)`>`.
+pub type PCondvar = crate::sync::PCondvar<Heap>;
+
+/// Compact form of [`PAtomicU64`](../../sync/struct.PAtomicU64.html)
+/// `<`[`Heap`](./struct.Heap.html)`>`.
+pub type PAtomicU64 = crate::sync::PAtomicU64<Heap>;
+
+/// Compact form of [`PAtomicPtr`](../../sync/struct.PAtomicPtr.html)
+/// `<T,`[`Heap`](./struct.Heap.html)`>`.
+pub type PAtomicPtr<T> = crate::sync::PAtomicPtr<T, Heap>;
+
 /// Compact form of [`PCell`](../../cell/struct.PCell.html)
 /// `<T,`[`Heap`](./struct.Heap.html)`>`.
 pub type PCell<T> = crate::cell::PCell<T, Heap>;
@@ -197,10 +217,18 @@ pub type PRefMut<'b, T> = crate::cell::RefMut<'b, T, Heap>;
 /// `<T,`[`Heap`](./struct.Heap.html)`>`.
 pub type VCell<T> = crate::cell::VCell<T, Heap>;
 
+/// Compact form of [`VersionedCell`](../../cell/struct.VersionedCell.html)
+/// `<T,`[`Heap`](./struct.Heap.html)`>`.
+pub type VersionedCell<T> = crate::cell::VersionedCell<T, Heap>;
+
 /// Compact form of [`Vec`](../../vec/struct.Vec.html)
 /// `<T,`[`Heap`](./struct.Heap.html)`>`.
 pub type PVec<T> = crate::vec::Vec<T, Heap>;
 
+/// Compact form of [`VecDeque`](../../vec_deque/struct.VecDeque.html)
+/// `<T,`[`Heap`](./struct.Heap.html)`>`.
+pub type PVecDeque<T> = crate::vec_deque::VecDeque<T, Heap>;
+
 /// Compact form of [`String`](../../str/struct.String.html)
 /// `<`[`Heap`](./struct.Heap.html)`>`.
 pub type PString = crate::str::String<Heap>;
@@ -0,0 +1,73 @@
+use crate::alloc::MemPool;
+use crate::cell::RootCell;
+use crate::result::Result;
+use crate::stm::Journal;
+use crate::*;
+use std::panic::UnwindSafe;
+
+/// An instance of a pool type opened at runtime
+///
+/// It is returned by [`open_instance()`] and keeps the instance open as long
+/// as it lives. The instances of a pool type share the same type, and the
+/// persistent pointers are resolved in the instance that the current thread
+/// works with. So, the root object is only reachable through [`enter()`] and
+/// [`transaction()`] which switch the current thread to this instance. The
+/// pointers from one pool type to another are still prevented at compile
+/// time. The closures given to these functions are `'static` and
+/// [`TxInSafe`], so they cannot capture the objects of other instances nor
+/// their journals. The root objects and the persistent pointers which are
+/// still reached from another instance, e.g. through a leaked reference,
+/// panic when they are dereferenced.
+///
+/// Threads spawned inside an instance start in the default instance. They
+/// may use [`with_instance()`] with the [`id()`] of the instance to work with
+/// it.
+///
+/// [`open_instance()`]: ./trait.MemPoolTraits.html#method.open_instance
+/// [`enter()`]: #method.enter
+/// [`transaction()`]: #method.transaction
+/// [`with_instance()`]: ./trait.MemPoolTraits.html#method.with_instance
+/// [`id()`]: #method.id
+/// [`TxInSafe`]: ../trait.TxInSafe.html
+pub struct PoolInstance<'a, U: 'a, A: MemPool> {
+    id: usize,
+    root: RootCell<'a, U, A>,
+}
+
+impl<'a, U: 'a + PSafe, A: MemPool> PoolInstance<'a, U, A> {
+    pub fn new(id: usize, root: RootCell<'a, U, A>) -> Self {
+        Self { id, root }
+    }
+
+    #[inline]
+    /// Returns the instance index
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    /// Runs `f` with a reference to the root object while the current thread
+    /// works with this instance
+    pub fn enter<T, F>(&self, f: F) -> T
+    where
+        F: FnOnce(&U) -> T + TxInSafe + 'static,
+    {
+        unsafe { A::with_instance_unchecked(self.id, || f(&self.root)) }
+    }
+
+    /// Executes a transaction on this instance
+    ///
+    /// It is the same as [`transaction()`] except that `body` also receives
+    /// a reference to the root object.
+    ///
+    /// [`transaction()`]: ../stm/fn.transaction.html
+    pub fn transaction<T, F>(&self, body: F) -> Result<T>
+    where
+        F: FnOnce(&U, &'static Journal<A>) -> T + TxInSafe + UnwindSafe + 'static,
+        T: TxOutSafe,
+    {
+        let root = &self.root;
+        unsafe {
+            A::with_instance_unchecked(self.id, move || A::transaction(move |j| body(root, j)))
+        }
+    }
+}
@@ -2,11 +2,21 @@
 
 mod alg;
 mod pool;
+mod trace;
+mod compact;
+mod check;
+mod instance;
 
 pub mod heap;
 
 pub use alg::buddy::*;
+pub use alg::slab::*;
+pub use alg::{PoolLayout, NativeLayout, libpmem};
 pub use pool::*;
+pub use trace::*;
+pub use compact::*;
+pub use check::*;
+pub use instance::*;
 
 /// Determines how much of the `MemPool` is used for the trait object.
 ///
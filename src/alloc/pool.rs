@@ -84,8 +84,33 @@ pub mod open_flags {
     /// Open Flag: Creates a pool memory file of size 64TB
     pub const O_64TB: u32 = 0x00100000;
 
+    /// Open Flag: Uses redo logging by default in the transactions of the pool
+    ///
+    /// The flag only applies to the current session; it is not stored in the
+    /// pool file.
+    pub const O_REDO: u32 = 0x00200000;
+
     /// Open Flag: Open only to read info
+    ///
+    /// The file is opened read-only and mapped privately, so nothing is
+    /// written back to it. The recovery procedure does not run, and the pool
+    /// type is not checked; so, any file created by [`pool!()`] with the same
+    /// layout and allocation algorithm can be inspected. It cannot be combined
+    /// with other flags.
+    ///
+    /// [`pool!()`]: ../macro.pool.html
     pub const O_READINFO: u32 = u32::MAX;
+
+    /// Open Flag: Open only to recover the pool
+    ///
+    /// The file is opened without applying any other flag, and the recovery
+    /// procedure runs. The pool type is checked and the header should be
+    /// within the bounds of the file; otherwise, it fails without writing to
+    /// the file. To repair a file created by another pool type, use
+    /// [`MemPoolTraits::open_repair`]. It cannot be combined with other flags.
+    ///
+    /// [`MemPoolTraits::open_repair`]: ../trait.MemPoolTraits.html#method.open_repair
+    pub const O_REPAIR: u32 = u32::MAX - 1;
 }
 
 use open_flags::*;
@@ -96,7 +121,7 @@ pub const FLAG_HAS_ROOT: u64 = 0x0000_0001;
 /// This macro can be used to access static data of an arbitrary allocator
 #[macro_export]
 macro_rules! static_inner {
-    ($id:ident, $inner:ident, $body:block) => {
+    ($id:expr, $inner:ident, $body:block) => {
         unsafe {
             if let Some($inner) = $id {
                 let $inner = &mut *$inner;
@@ -222,6 +247,21 @@ where
         unimplemented!()
     }
 
+    /// Opens a pool file created by another pool type with the same layout
+    /// and allocation algorithm, and recovers it in place
+    ///
+    /// `pool` is the path of the module in which [`pool!()`] generated that
+    /// pool type (e.g., `corundum::default`). The file is recovered only if
+    /// its magic number belongs to that pool type, and its header is within
+    /// the bounds of the file. Otherwise, it fails with
+    /// [`Error::InvalidPool`] without writing to the file.
+    ///
+    /// [`pool!()`]: ../macro.pool.html
+    /// [`Error::InvalidPool`]: ../enum.Error.html#variant.InvalidPool
+    fn open_repair(_path: &str, _pool: &str) -> Result<PoolGuard<Self>> {
+        unimplemented!()
+    }
+
     /// Commits all changes and clears the logs for all threads
     ///
     /// This method should be called while dropping the `MemPool` object to
@@ -326,12 +366,33 @@ where
     /// }).unwrap();
     /// ```
     ///
+    /// ## Schema Migration
+    ///
+    /// The pool header keeps the [`Schema`] of the root type. If the pool has
+    /// an older version of a versioned root type, the root object is upgraded
+    /// in a transaction using its [`Migrate`] implementations before it is
+    /// returned. A root type can be versioned with the `#[schema]` and
+    /// `#[migrate]` attributes of `#[derive(Root)]`:
+    ///
+    /// ```ignore
+    /// #[derive(Root)]
+    /// #[schema(name = "Root", version = 2)]
+    /// #[migrate(RootV1)]
+    /// struct Root {
+    ///     count: PCell<i64>,
+    /// }
+    /// ```
+    ///
     /// # Errors
     ///
     /// * A volatile memory pool (e.g. `Heap`) doesn't have a root object.
     /// * The pool should be open before accessing the root object.
+    /// * The stored root schema is not the same as `U`'s, and there is no
+    ///   migration path to `U`.
     ///
     /// [`RootObj`]: ../stm/trait.RootObj.html
+    /// [`Schema`]: ../cell/struct.Schema.html
+    /// [`Migrate`]: ../cell/trait.Migrate.html
     /// [`Prc`]: ../prc/struct.Prc.html
     /// [`Parc`]: ../sync/parc/struct.Parc.html
     /// [`PCell`]: ./default/type.PCell.html
@@ -344,6 +405,111 @@ where
         unimplemented!()
     }
 
+    /// Opens a new instance of the pool type and retrieves its root object
+    ///
+    /// A pool type declared with [`pool!()`] is backed by static data. The
+    /// default instance is used by [`open()`], and the other instances are
+    /// opened at runtime using this function. All instances share the same
+    /// type, so each instance should be [entered] before accessing its
+    /// objects. The returned [`PoolInstance`] only gives access to the root
+    /// object while entered, and the instance is closed when it is dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use corundum::default::*;
+    /// use corundum::open_flags::*;
+    ///
+    /// type P = Allocator;
+    ///
+    /// let shards: Vec<_> = (0..4).map(|i| {
+    ///     P::open_instance::<PCell<i32>>(&format!("shard{}.pool", i), O_CF).unwrap()
+    /// }).collect();
+    ///
+    /// for (i, shard) in shards.iter().enumerate() {
+    ///     shard.transaction(move |root, j| root.set(i as i32, j)).unwrap();
+    /// }
+    ///
+    /// assert_eq!(shards[3].enter(|root| root.get()), 3);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// In addition to the errors of [`open()`], it fails if the file is
+    /// already open by another instance, or if there is no free instance.
+    ///
+    /// [`pool!()`]: ../macro.pool.html
+    /// [`open()`]: #method.open
+    /// [entered]: ./struct.PoolInstance.html#method.enter
+    /// [`PoolInstance`]: ./struct.PoolInstance.html
+    fn open_instance<'a, U: 'a + PSafe + RootObj<Self>>(
+        _path: &str,
+        _flags: u32,
+    ) -> Result<PoolInstance<'a, U, Self>> where Self: MemPool {
+        Err(Error::Unsupported(format!("`{}` does not support multiple instances", Self::name())))
+    }
+
+    /// Returns the index of the instance that the current thread works with
+    #[inline]
+    fn instance() -> usize {
+        0
+    }
+
+    /// Returns the index of the open instance whose address range contains
+    /// `addr`, if any
+    #[inline]
+    fn instance_of(addr: u64) -> Option<usize> {
+        if Self::contains(addr) {
+            Some(Self::instance())
+        } else {
+            None
+        }
+    }
+
+    /// Makes the current thread work with instance `id`, and returns the
+    /// previous instance index
+    ///
+    /// # Safety
+    ///
+    /// The references to persistent objects obtained from the previous
+    /// instance are not valid until it is restored.
+    #[inline]
+    unsafe fn set_instance(id: usize) -> usize {
+        assert_eq!(id, 0, "`{}` does not support multiple instances", Self::name());
+        0
+    }
+
+    /// Runs `f` while the current thread works with instance `id`
+    ///
+    /// The previous instance is restored when `f` returns or panics.
+    /// Persistent pointers only hold offsets which are resolved in the
+    /// instance that the current thread works with. So, `f` cannot capture
+    /// references to the persistent objects of other instances, nor the
+    /// journals. The root objects and the persistent pointers which are
+    /// dereferenced in an instance other than their own panic.
+    fn with_instance<T, F: FnOnce() -> T + TxInSafe + 'static>(id: usize, f: F) -> T {
+        unsafe { Self::with_instance_unchecked(id, f) }
+    }
+
+    /// Runs `f` while the current thread works with instance `id` without
+    /// restricting what `f` captures
+    ///
+    /// # Safety
+    ///
+    /// `f` should not dereference the persistent objects of other instances,
+    /// and the objects of instance `id` should not be dereferenced outside
+    /// `f`, e.g. after `f` returns.
+    unsafe fn with_instance_unchecked<T, F: FnOnce() -> T>(id: usize, f: F) -> T {
+        struct Restore<P: MemPoolTraits>(usize, PhantomData<P>);
+        impl<P: MemPoolTraits> Drop for Restore<P> {
+            fn drop(&mut self) {
+                unsafe { P::set_instance(self.0); }
+            }
+        }
+        let _restore = Restore::<Self>(Self::set_instance(id), PhantomData);
+        f()
+    }
+
     /// Returns true if the pool is open
     fn is_open() -> bool {
         unimplemented!()
@@ -356,14 +522,14 @@ where
 
     /// Applies open pool flags
     unsafe fn apply_flags(path: &str, flags: u32) -> Result<()> {
-        let mut size: u64 = flags as u64 >> 4;
+        let mut size: u64 = (flags & !O_REDO) as u64 >> 4;
         if size.count_ones() > 1 {
-            return Err("Cannot have multiple size flags".to_string());
+            return Err(Error::InvalidFlags("Cannot have multiple size flags"));
         } else if size == 0 {
             size = DEFAULT_POOL_SIZE;
         } else {
             if flags & (O_C | O_CNE) == 0 {
-                return Err("Cannot use size flag without a create flag".to_string());
+                return Err(Error::InvalidFlags("Cannot use size flag without a create flag"));
             }
             size <<= 30;
         }
@@ -496,7 +662,7 @@ where
         if Self::allocated(off, mem::size_of::<T>()) {
             Ok(Self::get_unchecked(off))
         } else {
-            Err(format!("Access Violation (0x{:x})", off))
+            Err(Error::AccessViolation(off))
         }
     }
 
@@ -506,7 +672,7 @@ where
         if Self::allocated(off, mem::size_of::<T>()) {
             Ok(Self::get_mut_unchecked(off))
         } else {
-            Err(format!("Access Violation (0x{:x})", off))
+            Err(Error::AccessViolation(off))
         }
     }
 
@@ -516,7 +682,7 @@ where
         if Self::valid(x) {
             Ok(x as *const u8 as u64 - Self::start())
         } else {
-            Err(format!("out of valid range ({:p})", x).to_string())
+            Err(Error::OutOfRange(x as *const u8 as u64))
         }
     }
 
@@ -547,6 +713,163 @@ where
         unimplemented!()
     }
 
+    /// Grows the pool to `new_size` bytes while it is open
+    ///
+    /// The pool file is extended and remapped in place, and the new range is
+    /// added to the free lists along with the new pool size in a single
+    /// failure-atomic section. If it crashes in the middle, the pool keeps its
+    /// previous size. The address range past the end of the pool is reserved
+    /// when it is opened (see [`GROWTH_RESERVE`]), so the mapping never moves.
+    /// It fails if a transaction is running, or if the new size exceeds the
+    /// reserved range.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use corundum::default::*;
+    ///
+    /// let _pool = Allocator::open_no_root("grow.pool", O_CF).unwrap();
+    /// let size = Allocator::size();
+    /// Allocator::grow(2 * size).unwrap();
+    /// assert_eq!(Allocator::size(), 2 * size);
+    /// ```
+    ///
+    /// [`GROWTH_RESERVE`]: ./constant.GROWTH_RESERVE.html
+    fn grow(_new_size: usize) -> Result<()> {
+        Err(Error::Unsupported(format!("`{}` does not support growing", Self::name())))
+    }
+
+    /// Compacts a pool image file offline and truncates it
+    ///
+    /// It opens the pool at `path`, walks the object graph from the root
+    /// object of type `U`, and moves the live blocks to the low end of a new
+    /// image in which all persistent pointers are rewritten. The new image
+    /// replaces the original file only when it is complete. So, a crash in the
+    /// middle leaves the original file intact. On success, it returns the
+    /// pool sizes before and after the compaction.
+    ///
+    /// The pool should not be open when calling this function. All types
+    /// reachable from the root object should implement [`Trace`] so that
+    /// every persistent pointer is discovered. The pointees of raw pointers
+    /// (e.g. [`PAtomicPtr`]) are kept and the pointers are rewritten, but the
+    /// pointees are not traversed, unless an owning pointer reaches them too.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use corundum::default::*;
+    ///
+    /// type P = Allocator;
+    ///
+    /// // The pool is closed as soon as the root object is dropped
+    /// let _ = P::open::<PVec<Pbox<i32>>>("compact.pool", O_1GB | O_CF).unwrap();
+    ///
+    /// let (before, after) = P::compact::<PVec<Pbox<i32>>>("compact.pool").unwrap();
+    /// assert!(after < before);
+    /// ```
+    ///
+    /// [`Trace`]: ./trait.Trace.html
+    /// [`PAtomicPtr`]: ../sync/struct.PAtomicPtr.html
+    fn compact<U: PSafe + RootObj<Self> + Trace<Self>>(_path: &str) -> Result<(usize, usize)>
+    where Self: MemPool {
+        Err(Error::Unsupported(format!("`{}` does not support compaction", Self::name())))
+    }
+
+    /// Checks the integrity of the object graph of the open pool
+    ///
+    /// Unlike [`verify`], which only looks at the free lists, it walks the
+    /// object graph from the root object of type `U`. It confirms that every
+    /// reachable pointer lands in an allocated block, that no block with a
+    /// single owner (e.g. of a [`Pbox`]) is reachable twice, and that the
+    /// counters of [`Prc`] and [`Parc`] allocations match the actual number of
+    /// references. It also reports the allocated memory which is not
+    /// reachable from the root object as leaks. In pools with [`SlabAlg`],
+    /// leaked small objects are only found when their entire slab page is
+    /// unreachable.
+    ///
+    /// It should not be called while a transaction is running in any thread.
+    /// It fails with [`UncommittedTransaction`] if a journal in the pool is
+    /// not committed yet. All types reachable from the root object should
+    /// implement [`Trace`] so that every persistent pointer is discovered.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use corundum::default::*;
+    /// use corundum::open_flags::*;
+    ///
+    /// type P = Allocator;
+    ///
+    /// let root = P::open::<PRefCell<Option<Prc<i32>>>>("check.pool", O_CF).unwrap();
+    /// P::transaction(|j| {
+    ///     *root.borrow_mut(j) = Some(Prc::new(1, j));
+    /// }).unwrap();
+    ///
+    /// let report = P::check_pool::<PRefCell<Option<Prc<i32>>>>().unwrap();
+    /// assert!(report.is_ok(), "{}", report);
+    /// ```
+    ///
+    /// [`verify`]: #method.verify
+    /// [`Pbox`]: ../struct.Pbox.html
+    /// [`Prc`]: ../prc/struct.Prc.html
+    /// [`Parc`]: ../sync/struct.Parc.html
+    /// [`SlabAlg`]: ./struct.SlabAlg.html
+    /// [`Trace`]: ./trait.Trace.html
+    /// [`UncommittedTransaction`]: ../enum.Error.html#variant.UncommittedTransaction
+    fn check_pool<U: PSafe + RootObj<Self> + Trace<Self>>() -> Result<Report>
+    where Self: MemPool {
+        Err(Error::Unsupported(format!("`{}` does not support checking", Self::name())))
+    }
+
+    /// Reclaims the memory which is not reachable from the root object
+    ///
+    /// Reference cycles of [`Prc`] and [`Parc`] are never dropped, and their
+    /// memory leaks. This function finds the leaks as [`check_pool`] does,
+    /// and frees them in a single transaction. The leaked objects are not
+    /// dropped, as their types are unknown. Instead, the counters of the
+    /// reachable allocations that they refer to are set to the number of the
+    /// remaining references. It returns the number of reclaimed bytes.
+    ///
+    /// Nothing is reclaimed if the object graph is corrupted, i.e. if there
+    /// is a dangling or an aliased pointer. In pools with [`SlabAlg`], leaked
+    /// ranges which may contain slab pages are not reclaimed.
+    ///
+    /// It should not be called while a transaction is running in any thread,
+    /// or while a volatile pointer (e.g. [`VWeak`]) refers to an unreachable
+    /// object. It fails with [`UncommittedTransaction`] if a journal in the
+    /// pool is not committed yet, e.g. after dropping a persistent object
+    /// outside a transaction. All types reachable from the root object should
+    /// implement [`Trace`] so that every persistent pointer is discovered.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use corundum::default::*;
+    /// use corundum::open_flags::*;
+    ///
+    /// type P = Allocator;
+    ///
+    /// let root = P::open::<PRefCell<Option<Prc<i32>>>>("gc.pool", O_CF).unwrap();
+    /// P::transaction(|j| {
+    ///     *root.borrow_mut(j) = Some(Prc::new(1, j));
+    /// }).unwrap();
+    ///
+    /// // Nothing is leaked
+    /// assert_eq!(P::collect_garbage::<PRefCell<Option<Prc<i32>>>>().unwrap(), 0);
+    /// ```
+    ///
+    /// [`check_pool`]: #method.check_pool
+    /// [`Prc`]: ../prc/struct.Prc.html
+    /// [`Parc`]: ../sync/struct.Parc.html
+    /// [`VWeak`]: ../prc/struct.VWeak.html
+    /// [`SlabAlg`]: ./struct.SlabAlg.html
+    /// [`Trace`]: ./trait.Trace.html
+    /// [`UncommittedTransaction`]: ../enum.Error.html#variant.UncommittedTransaction
+    fn collect_garbage<U: PSafe + RootObj<Self> + Trace<Self>>() -> Result<usize>
+    where Self: MemPool {
+        Err(Error::Unsupported(format!("`{}` does not support garbage collection", Self::name())))
+    }
+
     /// Total occupied space
     fn used() -> usize {
         Self::size() - Self::available()
@@ -816,8 +1139,8 @@ where
     }
 
     /// Allocates new memory and then copies `x` into it with `DropOnFailure` log
-    unsafe fn new_copy<'a, T: 'a>(x: &T, j: &Journal<Self>) -> &'a mut T 
-    where T: ?Sized, Self: MemPool {
+    unsafe fn new_copy<'a, T: ?Sized + 'a>(x: &T, j: &Journal<Self>) -> &'a mut T 
+    where Self: MemPool {
         let s = mem::size_of_val(x);
         debug_assert!(s != 0, "Cannot allocated ZST");
 
@@ -931,7 +1254,7 @@ where
     }
 
     /// Creates a `DropOnCommit` log for the value `x`
-    unsafe fn free<'a, T: PSafe + ?Sized>(x: &mut T) where Self: MemPool {
+    unsafe fn free<T: PSafe + ?Sized>(x: &mut T) where Self: MemPool {
         // std::ptr::drop_in_place(x);
         let off = Self::off_unchecked(x);
         let len = mem::size_of_val(x);
@@ -943,9 +1266,9 @@ where
     }
 
     /// Creates a `DropOnCommit` log for the value `x`
-    unsafe fn free_slice<'a, T: PSafe>(x: &[T]) where Self: MemPool {
+    unsafe fn free_slice<T: PSafe>(x: &[T]) where Self: MemPool {
         // eprintln!("FREEING {} of size {}", x as *mut u8 as u64, len);
-        if x.len() > 0 {
+        if !x.is_empty() {
             let off = Self::off_unchecked(x);
             Log::drop_on_commit(
                 off,
@@ -956,7 +1279,7 @@ where
     }
 
     /// Frees the allocation for value `x` immediately
-    unsafe fn free_nolog<'a, T: ?Sized>(x: &T) {
+    unsafe fn free_nolog<T: ?Sized>(x: &T) {
         Self::perform(
             Self::pre_dealloc(x as *const _ as *mut u8, mem::size_of_val(x))
         );
@@ -974,7 +1297,10 @@ where
     }
 
     /// Recovers from a crash
-    unsafe fn recover() {
+    ///
+    /// It fails if the decision of a chaperoned session that a journal is
+    /// attached to cannot be found.
+    unsafe fn recover() -> Result<()> {
         unimplemented!()
     }
 
@@ -1010,6 +1336,25 @@ where
         }
     }
 
+    #[inline]
+    /// Makes all changes durable without committing them
+    ///
+    /// It is the first phase of the two-phase commit of a chaperoned session.
+    ///
+    /// # Safety
+    ///
+    /// This function is for internal use and should not be called elsewhere.
+    ///
+    unsafe fn prepare_commit() where Self: MemPool {
+        if let Some(journal) = Journal::<Self>::current(false) {
+            if *journal.1 > 0 {
+                log!(Self, White, "PREPARE", "JRNL: {:?}", journal.0);
+
+                as_mut(journal.0).prepare();
+            }
+        }
+    }
+
     #[inline]
     /// Commits all changes without clearing the logs
     ///
@@ -1170,13 +1515,15 @@ where
     /// 
     #[inline]
     #[track_caller]
-    fn transaction<T, F: FnOnce(&'static Journal<Self>) -> T>(body: F) -> Result<T>
+    fn transaction<T, F>(body: F) -> Result<T>
     where
-        F: TxInSafe + UnwindSafe,
+        F: FnOnce(&'static Journal<Self>) -> T + TxInSafe + UnwindSafe,
         T: TxOutSafe, Self: alloc::pool::MemPool
     {
         #[cfg(feature = "stat_perf")]
         let _perf = crate::stat::Measure::<Self>::Transaction;
+
+        let _span = crate::__trace_span!(Self, "transaction");
         
         #[cfg(feature = "check_allocator_cyclic_links")]
         debug_assert!(Self::verify());
@@ -1186,11 +1533,11 @@ where
         let res = std::panic::catch_unwind(|| {
             let chaperon = Chaperon::current();
             if let Some(ptr) = chaperon {
-                // FIXME: Chaperone session is corrupted. fix it.
                 unsafe {
                     *cptr = true;
-                    let mut chaperon = &mut *ptr;
-                    chaperon.postpone(
+                    let chaperon = &mut *ptr;
+                    Chaperon::postpone(
+                        Self::prepare_commit,
                         Self::commit_no_clear,
                         Self::rollback_no_clear,
                         Self::clear,
@@ -1202,7 +1549,10 @@ where
                         let j = Journal::<Self>::current(true).unwrap();
                         *j.1 += 1;
                         let journal = as_mut(j.0);
-                        journal.start_session(&mut chaperon);
+                        if *j.1 == 1 {
+                            journal.set_log_mode(Self::log_mode());
+                        }
+                        journal.start_session(chaperon);
                         journal.unset(JOURNAL_COMMITTED);
                         journal
                     })
@@ -1215,7 +1565,11 @@ where
                     unsafe {
                         let j = Journal::<Self>::current(true).unwrap();
                         *j.1 += 1;
-                        utils::as_mut(j.0).unset(JOURNAL_COMMITTED);
+                        let journal = utils::as_mut(j.0);
+                        if *j.1 == 1 {
+                            journal.set_log_mode(Self::log_mode());
+                        }
+                        journal.unset(JOURNAL_COMMITTED);
                         &*j.0
                     }
                 })
@@ -1231,27 +1585,179 @@ where
         unsafe {
             crate::ll::sfence();
 
-            if let Ok(res) = res {
-                if !chaperoned {
-                    Self::commit();
+            match res {
+                Ok(res) => {
+                    if !chaperoned {
+                        Self::commit();
+                        crate::__trace_event!(Self, "commit");
+                    }
+                    Ok(res)
                 }
-                Ok(res)
-            } else {
-                if !chaperoned {
-                    Self::rollback();
-                    Err("Unsuccessful transaction".to_string())
-                } else {
-                    // Propagates the panic to the top level in enforce rollback
-                    panic!("Unsuccessful chaperoned transaction");
+                Err(payload) => {
+                    if !chaperoned {
+                        Self::rollback();
+                        crate::__trace_event!(Self, "rollback");
+                        Err(Error::aborted(payload))
+                    } else {
+                        // Propagates the panic to the top level in enforce rollback
+                        panic!("Unsuccessful chaperoned transaction");
+                    }
+                }
+            }
+        }
+    }
+
+    /// Executes a transaction with timed locking, and re-runs it if a lock
+    /// could not be acquired in time
+    ///
+    /// While `body` runs, every [`PMutex::lock`] waits at most for the lock
+    /// timeout specified in `opts`. If the timeout passes, the transaction is
+    /// rolled back, which also releases the locks that it already holds. Then,
+    /// after the backoff delay, `body` is executed again in a new transaction.
+    /// Since the body may run multiple times, it is an `Fn` closure.
+    ///
+    /// The transaction fails if it panics for any other reason, or if the
+    /// locks time out in all attempts. If it is nested in another
+    /// transaction, the failure is propagated to the outer one, and the retry
+    /// is left to the top-most transaction.
+    ///
+    /// If `opts` specifies a [`LogMode`], the transaction uses it instead of
+    /// the pool's default mode. A nested transaction follows the mode and the
+    /// scratchpad size of the outer one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use corundum::default::*;
+    /// use corundum::stm::TxOptions;
+    /// use std::time::Duration;
+    ///
+    /// type P = Allocator;
+    ///
+    /// let root = P::open::<PMutex<i32>>("foo.pool", O_CF).unwrap();
+    ///
+    /// let opts = TxOptions::new()
+    ///     .retries(3)
+    ///     .backoff(Duration::from_millis(10))
+    ///     .lock_timeout(Duration::from_millis(100));
+    ///
+    /// P::transaction_with(opts, |j| {
+    ///     *root.lock(j) += 1;
+    /// }).unwrap();
+    /// ```
+    ///
+    /// [`PMutex::lock`]: ../sync/struct.PMutex.html#method.lock
+    /// [`LogMode`]: ../stm/enum.LogMode.html
+    #[track_caller]
+    fn transaction_with<T, F>(opts: TxOptions, body: F) -> Result<T>
+    where
+        F: Fn(&'static Journal<Self>) -> T + TxInSafe + RefUnwindSafe,
+        T: TxOutSafe, Self: alloc::pool::MemPool
+    {
+        let mut attempt = 0;
+        loop {
+            take_timed_out();
+            let prev = set_lock_timeout(opts.timeout());
+            let res = Self::transaction(|j| {
+                if let Some((_, cnt)) = Journal::<Self>::try_current() {
+                    if unsafe { *cnt } == 1 {
+                        let journal = unsafe { utils::as_mut(j) };
+                        if let Some(mode) = opts.mode() {
+                            unsafe { journal.set_log_mode(mode); }
+                        }
+                        #[cfg(any(feature = "use_pspd", feature = "use_vspd"))]
+                        if let Some(size) = opts.scratchpad() {
+                            journal.set_scratchpad_size(size);
+                        }
+                    }
+                }
+                body(j)
+            });
+            set_lock_timeout(prev);
+            match res {
+                Ok(res) => return Ok(res),
+                Err(e) => {
+                    if !take_timed_out() {
+                        return Err(e);
+                    }
+                    if attempt == opts.max_retries() {
+                        return Err(Error::LockTimeout(attempt + 1));
+                    }
+                    attempt += 1;
+                    std::thread::sleep(opts.delay(attempt));
                 }
             }
         }
     }
 
+    /// Executes a read-only transaction on a consistent snapshot of the
+    /// versioned data
+    ///
+    /// The snapshot is taken when the transaction starts. The versioned
+    /// containers, such as [`VersionedCell`], return the values that were
+    /// committed before that point when they are read through the snapshot,
+    /// regardless of the transactions that commit while `body` runs. The
+    /// read transaction does not take any lock and does not write any log;
+    /// hence, it never blocks the writers, and it is never blocked by them.
+    ///
+    /// The versions that an active snapshot may still observe are retained
+    /// until the snapshot is dropped. Long-running read transactions
+    /// therefore delay reclaiming the old versions.
+    ///
+    /// The consistency only covers the versioned containers. Any other
+    /// persistent object that `body` reads, such as a [`PVec`] or a
+    /// [`PCell`], is read as it is at the moment, including the uncommitted
+    /// changes of the running transactions. A collection may be kept in a
+    /// [`VersionedCell`] to be read consistently.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use corundum::default::*;
+    ///
+    /// type P = Allocator;
+    ///
+    /// let root = P::open::<VersionedCell<i32>>("foo.pool", O_CF).unwrap();
+    ///
+    /// P::transaction(|j| root.set(1, j)).unwrap();
+    ///
+    /// P::read_transaction(|s| {
+    ///     assert_eq!(*root.read(s).unwrap(), 1);
+    ///
+    ///     let writer = root.clone();
+    ///     std::thread::spawn(move || {
+    ///         P::transaction(|j| writer.set(2, j)).unwrap()
+    ///     }).join().unwrap();
+    ///
+    ///     // The snapshot does not observe the new value
+    ///     assert_eq!(*root.read(s).unwrap(), 1);
+    /// }).unwrap();
+    ///
+    /// P::read_transaction(|s| assert_eq!(*root.read(s).unwrap(), 2)).unwrap();
+    /// ```
+    ///
+    /// [`VersionedCell`]: ../cell/struct.VersionedCell.html
+    /// [`PVec`]: ../vec/struct.Vec.html
+    /// [`PCell`]: ../cell/struct.PCell.html
+    fn read_transaction<T, F>(body: F) -> Result<T>
+    where
+        F: FnOnce(&Snapshot<Self>) -> T + TxInSafe + UnwindSafe,
+        T: TxOutSafe, Self: alloc::pool::MemPool
+    {
+        let snapshot = Snapshot::new();
+        std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| body(&snapshot)))
+            .map_err(Error::aborted)
+    }
+
     fn gen() -> u32 {
         0
     }
 
+    /// Returns the default logging mode of the transactions
+    fn log_mode() -> LogMode {
+        LogMode::Undo
+    }
+
     fn tx_gen() -> u32 {
         0
     }
@@ -1268,18 +1774,30 @@ where
     }
 }
 
-pub struct PoolGuard<P: MemPoolTraits>(pub PhantomData<P>);
+pub struct PoolGuard<P: MemPoolTraits> {
+    instance: usize,
+    phantom: PhantomData<P>,
+}
 
 impl<P: MemPoolTraits> PoolGuard<P> {
+    /// Creates a guard for the instance that the current thread works with
     pub fn new() -> Self {
-        Self(PhantomData)
+        Self {
+            instance: P::instance(),
+            phantom: PhantomData,
+        }
+    }
+
+    /// Returns the instance index of the pool
+    pub fn instance(&self) -> usize {
+        self.instance
     }
 }
 
 impl<P: MemPoolTraits> Drop for PoolGuard<P> {
     fn drop(&mut self) {
         unsafe {
-            P::close().unwrap();
+            P::with_instance_unchecked(self.instance, || P::close().unwrap());
         }
 
         crate::__cfg_stat_perf!({
@@ -1302,16 +1820,9 @@ pub unsafe trait MemPool:
     UnwindSafe {}
 
 pub(crate) fn create_file(filename: &str, size: u64) -> Result<()> {
-    let file = OpenOptions::new().write(true).create(true).open(filename);
-    if file.is_err() {
-        Err(format!("{}", file.err().unwrap()))
-    } else {
-        if let Some(e) = file.unwrap().set_len(size).err() {
-            Err(format!("{}", e))
-        } else {
-            Ok(())
-        }
-    }
+    let file = OpenOptions::new().write(true).create(true).truncate(false).open(filename)?;
+    file.set_len(size)?;
+    Ok(())
 }
 
 #[cfg(test)]
@@ -1340,4 +1851,104 @@ mod test {
             println!("Error: '{}'", e);
         }
     }
+
+    #[test]
+    fn heap_unsupported() {
+        use crate::alloc::heap::Heap;
+        use crate::Error;
+
+        assert!(matches!(Heap::grow(1 << 20), Err(Error::Unsupported(_))));
+        assert!(matches!(Heap::compact::<crate::cell::PCell<i32, Heap>>("heap.pool"),
+            Err(Error::Unsupported(_))));
+    }
+
+    #[cfg(feature = "tracing")]
+    mod tracing {
+        use crate::open_flags::*;
+        use crate::alloc::pool::MemPoolTraits;
+        use crate::__tracing::field::{Field, Visit};
+        use crate::__tracing::span::{Attributes, Id, Record};
+        use crate::__tracing::{Event, Metadata, Subscriber};
+        use std::sync::{Arc, Mutex};
+
+        crate::pool!(traced, P);
+        use traced::*;
+
+        /// Records the names of the spans and the messages and kinds of
+        /// the events. The events tagged with a pool are recorded once more
+        /// with the pool name after `@`.
+        struct Recorder(Arc<Mutex<Vec<String>>>);
+
+        struct Fields(Vec<String>, Option<String>);
+
+        impl Visit for Fields {
+            fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+                let value = format!("{:?}", value).replace('"', "");
+                if field.name() == "message" || field.name() == "kind" {
+                    self.0.push(value);
+                } else if field.name() == "pool" {
+                    self.1 = Some(value);
+                }
+            }
+        }
+
+        impl Subscriber for Recorder {
+            fn enabled(&self, _: &Metadata<'_>) -> bool {
+                true
+            }
+
+            fn new_span(&self, span: &Attributes<'_>) -> Id {
+                self.0.lock().unwrap().push(span.metadata().name().to_string());
+                Id::from_u64(1)
+            }
+
+            fn record(&self, _: &Id, _: &Record<'_>) {}
+
+            fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+            fn event(&self, event: &Event<'_>) {
+                let mut fields = Fields(vec![], None);
+                event.record(&mut fields);
+                let mut records = self.0.lock().unwrap();
+                records.push(fields.0.join(":"));
+                if let Some(pool) = fields.1 {
+                    records.push(format!("{}@{}", fields.0.join(":"), pool));
+                }
+            }
+
+            fn enter(&self, _: &Id) {}
+
+            fn exit(&self, _: &Id) {}
+        }
+
+        #[test]
+        fn trace_transaction() {
+            let root = P::open::<PRefCell<Option<Pbox<i32>>>>("traced.pool", O_CF).unwrap();
+            let records = Arc::new(Mutex::new(vec![]));
+            crate::__tracing::subscriber::with_default(Recorder(records.clone()), || {
+                P::transaction(|j| {
+                    *root.borrow_mut(j) = Some(Pbox::new(1, j));
+                }).unwrap();
+                P::transaction(|j| {
+                    *root.borrow_mut(j) = None;
+                }).unwrap();
+            });
+            let records = records.lock().unwrap();
+            let find = |r: &str| records.iter().position(|x| x == r)
+                .unwrap_or_else(|| panic!("`{}` is not in {:?}", r, records));
+            let last = |r: &str| records.iter().rposition(|x| x == r).unwrap();
+            assert_eq!(records[0], "transaction");
+            #[cfg(not(any(feature = "use_pspd", feature = "use_vspd")))] {
+                assert!(find("alloc") < find("log:DataLog"));
+                assert!(find("log:DataLog") < find("commit"));
+            }
+            assert!(find("log:DropOnCommit") < last("dealloc"));
+            assert!(last("dealloc") < last("commit"));
+            assert!(records.iter().any(|x| x == "persist"));
+            assert_eq!(records.iter().filter(|x| *x == "persist").count(),
+                records.iter().filter(|x| *x == "persist@traced").count());
+            assert_eq!(records.iter().filter(|x| *x == "transaction").count(), 2);
+            assert_eq!(records.iter().filter(|x| *x == "commit").count(), 2);
+        }
+    }
 }
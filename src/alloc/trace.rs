@@ -0,0 +1,175 @@
+//! Persistent object graph traversal
+
+use crate::alloc::MemPool;
+use std::marker::PhantomData;
+
+/// A visitor of persistent pointers
+///
+/// A `Visitor` is passed to [`Trace::trace`] to discover the persistent memory
+/// blocks which are reachable from an object. Each persistent pointer type
+/// (e.g. [`Pbox`], [`Prc`], [`Parc`], and [`Vec`]) reports the location of its
+/// offset field along with the size of the pointee block.
+///
+/// [`Pbox`]: ../struct.Pbox.html
+/// [`Prc`]: ../prc/struct.Prc.html
+/// [`Parc`]: ../sync/struct.Parc.html
+/// [`Vec`]: ../vec/struct.Vec.html
+pub trait Visitor {
+    /// Visits a persistent pointer whose offset is stored in `off`. `len` is
+    /// the size of the pointee block in bytes. It returns `true` if the block
+    /// is visited for the first time, and its contents should be traversed.
+    fn visit(&mut self, off: &u64, len: usize) -> bool;
+
+    /// Visits a strong reference-counted pointer (e.g. [`Prc`] and [`Parc`]).
+    /// `counters` returns the strong and the weak counters stored in the
+    /// block, and it should be called only if the block is allocated. By
+    /// default, it is the same as [`visit`].
+    ///
+    /// [`Prc`]: ../prc/struct.Prc.html
+    /// [`Parc`]: ../sync/struct.Parc.html
+    /// [`visit`]: #tymethod.visit
+    #[inline]
+    fn visit_strong<'a, F: FnOnce() -> (&'a usize, &'a usize)>(
+        &mut self,
+        off: &u64,
+        len: usize,
+        _counters: F,
+    ) -> bool {
+        self.visit(off, len)
+    }
+
+    /// Visits a weak reference-counted pointer. The contents of the block are
+    /// never traversed through a weak pointer as the value is either dropped
+    /// or reachable through a strong one. `counters` is the same as in
+    /// [`visit_strong`].
+    ///
+    /// [`visit_strong`]: #method.visit_strong
+    #[inline]
+    fn visit_weak<'a, F: FnOnce() -> (&'a usize, &'a usize)>(
+        &mut self,
+        off: &u64,
+        len: usize,
+        _counters: F,
+    ) {
+        self.visit(off, len);
+    }
+
+    /// Visits a raw pointer whose offset is stored in `off`, and which does
+    /// not own its pointee (e.g. [`PAtomicPtr`]). `len` is the size of the
+    /// pointee in bytes. The pointee is neither traversed nor counted, but the
+    /// memory that it points to should be kept. By default, it does nothing.
+    ///
+    /// [`PAtomicPtr`]: ../sync/struct.PAtomicPtr.html
+    #[inline]
+    fn visit_raw(&mut self, _off: &u64, _len: usize) {}
+}
+
+/// Discovers persistent pointers in an object
+///
+/// This trait is used to walk the object graph starting from the root object,
+/// e.g., for [compacting] a pool. Implementations should report every
+/// persistent pointer to the visitor, and traverse the pointee if the visitor
+/// asks for it.
+///
+/// ## Derivable
+///
+/// This trait can be used with `#[derive]` if all fields are `Trace`. The
+/// `derive`d implementation of `Trace` calls [`trace`] on each field. It uses
+/// [`default::Allocator`] by default. It is possible to change the pool type(s)
+/// by using `pools()` attribute.
+///
+/// ## How can I implement `Trace`?
+///
+/// Types without any persistent pointer should have a trivial implementation.
+/// Otherwise, call [`trace`] on every field.
+///
+/// ```
+/// # use corundum::default::*;
+/// # use corundum::Visitor;
+/// struct Node {
+///     val: i32,
+///     next: Option<Pbox<Node>>,
+/// }
+///
+/// impl Trace<Allocator> for Node {
+///     fn trace<V: Visitor>(&self, v: &mut V) {
+///         self.val.trace(v);
+///         self.next.trace(v);
+///     }
+/// }
+/// ```
+///
+/// [compacting]: ./trait.MemPoolTraits.html#method.compact
+/// [`trace`]: #tymethod.trace
+/// [`default::Allocator`]: ../default/struct.Allocator.html
+pub trait Trace<A: MemPool> {
+    /// Reports the persistent pointers of `self` to the visitor `v`
+    fn trace<V: Visitor>(&self, v: &mut V);
+}
+
+impl<T: Trace<A>, A: MemPool> Trace<A> for Option<T> {
+    #[inline]
+    fn trace<V: Visitor>(&self, v: &mut V) {
+        if let Some(x) = self {
+            x.trace(v);
+        }
+    }
+}
+
+impl<T: Trace<A>, A: MemPool> Trace<A> for [T] {
+    #[inline]
+    fn trace<V: Visitor>(&self, v: &mut V) {
+        for x in self {
+            x.trace(v);
+        }
+    }
+}
+
+impl<T: Trace<A>, A: MemPool, const N: usize> Trace<A> for [T; N] {
+    #[inline]
+    fn trace<V: Visitor>(&self, v: &mut V) {
+        for x in self {
+            x.trace(v);
+        }
+    }
+}
+
+impl<T: ?Sized, A: MemPool> Trace<A> for PhantomData<T> {
+    #[inline]
+    fn trace<V: Visitor>(&self, _v: &mut V) {}
+}
+
+use impl_trait_for_tuples::*;
+
+#[impl_for_tuples(32)]
+impl<A: MemPool> Trace<A> for Tuple {
+    #[inline]
+    fn trace<V: Visitor>(&self, v: &mut V) {
+        for_tuples!( #( Tuple.trace(v); )* );
+    }
+}
+
+/// Implementations of `Trace` for primitive types.
+mod impls {
+
+    use super::{Trace, Visitor};
+    use crate::alloc::MemPool;
+
+    macro_rules! impl_trace {
+        ($($t:ty)*) => {
+            $(
+                impl<A: MemPool> Trace<A> for $t {
+                    #[inline]
+                    fn trace<V: Visitor>(&self, _v: &mut V) {}
+                }
+            )*
+        }
+    }
+
+    impl_trace! {
+        usize u8 u16 u32 u64 u128
+        isize i8 i16 i32 i64 i128
+        f32 f64
+        bool char
+    }
+}
@@ -0,0 +1,180 @@
+//! Offline inspection of pool files
+//!
+//! It opens any pool file created by `pool!()` with the native or the
+//! PMDK-compatible layout, without knowing the root type. The file is not
+//! modified unless it is repaired.
+//!
+//! ```text
+//! usage: corundum-tool <command> <file> [args]
+//!
+//!   info   <file>             Prints the header, the usage, the free lists,
+//!                             and the journals in flight
+//!   check  <file>             Verifies the free lists
+//!   dump   <file>             Prints the logs of the journals in flight
+//!   dump   <file> <off> <len> Prints `len` bytes at offset `off` in hex
+//!   repair <file> [<pool>]    Recovers the pool in place and verifies it
+//! ```
+//!
+//! `repair` refuses the file if it was not created by the pool type that
+//! `pool!()` generated in module `<pool>`, or if its header is out of the
+//! bounds of the file. `<pool>` defaults to `corundum::default` for the native
+//! layout, and is required for the PMDK-compatible layout.
+//!
+//! Offsets may be given in hex with a `0x` prefix. The exit code is 1 if the
+//! verification fails, and 2 if the file cannot be opened. Journals are read
+//! with the layout of this build; so, the tool should be built with the same
+//! journal features (e.g. `use_pspd`, `use_vspd`, and `pin_journals`) as the
+//! program that wrote the pool.
+
+use corundum::open_flags::*;
+use corundum::result::Result;
+use corundum::stm::Journal;
+use corundum::{Error, MemPool};
+use std::env;
+use std::fs::File;
+use std::io::Read;
+use std::process;
+
+corundum::pool!(pmem, P, corundum::libpmem::PmdkHeaderLayout);
+
+fn usage(prog: &str) -> ! {
+    eprintln!("usage: {} <command> <file> [args]", prog);
+    eprintln!();
+    eprintln!("  info   <file>             Prints the header, the usage, the free lists,");
+    eprintln!("                            and the journals in flight");
+    eprintln!("  check  <file>             Verifies the free lists");
+    eprintln!("  dump   <file>             Prints the logs of the journals in flight");
+    eprintln!("  dump   <file> <off> <len> Prints `len` bytes at offset `off` in hex");
+    eprintln!("  repair <file> [<pool>]    Recovers the pool in place and verifies it");
+    eprintln!();
+    eprintln!("<pool> is the path of the module of the pool type that created the file");
+    eprintln!("(default: `corundum::default`; required for the PMDK-compatible layout).");
+    process::exit(2);
+}
+
+/// Returns true if the file has a libpmemobj pool header
+fn is_pmemobj(path: &str) -> bool {
+    let mut sig = [0u8; 8];
+    match File::open(path) {
+        Ok(mut f) => f.read_exact(&mut sig).is_ok() && &sig == corundum::libpmem::OBJ_HDR_SIG,
+        Err(_) => false,
+    }
+}
+
+fn parse(s: &str) -> Option<u64> {
+    if let Some(hex) = s.strip_prefix("0x") {
+        u64::from_str_radix(hex, 16).ok()
+    } else {
+        s.parse().ok()
+    }
+}
+
+/// Collects the journals in flight, and stops if the list has a cycle
+fn journals<P: MemPool>() -> Vec<(u64, &'static Journal<P>)> {
+    let mut res = vec![];
+    unsafe {
+        let mut curr = *P::journals_head();
+        while let Ok(j) = P::deref::<Journal<P>>(curr) {
+            if res.iter().any(|(off, _)| *off == curr) {
+                eprintln!("A cyclic link detected in the list of journals");
+                break;
+            }
+            res.push((curr, j));
+            curr = j.next_off();
+        }
+    }
+    res
+}
+
+fn check<P: MemPool>() -> bool {
+    let ok = P::verify();
+    println!("Free lists: {}", if ok { "OK" } else { "FAILED" });
+    println!("Journals in flight: {}", journals::<P>().len());
+    ok
+}
+
+fn hexdump<P: MemPool>(off: u64, len: u64) -> Result<()> {
+    if off.checked_add(len).is_none_or(|end| end > P::size() as u64) {
+        return Err(Error::OutOfRange(off.saturating_add(len)));
+    }
+    // Free blocks and the meta-data are dumped, too
+    let bytes = unsafe {
+        std::slice::from_raw_parts((P::start() + off) as *const u8, len as usize)
+    };
+    for (i, line) in bytes.chunks(16).enumerate() {
+        let hex: Vec<String> = line.iter().map(|b| format!("{:02x}", b)).collect();
+        let ascii: String = line.iter()
+            .map(|b| if b.is_ascii_graphic() || *b == b' ' { *b as char } else { '.' })
+            .collect();
+        println!("{:08x}  {:<47}  |{}|", off + 16 * i as u64, hex.join(" "), ascii);
+    }
+    Ok(())
+}
+
+fn run<P: MemPool>(prog: &str, cmd: &str, path: &str, pool: Option<&str>, args: &[String]) -> Result<bool> {
+    match (cmd, args.len()) {
+        ("info", 0) => {
+            let _pool = P::open_no_root(path, O_READINFO)?;
+            P::print_info();
+            Ok(true)
+        }
+        ("check", 0) => {
+            let _pool = P::open_no_root(path, O_READINFO)?;
+            Ok(check::<P>())
+        }
+        ("dump", 0) => {
+            let _pool = P::open_no_root(path, O_READINFO)?;
+            for (off, j) in journals::<P>() {
+                println!("{:-^80}", format!(" Journal @({:x}) ", off));
+                print!("{}", j.recovery_info(4));
+            }
+            Ok(true)
+        }
+        ("dump", 2) => {
+            let (off, len) = match (parse(&args[0]), parse(&args[1])) {
+                (Some(off), Some(len)) => (off, len),
+                _ => usage(prog),
+            };
+            let _pool = P::open_no_root(path, O_READINFO)?;
+            hexdump::<P>(off, len)?;
+            Ok(true)
+        }
+        ("repair", 0) => {
+            let pool = pool.unwrap_or_else(|| usage(prog));
+            let _pool = P::open_repair(path, pool)?;
+            Ok(check::<P>())
+        }
+        ("repair", 1) => {
+            let _pool = P::open_repair(path, &args[0])?;
+            Ok(check::<P>())
+        }
+        _ => usage(prog),
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    if args.len() < 3 {
+        usage(&args[0]);
+    }
+
+    // Also looks for cycles in the free lists
+    if env::var("VERIFY").is_err() {
+        env::set_var("VERIFY", "2");
+    }
+
+    let res = if is_pmemobj(&args[2]) {
+        run::<pmem::P>(&args[0], &args[1], &args[2], None, &args[3..])
+    } else {
+        run::<corundum::default::Allocator>(&args[0], &args[1], &args[2],
+            Some("corundum::default"), &args[3..])
+    };
+    match res {
+        Ok(true) => {}
+        Ok(false) => process::exit(1),
+        Err(e) => {
+            eprintln!("{}: {}", args[2], e);
+            process::exit(2);
+        }
+    }
+}
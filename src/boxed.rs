@@ -1,6 +1,6 @@
 //! A persistent pointer type for persistent memory allocation
 
-use crate::alloc::MemPool;
+use crate::alloc::{MemPool, Trace, Visitor};
 use crate::cell::RootObj;
 use crate::clone::*;
 use crate::ptr::Ptr;
@@ -250,12 +250,11 @@ impl<T: PSafe, A: MemPool> Pbox<T, A> {
     /// ```
     ///
     pub fn initialize(boxed: &Option<Pbox<T, A>>, value: T) -> crate::result::Result<()> {
-        assert!(
-            !Journal::<A>::is_running(),
-            "Pbox::initialize() cannot be used inside a transaction"
-        );
+        if Journal::<A>::is_running() {
+            return Err(crate::Error::TransactionRunning);
+        }
         match boxed {
-            Some(_) => Err("already initialized".to_string()),
+            Some(_) => Err(crate::Error::AlreadyInitialized),
             None => if A::valid(boxed) {
                 unsafe {
                     let new = A::atomic_new(value);
@@ -270,7 +269,7 @@ impl<T: PSafe, A: MemPool> Pbox<T, A> {
                 }
                 Ok(())
             } else {
-                Err("The object is not in the PM".to_string())
+                Err(crate::Error::NotInPool)
             }
         }
     }
@@ -342,7 +341,7 @@ impl<T: PSafe + ?Sized, A: MemPool> Pbox<T, A> {
     #[inline]
     #[doc(hidden)]
     pub(crate) fn into_unique(b: Pbox<T, A>) -> Ptr<T, A> {
-        let unique = b.0.clone();
+        let unique = b.0;
         mem::forget(b);
         // Pbox is kind-of a library type, but recognized as a "unique pointer" by
         // Stacked Borrows.  This function here corresponds to "reborrowing to
@@ -449,7 +448,7 @@ impl<T: RootObj<A> + PSafe, A: MemPool> RootObj<A> for Pbox<T, A> {
     }
 }
 
-impl<T: PSafe + PClone<A> + ?Sized, A: MemPool> PClone<A> for Pbox<T, A> {
+impl<T: PSafe + PClone<A>, A: MemPool> PClone<A> for Pbox<T, A> {
     /// Returns a new box with a `pclone()` of this box's contents.
     ///
     /// # Examples
@@ -476,15 +475,20 @@ impl<T: PSafe + PClone<A> + ?Sized, A: MemPool> PClone<A> for Pbox<T, A> {
     }
 }
 
+impl<T: PSafe + Trace<A>, A: MemPool> Trace<A> for Pbox<T, A> {
+    #[inline]
+    fn trace<V: Visitor>(&self, v: &mut V) {
+        if !self.0.is_dangling() && v.visit(self.0.off_ref(), mem::size_of::<T>()) {
+            (**self).trace(v);
+        }
+    }
+}
+
 impl<T: PSafe + PartialEq + ?Sized, A: MemPool> PartialEq for Pbox<T, A> {
     #[inline]
     fn eq(&self, other: &Pbox<T, A>) -> bool {
         PartialEq::eq(&**self, &**other)
     }
-    #[inline]
-    fn ne(&self, other: &Pbox<T, A>) -> bool {
-        PartialEq::ne(&**self, &**other)
-    }
 }
 
 impl<T: PSafe + PartialOrd + ?Sized, A: MemPool> PartialOrd for Pbox<T, A> {
@@ -634,6 +638,15 @@ impl<T: PSafe, A: MemPool> DerefMut for Pbox<T, A> {
             unsafe {
                 d.create_log(&*journal, Notifier::NonAtomic(Ptr::from_ref(&self.1)));
             }
+        } else if self.1 == 0 {
+            // The box may be in a draft of a redo transaction
+            if let Some((journal, _)) = Journal::<A>::try_current() {
+                unsafe {
+                    if (*journal).in_draft(&self.1) {
+                        d.create_log(&*journal, (*journal).notifier(&self.1));
+                    }
+                }
+            }
         }
         d
     }
@@ -200,8 +200,8 @@ impl<T: PSafe, A: MemPool> PCell<T, A> {
         if ptr::eq(this, that) {
             return;
         }
-        self.create_log(journal);
-        other.create_log(journal);
+        let this = self.create_log(journal);
+        let that = other.create_log(journal);
 
         // SAFETY: This can be risky if called from separate threads, but `PCell`
         // is `!Sync` so this won't happen. This also won't invalidate any
@@ -234,18 +234,7 @@ impl<T: PSafe, A: MemPool> PCell<T, A> {
         // SAFETY: This can cause data races if called from a separate thread,
         // but `PCell` is `!Sync` so this won't happen.
 
-        self.create_log(journal);
-
-        #[cfg(any(feature = "use_pspd", feature = "use_vspd"))] {
-            if let Some(tmp) = *self.temp {
-                mem::replace(unsafe { &mut *tmp }, val)
-            } else {
-                mem::replace(unsafe { &mut *self.value.get() }, val)
-            }
-        }
-
-        #[cfg(not(any(feature = "use_pspd", feature = "use_vspd")))]
-        mem::replace(unsafe { &mut (*self.value.get()).1 }, val)
+        mem::replace(unsafe { &mut *self.create_log(journal) }, val)
     }
 
     /// Unwraps the value.
@@ -301,19 +290,7 @@ impl<T: PSafe, A: MemPool> PCell<T, A> {
         // SAFETY: This can cause data races if called from a separate thread,
         // but `PCell` is `!Sync` so this won't happen.
 
-        unsafe {
-            #[cfg(any(feature = "use_pspd", feature = "use_vspd"))] {
-                if let Some(tmp) = *self.temp {
-                    *tmp
-                } else {
-                    *self.value.get()
-                }
-            }
-            
-            #[cfg(not(any(feature = "use_pspd", feature = "use_vspd")))] {
-                (*self.value.get()).1
-            }
-        }
+        unsafe { *self.value_ptr() }
     }
 
     /// Updates the contained value using a function and returns the new value.
@@ -350,7 +327,11 @@ impl<T: PSafe, A: MemPool> PCell<T, A> {
 impl<T: PSafe + ?Sized, A: MemPool> PCell<T, A> {
     #[inline]
     #[track_caller]
-    pub(crate) fn create_log(&self, journal: &Journal<A>) {
+    /// Takes a log, if it was not already taken, and returns a pointer to
+    /// the location to write to
+    ///
+    /// In a redo transaction, the location is the draft of the value.
+    pub(crate) fn create_log(&self, journal: &Journal<A>) -> *mut T {
         unsafe {
             let inner = &mut *self.value.get();
             #[cfg(any(feature = "use_pspd", feature = "use_vspd"))] {
@@ -359,15 +340,42 @@ impl<T: PSafe + ?Sized, A: MemPool> PCell<T, A> {
                         self.temp.as_mut().replace(p);
                     }
                 }
+                self.value_ptr()
             }
             #[cfg(not(any(feature = "use_pspd", feature = "use_vspd")))] {
                 use crate::ptr::Ptr;
-                use crate::stm::Notifier;
-                if inner.0 == 0 {
+                use crate::stm::{Notifier, FLAG_LOGGED};
+                if inner.0 != FLAG_LOGGED {
+                    if let Some(p) = journal.draft_redo(&inner.1, &mut inner.0) {
+                        return p;
+                    }
                     assert!(A::valid(inner), "The object is not in the pool's valid range");
                     inner.1.create_log(journal, Notifier::NonAtomic(Ptr::from_ref(&inner.0)));
                 }
+                &mut inner.1
+            }
+        }
+    }
+
+    /// Returns a pointer to the current value, which is the draft of the
+    /// value if it is drafted in the running redo transaction
+    #[inline]
+    fn value_ptr(&self) -> *mut T {
+        #[cfg(any(feature = "use_pspd", feature = "use_vspd"))] {
+            if let Some(tmp) = *self.temp {
+                tmp
+            } else {
+                self.value.get()
+            }
+        }
+        #[cfg(not(any(feature = "use_pspd", feature = "use_vspd")))] unsafe {
+            let inner = &mut *self.value.get();
+            if inner.0 == crate::stm::FLAG_DRAFTED {
+                if let Some(p) = Journal::<A>::drafted(&inner.1) {
+                    return p;
+                }
             }
+            &mut inner.1
         }
     }
 
@@ -397,19 +405,7 @@ impl<T: PSafe + ?Sized, A: MemPool> PCell<T, A> {
         // but `PCell` is `!Sync` so this won't happen, and `&mut` guarantees
         // unique access.
 
-        self.create_log(journal);
-
-        #[cfg(any(feature = "use_pspd", feature = "use_vspd"))] unsafe {
-            if let Some(tmp) = *self.temp {
-                &mut *tmp
-            } else {
-                &mut *self.value.get()
-            }
-        }
-
-        #[cfg(not(any(feature = "use_pspd", feature = "use_vspd")))] {
-            unsafe { &mut (*self.value.get()).1 }
-        }
+        unsafe { &mut *self.create_log(journal) }
     }
     
     /// Returns a mutable reference to the underlying data without taking a log
@@ -437,16 +433,7 @@ impl<T: PSafe + ?Sized, A: MemPool> PCell<T, A> {
     /// ```
     #[inline]
     pub unsafe fn as_mut(&self) -> &mut T {
-        #[cfg(any(feature = "use_pspd", feature = "use_vspd"))] {
-            if let Some(tmp) = *self.temp {
-                &mut *tmp
-            } else {
-                &mut *self.value.get()
-            }
-        }
-        #[cfg(not(any(feature = "use_pspd", feature = "use_vspd")))] {
-            &mut (*self.value.get()).1
-        }
+        &mut *self.value_ptr()
     }
 }
 
@@ -476,12 +463,19 @@ impl<T: PSafe + Default, A: MemPool> PCell<T, A> {
 #[allow(invalid_reference_casting)]
 impl<T: fmt::Debug + PSafe + Copy, A: MemPool> fmt::Debug for PCell<T, A> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        unsafe { (*self.value_ptr()).fmt(f) }
+    }
+}
+
+impl<T: PSafe + Trace<A>, A: MemPool> Trace<A> for PCell<T, A> {
+    #[inline]
+    fn trace<V: Visitor>(&self, v: &mut V) {
         #[cfg(any(feature = "use_pspd", feature = "use_vspd"))] {
-            unsafe { (*self.value.get()).fmt(f) }
+            unsafe { (*self.value.get()).trace(v) }
         }
 
         #[cfg(not(any(feature = "use_pspd", feature = "use_vspd")))] {
-            unsafe { (*self.value.get()).1.fmt(f) }
+            unsafe { (*self.value.get()).1.trace(v) }
         }
     }
 }
@@ -489,25 +483,13 @@ impl<T: fmt::Debug + PSafe + Copy, A: MemPool> fmt::Debug for PCell<T, A> {
 impl<T: PSafe + Logger<A> + Copy, A: MemPool> PClone<A> for PCell<T, A> {
     #[inline]
     fn pclone(&self, _j: &Journal<A>) -> PCell<T, A> {
-        #[cfg(any(feature = "use_pspd", feature = "use_vspd"))] {
-            unsafe { PCell::new(*self.value.get()) }
-        }
-
-        #[cfg(not(any(feature = "use_pspd", feature = "use_vspd")))] {
-            unsafe { PCell::new((*self.value.get()).1) }
-        }
+        PCell::new(self.get())
     }
 }
 
 impl<T: PSafe + Logger<A> + Copy, A: MemPool> Clone for PCell<T, A> {
     #[inline]
     fn clone(&self) -> PCell<T, A> {
-        #[cfg(any(feature = "use_pspd", feature = "use_vspd"))] {
-            unsafe { PCell::new((*self.value.get()).clone()) }
-        }
-
-        #[cfg(not(any(feature = "use_pspd", feature = "use_vspd")))] {
-            unsafe { PCell::new((*self.value.get()).1.clone()) }
-        }
+        PCell::new(self.get())
     }
 }
\ No newline at end of file
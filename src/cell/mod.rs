@@ -6,6 +6,7 @@ mod rootcell;
 mod vcell;
 mod tcell;
 mod lazy;
+mod versioned;
 
 pub use cell::*;
 pub use refcell::*;
@@ -13,3 +14,4 @@ pub use rootcell::*;
 pub use vcell::*;
 pub use tcell::*;
 pub use lazy::*;
+pub use versioned::*;
@@ -210,6 +210,7 @@ impl<T: PSafe + Debug + ?Sized, A: MemPool> Debug for PRefCell<T, A> {
 impl<T: PSafe + ?Sized, A: MemPool> PRefCell<T, A> {
     #[inline(always)]
     #[allow(clippy::mut_from_ref)]
+    #[allow(invalid_reference_casting)]
     fn self_mut(&self) -> &mut Self {
         unsafe {
             let ptr: *const Self = self;
@@ -240,20 +241,7 @@ impl<T: PSafe + ?Sized, A: MemPool> PRefCell<T, A> {
     /// }).unwrap();
     /// ```
     pub fn get_mut(&mut self, journal: &Journal<A>) -> &mut T {
-        let inner = unsafe { &mut *self.value.get() };
-        self.create_log(journal);
-
-        #[cfg(any(feature = "use_pspd", feature = "use_vspd"))] unsafe {
-            if let Some(tmp) = *self.temp {
-                &mut *tmp
-            } else {
-                &mut *inner
-            }
-        }
-    
-        #[cfg(not(any(feature = "use_pspd", feature = "use_vspd")))] {
-            &mut inner.1
-        }
+        unsafe { &mut *self.create_log(journal) }
     }
 
     #[inline]
@@ -281,34 +269,35 @@ impl<T: PSafe + ?Sized, A: MemPool> PRefCell<T, A> {
     /// 
     /// ```
     pub unsafe fn as_mut(&self) -> &mut T {
+        &mut *self.value_ptr()
+    }
+
+    #[inline]
+    /// Returns an immutable reference of the inner value
+    pub fn as_ref(&self) -> &T {
+        unsafe { &*self.value_ptr() }
+    }
+
+    /// Returns a pointer to the current value, which is the draft of the
+    /// value if it is drafted in the running redo transaction
+    #[inline]
+    fn value_ptr(&self) -> *mut T {
         #[cfg(any(feature = "use_pspd", feature = "use_vspd"))] {
             if let Some(tmp) = *self.temp {
-                &mut *tmp
+                tmp
             } else {
-                &mut *self.value.get()
+                self.value.get()
             }
         }
-    
-        #[cfg(not(any(feature = "use_pspd", feature = "use_vspd")))] {
-            &mut (*self.value.get()).1
-        }
-    }
 
-    #[inline]
-    /// Returns an immutable reference of the inner value
-    pub fn as_ref(&self) -> &T {
-        unsafe {
-            #[cfg(any(feature = "use_pspd", feature = "use_vspd"))] {
-                if let Some(tmp) = *self.temp {
-                    &*tmp
-                } else {
-                    &*self.value.get()
+        #[cfg(not(any(feature = "use_pspd", feature = "use_vspd")))] unsafe {
+            let inner = &mut *self.value.get();
+            if inner.0 == crate::stm::FLAG_DRAFTED {
+                if let Some(p) = Journal::<A>::drafted(&inner.1) {
+                    return p;
                 }
             }
-        
-            #[cfg(not(any(feature = "use_pspd", feature = "use_vspd")))] {
-                &(*self.value.get()).1
-            }
+            &mut inner.1
         }
     }
 
@@ -361,25 +350,32 @@ impl<T: PSafe + ?Sized, A: MemPool> PRefCell<T, A> {
 
     #[inline]
     #[track_caller]
-    pub(crate) fn create_log(&self, journal: &Journal<A>) {
+    /// Takes a log, if it was not already taken, and returns a pointer to
+    /// the location to write to
+    ///
+    /// In a redo transaction, the location is the draft of the value.
+    pub(crate) fn create_log(&self, journal: &Journal<A>) -> *mut T {
         unsafe {
             let inner = &mut *self.value.get();
             #[cfg(any(feature = "use_pspd", feature = "use_vspd"))] {
                 if self.temp.is_none() {
-                    if self.temp.is_none() {
-                        if let Some(p) = journal.draft(inner) {
-                            self.temp.as_mut().replace(p);
-                        }
+                    if let Some(p) = journal.draft(inner) {
+                        self.temp.as_mut().replace(p);
                     }
                 }
+                self.value_ptr()
             }
             #[cfg(not(any(feature = "use_pspd", feature = "use_vspd")))] {
                 use crate::ptr::Ptr;
-                use crate::stm::{Notifier, Logger};
-                if inner.0 == 0 {
+                use crate::stm::{Notifier, Logger, FLAG_LOGGED};
+                if inner.0 != FLAG_LOGGED {
+                    if let Some(p) = journal.draft_redo(&inner.1, &mut inner.0) {
+                        return p;
+                    }
                     assert!(A::valid(inner), "The object is not in the pool's valid range");
                     inner.1.create_log(journal, Notifier::NonAtomic(Ptr::from_ref(&inner.0)));
                 }
+                &mut inner.1
             }
         }
     }
@@ -497,21 +493,17 @@ impl<T: PSafe, A: MemPool> PRefCell<T, A> {
             LogNonNull::new_unchecked(inner, journal)
         }
         #[cfg(not(any(feature = "use_pspd", feature = "use_vspd")))] {
-            LogNonNull::new_unchecked(&mut inner.1, &mut inner.0, journal)
+            if let Some(p) = journal.draft_redo(&inner.1, &mut inner.0) {
+                LogNonNull::new_unchecked(p, &mut inner.0, journal)
+            } else {
+                LogNonNull::new_unchecked(&mut inner.1, &mut inner.0, journal)
+            }
         }
     }
 
     /// Returns a `NonNull` pointer to the data
     pub fn as_non_null(&self) -> NonNull<T> {
-        unsafe { 
-            let inner = &mut *self.value.get();
-            #[cfg(any(feature = "use_pspd", feature = "use_vspd"))] {
-                NonNull::new_unchecked(inner)
-            }
-            #[cfg(not(any(feature = "use_pspd", feature = "use_vspd")))] {
-                NonNull::new_unchecked(&mut inner.1)
-            }
-        }
+        NonNull::new_unchecked(self.value_ptr())
     }
 }
 
@@ -523,6 +515,13 @@ impl<T: PSafe + PClone<A>, A: MemPool> PClone<A> for PRefCell<T, A> {
     }
 }
 
+impl<T: PSafe + Trace<A>, A: MemPool> Trace<A> for PRefCell<T, A> {
+    #[inline]
+    fn trace<V: Visitor>(&self, v: &mut V) {
+        self.as_ref().trace(v);
+    }
+}
+
 impl<T: PSafe + Clone, A: MemPool> Clone for PRefCell<T, A> {
     #[inline]
     fn clone(&self) -> PRefCell<T, A> {
@@ -542,7 +541,7 @@ impl<T: PSafe + Eq + ?Sized, A: MemPool> Eq for PRefCell<T, A> {}
 impl<T: PSafe + PartialOrd + ?Sized, A: MemPool> PartialOrd for PRefCell<T, A> {
     #[inline]
     fn partial_cmp(&self, other: &PRefCell<T, A>) -> Option<std::cmp::Ordering> {
-        self.as_ref().partial_cmp(&*other.as_ref())
+        self.as_ref().partial_cmp(other.as_ref())
     }
 
     #[inline]
@@ -569,7 +568,7 @@ impl<T: PSafe + PartialOrd + ?Sized, A: MemPool> PartialOrd for PRefCell<T, A> {
 impl<T: PSafe + Ord + ?Sized, A: MemPool> Ord for PRefCell<T, A> {
     #[inline]
     fn cmp(&self, other: &PRefCell<T, A>) -> std::cmp::Ordering {
-        self.as_ref().cmp(&*other.as_ref())
+        self.as_ref().cmp(other.as_ref())
     }
 }
 
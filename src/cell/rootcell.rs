@@ -48,14 +48,14 @@ impl<T: PSafe, A: MemPool> Clone for RootCell<'_, T, A> {
 impl<T: Ord + PSafe, A: MemPool> Ord for RootCell<'_, T, A> {
     #[inline]
     fn cmp(&self, other: &Self) -> Ordering {
-        self.deref().cmp(&other.deref())
+        self.deref().cmp(other.deref())
     }
 }
 
 impl<T: PSafe + PartialOrd, A: MemPool> PartialOrd for RootCell<'_, T, A> {
     #[inline]
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        self.deref().partial_cmp(&other.deref())
+        self.deref().partial_cmp(other.deref())
     }
 }
 
@@ -72,8 +72,13 @@ impl<T: PSafe, A: MemPool> Deref for RootCell<'_, T, A> {
     type Target = T;
 
     #[inline]
+    #[track_caller]
     fn deref(&self) -> &T {
-        &*self.0
+        // The offsets in the root object are resolved in the current instance
+        assert_eq!(self.1.instance(), A::instance(),
+            "The root object of instance {} of `{}` is dereferenced in another instance",
+            self.1.instance(), A::name());
+        self.0
     }
 }
 
@@ -98,12 +103,48 @@ impl<T: Display + PSafe, A: MemPool> Display for RootCell<'_, T, A> {
 /// [`Default`]: std::default::Default
 pub trait RootObj<A: MemPool> {
     fn init(journal: &Journal<A>) -> Self;
+
+    /// Returns the schema of the root type which is stored in the pool header
+    /// 
+    /// By default, a root type is identified by its type name and size, and
+    /// its version is `0`. Versioned types (e.g., using `#[schema]` attribute
+    /// of `#[derive(Root)]`) use a stable name instead, so that an older
+    /// version of the root object can be found and [migrated](Migrate).
+    fn schema() -> Schema where Self: Sized {
+        Schema::of::<Self>()
+    }
+
+    /// Converts an older version of the root object located at `off` to the
+    /// current version
+    /// 
+    /// It is called by [`open()`] in a transaction when the pool header
+    /// indicates an older schema. It returns `None` if there is no migration
+    /// path from `from`. The default implementation has no migration path.
+    /// 
+    /// # Safety
+    /// 
+    /// `off` should be the offset of a valid root object with schema `from`.
+    /// The old root object is moved out and its memory is reclaimed on commit.
+    /// 
+    /// [`open()`]: ../alloc/trait.MemPool.html#method.open
+    unsafe fn upgrade(_from: Schema, _off: u64, _journal: &Journal<A>) -> Option<Self>
+    where Self: Sized {
+        None
+    }
 }
 
 impl<T: Default, A: MemPool> RootObj<A> for T {
     default fn init(_journal: &Journal<A>) -> Self {
         T::default()
     }
+
+    default fn schema() -> Schema {
+        Schema::of::<Self>()
+    }
+
+    default unsafe fn upgrade(_from: Schema, _off: u64, _journal: &Journal<A>) -> Option<Self> {
+        None
+    }
 }
 
 impl<T: Default, A: MemPool> RootObj<A> for &[T] {
@@ -113,11 +154,143 @@ impl<T: Default, A: MemPool> RootObj<A> for &[T] {
 }
 
 impl<T: RootObj<A>, A: MemPool> RootObj<A> for &[T] {
+    #[allow(clippy::only_used_in_recursion)]
     default fn init(journal: &Journal<A>) -> Self {
         <&[T]>::init(journal)
     }
 }
 
+/// Schema header of the root object
+/// 
+/// The pool keeps the schema of its root object to verify the root type on
+/// [`open()`], and to find out if the root object needs to be [migrated].
+/// 
+/// [`open()`]: ../alloc/trait.MemPool.html#method.open
+/// [migrated]: ./trait.Migrate.html
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Schema {
+    /// A hash value identifying the root type across versions
+    pub id: u64,
+
+    /// The version of the root type
+    pub version: u32,
+}
+
+impl Schema {
+    /// Creates the schema of an unversioned type `T` out of its type name and
+    /// size
+    pub fn of<T>() -> Self {
+        // Replace it with std::any::TypeId::of::<T>() when it
+        // is available in the future for non-'static types
+        let id = format!("{} ({})", std::any::type_name::<T>(),
+            std::mem::size_of::<T>());
+        Self { id: Self::hash(&id), version: 0 }
+    }
+
+    /// Creates a versioned schema with the given name
+    pub fn named(name: &str, version: u32) -> Self {
+        Self { id: Self::hash(name), version }
+    }
+
+    fn hash(s: &str) -> u64 {
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::{Hash, Hasher};
+
+        let mut h = DefaultHasher::new();
+        s.hash(&mut h);
+        h.finish()
+    }
+}
+
+/// Converts an older version of the root object to a newer one
+/// 
+/// When a pool with an older schema version is opened with a newer root type,
+/// [`open()`] moves the old root object out of the pool, passes it to
+/// `migrate` in a transaction, and stores the result as the new root object.
+/// Migration steps can be chained: if `From` is itself migratable from an
+/// older type, the pool may be opened from any version in the chain.
+/// 
+/// The `#[schema]` and `#[migrate]` attributes of `#[derive(Root)]` implement
+/// the [`RootObj::schema`] and [`RootObj::upgrade`] functions accordingly.
+/// They can be implemented manually using [`upgrade_root`], as well.
+/// 
+/// # Examples
+/// 
+/// ```
+/// # use corundum::default::*;
+/// # use corundum::{Schema, Migrate, upgrade_root};
+/// struct RootV1 {
+///     count: PCell<i32>,
+/// }
+/// 
+/// struct Root {
+///     count: PCell<i64>,
+///     name: PRefCell<PString>,
+/// }
+/// 
+/// impl RootObj<Allocator> for RootV1 {
+///     fn init(_: &Journal) -> Self {
+///         Self { count: PCell::new(0) }
+///     }
+///     fn schema() -> Schema {
+///         Schema::named("Root", 1)
+///     }
+/// }
+/// 
+/// impl RootObj<Allocator> for Root {
+///     fn init(_: &Journal) -> Self {
+///         Self { count: PCell::new(0), name: PRefCell::new(PString::new()) }
+///     }
+///     fn schema() -> Schema {
+///         Schema::named("Root", 2)
+///     }
+///     unsafe fn upgrade(from: Schema, off: u64, j: &Journal) -> Option<Self> {
+///         upgrade_root::<RootV1, Self, Allocator>(from, off, j)
+///     }
+/// }
+/// 
+/// impl Migrate<RootV1, Allocator> for Root {
+///     fn migrate(old: RootV1, j: &Journal) -> Self {
+///         Self {
+///             count: PCell::new(old.count.get() as i64),
+///             name: PRefCell::new("unnamed".to_pstring(j)),
+///         }
+///     }
+/// }
+/// ```
+/// 
+/// [`open()`]: ../alloc/trait.MemPool.html#method.open
+pub trait Migrate<From, A: MemPool>: Sized {
+    /// Creates the new version of the root object out of the old one
+    fn migrate(old: From, journal: &Journal<A>) -> Self;
+}
+
+/// Upgrades the root object at `off` with schema `from` to type `T` through
+/// its predecessor `F`
+/// 
+/// If `from` is not the schema of `F`, it first upgrades the root object to
+/// `F` recursively. It returns `None` if there is no migration path.
+/// 
+/// # Safety
+/// 
+/// It should be called in a transaction, and `off` should be the offset of a
+/// valid root object with schema `from`.
+pub unsafe fn upgrade_root<F: PSafe + RootObj<A>, T: Migrate<F, A>, A: MemPool>(
+    from: Schema,
+    off: u64,
+    journal: &Journal<A>,
+) -> Option<T> {
+    let old = if from == F::schema() {
+        let old = A::deref_mut::<F>(off).ok()?;
+        let val = std::ptr::read(old);
+        A::free(old);
+        val
+    } else {
+        F::upgrade(from, off, journal)?
+    };
+    Some(T::migrate(old, journal))
+}
+
 // impl<T: RootObj<A>, Q: RootObj<A>, A: MemPool> RootObj<A> for (T, Q) {
 //     fn init(j: &Journal<A>) -> Self {
 //         (T::init(j), Q::init(j))
@@ -1,6 +1,6 @@
 use crate::RootObj;
 use crate::stm::Journal;
-use crate::alloc::MemPool;
+use crate::alloc::{MemPool, Trace, Visitor};
 use crate::{PSafe, VSafe, utils};
 use std::cmp::*;
 use std::mem::*;
@@ -44,7 +44,7 @@ use std::fmt::{self,Debug};
 /// 
 /// [`Default`]: std::default::Default
 /// [`VSafe`]: ../trait.VSafe.html
-pub struct TCell<T: Default + VSafe + ?Sized, A: MemPool> {
+pub struct TCell<T: Default + VSafe, A: MemPool> {
     gen: u32,
     tx_gen: u32,
     phantom: PhantomData<(A, T)>,
@@ -58,9 +58,9 @@ impl<T, A> !Send for TCell<T, A> {}
 impl<T, A> !Sync for TCell<T, A> {}
 
 /// Safe to be stored in persistent memory
-unsafe impl<T: Default + VSafe + ?Sized, A: MemPool> PSafe for TCell<T, A> {}
+unsafe impl<T: Default + VSafe, A: MemPool> PSafe for TCell<T, A> {}
 
-impl<T: Default + Debug + VSafe + ?Sized, A: MemPool> Debug for TCell<T, A> {
+impl<T: Default + Debug + VSafe, A: MemPool> Debug for TCell<T, A> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
         write!(f, "{:?}", self.deref())
     }
@@ -107,12 +107,12 @@ impl<T: Default + VSafe, A: MemPool> TCell<T, A> {
                 let j = &*j;
                 let tx_gen = j.gen();
                 if self.gen != gen || self.tx_gen != tx_gen {
-                    forget(replace(&mut self.value, T::default()));
+                    forget(take(&mut self.value));
                     self.gen = gen;
                     self.tx_gen = tx_gen;
                 }
             } else {
-                forget(replace(&mut self.value, T::default()));
+                forget(take(&mut self.value));
                 self.gen = gen;
             }
         }
@@ -131,6 +131,12 @@ impl<T: Default + VSafe, A: MemPool> RootObj<A> for TCell<T, A> {
     }
 }
 
+impl<T: Default + VSafe, A: MemPool> Trace<A> for TCell<T, A> {
+    /// Transient cells do not contain persistent pointers
+    #[inline]
+    fn trace<V: Visitor>(&self, _v: &mut V) {}
+}
+
 impl<T: Default + VSafe, A: MemPool> Deref for TCell<T, A> {
     type Target = T;
 
@@ -200,7 +206,7 @@ impl<T: Default + VSafe + PartialEq + Copy, A: MemPool> PartialEq<T> for TCell<T
 impl<T: Default + VSafe + PartialOrd + Copy, A: MemPool> PartialOrd<T> for TCell<T, A> {
     #[inline]
     fn partial_cmp(&self, other: &T) -> Option<Ordering> {
-        self.value.partial_cmp(&other)
+        self.value.partial_cmp(other)
     }
 
     #[inline]
@@ -1,4 +1,4 @@
-use crate::alloc::MemPool;
+use crate::alloc::{MemPool, Trace, Visitor};
 use crate::{PSafe, VSafe, utils};
 use std::cmp::*;
 use std::mem::*;
@@ -34,15 +34,15 @@ use std::ops::{Deref, DerefMut};
 /// 
 /// [`Default`]: std::default::Default
 /// [`VSafe`]: ../trait.VSafe.html
-pub struct VCell<T: Default + VSafe + ?Sized, A: MemPool> {
+pub struct VCell<T: Default + VSafe, A: MemPool> {
     phantom: PhantomData<(A, T)>,
     gen: u32,
     value: T,
 }
 
 /// Safe to transfer between thread boundaries
-unsafe impl<T: Default + VSafe + ?Sized, A: MemPool> Send for VCell<T, A> {}
-unsafe impl<T: Default + VSafe + ?Sized, A: MemPool> PSafe for VCell<T, A> {}
+unsafe impl<T: Default + VSafe, A: MemPool> Send for VCell<T, A> {}
+unsafe impl<T: Default + VSafe, A: MemPool> PSafe for VCell<T, A> {}
 
 /// Not safe for thread data sharing
 impl<T, A: MemPool> !Sync for VCell<T, A> {}
@@ -87,7 +87,7 @@ impl<T: Default + VSafe, A: MemPool> VCell<T, A> {
                 let z = A::zone(off);
                 A::prepare(z); // Used as a global lock
                 if self.gen != gen {
-                    forget(replace(&mut self.value, T::default()));
+                    forget(take(&mut self.value));
                     self.gen = gen;
                 }
                 A::perform(z);
@@ -107,6 +107,12 @@ impl<T: Default + VSafe, A: MemPool> Default for VCell<T, A> {
     }
 }
 
+impl<T: Default + VSafe, A: MemPool> Trace<A> for VCell<T, A> {
+    /// Volatile cells do not contain persistent pointers
+    #[inline]
+    fn trace<V: Visitor>(&self, _v: &mut V) {}
+}
+
 impl<T: Default + VSafe, A: MemPool> Deref for VCell<T, A> {
     type Target = T;
 
@@ -176,7 +182,7 @@ impl<T: Default + VSafe + PartialEq + Copy, A: MemPool> PartialEq<T> for VCell<T
 impl<T: Default + VSafe + PartialOrd + Copy, A: MemPool> PartialOrd<T> for VCell<T, A> {
     #[inline]
     fn partial_cmp(&self, other: &T) -> Option<Ordering> {
-        self.value.partial_cmp(&other)
+        self.value.partial_cmp(other)
     }
 
     #[inline]
@@ -0,0 +1,297 @@
+use crate::alloc::MemPool;
+use crate::boxed::Pbox;
+use crate::cell::RootObj;
+use crate::clone::PClone;
+use crate::ll::persist_obj;
+use crate::stm::{oldest_snapshot, Journal, Log, Logger, Notifier, Snapshot};
+use crate::sync::PMutex;
+use crate::*;
+use std::fmt;
+use std::marker::PhantomData;
+use std::panic::{RefUnwindSafe, UnwindSafe};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// A persistent multi-version memory location for read-only transactions
+///
+/// A `VersionedCell` keeps a chain of committed versions of its value, each
+/// stamped with the time its transaction committed. Writers use
+/// [`set()`](#method.set) and [`update()`](#method.update) inside regular
+/// transactions; they are serialized by an internal lock which is held until
+/// the transaction commits. Readers use [`read()`](#method.read) with the
+/// [`Snapshot`] of a [`read_transaction`], and obtain the newest version
+/// committed before the snapshot was taken, without taking any lock.
+///
+/// Old versions are reclaimed by the writers once no active snapshot can
+/// observe them. After a restart, there is no active snapshot, and every
+/// committed version is visible.
+///
+/// The value is versioned as a whole. To read a collection consistently in
+/// a snapshot, such as a [`PVec`], it can be kept in a `VersionedCell` and
+/// modified by copy-on-write with [`update()`](#method.update). Any other
+/// data that the snapshot reads directly is not versioned.
+///
+/// # Examples
+///
+/// ```
+/// use corundum::default::*;
+///
+/// type P = Allocator;
+///
+/// #[derive(Root)]
+/// struct Account {
+///     checking: VersionedCell<i32>,
+///     savings: VersionedCell<i32>,
+/// }
+///
+/// let root = P::open::<Account>("foo.pool", O_CF).unwrap();
+///
+/// P::transaction(|j| {
+///     root.checking.set(100, j);
+///     root.savings.set(0, j);
+/// }).unwrap();
+///
+/// let total = P::read_transaction(|s| {
+///     *root.checking.read(s).unwrap() + *root.savings.read(s).unwrap()
+/// }).unwrap();
+///
+/// assert_eq!(total, 100);
+/// ```
+///
+/// [`Snapshot`]: ../stm/struct.Snapshot.html
+/// [`read_transaction`]: ../alloc/trait.MemPool.html#method.read_transaction
+/// [`PVec`]: ../vec/struct.Vec.html
+pub struct VersionedCell<T: PSafe, A: MemPool> {
+    head: u64,
+    lock: PMutex<(), A>,
+    phantom: PhantomData<T>,
+}
+
+/// A single version of the data in a [`VersionedCell`]
+///
+/// A version is visible to a snapshot if it was created in a previous
+/// lifetime of the pool (`gen` differs), or if its commit timestamp is not
+/// newer than the snapshot's. Uncommitted versions have the maximum
+/// timestamp.
+#[repr(C)]
+struct Version<T: PSafe, A: MemPool> {
+    ts: u64,
+    gen: u32,
+    next: u64,
+    value: T,
+    phantom: PhantomData<A>,
+}
+
+impl<T: PSafe, A: MemPool> Version<T, A> {
+    #[inline]
+    fn ts(&self) -> u64 {
+        unsafe { AtomicU64::from_ptr(&self.ts as *const u64 as *mut u64).load(Ordering::Acquire) }
+    }
+
+    #[inline]
+    fn visible(&self, ts: u64) -> bool {
+        self.gen != A::gen() || self.ts() <= ts
+    }
+
+    #[inline]
+    fn is_uncommitted(&self) -> bool {
+        self.gen == A::gen() && self.ts() == u64::MAX
+    }
+
+    #[inline]
+    fn next(&self) -> Option<&mut Self> {
+        if self.next == u64::MAX {
+            None
+        } else {
+            unsafe { Some(A::get_mut_unchecked(self.next)) }
+        }
+    }
+}
+
+impl<T: PSafe, A: MemPool> !TxOutSafe for VersionedCell<T, A> {}
+impl<T: PSafe, A: MemPool> UnwindSafe for VersionedCell<T, A> {}
+impl<T: PSafe, A: MemPool> RefUnwindSafe for VersionedCell<T, A> {}
+
+unsafe impl<T: PSafe, A: MemPool> TxInSafe for VersionedCell<T, A> {}
+unsafe impl<T: PSafe, A: MemPool> PSafe for VersionedCell<T, A> {}
+unsafe impl<T: PSafe + Send, A: MemPool> Send for VersionedCell<T, A> {}
+unsafe impl<T: PSafe + Send + Sync, A: MemPool> Sync for VersionedCell<T, A> {}
+unsafe impl<T: PSafe + Send, A: MemPool> PSend for VersionedCell<T, A> {}
+
+impl<T: PSafe, A: MemPool> VersionedCell<T, A> {
+    /// Creates a new versioned cell with initial value `v`
+    ///
+    /// The initial version is visible to all snapshots.
+    pub fn new(v: T, journal: &Journal<A>) -> Self {
+        unsafe {
+            let off = Self::alloc(Version {
+                ts: 0,
+                gen: 0,
+                next: u64::MAX,
+                value: v,
+                phantom: PhantomData,
+            }, journal);
+            Self {
+                head: off,
+                lock: PMutex::new(()),
+                phantom: PhantomData,
+            }
+        }
+    }
+
+    unsafe fn alloc(v: Version<T, A>, journal: &Journal<A>) -> u64 {
+        let p = A::new_uninit_for_layout(std::mem::size_of::<Version<T, A>>(), journal)
+            as *mut Version<T, A>;
+        std::ptr::write(p, v);
+        persist_obj(&*p, true);
+        A::off_unchecked(p)
+    }
+
+    #[inline]
+    fn head(&self) -> &mut Version<T, A> {
+        unsafe {
+            let off = AtomicU64::from_ptr(&self.head as *const u64 as *mut u64)
+                .load(Ordering::Acquire);
+            A::get_mut_unchecked(off)
+        }
+    }
+
+    /// Returns the newest version of the value visible to `snapshot`
+    ///
+    /// It never blocks, even if a writer holds the cell.
+    ///
+    /// # Errors
+    ///
+    /// It returns [`Error::NoVisibleVersion`] if the cell has no version as
+    /// old as the snapshot.
+    ///
+    /// [`Error::NoVisibleVersion`]: ../enum.Error.html#variant.NoVisibleVersion
+    pub fn read<'a>(&'a self, snapshot: &'a Snapshot<A>) -> crate::result::Result<&'a T> {
+        let ts = snapshot.timestamp();
+        let mut curr = self.head();
+        while !curr.visible(ts) {
+            curr = curr.next().ok_or(Error::NoVisibleVersion(ts))?;
+        }
+        Ok(&curr.value)
+    }
+
+    /// Sets the value as a new version, which becomes visible to the
+    /// snapshots taken after the transaction commits
+    ///
+    /// It locks the cell until the end of the transaction. Setting it again
+    /// in the same transaction replaces the uncommitted version.
+    pub fn set(&self, v: T, journal: &Journal<A>) {
+        let _guard = self.lock.lock(journal);
+        let head = self.head();
+        if head.is_uncommitted() {
+            head.value = v;
+            persist_obj(&head.value, true);
+            return;
+        }
+        unsafe {
+            let off = Self::alloc(Version {
+                ts: u64::MAX,
+                gen: A::gen(),
+                next: self.head,
+                value: v,
+                phantom: PhantomData,
+            }, journal);
+            Log::stamp_on_commit(off, journal);
+            self.head.create_log(journal, Notifier::None);
+            AtomicU64::from_ptr(&self.head as *const u64 as *mut u64)
+                .store(off, Ordering::Release);
+        }
+        self.prune(journal);
+    }
+
+    /// Replaces the value with the result of `f` on the latest version
+    pub fn update<F: FnOnce(&T) -> T>(&self, f: F, journal: &Journal<A>) {
+        let v = {
+            let _guard = self.lock.lock(journal);
+            f(&self.head().value)
+        };
+        self.set(v, journal);
+    }
+
+    /// Reclaims the versions that no snapshot can observe anymore
+    fn prune(&self, journal: &Journal<A>) {
+        let oldest = oldest_snapshot();
+        let mut curr = self.head();
+        while curr.is_uncommitted() || !curr.visible(oldest) {
+            curr = match curr.next() {
+                Some(next) => next,
+                None => return,
+            };
+        }
+        let mut next = curr.next;
+        if next == u64::MAX {
+            return;
+        }
+        unsafe {
+            curr.next.create_log(journal, Notifier::None);
+            curr.next = u64::MAX;
+            while next != u64::MAX {
+                let v = A::get_mut_unchecked::<Version<T, A>>(next);
+                next = v.next;
+                drop(Pbox::<Version<T, A>, A>::from_raw(v));
+            }
+        }
+    }
+
+    /// Returns the number of versions retained in the cell
+    pub fn versions(&self) -> usize {
+        let mut cnt = 1;
+        let mut curr = self.head();
+        while let Some(next) = curr.next() {
+            cnt += 1;
+            curr = next;
+        }
+        cnt
+    }
+}
+
+impl<T: PSafe, A: MemPool> Drop for VersionedCell<T, A> {
+    fn drop(&mut self) {
+        let mut next = self.head;
+        while next != u64::MAX {
+            unsafe {
+                let v = A::get_mut_unchecked::<Version<T, A>>(next);
+                next = v.next;
+                drop(Pbox::<Version<T, A>, A>::from_raw(v));
+            }
+        }
+    }
+}
+
+impl<T: PSafe + Default, A: MemPool> RootObj<A> for VersionedCell<T, A> {
+    fn init(journal: &Journal<A>) -> Self {
+        Self::new(T::default(), journal)
+    }
+}
+
+impl<T: PSafe + PClone<A>, A: MemPool> PClone<A> for VersionedCell<T, A> {
+    /// Creates a new cell with a copy of the latest version
+    fn pclone(&self, journal: &Journal<A>) -> Self {
+        Self::new(self.head().value.pclone(journal), journal)
+    }
+}
+
+impl<T: PSafe + Trace<A>, A: MemPool> Trace<A> for VersionedCell<T, A> {
+    fn trace<V: Visitor>(&self, v: &mut V) {
+        let size = std::mem::size_of::<Version<T, A>>();
+        let mut off = &self.head;
+        while *off != u64::MAX && v.visit(off, size) {
+            let version = unsafe { A::get_unchecked::<Version<T, A>>(*off) };
+            version.value.trace(v);
+            off = &version.next;
+        }
+    }
+}
+
+impl<T: PSafe + fmt::Debug, A: MemPool> fmt::Debug for VersionedCell<T, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("VersionedCell")
+            .field("latest", &self.head().value)
+            .field("versions", &self.versions())
+            .finish()
+    }
+}
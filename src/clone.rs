@@ -87,8 +87,11 @@ impl<T: PSafe + PClone<A>, A: MemPool> PClone<A> for Option<T> {
     }
 }
 
-impl<T: PSafe + PClone<A> + ?Sized, A: MemPool> PClone<A> for &[T] {
+impl<T: PSafe + PClone<A>, A: MemPool> PClone<A> for &[T] {
     fn pclone(&self, j: &Journal<A>) -> Self {
+        if self.is_empty() {
+            return &[];
+        }
         let res = unsafe { A::new_slice(self, j) };
         for i in 0..res.len() {
             res[i] = self[i].pclone(j);
@@ -99,18 +102,14 @@ impl<T: PSafe + PClone<A> + ?Sized, A: MemPool> PClone<A> for &[T] {
 
 impl<T: PSafe + PClone<A>, A: MemPool, const N: usize> PClone<A> for [T;N] {
     fn pclone(&self, j: &Journal<A>) -> Self {
-        use std::mem::MaybeUninit;
-        let mut res = unsafe { MaybeUninit::<Self>::uninit().assume_init() };
-        for i in 0..res.len() {
-            std::mem::forget(std::mem::replace(&mut res[i],self[i].pclone(j)));
-        }
-        res
+        std::array::from_fn(|i| self[i].pclone(j))
     }
 }
 
 use impl_trait_for_tuples::*;
 
 #[impl_for_tuples(32)]
+#[allow(clippy::unused_unit)]
 impl<A: MemPool> PClone<A> for Tuple {
     fn pclone(&self, j: &Journal<A>) -> Self {
         for_tuples!( ( #( Tuple.pclone(j) ),* ) )
@@ -0,0 +1,234 @@
+//! Deterministic crash injection
+//!
+//! To enable it, you need to add `"crash_test"` to the feature list. A
+//! [`CrashTest`] runs a workload on a fresh pool, and takes a snapshot of the
+//! pool file at every *persist point*, or only at the Nth one. Then, it opens
+//! every snapshot, which runs the recovery procedure, and checks the
+//! recovered root object with an invariant checker. If the workload is
+//! deterministic, a failing persist point can be reproduced with
+//! [`at()`](struct.CrashTest.html#method.at).
+//!
+//! A persist point is a call to [`ll::persist`] (or [`ll::persist_obj`]) on
+//! the memory of the pool, or a call to [`ll::sfence`] after an unfenced
+//! flush in the same thread. A snapshot contains every store issued before
+//! its persist point, flushed or not; so, it models a crash right after that
+//! point, but not the reordering of the stores which are not flushed yet.
+//!
+//! # Examples
+//!
+//! ```
+//! use corundum::default::*;
+//! use corundum::crash::CrashTest;
+//!
+//! type P = Allocator;
+//!
+//! #[derive(Root)]
+//! struct Accounts {
+//!     checking: PCell<i32>,
+//!     savings: PCell<i32>,
+//! }
+//!
+//! let points = CrashTest::new("accounts.pool").run::<P, Accounts, _, _>(
+//!     |root| {
+//!         P::transaction(|j| {
+//!             root.checking.set(root.checking.get() - 10, j);
+//!             root.savings.set(root.savings.get() + 10, j);
+//!         }).unwrap();
+//!     },
+//!     |root| {
+//!         assert_eq!(root.checking.get() + root.savings.get(), 0);
+//!     }
+//! ).unwrap();
+//!
+//! assert!(points > 0);
+//! ```
+//!
+//! [`ll::persist`]: ../ll/fn.persist.html
+//! [`ll::persist_obj`]: ../ll/fn.persist_obj.html
+//! [`ll::sfence`]: ../ll/fn.sfence.html
+
+use crate::alloc::MemPool;
+use crate::result::Result;
+use crate::{Error, PSafe, RootObj};
+use crate::open_flags::*;
+use std::cell::Cell;
+use std::fs;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+static ARMED: AtomicBool = AtomicBool::new(false);
+static RECORDER: Mutex<Option<Recorder>> = Mutex::new(None);
+static RUNNING: Mutex<()> = Mutex::new(());
+
+thread_local! {
+    /// True if this thread has flushed the pool without a fence since the
+    /// last persist point
+    static PENDING: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Takes the snapshots of a pool file while a workload is running
+struct Recorder {
+    path: String,
+    dir: PathBuf,
+    rng: std::ops::Range<u64>,
+    at: Option<usize>,
+    count: usize,
+    snapshots: Vec<(usize, PathBuf)>,
+}
+
+impl Recorder {
+    fn record(&mut self) {
+        self.count += 1;
+        if self.at.is_none_or(|n| n == self.count) {
+            let snapshot = self.dir.join(format!("{}.pool", self.count));
+            if let Err(e) = fs::copy(&self.path, &snapshot) {
+                panic!("Could not take snapshot {}: {}", snapshot.display(), e);
+            }
+            self.snapshots.push((self.count, snapshot));
+        }
+    }
+}
+
+fn record() {
+    let mut recorder = match RECORDER.lock() {
+        Ok(g) => g,
+        Err(p) => p.into_inner(),
+    };
+    if let Some(recorder) = recorder.as_mut() {
+        recorder.record();
+    }
+}
+
+#[doc(hidden)]
+/// Marks a persist point if `len` bytes at `ptr` are in the pool under test
+pub fn persist_point(ptr: *const u8, len: usize, fence: bool) {
+    if !ARMED.load(Ordering::Acquire) {
+        return;
+    }
+    let addr = ptr as u64;
+    let in_pool = match RECORDER.lock() {
+        Ok(g) => g.as_ref().is_some_and(|r| {
+            addr < r.rng.end && addr + len as u64 > r.rng.start
+        }),
+        Err(_) => false,
+    };
+    if in_pool {
+        PENDING.with(|p| p.set(!fence));
+        record();
+    }
+}
+
+#[doc(hidden)]
+/// Marks a persist point if this thread has an unfenced flush
+pub fn fence_point() {
+    if ARMED.load(Ordering::Acquire) && PENDING.with(|p| p.replace(false)) {
+        record();
+    }
+}
+
+/// Disarms the recorder even if the workload panics
+struct Disarm;
+
+impl Drop for Disarm {
+    fn drop(&mut self) {
+        ARMED.store(false, Ordering::Release);
+        PENDING.with(|p| p.set(false));
+    }
+}
+
+/// A deterministic crash-consistency test
+///
+/// See the [module-level documentation](index.html) for more details.
+pub struct CrashTest {
+    path: String,
+    at: Option<usize>,
+}
+
+impl CrashTest {
+    /// Creates a new crash test which uses the pool file at `path`
+    ///
+    /// The file is formatted at the beginning of every run. The snapshots
+    /// are stored in the `<path>.crash` directory.
+    pub fn new(path: &str) -> Self {
+        Self {
+            path: path.to_string(),
+            at: None,
+        }
+    }
+
+    /// Only takes a snapshot at the `n`th persist point (starting from 1)
+    pub fn at(mut self, n: usize) -> Self {
+        self.at = Some(n);
+        self
+    }
+
+    /// Runs `workload` on a fresh pool, and then checks the invariants of
+    /// the recovered root object with `check` at the persist points
+    ///
+    /// The pool is created and its root object is initialized before the
+    /// workload starts, so the persist points of the initialization are not
+    /// counted. `check` is also applied on the final state of the pool. It
+    /// should panic if an invariant does not hold.
+    ///
+    /// It returns the number of the persist points in the workload. If
+    /// `check` fails on a snapshot, the snapshot is kept, and its path is
+    /// reported in [`Error::Inconsistent`](../enum.Error.html).
+    pub fn run<P, R, W, C>(&self, workload: W, check: C) -> Result<usize>
+    where
+        P: MemPool,
+        R: RootObj<P> + PSafe,
+        W: FnOnce(&R),
+        C: Fn(&R),
+    {
+        let _running = match RUNNING.lock() {
+            Ok(g) => g,
+            Err(p) => p.into_inner(),
+        };
+        let dir = PathBuf::from(format!("{}.crash", self.path));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir)?;
+
+        {
+            let root = P::open::<R>(&self.path, O_CF)?;
+            *RECORDER.lock().unwrap() = Some(Recorder {
+                path: self.path.clone(),
+                dir: dir.clone(),
+                rng: P::rng(),
+                at: self.at,
+                count: 0,
+                snapshots: vec![],
+            });
+            let _disarm = Disarm;
+            ARMED.store(true, Ordering::Release);
+            workload(&root);
+        }
+        let recorder = RECORDER.lock().unwrap().take().unwrap();
+
+        let mut snapshots = recorder.snapshots;
+        snapshots.push((recorder.count + 1, PathBuf::from(&self.path)));
+        for (n, snapshot) in snapshots {
+            let file = snapshot.to_string_lossy().to_string();
+            let res = catch_unwind(AssertUnwindSafe(|| -> Result<()> {
+                let root = P::open::<R>(&file, O_CFNE)?;
+                check(&root);
+                Ok(())
+            }));
+            let point = if n > recorder.count { None } else { Some(n) };
+            match res {
+                Ok(Ok(())) => if n <= recorder.count {
+                    let _ = fs::remove_file(&snapshot);
+                },
+                Ok(Err(e)) => {
+                    return Err(Error::Recovery { point, source: Box::new(e) });
+                }
+                Err(_) => {
+                    return Err(Error::Inconsistent { point, snapshot: file });
+                }
+            }
+        }
+        let _ = fs::remove_dir(&dir);
+        Ok(recorder.count)
+    }
+}
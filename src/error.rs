@@ -0,0 +1,225 @@
+//! The error type of the fallible operations
+
+use std::{error, fmt, io};
+
+/// The error type of the fallible operations, such as opening a pool or
+/// running a transaction
+///
+/// # Examples
+///
+/// ```
+/// use corundum::default::*;
+/// use corundum::Error;
+///
+/// type P = Allocator;
+///
+/// match P::open::<PCell<i32>>("missing.pool", O_F) {
+///     Err(Error::NotFound(path)) => assert_eq!(path, "missing.pool"),
+///     _ => unreachable!(),
+/// }
+///
+/// let _root = P::open::<PCell<i32>>("foo.pool", O_CF).unwrap();
+/// let res = P::transaction(|_| panic!("abort"));
+/// assert!(matches!(res, Err(Error::Aborted(Some(ref msg))) if msg == "abort"));
+/// ```
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// An I/O operation on a pool file failed
+    Io(io::Error),
+
+    /// The pool file does not exist
+    NotFound(String),
+
+    /// The file is not a valid pool image, or its metadata is corrupted
+    InvalidPool(String),
+
+    /// The open flags are not valid
+    InvalidFlags(&'static str),
+
+    /// The requested pool size is not valid
+    InvalidSize(String),
+
+    /// The pool file is already open by another instance
+    AlreadyOpen(String),
+
+    /// The pool is not open
+    NotOpen,
+
+    /// All instances of the pool type are in use
+    TooManyInstances {
+        /// The name of the pool type
+        pool: &'static str,
+
+        /// The maximum number of instances
+        max: usize,
+    },
+
+    /// The pool type does not support the operation
+    Unsupported(String),
+
+    /// A zero-sized type cannot be the root type
+    ZeroSizedRoot,
+
+    /// The root type is not compatible with the root object of the pool
+    IncompatibleRoot,
+
+    /// There is no migration path from the stored version of the root type
+    NoMigrationPath(u32),
+
+    /// The operation is not allowed while a transaction is running
+    TransactionRunning,
+
+    /// The pool cannot be opened while it has an uncommitted transaction
+    UncommittedTransaction,
+
+    /// The transaction panicked, and it is rolled back
+    ///
+    /// It contains the panic message, if it is a string.
+    Aborted(Option<String>),
+
+    /// The transaction could not acquire its locks in the given number of
+    /// attempts
+    LockTimeout(usize),
+
+    /// The offset does not point to an allocated object
+    AccessViolation(u64),
+
+    /// The address is not in the pool
+    OutOfRange(u64),
+
+    /// The object is already initialized
+    AlreadyInitialized,
+
+    /// The object is not in the persistent memory
+    NotInPool,
+
+    /// No version of a versioned object is visible to the snapshot with the
+    /// given timestamp
+    NoVisibleVersion(u64),
+
+    /// Another chaperoned session is open
+    ChaperonBusy,
+
+    /// The chaperon file name is not valid
+    InvalidChaperon(String),
+
+    /// The chaperon file could not be created
+    Chaperon {
+        /// The chaperon file name
+        path: String,
+
+        /// The underlying error
+        source: io::Error,
+    },
+
+    /// The invariants do not hold in a snapshot taken by a crash test
+    Inconsistent {
+        /// The persist point at which the snapshot was taken, which can be
+        /// passed to [`CrashTest::at`](crash/struct.CrashTest.html#method.at),
+        /// or `None` for the end of the workload
+        point: Option<usize>,
+
+        /// The path of the snapshot
+        snapshot: String,
+    },
+
+    /// A snapshot taken by a crash test could not be recovered
+    Recovery {
+        /// The persist point at which the snapshot was taken, which can be
+        /// passed to [`CrashTest::at`](crash/struct.CrashTest.html#method.at),
+        /// or `None` for the end of the workload
+        point: Option<usize>,
+
+        /// The underlying error
+        source: Box<Error>,
+    },
+}
+
+impl Error {
+    /// Creates an `Aborted` error from the payload of a panic
+    pub(crate) fn aborted(payload: Box<dyn std::any::Any + Send>) -> Self {
+        if let Some(msg) = payload.downcast_ref::<&str>() {
+            Error::Aborted(Some(msg.to_string()))
+        } else if let Ok(msg) = payload.downcast::<String>() {
+            Error::Aborted(Some(*msg))
+        } else {
+            Error::Aborted(None)
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "{}", e),
+            Error::NotFound(path) => write!(f, "Pool file `{}` does not exist", path),
+            Error::InvalidPool(msg) => write!(f, "{}", msg),
+            Error::InvalidFlags(msg) => write!(f, "{}", msg),
+            Error::InvalidSize(msg) => write!(f, "{}", msg),
+            Error::AlreadyOpen(path) => write!(f, "Pool file `{}` is already open", path),
+            Error::NotOpen => write!(f, "No memory pool is open"),
+            Error::TooManyInstances { pool, max } => {
+                write!(f, "Cannot open more than {} instances of `{}`", max, pool)
+            }
+            Error::Unsupported(msg) => write!(f, "{}", msg),
+            Error::ZeroSizedRoot => write!(f, "root type cannot be a ZST"),
+            Error::IncompatibleRoot => write!(f, "Incompatible root type"),
+            Error::NoMigrationPath(version) => {
+                write!(f, "No migration path from root schema version {}", version)
+            }
+            Error::TransactionRunning => {
+                write!(f, "The operation is not allowed while a transaction is running")
+            }
+            Error::UncommittedTransaction => {
+                write!(f, "An uncommitted transaction exists in the pool")
+            }
+            Error::Aborted(None) => write!(f, "Unsuccessful transaction"),
+            Error::Aborted(Some(msg)) => write!(f, "Unsuccessful transaction: {}", msg),
+            Error::LockTimeout(attempts) => {
+                write!(f, "Unsuccessful transaction: lock timed out in {} attempt(s)", attempts)
+            }
+            Error::AccessViolation(off) => write!(f, "Access Violation (0x{:x})", off),
+            Error::OutOfRange(addr) => write!(f, "out of valid range (0x{:x})", addr),
+            Error::AlreadyInitialized => write!(f, "already initialized"),
+            Error::NotInPool => write!(f, "The object is not in the PM"),
+            Error::NoVisibleVersion(ts) => {
+                write!(f, "No version is visible to the snapshot at {}", ts)
+            }
+            Error::ChaperonBusy => write!(f, "Another chaperoned transaction is open"),
+            Error::InvalidChaperon(msg) => write!(f, "{}", msg),
+            Error::Chaperon { path, .. } => {
+                write!(f, "could not create chaperon file `{}`", path)
+            }
+            Error::Inconsistent { point: Some(n), snapshot } => {
+                write!(f, "Invariants do not hold at persist point {} (snapshot: {})", n, snapshot)
+            }
+            Error::Inconsistent { point: None, snapshot } => {
+                write!(f, "Invariants do not hold at the end of the workload (snapshot: {})", snapshot)
+            }
+            Error::Recovery { point: Some(n), .. } => {
+                write!(f, "Could not recover the snapshot at persist point {}", n)
+            }
+            Error::Recovery { point: None, .. } => {
+                write!(f, "Could not recover the pool at the end of the workload")
+            }
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::Io(e) => Some(e),
+            Error::Chaperon { source, .. } => Some(source),
+            Error::Recovery { source, .. } => Some(source.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
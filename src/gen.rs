@@ -11,7 +11,7 @@ use crate::stm::Journal;
 use crate::clone::PClone;
 use crate::alloc::*;
 use crate::ptr::*;
-use crate::stm::{Logger,Notifier};
+use crate::stm::Logger;
 
 pub static mut CODE_SEGMENT_BASE: i64 = 0;
 
@@ -136,6 +136,16 @@ impl<T, P: MemPool> PClone<P> for ByteArray<T, P> {
     }
 }
 
+impl<T, P: MemPool> Trace<P> for ByteArray<T, P> {
+    /// Visits the byte array without traversing its contents as its type is
+    /// opaque
+    fn trace<V: Visitor>(&self, v: &mut V) {
+        if self.bytes.capacity() > 0 {
+            v.visit(&self.bytes.off, self.bytes.capacity());
+        }
+    }
+}
+
 impl<T, P: MemPool> Drop for ByteArray<T, P> {
     fn drop(&mut self) {
         unsafe {
@@ -233,9 +243,7 @@ impl<T, P: MemPool> ByteArray<T, P> {
     #[inline]
     /// Swaps the contents of two `ByteArray`s
     pub fn swap(&mut self, other: &mut Self) {
-        let slice = self.bytes;
-        self.bytes = other.bytes;
-        other.bytes = slice;
+        std::mem::swap(&mut self.bytes, &mut other.bytes);
     }
 
     #[inline]
@@ -248,7 +256,7 @@ impl<T, P: MemPool> ByteArray<T, P> {
         unsafe {
             let slice = utils::as_mut(self).bytes.as_slice_mut();
             if self.logged == 0 {
-                slice.create_log(j, Notifier::NonAtomic(Ptr::from_ref(&self.logged)));
+                slice.create_log(j, j.notifier(&self.logged));
             }
             std::ptr::copy_nonoverlapping(new.ptr, slice as *mut [u8] as *mut c_void, slice.len())
         }
@@ -296,7 +304,7 @@ impl<T, P: MemPool> Gen<T, P> {
     #[inline]
     fn from_ptr(obj: *const T) -> Self {
         Self {
-            ptr: obj as *const T as *const c_void,
+            ptr: obj as *const c_void,
             len: size_of::<T>(),
             destructor_address: 0,
             phantom: PhantomData
@@ -13,7 +13,7 @@
 //! | Unlogged Updates    | An unrecoverable update to persistent data | Modifications are enforced to be inside atomic [`transaction`]s. | 
 //! | Data Race           | Updating persistent data simultaneously in two threads | Mutable borrowing is limited to [`PMutex`] which uses a transaction-wide lock to provide both atomicity and isolation. |
 //! | Locked Mutex        | A persistent mutex remains locked on powerfail | [`PMutex`] uses [`VCell`] which resets at restart. |
-//! | Memory Leaks\*      | An allocated memory becomes unreachable | Persistent objects, except the root object, cannot cross transaction boundaries, and memory allocation is available only inside a transaction. Therefore, the allocation can survive only if there is a reference from the root object (or a decedent of it) to the data. <br>\* Cyclic references are not prevented in this version, which lead to a memory leak. Please visit [`this link`] for the information on how to manually resolve that issue. |
+//! | Memory Leaks\*      | An allocated memory becomes unreachable | Persistent objects, except the root object, cannot cross transaction boundaries, and memory allocation is available only inside a transaction. Therefore, the allocation can survive only if there is a reference from the root object (or a decedent of it) to the data. <br>\* Cyclic references are not prevented in this version, which lead to a memory leak. Please visit [`this link`] for the information on how to manually resolve that issue, or how to reclaim the leaked memory. |
 //!
 //! For more technical details on the implementation, please refer to Corundum's
 //! academic [paper] and/or watch the [presentation] 📺.
@@ -48,11 +48,13 @@
 //! following memory cells:
 //! 
 //! * [`PCell<T,P>`] (or [`PCell<T>`]): An unborrowable, mutable persistent
-//! memory location for a value of type `T` in pool `P`.
+//!   memory location for a value of type `T` in pool `P`.
 //! * [`PRefCell<T,P>`] (or [`PRefCell<T>`]): A mutable persistent memory location
-//! with dynamically checked borrow rules for a value of type `T` in pool `P`.
+//!   with dynamically checked borrow rules for a value of type `T` in pool `P`.
 //! * [`PMutex<T,P>`] (or [`PMutex<T>`]): A mutual exclusion primitive useful for
-//! protecting shared persistent data of type `T` in pool `P`.
+//!   protecting shared persistent data of type `T` in pool `P`.
+//! * [`PRwLock<T,P>`] (or [`PRwLock<T>`]): A reader-writer lock which allows
+//!   journal-free concurrent reads of shared persistent data.
 //! 
 //! The following example creates a pool file for a linked-list-based stack, and
 //! obtains the root object of type `Node`.
@@ -82,6 +84,18 @@
 //! }
 //! ```
 //! 
+//! # Tracing
+//! With the `tracing` feature, Corundum reports its activity to the
+//! [`tracing`](https://docs.rs/tracing) ecosystem under the `corundum` target.
+//! Each transaction is a `transaction` span, which ends with a `commit` or a
+//! `rollback` event. Within it, `log` events are emitted for the created logs
+//! (e.g. `DataLog`, `DropOnCommit`, and `UnlockOnCommit`), `alloc` and
+//! `dealloc` events for the allocations, and `persist` events for all flushes
+//! to the persistent memory. Every span and event carries the name of the
+//! pool, and the offsets and the sizes of the affected memory, where they
+//! apply. A `persist` event of memory outside all open pools carries the
+//! address instead.
+//! 
 //! [`PSafe`]: ./trait.PSafe.html
 //! [`VCell`]: ./cell/struct.VCell.html
 //! [`VWeak`]: ./prc/struct.VWeak.html
@@ -99,40 +113,35 @@
 //! [`PRefCell<T>`]: ./alloc/default/type.PRefCell.html
 //! [`PMutex<T,P>`]: ./sync/struct.PMutex.html
 //! [`PMutex<T>`]: ./alloc/default/type.PMutex.html
+//! [`PRwLock<T,P>`]: ./sync/struct.PRwLock.html
+//! [`PRwLock<T>`]: ./alloc/default/type.PRwLock.html
 //! [`open<T>()`]: ./alloc/struct.MemPool.html#method.open
 
 #![feature(auto_traits)]
 #![feature(specialization)]
-#![feature(concat_idents)]
-#![feature(core_intrinsics)]
+#![cfg_attr(feature = "use_ntstore", feature(core_intrinsics))]
 #![feature(thread_id_value)]
 #![feature(negative_impls)]
-#![feature(backtrace)]
-#![feature(trusted_len)]
-#![feature(exact_size_is_empty)]
-#![feature(alloc_layout_extra)]
 #![feature(dropck_eyepatch)]
-#![feature(trivial_bounds)]
 #![feature(stmt_expr_attributes)]
-#![feature(trait_alias)]
-#![feature(slice_concat_trait)]
-#![feature(slice_partition_dedup)]
-#![feature(type_name_of_val)]
 #![feature(pattern)]
-#![feature(str_internals)]
-#![feature(toowned_clone_into)]
 #![feature(fn_traits)]
 #![feature(unboxed_closures)]
-#![feature(let_chains)]
-#![feature(c_variadic)]
 #![feature(rustc_attrs)]
-#![feature(allocator_api)]
-#![feature(associated_type_bounds)]
 // #![feature(async_stream)]
 
 #![allow(dead_code)]
 #![allow(incomplete_features)]
+#![allow(internal_features)]
 #![allow(type_alias_bounds)]
+#![allow(static_mut_refs)]
+#![allow(clippy::len_without_is_empty)]
+#![allow(clippy::missing_safety_doc)]
+#![allow(clippy::module_inception)]
+#![allow(clippy::mut_from_ref)]
+#![allow(clippy::new_ret_no_self)]
+#![allow(clippy::new_without_default)]
+#![allow(clippy::should_implement_trait)]
 
 pub(crate) const PAGE_LOG_SLOTS: usize = 128;
 
@@ -149,36 +158,47 @@ pub mod utils;
 pub mod stl;
 pub mod gen;
 
+#[cfg(feature = "crash_test")]
+pub mod crash;
+
 mod alloc;
 mod boxed;
 mod cell;
 mod clone;
 mod str;
 pub mod vec;
+pub mod vec_deque;
 mod convert;
+mod error;
 mod marker;
 mod tests;
 
 pub use cell::RootObj;
-pub use stm::transaction;
+pub use stm::{transaction, transaction_with, read_transaction};
 pub use marker::*;
 pub use crndm_derive::*;
 pub use boxed::*;
 pub use prc::Prc;
-pub use sync::{Parc,PMutex};
+pub use sync::{Parc,PMutex,PRwLock,PCondvar,PAtomicU64,PAtomicPtr};
 pub use clone::*;
 pub use vec::Vec as PVec;
+pub use vec_deque::VecDeque as PVecDeque;
 pub use self::str::{String as PString, ToPString, ToPStringSlice};
 pub use cell::*;
 pub use alloc::*;
 pub use convert::*;
 pub use stm::Journal;
+pub use error::Error;
+
+#[cfg(feature = "tracing")]
+#[doc(hidden)]
+pub use tracing as __tracing;
 
 // This is an example of defining a new buddy allocator type
 // `Allocator` is the default allocator with Buddy Allocation algorithm
 crate::pool!(default);
 
-/// A `Result` type with string error messages
+/// A `Result` type with [`Error`](../enum.Error.html)
 pub mod result {
-    pub type Result<T: ?Sized> = std::result::Result<T, String>;
+    pub type Result<T: ?Sized> = std::result::Result<T, crate::Error>;
 }
\ No newline at end of file
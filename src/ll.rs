@@ -15,6 +15,44 @@ use std::arch::x86::{_mm_mfence, _mm_sfence, clflush};
 #[cfg(target_arch = "x86_64")]
 use std::arch::x86_64::{_mm_clflush, _mm_mfence, _mm_sfence};
 
+/// Address ranges of the open pools along with their names, so that the
+/// `persist` events can be tagged with the pool and the offset
+#[cfg(feature = "tracing")]
+static POOLS: std::sync::RwLock<Vec<(u64, u64, &'static str)>> =
+    std::sync::RwLock::new(Vec::new());
+
+/// Registers the range `[start, end)` of an open pool named `name`, or
+/// updates it if the pool is already registered
+#[doc(hidden)]
+#[inline]
+pub fn register_pool(start: u64, end: u64, name: &'static str) {
+    #[cfg(feature = "tracing")]
+    {
+        let mut pools = POOLS.write().unwrap_or_else(|p| p.into_inner());
+        match pools.iter_mut().find(|p| p.0 == start) {
+            Some(p) => *p = (start, end, name),
+            None => pools.push((start, end, name)),
+        }
+    }
+}
+
+/// Unregisters the pool starting at `start`
+#[doc(hidden)]
+#[inline]
+pub fn unregister_pool(start: u64) {
+    #[cfg(feature = "tracing")]
+    POOLS.write().unwrap_or_else(|p| p.into_inner()).retain(|p| p.0 != start);
+}
+
+/// Returns the name of the open pool containing `addr` and the offset of
+/// `addr` in it
+#[cfg(feature = "tracing")]
+fn resolve(addr: u64) -> Option<(&'static str, u64)> {
+    POOLS.read().unwrap_or_else(|p| p.into_inner()).iter()
+        .find(|p| addr >= p.0 && addr < p.1)
+        .map(|p| (p.2, addr - p.0))
+}
+
 /// Synchronize caches and memories and acts like a write barrier
 #[inline(always)]
 pub fn persist_with_log<T: ?Sized, A: MemPool>(ptr: *const T, len: usize, fence: bool) {
@@ -24,12 +62,32 @@ pub fn persist_with_log<T: ?Sized, A: MemPool>(ptr: *const T, len: usize, fence:
             A::off_unchecked(ptr) + (len as u64 - 1), len   
         );
     }
-    persist(ptr, len, fence)
+    crate::__trace_event!(A, "persist",
+        off = ptr as *const u8 as u64 - A::start(), len = len, fence = fence);
+    flush(ptr, len, fence)
 }
 
 /// Synchronize caches and memories and acts like a write barrier
+///
+/// With the `tracing` feature, the event is tagged with the pool containing
+/// `ptr` and the offset in it. If no open pool contains `ptr`, the event
+/// carries the address instead.
 #[inline(always)]
 pub fn persist<T: ?Sized>(ptr: *const T, len: usize, fence: bool) {
+    flush(ptr, len, fence);
+
+    #[cfg(feature = "tracing")]
+    match resolve(ptr as *const u8 as u64) {
+        Some((pool, off)) => crate::__tracing::trace!(target: "corundum",
+            pool = pool, off = off, len = len, fence = fence, "persist"),
+        None => crate::__tracing::trace!(target: "corundum",
+            addr = ptr as *const u8 as u64, len = len, fence = fence, "persist"),
+    }
+}
+
+/// Flushes `len` bytes at `ptr` without emitting a `persist` event
+#[inline(always)]
+fn flush<T: ?Sized>(ptr: *const T, len: usize, fence: bool) {
     #[cfg(feature = "stat_perf")]
     let _perf = crate::stat::Measure::<crate::default::Allocator>::Sync(std::time::Instant::now());
 
@@ -55,6 +113,9 @@ pub fn persist<T: ?Sized>(ptr: *const T, len: usize, fence: bool) {
             }
         }
     }
+
+    #[cfg(feature = "crash_test")]
+    crate::crash::persist_point(ptr as *const u8, len, fence);
 }
 
 #[inline(always)]
@@ -127,12 +188,15 @@ pub fn sfence() {
     #[cfg(any(feature = "use_clwb", feature = "use_clflushopt"))] unsafe {
         _mm_sfence();
     }
+
+    #[cfg(feature = "crash_test")]
+    crate::crash::fence_point();
 }
 
 /// Memory fence
 #[inline]
 pub fn mfence() {
     unsafe {
-        std::intrinsics::atomic_fence_acqrel()
+        std::sync::atomic::fence(std::sync::atomic::Ordering::AcqRel)
     }
 }
@@ -8,7 +8,6 @@ use std::pin::Pin;
 use std::ops::{Deref, DerefMut};
 use std::future::Future;
 use std::panic::{RefUnwindSafe, UnwindSafe};
-use std::any::Any;
 use std::cell::UnsafeCell;
 use std::fmt;
 
@@ -128,7 +127,7 @@ pub unsafe auto trait LooseTxInUnsafe {}
 
 /// Any type is okay to be transferred to a transaction
 //unsafe impl<T> LooseTxInUnsafe for dyn Any {}
-unsafe impl<'a, T> LooseTxInUnsafe for &'a mut T {}
+unsafe impl<T> LooseTxInUnsafe for &mut T {}
 unsafe impl<T> LooseTxInUnsafe for *const T {}
 unsafe impl<T> LooseTxInUnsafe for *mut T {}
 
@@ -234,6 +233,7 @@ pub unsafe auto trait VSafe {}
 
 unsafe impl<T: ?Sized> VSafe for *const T {}
 unsafe impl<T: ?Sized> VSafe for *mut T {}
+unsafe impl<T: ?Sized> VSafe for std::ptr::NonNull<T> {}
 unsafe impl<T: ?Sized> VSafe for &T {}
 unsafe impl<T: ?Sized> VSafe for &mut T {}
 
@@ -260,7 +260,7 @@ pub struct c_void {}
 
 impl Copy for c_void {}
 impl Clone for c_void { 
-    fn clone(&self) -> Self { Self { } }
+    fn clone(&self) -> Self { *self }
 }
 
 impl<P: MemPool> crate::clone::PClone<P> for c_void {
@@ -143,7 +143,13 @@
 //! C is dropped
 //! ```
 //! 
+//! Memory which is already leaked can be reclaimed by [`collect_garbage`]. It
+//! frees the allocations which are not reachable from the root object without
+//! dropping them, so it is not a replacement for weak links when the values
+//! have a meaningful `Drop`.
+//! 
 //! [`pclone`]: ./struct.Prc.html#method.pclone
+//! [`collect_garbage`]: ../alloc/trait.MemPoolTraits.html#method.collect_garbage
 //! 
 use std::panic::RefUnwindSafe;
 use std::panic::UnwindSafe;
@@ -504,8 +510,6 @@ impl<T: PSafe, A: MemPool> Prc<mem::MaybeUninit<T>, A> {
 }
 
 impl<T: PSafe, A: MemPool> Prc<MaybeUninit<T>, A> {
-    #[inline]
-
     /// Returns a mutable reference into the given `Prc`, if there are
     /// no other `Prc` or `Weak` pointers to the same allocation.
     ///
@@ -724,12 +728,25 @@ impl<T: PSafe, A: MemPool> PmemUsage for Prc<T, A> {
     }
 }
 
-impl<T: PSafe + PmemUsage + ?Sized, A: MemPool> PmemUsage for Prc<T, A> {
+impl<T: PSafe + PmemUsage, A: MemPool> PmemUsage for Prc<T, A> {
     fn size_of() -> usize {
         Ptr::<PrcBox<T, A>, A>::size_of() + T::size_of()
     }
 }
 
+impl<T: PSafe + Trace<A>, A: MemPool> Trace<A> for Prc<T, A> {
+    #[inline]
+    fn trace<V: Visitor>(&self, v: &mut V) {
+        let counters = || {
+            let counter = self.counter();
+            (&counter.strong, &counter.weak)
+        };
+        if v.visit_strong(self.ptr.off_ref(), mem::size_of::<PrcBox<T, A>>(), counters) {
+            self.inner().value.trace(v);
+        }
+    }
+}
+
 impl<T: PSafe + ?Sized, A: MemPool> Deref for Prc<T, A> {
     type Target = T;
 
@@ -766,12 +783,11 @@ impl<T: PSafe, A: MemPool> Prc<T, A> {
     /// ```
     ///
     pub fn initialize(rc: &Option<Prc<T, A>>, value: T) -> crate::result::Result<()> {
-        assert!(
-            !Journal::<A>::is_running(),
-            "Prc::initialize() cannot be used inside a transaction"
-        );
+        if Journal::<A>::is_running() {
+            return Err(crate::Error::TransactionRunning);
+        }
         match rc {
-            Some(_) => Err("already initialized".to_string()),
+            Some(_) => Err(crate::Error::AlreadyInitialized),
             None => if A::valid(rc) {
                 unsafe {
                     let new = A::atomic_new(
@@ -810,7 +826,7 @@ impl<T: PSafe, A: MemPool> Prc<T, A> {
                 }
                 Ok(())
             } else {
-                Err("The object is not in the PM".to_string())
+                Err(crate::Error::NotInPool)
             }
         }
     }
@@ -1149,6 +1165,21 @@ impl<T: PSafe + ?Sized, A: MemPool> PClone<A> for Weak<T, A> {
     }
 }
 
+impl<T: PSafe, A: MemPool> Trace<A> for Weak<T, A> {
+    /// Visits the allocation without traversing the value. The value is
+    /// either dropped or reachable through a strong reference.
+    #[inline]
+    fn trace<V: Visitor>(&self, v: &mut V) {
+        if !self.ptr.is_dangling() {
+            let counters = || {
+                let counter = self.ptr.as_ref().counter();
+                (&counter.strong, &counter.weak)
+            };
+            v.visit_weak(self.ptr.off_ref(), mem::size_of::<PrcBox<T, A>>(), counters);
+        }
+    }
+}
+
 impl<T: PSafe + fmt::Debug + ?Sized, A: MemPool> fmt::Debug for Weak<T, A> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "(Weak)")
@@ -1162,8 +1193,25 @@ impl<T: PSafe + ?Sized, A: MemPool> RootObj<A> for Weak<T, A> {
 }
 
 trait PrcBoxPtr<T: PSafe + ?Sized, A: MemPool> {
+    /// Returns the counters in the allocation
     #[allow(clippy::mut_from_ref)]
-    fn count(&self) -> &mut Counter<A>;
+    fn counter(&self) -> &mut Counter<A>;
+
+    /// Returns the counters in effect, which is the draft in the scratchpad
+    /// if there is any
+    #[inline(always)]
+    #[allow(clippy::mut_from_ref)]
+    fn count(&self) -> &mut Counter<A> {
+        let ret = self.counter();
+
+        #[cfg(any(feature = "use_pspd", feature = "use_vspd"))] unsafe {
+            if let Some(inner) = *ret.temp {
+                return &mut *inner;
+            }
+        }
+
+        ret
+    }
 
     #[inline]
     fn strong(&self) -> usize {
@@ -1173,7 +1221,8 @@ trait PrcBoxPtr<T: PSafe + ?Sized, A: MemPool> {
     #[inline]
     #[cfg(not(feature = "no_log_rc"))]
     fn log_count(&self, journal: &Journal<A>) {
-        let inner = self.count();
+        // The draft itself should not be drafted again
+        let inner = self.counter();
         #[cfg(any(feature = "use_pspd", feature = "use_vspd"))] {
             if inner.temp.is_none() {
                 if let Some(p) = journal.draft(inner) {
@@ -1184,7 +1233,7 @@ trait PrcBoxPtr<T: PSafe + ?Sized, A: MemPool> {
         #[cfg(not(any(feature = "use_pspd", feature = "use_vspd")))] {
             if inner.has_log == 0 {
                 unsafe {
-                    inner.create_log(&*journal, Notifier::NonAtomic(Ptr::from_ref(&inner.has_log)));
+                    inner.create_log(journal, Notifier::NonAtomic(Ptr::from_ref(&inner.has_log)));
                 }
             }
         }
@@ -1192,10 +1241,10 @@ trait PrcBoxPtr<T: PSafe + ?Sized, A: MemPool> {
 
     #[inline]
     fn inc_strong(&self, _journal: &Journal<A>) {
-        let inner = self.count();
-        let strong = inner.strong;
+        let inner = self.counter();
+        let strong = self.strong();
 
-        if strong == 0 || strong == usize::max_value() {
+        if strong == 0 || strong == usize::MAX {
             std::process::abort();
         }
         #[cfg(not(feature = "no_log_rc"))] 
@@ -1212,7 +1261,7 @@ trait PrcBoxPtr<T: PSafe + ?Sized, A: MemPool> {
 
     #[inline]
     fn dec_strong(&self, _journal: &Journal<A>) {
-        let inner = self.count();
+        let inner = self.counter();
 
         #[cfg(not(feature = "no_log_rc"))]
         self.log_count(_journal);
@@ -1234,10 +1283,10 @@ trait PrcBoxPtr<T: PSafe + ?Sized, A: MemPool> {
 
     #[inline]
     fn inc_weak(&self, _journal: &Journal<A>) {
-        let inner = self.count();
+        let inner = self.counter();
         let weak = self.weak();
 
-        if weak == 0 || weak == usize::max_value() {
+        if weak == 0 || weak == usize::MAX {
             std::process::abort();
         }
 
@@ -1256,14 +1305,14 @@ trait PrcBoxPtr<T: PSafe + ?Sized, A: MemPool> {
 
     #[inline]
     fn dec_weak(&self, _journal: &Journal<A>) {
-        let inner = self.count();
+        let inner = self.counter();
 
         #[cfg(not(feature = "no_log_rc"))]
         self.log_count(_journal);
 
         #[cfg(any(feature = "use_pspd", feature = "use_vspd"))] unsafe {
             if let Some(inner) = *inner.temp {
-                (*inner).strong -= 1;
+                (*inner).weak -= 1;
                 return;
             }
         }
@@ -1274,36 +1323,21 @@ trait PrcBoxPtr<T: PSafe + ?Sized, A: MemPool> {
 
 impl<T: PSafe + ?Sized, A: MemPool> PrcBoxPtr<T, A> for Prc<T, A> {
     #[inline(always)]
-    fn count(&self) -> &mut Counter<A> {
-        let ret = &mut self.ptr.get_mut().counter;
-
-        #[cfg(any(feature = "use_pspd", feature = "use_vspd"))] unsafe {
-            if let Some(inner) = *ret.temp {
-                return &mut *inner;
-            }
-        }
-
-        ret
+    fn counter(&self) -> &mut Counter<A> {
+        &mut self.ptr.get_mut().counter
     }
 }
 
 impl<T: PSafe + ?Sized, A: MemPool> PrcBoxPtr<T, A> for PrcBox<T, A> {
     #[inline(always)]
-    fn count(&self) -> &mut Counter<A> {
-        let ret = unsafe {
+    #[allow(invalid_reference_casting)]
+    fn counter(&self) -> &mut Counter<A> {
+        unsafe {
             let ptr: *const Self = self;
             let ptr: *mut Self = ptr as *mut Self;
             let rcbox: &mut Self = &mut *ptr;
             &mut rcbox.counter
-        };
-
-        #[cfg(any(feature = "use_pspd", feature = "use_vspd"))] unsafe {
-            if let Some(inner) = *ret.temp {
-                return &mut *inner;
-            }
         }
-
-        ret
     }
 }
 
@@ -1332,7 +1366,7 @@ fn data_offset_sized<T, A: MemPool>() -> isize {
 #[inline]
 fn data_offset_align<A: MemPool>(align: usize) -> isize {
     let layout = std::alloc::Layout::new::<PrcBox<(), A>>();
-    (layout.size() + layout.padding_needed_for(align)) as isize
+    (layout.size().div_ceil(align) * align) as isize
 }
 
 /// `VWeak` is a version of [`Prc`] that holds a non-owning reference to the
@@ -1409,9 +1443,7 @@ impl<T: PSafe + ?Sized, A: MemPool> VWeak<T, A> {
     #[inline]
     fn inner(&self) -> Option<&PrcBox<T, A>> {
         unsafe {
-            if self.gen != A::gen() {
-                None
-            } else if !(*self.valid).valid {
+            if self.gen != A::gen() || !(*self.valid).valid {
                 None
             } else {
                 Some(&*self.ptr)
@@ -1447,19 +1479,15 @@ impl<T: ?Sized, A: MemPool> Drop for VWeak<T, A> {
     fn drop(&mut self) {
         unsafe {
             let this = &mut *self.valid;
-            if A::is_open() {
-                if self.gen == A::gen() {
-                    if !this.list.is_null() {
-                        let head = &mut (*this.list).head;
-                        if this.prev.is_null() {
-                            *head = this.next;
-                        } else {
-                            (*this.prev).next = this.next;
-                        }
-                        if !this.next.is_null() {
-                            (*this.next).prev = this.prev;
-                        }
-                    }
+            if A::is_open() && self.gen == A::gen() && !this.list.is_null() {
+                let head = &mut (*this.list).head;
+                if this.prev.is_null() {
+                    *head = this.next;
+                } else {
+                    (*this.prev).next = this.next;
+                }
+                if !this.next.is_null() {
+                    (*this.next).prev = this.prev;
                 }
             }
         }
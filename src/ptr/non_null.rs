@@ -24,9 +24,7 @@ pub struct NonNull<T: PSafe + ?Sized> {
 impl<T: PSafe + ?Sized> Copy for NonNull<T> {}
 impl<T: PSafe + ?Sized> Clone for NonNull<T> {
     fn clone(&self) -> Self {
-        Self {
-            ptr: self.ptr,
-        }
+        *self
     }
 }
 
@@ -37,7 +35,7 @@ impl<T: ?Sized> !Sync for NonNull<T> {}
 impl<T: PSafe + ?Sized> PartialEq for NonNull<T> {
     #[inline]
     fn eq(&self, other: &Self) -> bool {
-        self.ptr == other.ptr
+        std::ptr::addr_eq(self.ptr, other.ptr)
     }
 }
 
@@ -65,7 +63,7 @@ impl<T: PSafe> NonNull<T> {
         }
     }
 
-    pub fn as_ref<'a, 'b>(&'a self) -> &'b T {
+    pub fn as_ref<'b>(&self) -> &'b T {
         unsafe { &*self.ptr }
     }
 }
@@ -104,9 +102,9 @@ impl<T: fmt::Debug + PSafe + ?Sized> fmt::Debug for NonNull<T> {
 /// # Safety
 /// 
 /// * As in raw pointers, `LogNonNull` is `Copy` and is not bounded to a specific
-/// lifetime.
+///   lifetime.
 /// * It does not follow the borrow mechanism and thus multiple mutable access is
-/// possible.
+///   possible.
 /// 
 /// # Examples
 /// 
@@ -151,15 +149,7 @@ pub struct LogNonNull<T: PSafe + ?Sized, A: MemPool> {
 impl<T: PSafe + ?Sized, A: MemPool> Copy for LogNonNull<T, A> {}
 impl<T: PSafe + ?Sized, A: MemPool> Clone for LogNonNull<T, A> {
     fn clone(&self) -> Self {
-        Self {
-            ptr: self.ptr,
-            journal: self.journal,
-
-            #[cfg(not(any(feature = "use_pspd", feature = "use_vspd")))]
-            logged: self.logged,
-
-            phantom: PhantomData
-        }
+        *self
     }
 }
 
@@ -170,7 +160,7 @@ impl<T: ?Sized, A: MemPool> !Sync for LogNonNull<T, A> {}
 impl<T: PSafe + ?Sized, A: MemPool> PartialEq for LogNonNull<T, A> {
     #[inline]
     fn eq(&self, other: &Self) -> bool {
-        self.ptr == other.ptr
+        std::ptr::addr_eq(self.ptr, other.ptr)
     }
 }
 
@@ -240,8 +230,10 @@ impl<T: PSafe + ?Sized, A: MemPool> DerefMut for LogNonNull<T, A> {
             let value = &mut *self.ptr;
             #[cfg(not(any(feature = "use_pspd", feature = "use_vspd")))] {
                 use crate::ptr::Ptr;
-                use crate::stm::{Notifier, Logger};
-                if *self.logged == 0 {
+                use crate::stm::{Notifier, Logger, LogMode, FLAG_DRAFTED, FLAG_LOGGED};
+                let drafted = *self.logged == FLAG_DRAFTED
+                    && (*self.journal).log_mode() == LogMode::Redo;
+                if *self.logged != FLAG_LOGGED && !drafted {
                     value.create_log(&*self.journal, Notifier::NonAtomic(Ptr::from_raw(self.logged)));
                 }
             }
@@ -28,16 +28,31 @@ pub struct Ptr<T: ?Sized, A: MemPool> {
 
 /// `Ptr` pointers are not `Send` because the data they reference may be aliased.
 // N.B., this impl is unnecessary, but should provide better error messages.
-impl<A: MemPool, T> !Send for Ptr<T, A> {}
+impl<A: MemPool, T: ?Sized> !Send for Ptr<T, A> {}
 
 /// `Ptr` pointers are not `Sync` because the data they reference may be aliased.
 // N.B., this impl is unnecessary, but should provide better error messages.
-impl<A: MemPool, T> !Sync for Ptr<T, A> {}
-impl<A: MemPool, T> !TxOutSafe for Ptr<T, A> {}
+impl<A: MemPool, T: ?Sized> !Sync for Ptr<T, A> {}
+impl<A: MemPool, T: ?Sized> !TxOutSafe for Ptr<T, A> {}
 
 /// The allocator does not need to implement `PSafe`
 unsafe impl<A: MemPool, T: PSafe + ?Sized> PSafe for Ptr<T, A> {}
 
+/// Panics if `p` is stored in an instance of `A` other than the one that the
+/// current thread works with, in which its offset would resolve to another
+/// object
+#[inline]
+#[track_caller]
+pub(crate) fn check_instance<A: MemPool, T: ?Sized>(p: *const T) {
+    let addr = p as *const u8 as u64;
+    if !A::contains(addr) {
+        if let Some(id) = A::instance_of(addr) {
+            panic!("A persistent pointer of instance {} of `{}` is dereferenced in instance {}",
+                id, A::name(), A::instance());
+        }
+    }
+}
+
 impl<A: MemPool, T: ?Sized> Ptr<T, A> {
     #[inline]
     /// Gives a reference to the inner value if it is not dangling, otherwise, None.
@@ -77,30 +92,35 @@ impl<A: MemPool, T: ?Sized> Ptr<T, A> {
     #[inline]
     /// Returns the mutable reference of the value
     pub(crate) fn as_mut(&mut self) -> &mut T {
+        check_instance::<A, _>(self);
         unsafe { A::get_mut_unchecked(self.off) }
     }
 
     #[inline]
     /// Returns the reference of the value
     pub(crate) fn as_ref(&self) -> &T {
+        check_instance::<A, _>(self);
         unsafe { A::get_unchecked(self.off) }
     }
 
     #[inline]
     /// Returns the mutable raw pointer of the value
     pub(crate) fn as_mut_ptr(&mut self) -> *mut T {
+        check_instance::<A, _>(self);
         unsafe { A::get_mut_unchecked(self.off) }
     }
 
     #[inline]
     /// Returns the mutable raw pointer of the value
     pub(crate) fn get_mut_ptr(&self) -> *mut T {
+        check_instance::<A, _>(self);
         unsafe { A::get_mut_unchecked(self.off) }
     }
 
     #[inline]
     /// Returns the mutable raw pointer of the value
     pub(crate) fn as_ptr(&self) -> *const T {
+        check_instance::<A, _>(self);
         unsafe { A::get_mut_unchecked(self.off) }
     }
 
@@ -108,6 +128,7 @@ impl<A: MemPool, T: ?Sized> Ptr<T, A> {
     #[allow(clippy::mut_from_ref)]
     /// Returns the mutable reference of the value
     pub(crate) fn get_mut(&self) -> &mut T {
+        check_instance::<A, _>(self);
         unsafe { A::get_mut_unchecked(self.off) }
     }
 
@@ -257,10 +278,7 @@ impl<A: MemPool, T: PSafe + ?Sized> Copy for Ptr<T, A> {}
 
 impl<A: MemPool, T: PSafe + ?Sized> Clone for Ptr<T, A> {
     fn clone(&self) -> Self {
-        Self {
-            off: self.off,
-            marker: PhantomData,
-        }
+        *self
     }
 }
 
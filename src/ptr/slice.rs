@@ -1,4 +1,5 @@
 use crate::*;
+use super::ptr::check_instance;
 use std::marker::PhantomData;
 use std::ops::Index;
 
@@ -26,7 +27,7 @@ impl<T: PSafe, A: MemPool> Slice<T, A> {
 
     /// Creates a new fat pointer given a slice
     pub unsafe fn new(x: &[T]) -> Self {
-        if x.len() == 0 {
+        if x.is_empty() {
             Self::from_off_cap(u64::MAX, 0)
         } else {
             Self::from_off_cap(A::off_unchecked(x), x.len())
@@ -67,6 +68,7 @@ impl<T: PSafe, A: MemPool> Slice<T, A> {
     #[inline]
     pub fn get(&self, i: usize) -> &T {
         assert!(i < self.cap, "index out of range");
+        check_instance::<A, _>(self);
         unsafe {
             A::deref_mut(self.off + i as u64 * std::mem::size_of::<T>() as u64).unwrap()
         }
@@ -76,6 +78,7 @@ impl<T: PSafe, A: MemPool> Slice<T, A> {
     #[inline]
     pub fn get_mut(&self, i: usize) -> &mut T {
         assert!(i < self.cap, "index out of range");
+        check_instance::<A, _>(self);
         unsafe {
             A::deref_mut(self.off + i as u64 * std::mem::size_of::<T>() as u64).unwrap()
         }
@@ -85,6 +88,7 @@ impl<T: PSafe, A: MemPool> Slice<T, A> {
     /// the boundaries
     #[inline]
     pub unsafe fn get_unchecked(&self, i: usize) -> &mut T {
+        check_instance::<A, _>(self);
         A::get_mut_unchecked(self.off + i as u64 * std::mem::size_of::<T>() as u64)
     }
 
@@ -103,30 +107,35 @@ impl<T: PSafe, A: MemPool> Slice<T, A> {
     #[inline]
     /// Returns the mutable reference of the value
     pub(crate) fn as_mut(&mut self) -> &mut T {
+        check_instance::<A, _>(self);
         unsafe { A::get_mut_unchecked(self.off) }
     }
 
     #[inline]
     /// Returns the reference of the value
     pub(crate) fn as_ref(&self) -> &T {
+        check_instance::<A, _>(self);
         unsafe { A::get_unchecked(self.off) }
     }
 
     #[inline]
     /// Returns the mutable raw pointer of the value
     pub(crate) fn as_mut_ptr(&self) -> *mut T {
+        check_instance::<A, _>(self);
         unsafe { A::get_mut_unchecked(self.off) }
     }
 
     #[inline]
     /// Returns the mutable raw pointer of the value
     pub(crate) fn as_ptr(&self) -> *const T {
+        check_instance::<A, _>(self);
         unsafe { A::get_mut_unchecked(self.off) }
     }
 
     /// Converts the fat pointer into a slice of type `&[T]`
     #[inline]
     pub fn as_slice(&self) -> &[T] {
+        check_instance::<A, _>(self);
         Self::to_slice(self.off, self.cap)
     }
 
@@ -137,6 +146,7 @@ impl<T: PSafe, A: MemPool> Slice<T, A> {
 
     #[inline]
     pub(crate) fn as_slice_mut(&mut self) -> &mut [T] {
+        check_instance::<A, _>(self);
         unsafe { A::deref_slice_unchecked_mut(self.off, self.cap) }
     }
 
@@ -318,24 +318,24 @@ impl Stat {
                 use prelude::*;
 
                 let mut f = File::create(format!("{}/{}_hist.csv", _path, k))?;
-                f.write(b"lat,freq\n")?;
+                f.write_all(b"lat,freq\n")?;
 
                 let mut pairs = vec![];
                 for (tm,fr) in &v.points {
                     pairs.push((tm,fr));
                 }
-                pairs.sort_by(|x, y| x.cmp(&y));
+                pairs.sort();
 
                 for (tm,fr) in &pairs {
-                    f.write(format!("{},{}\n", tm, fr).to_string().as_bytes())?;
+                    f.write_all(format!("{},{}\n", tm, fr).to_string().as_bytes())?;
                 }
 
                 if points_enabled() {
                     let mut f = File::create(format!("{}/{}_points.csv", _path, k))?;
-                    f.write(format!("{}\n", k).to_string().as_bytes())?;
+                    f.write_all(format!("{}\n", k).to_string().as_bytes())?;
                     for (tm,fr) in &pairs {
                         for _ in 0..**fr {
-                            f.write(format!("{}\n", tm).to_string().as_bytes())?;
+                            f.write_all(format!("{}\n", tm).to_string().as_bytes())?;
                         }
                     }
                 }
@@ -422,7 +422,7 @@ Logging       {:>14} ns    avg(ns): {:<8}    cnt: {}",
                 v.min, v.max, v.cnt));
         }
         
-        lns.sort_by(|x, y| x.cmp(&y));
+        lns.sort();
         for ln in &lns {
             writeln!(f, "{}", ln)?;
         }
@@ -441,7 +441,7 @@ Logging       {:>14} ns    avg(ns): {:<8}    cnt: {}",
                 }
             }
             
-            _plots.sort_by(|x, y| x.replace('─',"").cmp(&y.replace('─',"")));
+            _plots.sort_by_key(|x| x.replace('─',""));
             for pl in &_plots {
                 writeln!(f, "{}", pl)?;
             }
@@ -481,7 +481,7 @@ pub fn save_histograms(_path: &'static str) -> Result<()> {
             Err(p) => p.into_inner(),
         };
         let mut total = Stat::default();
-        for (_, stat) in stat.iter() {
+        for stat in stat.values() {
             total += stat;
         }
         total.save_histograms(_path)
@@ -508,8 +508,8 @@ fn plot(data: &HashMap<u64, u64>, x: f32, freq_thr: u64) -> Option<(Vec<String>,
                 cnt += freq;
             }
         }
-        if cnt > 0 {
-            let avg = (sum / cnt) as i64;
+        if let Some(avg) = sum.checked_div(cnt) {
+            let avg = avg as i64;
     
             for (t,freq) in data {
                 let t = (*t as i64) - avg;
@@ -520,8 +520,8 @@ fn plot(data: &HashMap<u64, u64>, x: f32, freq_thr: u64) -> Option<(Vec<String>,
             }
     
             let v_max = freqs.iter().max()?;
-            for i in 0..freqs.len() {
-                let f = (freqs[i] * 19) / v_max;
+            for (i, freq) in freqs.iter().enumerate() {
+                let f = (freq * 19) / v_max;
                 let f = 19.min(f as usize);
                 for j in 0..f {
                     unsafe { res[19-j].as_bytes_mut()[i] = b'X'; }
@@ -0,0 +1,271 @@
+//! A persistent priority queue implemented with a binary heap
+
+use std::fmt::{self, Debug};
+use std::{mem, ptr};
+
+use crate::*;
+use crate::alloc::*;
+use crate::vec::Vec as PVec;
+use crate::clone::PClone;
+use crate::stm::{Journal, Logger, Notifier};
+
+/// A persistent priority queue implemented with a binary max-heap
+///
+/// Only the slots that a push or a pop moves an element into are logged,
+/// which is O(log n) elements per operation. Like [`Vec`], the modifications
+/// take `&mut self`, so a heap reachable from the root object should be
+/// wrapped in a [`PRefCell`].
+///
+/// [`Vec`]: ../../vec/struct.Vec.html
+/// [`PRefCell`]: ../../cell/struct.PRefCell.html
+///
+/// # Examples
+///
+/// ```
+/// use corundum::default::*;
+/// use corundum::open_flags::*;
+/// use corundum::stl::BinaryHeap;
+///
+/// type P = Allocator;
+///
+/// let root = P::open::<PRefCell<BinaryHeap<u64, P>>>("foo.pool", O_CF).unwrap();
+///
+/// P::transaction(|j| {
+///     let mut heap = root.borrow_mut(j);
+///     heap.push(1, j);
+///     heap.push(5, j);
+///     heap.push(2, j);
+///     assert_eq!(heap.pop(j), Some(5));
+/// }).unwrap();
+///
+/// assert_eq!(root.borrow().peek(), Some(&2));
+/// ```
+pub struct BinaryHeap<T: PSafe, P: MemPool> {
+    data: PVec<T, P>,
+}
+
+impl<T: PSafe + Ord, P: MemPool> RootObj<P> for BinaryHeap<T, P> {
+    fn init(_: &Journal<P>) -> Self {
+        Self::new()
+    }
+}
+
+impl<T: PSafe + Trace<P>, P: MemPool> Trace<P> for BinaryHeap<T, P> {
+    fn trace<V: Visitor>(&self, v: &mut V) {
+        self.data.trace(v);
+    }
+}
+
+impl<T: PSafe + PClone<P>, P: MemPool> PClone<P> for BinaryHeap<T, P> {
+    fn pclone(&self, j: &Journal<P>) -> Self {
+        let mut data = PVec::with_capacity(self.data.len(), j);
+        for e in self.data.iter() {
+            data.push(e.pclone(j), j);
+        }
+        Self { data }
+    }
+}
+
+/// Moves the element at `from` to the slot `to` after logging it, and
+/// leaves `from` logically uninitialized
+#[inline]
+unsafe fn relocate<T: PSafe, P: MemPool>(s: &mut [T], from: usize, to: usize, j: &Journal<P>) {
+    s[to].create_log(j, Notifier::None);
+    ptr::copy_nonoverlapping(&s[from], &mut s[to], 1);
+}
+
+impl<T: PSafe, P: MemPool> BinaryHeap<T, P> {
+    /// Creates an empty heap
+    pub fn new() -> Self {
+        Self { data: PVec::new() }
+    }
+
+    /// Returns the number of elements
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Returns `true` if the heap has no elements
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns an iterator over the elements in an arbitrary order
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.data.iter()
+    }
+
+    /// Removes all elements
+    pub fn clear(&mut self) {
+        self.data.clear();
+    }
+}
+
+impl<T: PSafe + Ord, P: MemPool> BinaryHeap<T, P> {
+    /// Returns the greatest element
+    pub fn peek(&self) -> Option<&T> {
+        self.data.first()
+    }
+
+    /// Adds `item` to the heap
+    pub fn push(&mut self, item: T, j: &Journal<P>) {
+        let data = &mut self.data;
+        let mut hole = data.len();
+        if hole < data.capacity() {
+            // The slot might hold an element popped in this transaction
+            unsafe {
+                let slot = data.off() + (hole * mem::size_of::<T>()) as u64;
+                P::get_mut_unchecked::<T>(slot).create_log(j, Notifier::None);
+            }
+        }
+        data.push(item, j);
+        let s = data.to_slice_mut();
+        unsafe {
+            let item = ptr::read(&s[hole]);
+            while hole > 0 {
+                let parent = (hole - 1) / 2;
+                if item <= s[parent] {
+                    break;
+                }
+                relocate(s, parent, hole, j);
+                hole = parent;
+            }
+            s[hole].create_log(j, Notifier::None);
+            ptr::write(&mut s[hole], item);
+        }
+    }
+
+    /// Removes the greatest element and returns it, or `None` if the heap is
+    /// empty
+    pub fn pop(&mut self, j: &Journal<P>) -> Option<T> {
+        let data = &mut self.data;
+        let len = data.len();
+        if len == 0 {
+            return None;
+        }
+        let s = data.to_slice_mut();
+        unsafe {
+            let top = ptr::read(&s[0]);
+            let last = ptr::read(&s[len - 1]);
+            let len = len - 1;
+            let mut hole = 0;
+            if len > 0 {
+                loop {
+                    let mut child = 2 * hole + 1;
+                    if child >= len {
+                        break;
+                    }
+                    if child + 1 < len && s[child + 1] > s[child] {
+                        child += 1;
+                    }
+                    if last >= s[child] {
+                        break;
+                    }
+                    relocate(s, child, hole, j);
+                    hole = child;
+                }
+                s[hole].create_log(j, Notifier::None);
+                ptr::write(&mut s[hole], last);
+            }
+            data.set_len(len);
+            Some(top)
+        }
+    }
+
+    /// Consumes the elements into a standard vector sorted in ascending order
+    pub fn drain_sorted(&mut self, j: &Journal<P>) -> std::vec::Vec<T> {
+        let mut res = std::vec::Vec::with_capacity(self.len());
+        while let Some(e) = self.pop(j) {
+            res.push(e);
+        }
+        res.reverse();
+        res
+    }
+}
+
+impl<T: PSafe + Debug, P: MemPool> Debug for BinaryHeap<T, P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::vec::Vec as StdVec;
+    use crate::*;
+    use crate::open_flags::*;
+    use crate::cell::PRefCell;
+    use crate::stm::Journal;
+    use crate::stl::test::{check_keys, Keys};
+    use crate::stl::test_pool::P;
+    use super::BinaryHeap;
+
+    type Heap = PRefCell<BinaryHeap<u64, P>, P>;
+
+    impl Keys for BinaryHeap<u64, P> {
+        const DUPLICATES: bool = true;
+
+        fn add(&mut self, k: u64, j: &Journal<P>) {
+            self.push(k, j);
+        }
+
+        fn take(&mut self, j: &Journal<P>) -> Option<u64> {
+            self.pop(j)
+        }
+
+        fn sorted(&self) -> StdVec<u64> {
+            let mut keys: StdVec<u64> = self.iter().cloned().collect();
+            keys.sort();
+            keys
+        }
+    }
+
+    /// Checks that every parent is not less than its children
+    fn is_heap(heap: &BinaryHeap<u64, P>) -> bool {
+        let s: StdVec<u64> = heap.iter().cloned().collect();
+        (1..s.len()).all(|i| s[(i - 1) / 2] >= s[i])
+    }
+
+    #[test]
+    fn heap_keys_test() {
+        check_keys::<BinaryHeap<u64, P>>("heap_keys");
+    }
+
+    #[test]
+    fn heap_order_test() {
+        let root = P::open::<Heap>("heap_order.pool", O_CF).unwrap();
+        let mut model = std::collections::BinaryHeap::new();
+
+        // Interleaves pushes and pops, and checks the order against the
+        // standard heap
+        for round in 0..10u64 {
+            let keys: StdVec<u64> = (0..100).map(|i| (round * 100 + i) * 7919 % 1000).collect();
+            model.extend(keys.iter().cloned());
+            let expected: StdVec<u64> = (0..50).map(|_| model.pop().unwrap()).collect();
+            assert!(expected.windows(2).all(|w| w[0] >= w[1]));
+
+            P::transaction(|j| {
+                let mut heap = root.borrow_mut(j);
+                for k in &keys {
+                    heap.push(*k, j);
+                }
+                for k in &expected {
+                    assert_eq!(heap.pop(j), Some(*k));
+                }
+            }).unwrap();
+            assert!(is_heap(&root.borrow()));
+            assert_eq!(root.borrow().peek(), model.peek());
+        }
+
+        P::transaction(|j| {
+            let mut heap = root.borrow_mut(j);
+            heap.push(1000, j);
+            assert_eq!(heap.peek(), Some(&1000));
+            assert_eq!(heap.pop(j), Some(1000));
+            assert_eq!(heap.drain_sorted(j), model.clone().into_sorted_vec());
+            assert!(heap.is_empty());
+            assert_eq!(heap.pop(j), None);
+            assert_eq!(heap.peek(), None);
+        }).unwrap();
+    }
+}
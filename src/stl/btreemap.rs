@@ -0,0 +1,602 @@
+//! An ordered persistent map based on a B-Tree
+
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+use std::fmt::{self, Debug};
+use std::marker::PhantomData;
+use std::mem;
+use std::ops::Bound::*;
+use std::ops::{Bound, RangeBounds, RangeFull};
+use std::vec::Vec as StdVec;
+
+use crate::*;
+use crate::alloc::*;
+use crate::vec::Vec as PVec;
+use crate::boxed::Pbox;
+use crate::clone::PClone;
+use crate::stm::{Journal, Logger, Notifier};
+
+/// Minimum degree of the tree; every node but the root has at least `B - 1`
+/// and at most `2 * B - 1` entries
+const B: usize = 6;
+const MIN_LEN: usize = B - 1;
+const MAX_LEN: usize = 2 * B - 1;
+
+struct Node<K: PSafe, V: PSafe, P: MemPool> {
+    entries: PVec<(K, V), P>,
+    children: PVec<Pbox<Node<K, V, P>, P>, P>,
+}
+
+impl<K: PSafe, V: PSafe, P: MemPool> Node<K, V, P> {
+    fn new() -> Self {
+        Self {
+            entries: PVec::new(),
+            children: PVec::new(),
+        }
+    }
+
+    #[inline]
+    fn is_leaf(&self) -> bool {
+        self.children.is_empty()
+    }
+
+    /// Takes a log of the node and its contents, and returns a mutable
+    /// reference to it
+    ///
+    /// The `DerefMut` of `Pbox` is not used because the boxes move between
+    /// nodes, and their log flags would move with them.
+    fn log<'a>(node: &'a mut Pbox<Self, P>, j: &Journal<P>) -> &'a mut Self {
+        unsafe {
+            let node = node.as_mut();
+            node.create_log(j, Notifier::None);
+            if !node.entries.is_empty() {
+                node.entries.as_slice_mut(j);
+            }
+            if !node.children.is_empty() {
+                node.children.as_slice_mut(j);
+            }
+            node
+        }
+    }
+
+    #[inline]
+    fn child(&mut self, i: usize, j: &Journal<P>) -> &mut Self {
+        Self::log(&mut self.children.to_slice_mut()[i], j)
+    }
+
+    /// Splits the full child at `i` and moves its median entry to `self`
+    fn split_child(&mut self, i: usize, j: &Journal<P>) {
+        let child = self.child(i, j);
+        let mut right = Self::new();
+        right.entries = child.entries.split_off(B, j);
+        if !child.is_leaf() {
+            right.children = child.children.split_off(B, j);
+        }
+        let median = child.entries.pop().unwrap();
+        self.entries.insert(i, median, j);
+        self.children.insert(i + 1, Pbox::new(right, j), j);
+    }
+
+    /// Moves the separator at `i` and all entries of the child at `i + 1`
+    /// to the child at `i`
+    fn merge(&mut self, i: usize, j: &Journal<P>) {
+        let sep = self.entries.remove(i);
+        let mut right = self.children.remove(i + 1);
+        let right = Self::log(&mut right, j);
+        let left = self.child(i, j);
+        left.entries.reserve(right.entries.len() + 1, j);
+        left.entries.push(sep, j);
+        left.entries.append(&mut right.entries, j);
+        left.children.reserve(right.children.len(), j);
+        left.children.append(&mut right.children, j);
+    }
+
+    /// Makes sure that the child at `i` has more than the minimum number of
+    /// entries before descending into it, and returns the new index of it
+    fn fill(&mut self, i: usize, j: &Journal<P>) -> usize {
+        if self.children[i].entries.len() > MIN_LEN {
+            i
+        } else if i > 0 && self.children[i - 1].entries.len() > MIN_LEN {
+            let left = self.child(i - 1, j);
+            let entry = left.entries.pop().unwrap();
+            let edge = left.children.pop();
+            let sep = mem::replace(&mut self.entries.to_slice_mut()[i - 1], entry);
+            let child = self.child(i, j);
+            child.entries.insert(0, sep, j);
+            if let Some(edge) = edge {
+                child.children.insert(0, edge, j);
+            }
+            i
+        } else if i + 1 < self.children.len()
+            && self.children[i + 1].entries.len() > MIN_LEN
+        {
+            let right = self.child(i + 1, j);
+            let entry = right.entries.remove(0);
+            let edge = if right.is_leaf() { None } else { Some(right.children.remove(0)) };
+            let sep = mem::replace(&mut self.entries.to_slice_mut()[i], entry);
+            let child = self.child(i, j);
+            child.entries.push(sep, j);
+            if let Some(edge) = edge {
+                child.children.push(edge, j);
+            }
+            i
+        } else if i + 1 < self.children.len() {
+            self.merge(i, j);
+            i
+        } else {
+            self.merge(i - 1, j);
+            i - 1
+        }
+    }
+
+    /// Removes the first (or the last if `last` is set) entry of the subtree
+    fn remove_edge(&mut self, last: bool, j: &Journal<P>) -> (K, V) {
+        if self.is_leaf() {
+            if last {
+                self.entries.pop().unwrap()
+            } else {
+                self.entries.remove(0)
+            }
+        } else {
+            let i = if last { self.children.len() - 1 } else { 0 };
+            let i = self.fill(i, j);
+            self.child(i, j).remove_edge(last, j)
+        }
+    }
+
+    fn remove<Q: ?Sized + Ord>(&mut self, key: &Q, j: &Journal<P>) -> Option<V>
+    where
+        K: Borrow<Q>,
+    {
+        match self.entries.binary_search_by(|e| e.0.borrow().cmp(key)) {
+            Ok(i) if self.is_leaf() => Some(self.entries.remove(i).1),
+            Ok(i) => {
+                let entry = if self.children[i].entries.len() > MIN_LEN {
+                    self.child(i, j).remove_edge(true, j)
+                } else if self.children[i + 1].entries.len() > MIN_LEN {
+                    self.child(i + 1, j).remove_edge(false, j)
+                } else {
+                    self.merge(i, j);
+                    return self.child(i, j).remove(key, j);
+                };
+                Some(mem::replace(&mut self.entries.to_slice_mut()[i], entry).1)
+            }
+            Err(_) if self.is_leaf() => None,
+            Err(i) => {
+                let i = self.fill(i, j);
+                self.child(i, j).remove(key, j)
+            }
+        }
+    }
+}
+
+impl<K: PSafe + PClone<P>, V: PSafe + PClone<P>, P: MemPool> PClone<P> for Node<K, V, P> {
+    fn pclone(&self, j: &Journal<P>) -> Self {
+        let mut res = Self::new();
+        res.entries.reserve(self.entries.len(), j);
+        for e in self.entries.iter() {
+            res.entries.push(e.pclone(j), j);
+        }
+        res.children.reserve(self.children.len(), j);
+        for c in self.children.iter() {
+            res.children.push(c.pclone(j), j);
+        }
+        res
+    }
+}
+
+impl<K: PSafe + Trace<P>, V: PSafe + Trace<P>, P: MemPool> Trace<P> for Node<K, V, P> {
+    fn trace<T: Visitor>(&self, v: &mut T) {
+        self.entries.trace(v);
+        self.children.trace(v);
+    }
+}
+
+/// An ordered persistent map based on a [B-Tree]
+///
+/// The keys are kept in sorted order, so the map supports looking up a
+/// [`range`] of keys besides the point queries. All modifications take a
+/// `&Journal<P>` and are undone if the enclosing transaction fails. Like
+/// [`Vec`], they also take `&mut self`, so a map reachable from the root
+/// object should be wrapped in a [`PRefCell`].
+///
+/// [B-Tree]: https://en.wikipedia.org/wiki/B-tree
+/// [`range`]: #method.range
+/// [`Vec`]: ../../vec/struct.Vec.html
+/// [`PRefCell`]: ../../cell/struct.PRefCell.html
+///
+/// # Examples
+///
+/// ```
+/// use corundum::default::*;
+/// use corundum::open_flags::*;
+/// use corundum::stl::BTreeMap;
+///
+/// type P = Allocator;
+///
+/// let root = P::open::<PRefCell<BTreeMap<u64, u64, P>>>("foo.pool", O_CF).unwrap();
+///
+/// P::transaction(|j| {
+///     let mut map = root.borrow_mut(j);
+///     for i in 0..10 {
+///         map.insert(i, i * i, j);
+///     }
+///     assert_eq!(map.remove(&3, j), Some(9));
+/// }).unwrap();
+///
+/// let map = root.borrow();
+/// assert_eq!(map.get(&4), Some(&16));
+/// assert_eq!(map.range(2..6).map(|(k, _)| *k).collect::<Vec<_>>(), [2, 4, 5]);
+/// ```
+pub struct BTreeMap<K: PSafe, V: PSafe, P: MemPool> {
+    root: Option<Pbox<Node<K, V, P>, P>>,
+    len: usize,
+}
+
+impl<K: PSafe + Ord, V: PSafe, P: MemPool> RootObj<P> for BTreeMap<K, V, P> {
+    fn init(_: &Journal<P>) -> Self {
+        Self::new()
+    }
+}
+
+impl<K: PSafe + Trace<P>, V: PSafe + Trace<P>, P: MemPool> Trace<P> for BTreeMap<K, V, P> {
+    fn trace<T: Visitor>(&self, v: &mut T) {
+        self.root.trace(v);
+    }
+}
+
+impl<K: PSafe, V: PSafe, P: MemPool> BTreeMap<K, V, P> {
+    /// Creates an empty map
+    pub fn new() -> Self {
+        Self {
+            root: None,
+            len: 0,
+        }
+    }
+
+    /// Returns the number of entries in the map
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the map has no entries
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Removes all entries
+    pub fn clear(&mut self) {
+        self.root = None;
+        self.len = 0;
+    }
+}
+
+impl<K: PSafe + Ord, V: PSafe, P: MemPool> BTreeMap<K, V, P> {
+    /// Returns a reference to the value of `key`
+    pub fn get<Q: ?Sized + Ord>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+    {
+        let mut node = self.root.as_deref()?;
+        loop {
+            match node.entries.binary_search_by(|e| e.0.borrow().cmp(key)) {
+                Ok(i) => return Some(&node.entries[i].1),
+                Err(_) if node.is_leaf() => return None,
+                Err(i) => node = &*node.children[i],
+            }
+        }
+    }
+
+    /// Returns `true` if the map has a value for `key`
+    pub fn contains_key<Q: ?Sized + Ord>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+    {
+        self.get(key).is_some()
+    }
+
+    /// Inserts `val` for `key` and returns the old value if there was one
+    pub fn insert(&mut self, key: K, val: V, j: &Journal<P>) -> Option<V> {
+        let root = &mut self.root;
+        if root.is_none() {
+            *root = Some(Pbox::new(Node::new(), j));
+        } else if root.as_ref().unwrap().entries.len() == MAX_LEN {
+            let mut up = Node::new();
+            up.children.push(root.take().unwrap(), j);
+            *root = Some(Pbox::new(up, j));
+            Node::log(root.as_mut().unwrap(), j).split_child(0, j);
+        }
+
+        let mut node = Node::log(root.as_mut().unwrap(), j);
+        loop {
+            let mut i = match node.entries.binary_search_by(|e| e.0.cmp(&key)) {
+                Ok(i) => {
+                    return Some(mem::replace(&mut node.entries.to_slice_mut()[i].1, val));
+                }
+                Err(i) if node.is_leaf() => {
+                    node.entries.insert(i, (key, val), j);
+                    self.len += 1;
+                    return None;
+                }
+                Err(i) => i,
+            };
+            if node.children[i].entries.len() == MAX_LEN {
+                node.split_child(i, j);
+                match key.cmp(&node.entries[i].0) {
+                    Ordering::Less => {}
+                    Ordering::Greater => i += 1,
+                    Ordering::Equal => {
+                        return Some(mem::replace(&mut node.entries.to_slice_mut()[i].1, val));
+                    }
+                }
+            }
+            node = node.child(i, j);
+        }
+    }
+
+    /// Removes `key` from the map and returns its value if there was one
+    pub fn remove<Q: ?Sized + Ord>(&mut self, key: &Q, j: &Journal<P>) -> Option<V>
+    where
+        K: Borrow<Q>,
+    {
+        if !self.contains_key(key) {
+            return None;
+        }
+        let node = Node::log(self.root.as_mut().unwrap(), j);
+        let val = node.remove(key, j);
+        if node.entries.is_empty() {
+            self.root = node.children.pop();
+        }
+        self.len -= 1;
+        val
+    }
+
+    /// Returns an iterator over the entries within `range` in ascending order
+    /// of keys
+    pub fn range<Q, R>(&self, range: R) -> Range<'_, K, V, P, Q, R>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Ord,
+        R: RangeBounds<Q>,
+    {
+        let mut stack = StdVec::new();
+        if let Some(root) = self.root.as_deref() {
+            Range::<K, V, P, Q, R>::descend(&mut stack, root, range.start_bound());
+        }
+        Range {
+            stack,
+            range,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Returns an iterator over all entries in ascending order of keys
+    pub fn iter(&self) -> Iter<'_, K, V, P> {
+        self.range(..)
+    }
+}
+
+impl<K: PSafe + Ord + PClone<P>, V: PSafe + PClone<P>, P: MemPool> PClone<P> for BTreeMap<K, V, P> {
+    fn pclone(&self, j: &Journal<P>) -> Self {
+        Self {
+            root: self.root.pclone(j),
+            len: self.len,
+        }
+    }
+}
+
+impl<'a, K: PSafe + Ord, V: PSafe, P: MemPool> IntoIterator for &'a BTreeMap<K, V, P> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V, P>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<K: PSafe + Ord + Debug, V: PSafe + Debug, P: MemPool> Debug for BTreeMap<K, V, P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+/// An iterator over a range of entries of a [`BTreeMap`]
+///
+/// [`BTreeMap`]: ./struct.BTreeMap.html
+pub struct Range<'a, K: PSafe, V: PSafe, P: MemPool, Q: ?Sized, R> {
+    stack: StdVec<(&'a Node<K, V, P>, usize)>,
+    range: R,
+    phantom: PhantomData<fn(&Q)>,
+}
+
+/// An iterator over the entries of a [`BTreeMap`]
+///
+/// [`BTreeMap`]: ./struct.BTreeMap.html
+pub type Iter<'a, K, V, P> = Range<'a, K, V, P, K, RangeFull>;
+
+impl<'a, K, V, P, Q, R> Range<'a, K, V, P, Q, R>
+where
+    K: PSafe + Borrow<Q>,
+    V: PSafe,
+    P: MemPool,
+    Q: ?Sized + Ord,
+{
+    /// Pushes the path to the first entry after `start` in the subtree
+    fn descend(
+        stack: &mut StdVec<(&'a Node<K, V, P>, usize)>,
+        mut node: &'a Node<K, V, P>,
+        start: Bound<&Q>,
+    ) {
+        loop {
+            let i = match start {
+                Included(s) => node.entries.partition_point(|e| e.0.borrow() < s),
+                Excluded(s) => node.entries.partition_point(|e| e.0.borrow() <= s),
+                Unbounded => 0,
+            };
+            stack.push((node, i));
+            if node.is_leaf() {
+                return;
+            }
+            node = &*node.children[i];
+        }
+    }
+}
+
+impl<'a, K, V, P, Q, R> Iterator for Range<'a, K, V, P, Q, R>
+where
+    K: PSafe + Borrow<Q>,
+    V: PSafe,
+    P: MemPool,
+    Q: ?Sized + Ord,
+    R: RangeBounds<Q>,
+{
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let (node, i) = *self.stack.last()?;
+            if i == node.entries.len() {
+                self.stack.pop();
+                continue;
+            }
+            self.stack.last_mut().unwrap().1 += 1;
+            if !node.is_leaf() {
+                Self::descend(&mut self.stack, &node.children[i + 1], Unbounded);
+            }
+            let (k, v) = &node.entries[i];
+            let past = match self.range.end_bound() {
+                Included(e) => k.borrow() > e,
+                Excluded(e) => k.borrow() >= e,
+                Unbounded => false,
+            };
+            if past {
+                self.stack.clear();
+                return None;
+            }
+            return Some((k, v));
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::ops::Bound::*;
+    use std::vec::Vec as StdVec;
+    use crate::*;
+    use crate::open_flags::*;
+    use crate::cell::PRefCell;
+    use crate::stm::Journal;
+    use crate::stl::test::{check_keys, Keys};
+    use crate::stl::test_pool::P;
+    use super::BTreeMap;
+
+    type Map = PRefCell<BTreeMap<u64, u64, P>, P>;
+
+    impl Keys for BTreeMap<u64, u64, P> {
+        fn add(&mut self, k: u64, j: &Journal<P>) {
+            self.insert(k, k * 2, j);
+        }
+
+        fn take(&mut self, j: &Journal<P>) -> Option<u64> {
+            let k = *self.iter().next()?.0;
+            assert_eq!(self.remove(&k, j), Some(k * 2));
+            Some(k)
+        }
+
+        fn sorted(&self) -> StdVec<u64> {
+            self.iter().map(|(k, _)| *k).collect()
+        }
+    }
+
+    /// Keys `0..n` in a scrambled order
+    fn keys(n: u64) -> StdVec<u64> {
+        (0..n).map(|i| i * 7919 % n).collect()
+    }
+
+    fn fill(root: &Map, n: u64) {
+        P::transaction(|j| {
+            let mut map = root.borrow_mut(j);
+            for k in keys(n) {
+                assert_eq!(map.insert(k, k * 2, j), None);
+            }
+        }).unwrap();
+    }
+
+    #[test]
+    fn btreemap_insert_test() {
+        let root = P::open::<Map>("btreemap_insert.pool", O_CF).unwrap();
+        fill(&root, 1000);
+
+        P::transaction(|j| {
+            let mut map = root.borrow_mut(j);
+            assert_eq!(map.insert(5, 0, j), Some(10));
+            assert_eq!(map.insert(5, 10, j), Some(0));
+        }).unwrap();
+
+        let map = root.borrow();
+        assert_eq!(map.len(), 1000);
+        assert!(map.iter().map(|(k, _)| *k).eq(0..1000));
+        assert!(map.range(100..=200).map(|(_, v)| *v).eq((100..=200).map(|k| k * 2)));
+        assert!(map.range((Excluded(997), Unbounded)).map(|(k, _)| *k).eq(998..1000));
+        assert_eq!(map.get(&999), Some(&1998));
+        assert_eq!(map.get(&1000), None);
+    }
+
+    #[test]
+    fn btreemap_remove_test() {
+        let root = P::open::<Map>("btreemap_remove.pool", O_CF).unwrap();
+        fill(&root, 1000);
+
+        P::transaction(|j| {
+            let mut map = root.borrow_mut(j);
+            for k in keys(1000).iter().filter(|k| *k % 3 != 0) {
+                assert_eq!(map.remove(k, j), Some(k * 2));
+            }
+            assert_eq!(map.remove(&1, j), None);
+        }).unwrap();
+
+        assert_eq!(root.borrow().len(), 334);
+        assert!(root.borrow().iter().map(|(k, _)| *k).eq((0..1000).step_by(3)));
+
+        P::transaction(|j| {
+            let mut map = root.borrow_mut(j);
+            for k in (0..1000).step_by(3) {
+                map.remove(&k, j);
+            }
+        }).unwrap();
+
+        assert!(root.borrow().is_empty());
+        assert_eq!(root.borrow().iter().next(), None);
+    }
+
+    #[test]
+    fn btreemap_abort_test() {
+        let root = P::open::<Map>("btreemap_abort.pool", O_CF).unwrap();
+        fill(&root, 1000);
+
+        let _ = P::transaction(|j| {
+            let mut map = root.borrow_mut(j);
+            map.clear();
+            for k in 0..500 {
+                map.insert(k, 0, j);
+            }
+            panic!("abort");
+        });
+
+        let _ = P::transaction(|j| {
+            let mut map = root.borrow_mut(j);
+            for k in 0..1000 {
+                map.remove(&k, j);
+            }
+            panic!("abort");
+        });
+
+        assert_eq!(root.borrow().len(), 1000);
+        assert!(root.borrow().iter().map(|(k, v)| (*k, *v)).eq((0..1000).map(|k| (k, k * 2))));
+    }
+
+    #[test]
+    fn btreemap_keys_test() {
+        check_keys::<BTreeMap<u64, u64, P>>("btreemap_keys");
+    }
+}
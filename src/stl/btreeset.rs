@@ -0,0 +1,197 @@
+//! An ordered persistent set based on a B-Tree
+
+use std::borrow::Borrow;
+use std::fmt::{self, Debug};
+use std::ops::RangeBounds;
+
+use crate::*;
+use crate::alloc::*;
+use crate::clone::PClone;
+use crate::stm::Journal;
+use super::BTreeMap;
+
+/// An ordered persistent set based on a B-Tree
+///
+/// It is a [`BTreeMap`] with no values, and shares its journaled
+/// modification and iteration order.
+///
+/// [`BTreeMap`]: ./struct.BTreeMap.html
+///
+/// # Examples
+///
+/// ```
+/// use corundum::default::*;
+/// use corundum::open_flags::*;
+/// use corundum::stl::BTreeSet;
+///
+/// type P = Allocator;
+///
+/// let root = P::open::<PRefCell<BTreeSet<u64, P>>>("foo.pool", O_CF).unwrap();
+///
+/// P::transaction(|j| {
+///     let mut set = root.borrow_mut(j);
+///     assert!(set.insert(3, j));
+///     assert!(set.insert(1, j));
+///     assert!(!set.insert(3, j));
+/// }).unwrap();
+///
+/// assert!(root.borrow().iter().eq(&[1, 3]));
+/// ```
+pub struct BTreeSet<T: PSafe, P: MemPool> {
+    map: BTreeMap<T, (), P>,
+}
+
+impl<T: PSafe + Ord, P: MemPool> RootObj<P> for BTreeSet<T, P> {
+    fn init(_: &Journal<P>) -> Self {
+        Self::new()
+    }
+}
+
+impl<T: PSafe + Trace<P>, P: MemPool> Trace<P> for BTreeSet<T, P> {
+    fn trace<V: Visitor>(&self, v: &mut V) {
+        self.map.trace(v);
+    }
+}
+
+impl<T: PSafe + Ord + PClone<P>, P: MemPool> PClone<P> for BTreeSet<T, P> {
+    fn pclone(&self, j: &Journal<P>) -> Self {
+        Self { map: self.map.pclone(j) }
+    }
+}
+
+impl<T: PSafe, P: MemPool> BTreeSet<T, P> {
+    /// Creates an empty set
+    pub fn new() -> Self {
+        Self { map: BTreeMap::new() }
+    }
+
+    /// Returns the number of elements in the set
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns `true` if the set has no elements
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Removes all elements
+    pub fn clear(&mut self) {
+        self.map.clear()
+    }
+}
+
+impl<T: PSafe + Ord, P: MemPool> BTreeSet<T, P> {
+    /// Returns `true` if the set contains `value`
+    pub fn contains<Q: ?Sized + Ord>(&self, value: &Q) -> bool
+    where
+        T: Borrow<Q>,
+    {
+        self.map.contains_key(value)
+    }
+
+    /// Adds `value` to the set, and returns `false` if it was already there
+    pub fn insert(&mut self, value: T, j: &Journal<P>) -> bool {
+        if self.map.contains_key(&value) {
+            false
+        } else {
+            self.map.insert(value, (), j);
+            true
+        }
+    }
+
+    /// Removes `value` from the set, and returns `true` if it was there
+    pub fn remove<Q: ?Sized + Ord>(&mut self, value: &Q, j: &Journal<P>) -> bool
+    where
+        T: Borrow<Q>,
+    {
+        self.map.remove(value, j).is_some()
+    }
+
+    /// Returns an iterator over the elements within `range` in ascending
+    /// order
+    pub fn range<Q, R>(&self, range: R) -> impl Iterator<Item = &T>
+    where
+        T: Borrow<Q>,
+        Q: ?Sized + Ord,
+        R: RangeBounds<Q>,
+    {
+        self.map.range(range).map(|(k, _)| k)
+    }
+
+    /// Returns an iterator over the elements in ascending order
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.map.iter().map(|(k, _)| k)
+    }
+}
+
+impl<T: PSafe + Ord + Debug, P: MemPool> Debug for BTreeSet<T, P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::ops::Bound::*;
+    use std::vec::Vec as StdVec;
+    use crate::*;
+    use crate::open_flags::*;
+    use crate::cell::PRefCell;
+    use crate::stm::Journal;
+    use crate::stl::test::{check_keys, Keys};
+    use crate::stl::test_pool::P;
+    use super::BTreeSet;
+
+    impl Keys for BTreeSet<u64, P> {
+        fn add(&mut self, k: u64, j: &Journal<P>) {
+            self.insert(k, j);
+        }
+
+        fn take(&mut self, j: &Journal<P>) -> Option<u64> {
+            let k = *self.iter().next()?;
+            assert!(self.remove(&k, j));
+            Some(k)
+        }
+
+        fn sorted(&self) -> StdVec<u64> {
+            self.iter().cloned().collect()
+        }
+    }
+
+    #[test]
+    fn btreeset_keys_test() {
+        check_keys::<BTreeSet<u64, P>>("btreeset_keys");
+    }
+
+    #[test]
+    fn btreeset_order_test() {
+        let root = P::open::<PRefCell<BTreeSet<u64, P>, P>>("btreeset_order.pool", O_CF).unwrap();
+        P::transaction(|j| {
+            let mut set = root.borrow_mut(j);
+            for i in 0..2000 {
+                assert_eq!(set.insert(i * 7919 % 1000, j), i < 1000);
+            }
+            for k in (0..1000).filter(|k| k % 3 == 1) {
+                assert!(set.remove(&k, j));
+            }
+        }).unwrap();
+
+        let set = root.borrow();
+        let model: StdVec<u64> = (0..1000).filter(|k| k % 3 != 1).collect();
+        assert!(set.iter().eq(model.iter()));
+
+        let range = |lo, hi| -> StdVec<u64> { set.range((lo, hi)).cloned().collect() };
+        let expect = |f: &dyn Fn(&u64) -> bool| -> StdVec<u64> {
+            model.iter().cloned().filter(|k| f(k)).collect()
+        };
+        assert_eq!(range(Included(100), Excluded(200)), expect(&|k| (100..200).contains(k)));
+        assert_eq!(range(Excluded(100), Included(200)), expect(&|k| *k > 100 && *k <= 200));
+        assert_eq!(range(Unbounded, Excluded(10)), expect(&|k| *k < 10));
+        assert_eq!(range(Included(990), Unbounded), expect(&|k| *k >= 990));
+        assert_eq!(range(Included(1), Included(1)), StdVec::<u64>::new());
+        assert_eq!(range(Excluded(999), Unbounded), StdVec::<u64>::new());
+        assert!(set.range::<u64, _>(..).eq(set.iter()));
+        assert!(!set.contains(&1000));
+    }
+}
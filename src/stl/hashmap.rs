@@ -1,157 +1,419 @@
-#![allow(dead_code)]
+//! A persistent hash map with incremental resizing
 
-use std::fmt::Display;
+use std::borrow::Borrow;
+use std::fmt::{self, Debug, Display};
 use std::collections::hash_map::DefaultHasher;
-use std::hash::{Hash, Hasher};
+use std::hash::{BuildHasher, BuildHasherDefault, Hash};
+use std::marker::PhantomData;
+use std::{mem, ptr};
 
 use crate::*;
 use crate::alloc::*;
 use crate::vec::Vec as PVec;
-use crate::cell::PRefCell;
-use crate::stm::Journal;
+use crate::boxed::Pbox;
+use crate::stm::{Journal, Logger, Notifier};
 use crate::clone::PClone;
 use crate::gen::Allocatable;
 
-const BUCKETS_MAX: usize = 16;
+/// The initial number of buckets
+const MIN_BUCKETS: usize = 16;
 
-type Bucket<K, P: MemPool> = PVec<PRefCell<(K, usize),P>,P>;
+/// The number of old buckets that are moved to the new table by every
+/// modification while resizing
+const MIGRATE_STEP: usize = 4;
 
-pub struct HashMap<K: PSafe, V: PSafe, P: MemPool> {
-    buckets: PVec<PRefCell<Bucket<K,P>,P>,P>,
-    values: PVec<PRefCell<V,P>,P>,
+type Link<K, V, P> = Option<Pbox<Node<K, V, P>, P>>;
+
+struct Node<K: PSafe, V: PSafe, P: MemPool> {
+    key: K,
+    val: V,
+    next: Link<K, V, P>,
+}
+
+struct Table<K: PSafe, V: PSafe, P: MemPool> {
+    buckets: PVec<Link<K, V, P>, P>,
+
+    /// The buckets before resizing; those before `moved` are already empty
+    old: PVec<Link<K, V, P>, P>,
+    moved: usize,
+    len: usize,
+}
+
+#[inline]
+fn index(hash: u64, buckets: usize) -> usize {
+    hash as usize & (buckets - 1)
+}
+
+/// Takes a log of `x` and returns a mutable reference to it
+///
+/// The log flags of the `Pbox`es are not used because the boxes move between
+/// buckets, and their flags would move with them.
+#[inline]
+fn logged<'a, T: PSafe, P: MemPool>(x: &'a mut T, j: &Journal<P>) -> &'a mut T {
+    unsafe { x.create_log(j, Notifier::None); }
+    x
+}
+
+impl<K: PSafe, V: PSafe, P: MemPool> Table<K, V, P> {
+    fn with_buckets(n: usize, j: &Journal<P>) -> Self {
+        Self {
+            buckets: Self::empty_buckets(n, j),
+            old: PVec::new(),
+            moved: 0,
+            len: 0,
+        }
+    }
+
+    fn empty_buckets(n: usize, j: &Journal<P>) -> PVec<Link<K, V, P>, P> {
+        let mut buckets = PVec::with_capacity(n, j);
+        for _ in 0..n {
+            buckets.push(None, j);
+        }
+        buckets
+    }
+
+    #[inline]
+    fn is_resizing(&self) -> bool {
+        !self.old.is_empty()
+    }
+
+    /// Returns the head of the chain that `hash` belongs to
+    fn bucket(&self, hash: u64) -> &Link<K, V, P> {
+        if self.is_resizing() {
+            let i = index(hash, self.old.len());
+            if i >= self.moved {
+                return &self.old[i];
+            }
+        }
+        &self.buckets[index(hash, self.buckets.len())]
+    }
+
+    fn bucket_mut(&mut self, hash: u64) -> &mut Link<K, V, P> {
+        if self.is_resizing() {
+            let i = index(hash, self.old.len());
+            if i >= self.moved {
+                return &mut self.old.to_slice_mut()[i];
+            }
+        }
+        let i = index(hash, self.buckets.len());
+        &mut self.buckets.to_slice_mut()[i]
+    }
+}
+
+/// A persistent hash map
+///
+/// The entries are chained in an array of buckets which doubles in size when
+/// the load factor exceeds 3/4, and halves when it falls below 1/8. Resizing
+/// is incremental: every modification moves a few buckets to the new array,
+/// and lookups check both arrays in the meantime. All modifications take a
+/// `&Journal<P>`, so a crash in the middle of resizing is rolled back like
+/// any other change. They also take `&mut self`, so a map reachable from the
+/// root object should be wrapped in a [`PRefCell`] or a [`PMutex`].
+///
+/// Every entry lives in its own persistent allocation, and resizing only
+/// relinks them. So, the references to the values stay valid until they are
+/// removed.
+///
+/// The hashes are persistent, so the hasher is built with `S::default()` on
+/// every access and has to be deterministic across runs. The default is the
+/// [`DefaultHasher`] with fixed keys. Methods with a `_hash` suffix take a
+/// precomputed hash which should agree with the one `S` produces for the key.
+///
+/// [`DefaultHasher`]: std::collections::hash_map::DefaultHasher
+/// [`PRefCell`]: ../../cell/struct.PRefCell.html
+/// [`PMutex`]: ../../sync/struct.PMutex.html
+///
+/// # Examples
+///
+/// ```
+/// use corundum::default::*;
+/// use corundum::open_flags::*;
+/// use corundum::stl::HashMap;
+///
+/// type P = Allocator;
+///
+/// let root = P::open::<PRefCell<HashMap<u64, u64, P>>>("foo.pool", O_CF).unwrap();
+///
+/// P::transaction(|j| {
+///     let mut map = root.borrow_mut(j);
+///     for i in 0..100 {
+///         map.insert(i, i * i, j);
+///     }
+///     assert_eq!(map.remove(&3, j), Some(9));
+///     *map.entry(4, j).or_insert(0) += 1;
+/// }).unwrap();
+///
+/// let map = root.borrow();
+/// assert_eq!(map.get(&4), Some(&17));
+/// assert_eq!(map.len(), 99);
+/// ```
+pub struct HashMap<K: PSafe, V: PSafe, P: MemPool, S = BuildHasherDefault<DefaultHasher>> {
+    table: Table<K, V, P>,
+    hasher: PhantomData<fn() -> S>,
 }
 
-impl<K: PartialEq + Hash + PSafe, V: PSafe, P: MemPool> RootObj<P> for HashMap<K, V, P> {
+/// `S` is only a type tag, and no hasher is stored in the pool
+unsafe impl<K: PSafe, V: PSafe, P: MemPool, S> PSafe for HashMap<K, V, P, S> {}
+
+impl<K: PSafe, V: PSafe, P: MemPool, S> RootObj<P> for HashMap<K, V, P, S> {
     fn init(j: &Journal<P>) -> Self {
         Self::new(j)
     }
 }
 
-impl<K: PSafe, V: PSafe, P: MemPool> HashMap<K, V, P> {
-    pub fn foreach<F: FnMut(&K, &V) -> ()>(&self, mut f: F) {
-        for i in 0..BUCKETS_MAX {
-            for e in &*self.buckets[i].borrow() {
-                let e = e.borrow();
-                f(&e.0, &self.values[e.1].borrow());
-            }
-        }
+impl<K: PSafe + Trace<P>, V: PSafe + Trace<P>, P: MemPool> Trace<P> for Node<K, V, P> {
+    fn trace<T: Visitor>(&self, v: &mut T) {
+        self.key.trace(v);
+        self.val.trace(v);
+        self.next.trace(v);
     }
 }
 
-impl<K: PSafe, V: PSafe, P: MemPool> HashMap<K, V, P>
-where
-    K: PartialEq + Hash
-{
+impl<K: PSafe + Trace<P>, V: PSafe + Trace<P>, P: MemPool, S> Trace<P> for HashMap<K, V, P, S> {
+    fn trace<T: Visitor>(&self, v: &mut T) {
+        self.table.buckets.trace(v);
+        self.table.old.trace(v);
+    }
+}
+
+impl<K: PSafe, V: PSafe, P: MemPool, S> HashMap<K, V, P, S> {
+    /// Creates an empty map
     pub fn new(j: &Journal<P>) -> Self {
-        let mut buckets = PVec::with_capacity(BUCKETS_MAX, j);
-        for _ in 0..BUCKETS_MAX {
-            buckets.push(PRefCell::new(PVec::new()), j)
-        }
+        Self::with_capacity(0, j)
+    }
+
+    /// Creates an empty map with enough buckets for `cap` entries
+    pub fn with_capacity(cap: usize, j: &Journal<P>) -> Self {
+        let n = (cap * 4 / 3 + 1).next_power_of_two().max(MIN_BUCKETS);
         Self {
-            buckets,
-            values: PVec::new(),
+            table: Table::with_buckets(n, j),
+            hasher: PhantomData,
         }
     }
 
-    pub fn get(&self, key: K) -> Option<&V> {
-        let mut hasher = DefaultHasher::new();
-        key.hash(&mut hasher);
-        let index = (hasher.finish() as usize) % BUCKETS_MAX;
+    /// Returns the number of entries in the map
+    pub fn len(&self) -> usize {
+        self.table.len
+    }
 
-        for e in &*self.buckets[index].borrow() {
-            let e = e.borrow();
-            if e.0 == key {
-                return Some(unsafe { &*(&*self.values[e.1].borrow() as *const V) });
-            }
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the number of buckets
+    pub fn capacity(&self) -> usize {
+        self.table.buckets.len()
+    }
+
+    /// Returns an iterator over the entries in an arbitrary order
+    pub fn iter(&self) -> Iter<'_, K, V, P> {
+        let table = &self.table;
+        Iter {
+            buckets: [table.old.as_slice(), table.buckets.as_slice()],
+            node: None,
+            len: table.len,
         }
-        None
     }
 
-    pub fn get_with_hash<Key>(&self, key: Key, key_hash: u64) -> Option<&V>
-    where K: PartialEq<Key> {
-        let index = (key_hash as usize) % BUCKETS_MAX;
+    /// Returns an iterator over the keys in an arbitrary order
+    pub fn keys(&self) -> impl Iterator<Item = &K> {
+        self.iter().map(|(k, _)| k)
+    }
+
+    /// Returns an iterator over the values in an arbitrary order
+    pub fn values(&self) -> impl Iterator<Item = &V> {
+        self.iter().map(|(_, v)| v)
+    }
+
+    pub fn foreach<F: FnMut(&K, &V)>(&self, mut f: F) {
+        for (k, v) in self.iter() {
+            f(k, v);
+        }
+    }
 
-        for e in &*self.buckets[index].borrow() {
-            let e = e.borrow();
-            if e.0 == key {
-                return Some(unsafe { &*(&*self.values[e.1].borrow() as *const V) });
+    /// Removes all entries and shrinks the map to the initial size
+    pub fn clear(&mut self, j: &Journal<P>) {
+        self.table = Table::with_buckets(MIN_BUCKETS, j);
+    }
+
+    fn find<F: Fn(&K) -> bool>(&self, hash: u64, eq: F) -> Option<&Node<K, V, P>> {
+        let mut link = self.table.bucket(hash);
+        while let Some(node) = link {
+            if eq(&node.key) {
+                return Some(node);
             }
+            link = &node.next;
         }
         None
     }
 
-    pub fn put(&mut self, key: K, val: V, j: &Journal<P>) {
-        let mut hasher = DefaultHasher::new();
-        key.hash(&mut hasher);
-        let index = (hasher.finish() as usize) % BUCKETS_MAX;
-        let mut bucket = self.buckets[index].borrow_mut(j);
-
-        for e in &*bucket {
-            let e = e.borrow();
-            if e.0 == key {
-                *self.values[e.1].borrow_mut(j) = val;
-                return;
+    fn find_mut<F: Fn(&K) -> bool>(&mut self, hash: u64, eq: F) -> Option<&mut Node<K, V, P>> {
+        let mut link = self.table.bucket_mut(hash);
+        while let Some(node) = link {
+            let node = unsafe { node.as_mut() };
+            if eq(&node.key) {
+                return Some(node);
             }
+            link = &mut node.next;
         }
+        None
+    }
 
-        self.values.push(PRefCell::new(val), j);
-        bucket.push(PRefCell::new((key, self.values.len() - 1)), j);
+    /// Unlinks the first entry of chain `hash` for which `eq` holds, and
+    /// returns its key and value
+    fn take<F: Fn(&K) -> bool>(&mut self, hash: u64, eq: F, j: &Journal<P>) -> Option<(K, V)> {
+        self.find(hash, &eq)?;
+        let table = &mut self.table;
+        let mut link = table.bucket_mut(hash);
+        while !eq(&link.as_ref().unwrap().key) {
+            link = unsafe { &mut link.as_mut().unwrap().as_mut().next };
+        }
+        let node = Pbox::into_raw(logged(link, j).take().unwrap());
+        unsafe {
+            // The node is freed without dropping; its contents move out
+            let Node { key, val, next } = ptr::read(node);
+            *link = next;
+            P::free(&mut *node);
+            table.len -= 1;
+            Some((key, val))
+        }
     }
+}
 
-    pub fn put_with_hash<Key>(&mut self, key: Key, key_hash: u64, val: V, j: &Journal<P>)
-    where K: PartialEq<Key> + PFrom<Key, P> {
-        let index = (key_hash as usize) % BUCKETS_MAX;
-        let mut bucket = self.buckets[index].borrow_mut(j);
+impl<K: PSafe, V: PSafe, P: MemPool, S> HashMap<K, V, P, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher + Default,
+{
+    fn hash<Q: ?Sized + Hash>(key: &Q) -> u64 {
+        S::default().hash_one(key)
+    }
 
-        for e in &*bucket {
-            let e = e.borrow();
-            if e.0 == key {
-                *self.values[e.1].borrow_mut(j) = val;
+    /// Moves a few buckets of the old array to the new one
+    fn migrate(table: &mut Table<K, V, P>, j: &Journal<P>) {
+        for _ in 0..MIGRATE_STEP {
+            if !table.is_resizing() {
                 return;
             }
+            let mut chain = logged(&mut table.old.to_slice_mut()[table.moved], j).take();
+            while let Some(mut node) = chain {
+                let n = unsafe { node.as_mut() };
+                chain = logged(&mut n.next, j).take();
+                let i = index(Self::hash(&n.key), table.buckets.len());
+                let head = logged(&mut table.buckets.to_slice_mut()[i], j);
+                n.next = head.take();
+                *head = Some(node);
+            }
+            table.moved += 1;
+            if table.moved == table.old.len() {
+                table.old = PVec::new();
+                table.moved = 0;
+            }
         }
+    }
+
+    /// Starts moving the entries to `n` buckets, if the map is not already
+    /// resizing
+    fn resize(table: &mut Table<K, V, P>, j: &Journal<P>) {
+        if table.is_resizing() {
+            return;
+        }
+        let cap = table.buckets.len();
+        let n = if table.len > cap / 4 * 3 {
+            cap * 2
+        } else if cap > MIN_BUCKETS && table.len < cap / 8 {
+            cap / 2
+        } else {
+            return;
+        };
+        table.old = mem::replace(&mut table.buckets, Table::empty_buckets(n, j));
+        table.moved = 0;
+        Self::migrate(table, j);
+    }
 
-        self.values.push(PRefCell::new(val), j);
-        bucket.push(PRefCell::new((K::pfrom(key, j), self.values.len() - 1)), j);
+    /// Adds a new entry without looking for an existing one
+    ///
+    /// The returned reference is valid for as long as the map is borrowed,
+    /// because resizing relinks the entries without moving them.
+    fn push(&mut self, hash: u64, key: K, val: V, j: &Journal<P>) -> &mut V {
+        let table = &mut self.table;
+        Self::migrate(table, j);
+        let head = logged(table.bucket_mut(hash), j);
+        let next = head.take();
+        let node = head.insert(Pbox::new(Node { key, val, next }, j));
+        let val = unsafe { &mut *(&mut node.as_mut().val as *mut V) };
+        table.len += 1;
+        Self::resize(table, j);
+        val
+    }
+
+    /// Returns a reference to the value of `key`
+    pub fn get<Q: ?Sized + Eq + Hash>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+    {
+        self.find(Self::hash(key), |k| k.borrow() == key).map(|node| &node.val)
+    }
+
+    pub fn get_with_hash<Key>(&self, key: Key, key_hash: u64) -> Option<&V>
+    where K: PartialEq<Key> {
+        self.find(key_hash, |k| *k == key).map(|node| &node.val)
+    }
+
+    /// Returns `true` if the map has a value for `key`
+    pub fn contains_key<Q: ?Sized + Eq + Hash>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+    {
+        self.get(key).is_some()
+    }
+
+    /// Inserts `val` for `key` and returns the old value if there was one
+    pub fn insert(&mut self, key: K, val: V, j: &Journal<P>) -> Option<V> {
+        let hash = Self::hash(&key);
+        if let Some(node) = self.find_mut(hash, |k| *k == key) {
+            Some(mem::replace(logged(&mut node.val, j), val))
+        } else {
+            self.push(hash, key, val, j);
+            None
+        }
+    }
+
+    pub fn put(&mut self, key: K, val: V, j: &Journal<P>) {
+        self.insert(key, val, j);
+    }
+
+    pub fn put_with_hash<Key>(&mut self, key: Key, key_hash: u64, val: V, j: &Journal<P>)
+    where K: PartialEq<Key> + PFrom<Key, P> {
+        if let Some(node) = self.find_mut(key_hash, |k| *k == key) {
+            *logged(&mut node.val, j) = val;
+        } else {
+            self.push(key_hash, K::pfrom(key, j), val, j);
+        }
     }
 
     pub fn get_or_insert<F: FnOnce()->V>(&mut self, key: K, f: F, j: &Journal<P>) -> &V {
-        let mut hasher = DefaultHasher::new();
-        key.hash(&mut hasher);
-        let index = (hasher.finish() as usize) % BUCKETS_MAX;
-
-        for e in &*self.buckets[index].borrow() {
-            let e = e.borrow();
-            if e.0 == key {
-                return unsafe { &*(&*self.values[e.1].borrow() as *const V) };
-            }
+        let hash = Self::hash(&key);
+        if self.find(hash, |k| *k == key).is_none() {
+            self.push(hash, key, f(), j)
+        } else {
+            &self.find(hash, |k| *k == key).unwrap().val
         }
-        self.put_once(key, f(), j)
     }
 
-    pub fn get_or_insert_with_hash<Key, F: FnOnce()->V>(&mut self, key: Key, key_hash: u64, f: F, j: &Journal<P>) -> &V 
+    pub fn get_or_insert_with_hash<Key, F: FnOnce()->V>(&mut self, key: Key, key_hash: u64, f: F, j: &Journal<P>) -> &V
     where K: PartialEq<Key> + PFrom<Key, P>
     {
-        let index = (key_hash as usize) % BUCKETS_MAX;
-
-        for e in &*self.buckets[index].borrow() {
-            let e = e.borrow();
-            if e.0 == key {
-                return unsafe { &*(&*self.values[e.1].borrow() as *const V) };
-            }
+        if self.find(key_hash, |k| *k == key).is_none() {
+            self.push(key_hash, K::pfrom(key, j), f(), j)
+        } else {
+            &self.find(key_hash, |k| *k == key).unwrap().val
         }
-        self.put_once(K::pfrom(key, j), f(), j)
     }
 
+    /// Inserts `key` without checking if it already exists
     pub fn put_once(&mut self, key: K, val: V, j: &Journal<P>) -> &V {
-        let mut hasher = DefaultHasher::new();
-        key.hash(&mut hasher);
-        let index = (hasher.finish() as usize) % BUCKETS_MAX;
-        let mut bucket = self.buckets[index].borrow_mut(j);
-        self.values.push(PRefCell::new(val), j);
-        bucket.push(PRefCell::new((key, self.values.len() - 1)), j);
-        let new = self.values.last().unwrap().borrow();
-        unsafe { &*(&*new as *const V) }
+        self.push(Self::hash(&key), key, val, j)
     }
 
     pub fn update_with<F: FnOnce(&mut V)>(&mut self, key: &K, j: &Journal<P>, f: F)
@@ -159,26 +421,14 @@ where
         V: Default,
         K: PClone<P>,
     {
-        let mut hasher = DefaultHasher::new();
-        key.hash(&mut hasher);
-        let index = (hasher.finish() as usize) % BUCKETS_MAX;
-        let mut bucket = self.buckets[index].borrow_mut(j);
-
-        for e in &*bucket {
-            let e = e.borrow();
-            if e.0 == *key {
-                f(&mut *self.values[e.1].borrow_mut(j));
-                return;
-            }
+        let hash = Self::hash(key);
+        if let Some(node) = self.find_mut(hash, |k| k == key) {
+            f(logged(&mut node.val, j));
+        } else {
+            let mut new = V::default();
+            f(&mut new);
+            self.push(hash, key.pclone(j), new, j);
         }
-
-        let mut new = V::default();
-        f(&mut new);
-        self.values.push(PRefCell::new(new), j);
-        bucket.push(
-            PRefCell::new((key.pclone(j), self.values.len() - 1)),
-            j,
-        );
     }
 
     pub fn update_with_hash<Key, Value, F: FnOnce(&mut Value)>(&mut self, key: &Key, value_size: usize, key_hash: u64, j: &Journal<P>, f: F)
@@ -187,48 +437,90 @@ where
         K: PClone<P> + PartialEq<Key> + PFrom<Key, P>,
         Key: Clone
     {
-        let index = (key_hash as usize) % BUCKETS_MAX;
-        let mut bucket = self.buckets[index].borrow_mut(j);
-
-        for e in &*bucket {
-            let e = e.borrow();
-            if e.0 == *key {
-                f(self.values[e.1].borrow_mut(j).as_mut());
-                return;
-            }
+        if let Some(node) = self.find_mut(key_hash, |k| k == key) {
+            f(logged(&mut node.val, j).as_mut());
+        } else {
+            let mut new = unsafe { V::alloc_zeroed(value_size, j) };
+            f(new.as_mut());
+            self.push(key_hash, K::pfrom(key.clone(), j), new, j);
         }
+    }
+
+    /// Removes `key` from the map and returns its value if there was one
+    pub fn remove<Q: ?Sized + Eq + Hash>(&mut self, key: &Q, j: &Journal<P>) -> Option<V>
+    where
+        K: Borrow<Q>,
+    {
+        self.remove_entry(key, j).map(|(_, v)| v)
+    }
 
-        let mut new = unsafe { V::alloc_zeroed(value_size, j) };
-        f(new.as_mut());
-        self.values.push(PRefCell::new(new), j);
-        bucket.push(
-            PRefCell::new((K::pfrom(key.clone(), j), self.values.len() - 1)),
-            j,
-        );
+    /// Removes `key` from the map and returns the stored key and value if
+    /// there was one
+    pub fn remove_entry<Q: ?Sized + Eq + Hash>(&mut self, key: &Q, j: &Journal<P>) -> Option<(K, V)>
+    where
+        K: Borrow<Q>,
+    {
+        let hash = Self::hash(key);
+        let res = self.take(hash, |k| k.borrow() == key, j);
+        if res.is_some() {
+            Self::migrate(&mut self.table, j);
+            Self::resize(&mut self.table, j);
+        }
+        res
     }
 
-    pub fn clear(&mut self, j: &Journal<P>) {
-        for i in 0..BUCKETS_MAX {
-            self.buckets[i].borrow_mut(j).clear();
+    /// Returns the entry of `key` for in-place manipulation
+    pub fn entry<'a>(&'a mut self, key: K, j: &'a Journal<P>) -> Entry<'a, K, V, P, S> {
+        let hash = Self::hash(&key);
+        match self.find_mut(hash, |k| *k == key).map(|node| node as *mut Node<K, V, P>) {
+            Some(node) => Entry::Occupied(OccupiedEntry { map: self, node, hash, journal: j }),
+            None => Entry::Vacant(VacantEntry { map: self, key, hash, journal: j }),
         }
-        self.values.clear();
     }
+}
 
-    pub fn is_empty(&self) -> bool {
-        for i in 0..BUCKETS_MAX {
-            if !self.buckets[i].borrow().is_empty() {
-                return false;
-            }
+impl<K, V, P: MemPool, S> PClone<P> for HashMap<K, V, P, S>
+where
+    K: PSafe + Eq + Hash + PClone<P>,
+    V: PSafe + PClone<P>,
+    S: BuildHasher + Default,
+{
+    fn pclone(&self, j: &Journal<P>) -> Self {
+        let mut table = Table::with_buckets(self.capacity(), j);
+        for (k, v) in self.iter() {
+            let i = index(Self::hash(k), table.buckets.len());
+            let head = &mut table.buckets.to_slice_mut()[i];
+            let next = head.take();
+            *head = Some(Pbox::new(Node { key: k.pclone(j), val: v.pclone(j), next }, j));
+            table.len += 1;
+        }
+        Self {
+            table,
+            hasher: PhantomData,
         }
-        true
     }
 }
 
-impl<K: PSafe + Display, V: PSafe + Display + Copy, P: MemPool> Display for HashMap<K, V, P> {
+impl<'a, K: PSafe, V: PSafe, P: MemPool, S> IntoIterator for &'a HashMap<K, V, P, S> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V, P>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<K: PSafe + Debug, V: PSafe + Debug, P: MemPool, S> Debug for HashMap<K, V, P, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<K: PSafe + Display, V: PSafe + Display + Copy, P: MemPool, S> Display for HashMap<K, V, P, S> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
         let mut vec = vec![];
         self.foreach(|word, freq| {
-            vec.push((word.to_string(), freq.clone()));
+            vec.push((word.to_string(), *freq));
         });
         vec.sort_by(|x, y| x.0.cmp(&y.0));
         for (word, freq) in vec {
@@ -236,4 +528,301 @@ impl<K: PSafe + Display, V: PSafe + Display + Copy, P: MemPool> Display for Hash
         }
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+/// An iterator over the entries of a [`HashMap`]
+///
+/// [`HashMap`]: ./struct.HashMap.html
+pub struct Iter<'a, K: PSafe, V: PSafe, P: MemPool> {
+    buckets: [&'a [Link<K, V, P>]; 2],
+    node: Option<&'a Node<K, V, P>>,
+    len: usize,
+}
+
+impl<'a, K: PSafe, V: PSafe, P: MemPool> Iterator for Iter<'a, K, V, P> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(node) = self.node {
+                self.node = node.next.as_deref();
+                self.len -= 1;
+                return Some((&node.key, &node.val));
+            }
+            let buckets = if self.buckets[0].is_empty() {
+                &mut self.buckets[1]
+            } else {
+                &mut self.buckets[0]
+            };
+            let (head, rest) = buckets.split_first()?;
+            *buckets = rest;
+            self.node = head.as_deref();
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+/// A view into a single entry of a [`HashMap`] which may either be vacant or
+/// occupied
+///
+/// It is constructed from the [`entry`] method.
+///
+/// [`HashMap`]: ./struct.HashMap.html
+/// [`entry`]: ./struct.HashMap.html#method.entry
+pub enum Entry<'a, K: PSafe, V: PSafe, P: MemPool, S> {
+    Occupied(OccupiedEntry<'a, K, V, P, S>),
+    Vacant(VacantEntry<'a, K, V, P, S>),
+}
+
+/// An occupied [`Entry`](./enum.Entry.html)
+pub struct OccupiedEntry<'a, K: PSafe, V: PSafe, P: MemPool, S> {
+    map: &'a mut HashMap<K, V, P, S>,
+
+    /// The entry's node, which stays in place while `map` is borrowed
+    node: *mut Node<K, V, P>,
+    hash: u64,
+    journal: &'a Journal<P>,
+}
+
+/// A vacant [`Entry`](./enum.Entry.html)
+pub struct VacantEntry<'a, K: PSafe, V: PSafe, P: MemPool, S> {
+    map: &'a mut HashMap<K, V, P, S>,
+    key: K,
+    hash: u64,
+    journal: &'a Journal<P>,
+}
+
+impl<'a, K: PSafe + Eq + Hash, V: PSafe, P: MemPool, S: BuildHasher + Default> Entry<'a, K, V, P, S> {
+    /// Returns the key of the entry
+    pub fn key(&self) -> &K {
+        match self {
+            Entry::Occupied(e) => e.key(),
+            Entry::Vacant(e) => e.key(),
+        }
+    }
+
+    /// Inserts `default` if the entry is vacant, and returns a mutable
+    /// reference to the value
+    pub fn or_insert(self, default: V) -> &'a mut V {
+        self.or_insert_with(|| default)
+    }
+
+    /// Inserts the result of `f` if the entry is vacant, and returns a
+    /// mutable reference to the value
+    pub fn or_insert_with<F: FnOnce() -> V>(self, f: F) -> &'a mut V {
+        match self {
+            Entry::Occupied(e) => e.into_mut(),
+            Entry::Vacant(e) => e.insert(f()),
+        }
+    }
+
+    /// Inserts the default value if the entry is vacant, and returns a
+    /// mutable reference to the value
+    pub fn or_default(self) -> &'a mut V
+    where
+        V: Default,
+    {
+        self.or_insert_with(V::default)
+    }
+
+    /// Applies `f` to the value if the entry is occupied
+    pub fn and_modify<F: FnOnce(&mut V)>(self, f: F) -> Self {
+        match self {
+            Entry::Occupied(mut e) => {
+                f(e.get_mut());
+                Entry::Occupied(e)
+            }
+            Entry::Vacant(e) => Entry::Vacant(e),
+        }
+    }
+}
+
+impl<'a, K: PSafe + Eq + Hash, V: PSafe, P: MemPool, S: BuildHasher + Default> OccupiedEntry<'a, K, V, P, S> {
+    pub fn key(&self) -> &K {
+        unsafe { &(*self.node).key }
+    }
+
+    pub fn get(&self) -> &V {
+        unsafe { &(*self.node).val }
+    }
+
+    /// Returns a mutable reference to the value after taking a log of it
+    pub fn get_mut(&mut self) -> &mut V {
+        logged(unsafe { &mut (*self.node).val }, self.journal)
+    }
+
+    /// Converts the entry into a mutable reference to the value that lives
+    /// as long as the borrow of the map
+    pub fn into_mut(self) -> &'a mut V {
+        logged(unsafe { &mut (*self.node).val }, self.journal)
+    }
+
+    /// Replaces the value and returns the old one
+    pub fn insert(&mut self, val: V) -> V {
+        mem::replace(self.get_mut(), val)
+    }
+
+    /// Removes the entry from the map and returns its value
+    pub fn remove(self) -> V {
+        self.remove_entry().1
+    }
+
+    /// Removes the entry from the map and returns its key and value
+    pub fn remove_entry(self) -> (K, V) {
+        let key = unsafe { &(*self.node).key as *const K };
+        let res = self.map.take(self.hash, |k| ptr::eq(k, key), self.journal).unwrap();
+        HashMap::<K, V, P, S>::resize(&mut self.map.table, self.journal);
+        res
+    }
+}
+
+impl<'a, K: PSafe + Eq + Hash, V: PSafe, P: MemPool, S: BuildHasher + Default> VacantEntry<'a, K, V, P, S> {
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    pub fn into_key(self) -> K {
+        self.key
+    }
+
+    /// Inserts `val` into the map, and returns a mutable reference to it
+    pub fn insert(self, val: V) -> &'a mut V {
+        self.map.push(self.hash, self.key, val, self.journal)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::*;
+    use crate::open_flags::*;
+    use crate::cell::PRefCell;
+    use crate::stm::Journal;
+    use crate::stl::test::{check_keys, Keys};
+    use crate::stl::test_pool::P;
+    use super::{Entry, HashMap};
+
+    type Map = PRefCell<HashMap<u64, u64, P>, P>;
+
+    impl Keys for HashMap<u64, u64, P> {
+        fn add(&mut self, k: u64, j: &Journal<P>) {
+            self.insert(k, k * 2, j);
+        }
+
+        fn take(&mut self, j: &Journal<P>) -> Option<u64> {
+            let k = *self.keys().next()?;
+            assert_eq!(self.remove(&k, j), Some(k * 2));
+            Some(k)
+        }
+
+        fn sorted(&self) -> Vec<u64> {
+            let mut keys: Vec<u64> = self.keys().cloned().collect();
+            keys.sort();
+            keys
+        }
+    }
+
+    fn fill(root: &Map, n: u64) {
+        P::transaction(|j| {
+            let mut map = root.borrow_mut(j);
+            for k in 0..n {
+                assert_eq!(map.insert(k, k * 2, j), None);
+            }
+        }).unwrap();
+    }
+
+    #[test]
+    fn hashmap_insert_test() {
+        let root = P::open::<Map>("hashmap_insert.pool", O_CF).unwrap();
+        fill(&root, 5000);
+
+        P::transaction(|j| {
+            assert_eq!(root.borrow_mut(j).insert(5, 0, j), Some(10));
+        }).unwrap();
+
+        let map = root.borrow();
+        assert_eq!(map.len(), 5000);
+        assert_eq!(map.capacity(), 8192);
+        assert_eq!(map.get(&5), Some(&0));
+        assert_eq!(map.get(&5000), None);
+        assert_eq!(map.get_with_hash(7, HashMap::<u64, u64, P>::hash(&7u64)), Some(&14));
+        let mut keys: Vec<u64> = map.keys().cloned().collect();
+        keys.sort();
+        assert!(keys.into_iter().eq(0..5000));
+    }
+
+    #[test]
+    fn hashmap_entry_test() {
+        let root = P::open::<Map>("hashmap_entry.pool", O_CF).unwrap();
+        fill(&root, 100);
+
+        P::transaction(|j| {
+            let mut map = root.borrow_mut(j);
+            *map.entry(5, j).or_insert(0) += 10;
+            *map.entry(100, j).or_insert(1) += 1;
+            map.entry(6, j).and_modify(|v| *v = 0);
+            if let Entry::Occupied(e) = map.entry(7, j) {
+                assert_eq!(e.remove(), 14);
+            }
+        }).unwrap();
+
+        let map = root.borrow();
+        assert_eq!(map.len(), 100);
+        assert_eq!(map.get(&5), Some(&20));
+        assert_eq!(map.get(&6), Some(&0));
+        assert_eq!(map.get(&7), None);
+        assert_eq!(map.get(&100), Some(&2));
+    }
+
+    #[test]
+    fn hashmap_remove_test() {
+        let root = P::open::<Map>("hashmap_remove.pool", O_CF).unwrap();
+        fill(&root, 5000);
+
+        P::transaction(|j| {
+            let mut map = root.borrow_mut(j);
+            for k in (0..5000).filter(|k| k % 10 != 0) {
+                assert_eq!(map.remove(&k, j), Some(k * 2));
+            }
+            assert_eq!(map.remove(&1, j), None);
+        }).unwrap();
+
+        assert_eq!(root.borrow().len(), 500);
+        assert_eq!(root.borrow().capacity(), 4096);
+        assert!((0..5000).all(|k| root.borrow().contains_key(&k) == (k % 10 == 0)));
+
+        P::transaction(|j| root.borrow_mut(j).clear(j)).unwrap();
+        assert!(root.borrow().is_empty());
+        assert_eq!(root.borrow().iter().next(), None);
+    }
+
+    #[test]
+    fn hashmap_abort_test() {
+        let root = P::open::<Map>("hashmap_abort.pool", O_CF).unwrap();
+        fill(&root, 5000);
+
+        // The map is in the middle of resizing when the transaction aborts
+        let _ = P::transaction(|j| {
+            let mut map = root.borrow_mut(j);
+            for k in 0..5000 {
+                map.remove(&k, j);
+            }
+            for k in 10000..15000 {
+                map.insert(k, 0, j);
+            }
+            panic!("abort");
+        });
+
+        let map = root.borrow();
+        assert_eq!(map.len(), 5000);
+        assert_eq!(map.iter().count(), 5000);
+        assert!((0..5000).all(|k| map.get(&k) == Some(&(k * 2))));
+    }
+
+    #[test]
+    fn hashmap_keys_test() {
+        check_keys::<HashMap<u64, u64, P>>("hashmap_keys");
+    }
+}
@@ -0,0 +1,254 @@
+//! A persistent hash set
+
+use std::borrow::Borrow;
+use std::collections::hash_map::DefaultHasher;
+use std::fmt::{self, Debug};
+use std::hash::{BuildHasher, BuildHasherDefault, Hash};
+
+use crate::*;
+use crate::alloc::*;
+use crate::clone::PClone;
+use crate::stm::Journal;
+use super::HashMap;
+
+/// A persistent hash set
+///
+/// It is a [`HashMap`] with no values, and resizes the same way. `S` is the
+/// deterministic hasher builder as described in [`HashMap`].
+///
+/// [`HashMap`]: ./struct.HashMap.html
+///
+/// # Examples
+///
+/// ```
+/// use corundum::default::*;
+/// use corundum::open_flags::*;
+/// use corundum::stl::HashSet;
+///
+/// type P = Allocator;
+///
+/// let root = P::open::<PRefCell<HashSet<u64, P>>>("foo.pool", O_CF).unwrap();
+///
+/// P::transaction(|j| {
+///     let mut set = root.borrow_mut(j);
+///     assert!(set.insert(3, j));
+///     assert!(!set.insert(3, j));
+///     assert!(set.remove(&3, j));
+/// }).unwrap();
+///
+/// assert!(root.borrow().is_empty());
+/// ```
+pub struct HashSet<T: PSafe, P: MemPool, S = BuildHasherDefault<DefaultHasher>> {
+    map: HashMap<T, (), P, S>,
+}
+
+impl<T: PSafe, P: MemPool, S> RootObj<P> for HashSet<T, P, S> {
+    fn init(j: &Journal<P>) -> Self {
+        Self::new(j)
+    }
+}
+
+impl<T: PSafe + Trace<P>, P: MemPool, S> Trace<P> for HashSet<T, P, S> {
+    fn trace<V: Visitor>(&self, v: &mut V) {
+        self.map.trace(v);
+    }
+}
+
+impl<T, P: MemPool, S> PClone<P> for HashSet<T, P, S>
+where
+    T: PSafe + Eq + Hash + PClone<P>,
+    S: BuildHasher + Default,
+{
+    fn pclone(&self, j: &Journal<P>) -> Self {
+        Self { map: self.map.pclone(j) }
+    }
+}
+
+impl<T: PSafe, P: MemPool, S> HashSet<T, P, S> {
+    /// Creates an empty set
+    pub fn new(j: &Journal<P>) -> Self {
+        Self { map: HashMap::new(j) }
+    }
+
+    /// Creates an empty set with enough buckets for `cap` elements
+    pub fn with_capacity(cap: usize, j: &Journal<P>) -> Self {
+        Self { map: HashMap::with_capacity(cap, j) }
+    }
+
+    /// Returns the number of elements in the set
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns `true` if the set has no elements
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Returns the number of buckets
+    pub fn capacity(&self) -> usize {
+        self.map.capacity()
+    }
+
+    /// Returns an iterator over the elements in an arbitrary order
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.map.keys()
+    }
+
+    /// Removes all elements
+    pub fn clear(&mut self, j: &Journal<P>) {
+        self.map.clear(j)
+    }
+}
+
+impl<T: PSafe + Eq + Hash, P: MemPool, S: BuildHasher + Default> HashSet<T, P, S> {
+    /// Returns `true` if the set contains `value`
+    pub fn contains<Q: ?Sized + Eq + Hash>(&self, value: &Q) -> bool
+    where
+        T: Borrow<Q>,
+    {
+        self.map.contains_key(value)
+    }
+
+    /// Adds `value` to the set, and returns `false` if it was already there
+    pub fn insert(&mut self, value: T, j: &Journal<P>) -> bool {
+        if self.map.contains_key(&value) {
+            false
+        } else {
+            self.map.put_once(value, (), j);
+            true
+        }
+    }
+
+    /// Removes `value` from the set, and returns `true` if it was there
+    pub fn remove<Q: ?Sized + Eq + Hash>(&mut self, value: &Q, j: &Journal<P>) -> bool
+    where
+        T: Borrow<Q>,
+    {
+        self.map.remove(value, j).is_some()
+    }
+}
+
+impl<T: PSafe + Debug, P: MemPool, S> Debug for HashSet<T, P, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::hash::{BuildHasherDefault, Hasher};
+    use std::vec::Vec as StdVec;
+    use crate::*;
+    use crate::open_flags::*;
+    use crate::cell::PRefCell;
+    use crate::stm::Journal;
+    use crate::stl::test::{check_keys, Keys};
+    use crate::stl::test_pool::P;
+    use super::HashSet;
+
+    impl Keys for HashSet<u64, P> {
+        fn add(&mut self, k: u64, j: &Journal<P>) {
+            self.insert(k, j);
+        }
+
+        fn take(&mut self, j: &Journal<P>) -> Option<u64> {
+            let k = *self.iter().next()?;
+            assert!(self.remove(&k, j));
+            Some(k)
+        }
+
+        fn sorted(&self) -> StdVec<u64> {
+            let mut keys: StdVec<u64> = self.iter().cloned().collect();
+            keys.sort();
+            keys
+        }
+    }
+
+    /// Puts the keys with the same remainder of 64 in the same chain
+    #[derive(Default)]
+    struct Collide(u64);
+
+    impl Hasher for Collide {
+        fn finish(&self) -> u64 {
+            self.0 % 64
+        }
+
+        fn write(&mut self, bytes: &[u8]) {
+            for b in bytes {
+                self.0 = (self.0 << 8) | *b as u64;
+            }
+        }
+
+        fn write_u64(&mut self, k: u64) {
+            self.0 = k;
+        }
+    }
+
+    #[test]
+    fn hashset_keys_test() {
+        check_keys::<HashSet<u64, P>>("hashset_keys");
+    }
+
+    #[test]
+    fn hashset_resize_test() {
+        type Set = PRefCell<HashSet<u64, P>, P>;
+        let root = P::open::<Set>("hashset_resize.pool", O_CF).unwrap();
+
+        // Grows while inserting, and keeps finding the keys in both tables
+        // in the middle of resizing
+        P::transaction(|j| {
+            let mut set = root.borrow_mut(j);
+            for k in 0..5000 {
+                assert!(set.insert(k, j));
+                assert!(set.contains(&(k / 2)));
+            }
+        }).unwrap();
+        assert_eq!(root.borrow().capacity(), 8192);
+
+        // Aborting in the middle of shrinking restores the larger table
+        let _ = P::transaction(|j| {
+            let mut set = root.borrow_mut(j);
+            for k in 0..4900 {
+                set.remove(&k, j);
+            }
+            panic!("abort");
+        });
+        assert_eq!(root.borrow().capacity(), 8192);
+        assert_eq!(root.borrow().len(), 5000);
+
+        P::transaction(|j| {
+            let mut set = root.borrow_mut(j);
+            for k in 0..4900 {
+                assert!(set.remove(&k, j));
+                assert!(set.contains(&4999));
+            }
+        }).unwrap();
+        assert!(root.borrow().capacity() < 8192);
+        assert!((0..5000).all(|k| root.borrow().contains(&k) == (k >= 4900)));
+        assert_eq!(root.borrow().iter().count(), 100);
+    }
+
+    #[test]
+    fn hashset_rehash_test() {
+        type Set = PRefCell<HashSet<u64, P, BuildHasherDefault<Collide>>, P>;
+        let root = P::open::<Set>("hashset_rehash.pool", O_CF).unwrap();
+
+        // Long chains are split between the buckets while growing
+        P::transaction(|j| {
+            let mut set = root.borrow_mut(j);
+            for k in 0..1000 {
+                assert!(set.insert(k, j));
+            }
+            assert!(!set.insert(999, j));
+        }).unwrap();
+
+        let set = root.borrow();
+        assert_eq!(set.len(), 1000);
+        assert!(set.capacity() > 64);
+        assert!((0..2000).all(|k| set.contains(&k) == (k < 1000)));
+        let mut keys: StdVec<u64> = set.iter().cloned().collect();
+        keys.sort();
+        assert!(keys.into_iter().eq(0..1000));
+    }
+}
@@ -1,2 +1,99 @@
-mod hashmap;
-pub use hashmap::HashMap;
\ No newline at end of file
+pub mod hashmap;
+pub mod hashset;
+pub mod btreemap;
+pub mod btreeset;
+pub mod binary_heap;
+pub use hashmap::HashMap;
+pub use hashset::HashSet;
+pub use btreemap::BTreeMap;
+pub use btreeset::BTreeSet;
+pub use binary_heap::BinaryHeap;
+
+#[cfg(test)]
+crate::pool!(test_pool, P);
+
+#[cfg(test)]
+pub(crate) mod test {
+    use std::vec::Vec as StdVec;
+    use crate::*;
+    use crate::open_flags::*;
+    use crate::cell::PRefCell;
+    use crate::clone::PClone;
+    use crate::stm::Journal;
+    use super::test_pool::P;
+
+    /// The operations of a collection of `u64` keys that the shared tests use
+    pub trait Keys: PSafe + RootObj<P> + PClone<P> {
+        /// Whether the collection keeps the duplicate keys
+        const DUPLICATES: bool = false;
+
+        fn add(&mut self, k: u64, j: &Journal<P>);
+
+        /// Removes an element and returns its key
+        fn take(&mut self, j: &Journal<P>) -> Option<u64>;
+
+        /// Returns all keys in ascending order
+        fn sorted(&self) -> StdVec<u64>;
+    }
+
+    /// 200 keys in `0..100`, each twice in a scrambled order
+    fn keys() -> StdVec<u64> {
+        (0..200).map(|i| i * 37 % 100).collect()
+    }
+
+    /// Checks filling, taking, aborting, cloning, and reopening the
+    /// collection in pool file `<name>.pool`
+    pub fn check_keys<C: Keys>(name: &str) {
+        let path = format!("{}.pool", name);
+        let mut expected = keys();
+        expected.sort();
+        if !C::DUPLICATES {
+            expected.dedup();
+        }
+
+        {
+            let root = P::open::<PRefCell<C, P>>(&path, O_CF).unwrap();
+            P::transaction(|j| {
+                let mut c = root.borrow_mut(j);
+                for k in keys() {
+                    c.add(k, j);
+                }
+            }).unwrap();
+            assert_eq!(root.borrow().sorted(), expected);
+
+            // The new elements may reuse the space of the ones taken in the
+            // same transaction
+            let _ = P::transaction(|j| {
+                let mut c = root.borrow_mut(j);
+                while c.take(j).is_some() {}
+                for k in 1000..1100 {
+                    c.add(k, j);
+                }
+                panic!("abort");
+            });
+            assert_eq!(root.borrow().sorted(), expected);
+
+            let mut taken = StdVec::new();
+            let half = expected.len() / 2;
+            let out = AssertTxInSafe(&mut taken);
+            let r = &root;
+            P::transaction(move |j| {
+                let mut out = out;
+                let mut c = r.borrow_mut(j);
+                out.extend((0..half).map(|_| c.take(j).unwrap()));
+            }).unwrap();
+            for k in taken {
+                let i = expected.iter().position(|e| *e == k).unwrap();
+                expected.remove(i);
+            }
+            assert_eq!(root.borrow().sorted(), expected);
+        }
+
+        let root = P::open::<PRefCell<C, P>>(&path, 0).unwrap();
+        assert_eq!(root.borrow().sorted(), expected);
+        P::transaction(|j| {
+            let copy = root.borrow().pclone(j);
+            assert_eq!(copy.sorted(), expected);
+        }).unwrap();
+    }
+}
@@ -1,20 +1,25 @@
 use crate::result::Result;
 use crate::cell::LazyCell;
+use crate::ll::persist_obj;
 use crate::{TxInSafe, TxOutSafe, utils};
 use std::collections::hash_map::HashMap;
 use std::fmt::{self, Debug};
 use std::fs::OpenOptions;
-use std::io::{self, Error, Write};
+use std::io::{self, Error, ErrorKind, Write};
 use std::panic::{RefUnwindSafe, UnwindSafe};
 use std::path::Path;
 use std::sync::Mutex;
 use std::thread::ThreadId;
-use std::{mem, panic, ptr, slice, str, thread};
+use std::{fs, mem, panic, slice, str, thread};
 
 const MAX_TRANS: usize = 4096;
 
+/// The maximum length of a chaperon filename that a journal can refer to
+pub(crate) const MAX_FILENAME: usize = 4096;
 
-/// A third-party observer for multi-pool transactions 
+const CHAPERON_MAGIC: u64 = u64::from_be_bytes(*b"CHAPERON");
+
+/// A third-party observer for multi-pool transactions
 ///
 /// It provides an atomic supper transaction (a [`session`]) for manipulating
 /// persistent data in multiple pools, atomically. The involved pools go to a
@@ -23,139 +28,215 @@ const MAX_TRANS: usize = 4096;
 /// until the end of the [`session`]. To keep track of pools' states, it creates
 /// a chaperon file with necessary information for recovering them, in case of a
 /// crash.
-/// 
+///
+/// The session ends with a two-phase commit. First, the updates of all
+/// involved pools are made durable. Then, the decision to commit is written
+/// to the chaperon file, and the pools commit one by one. If a crash happens
+/// in between, the next [`open`] of every involved pool consults the chaperon
+/// file: the pool is rolled forward if the session reached the commit point,
+/// and rolled back otherwise. The chaperon file is removed once all involved
+/// pools have finished their part.
+///
 /// [`session`]: #method.session
 /// [`commit`]: ../alloc/trait.MemPool.html#method.commit
 /// [`rollback`]: ../alloc/trait.MemPool.html#method.rollback
+/// [`open`]: ../alloc/trait.MemPool.html#method.open
 pub struct Chaperon {
+    magic: u64,
+    id: u32,
     len: usize,
+    committed: bool,
     completed: bool,
     done: [bool; MAX_TRANS],
-    filename: [u8; 4096],
+    filename: [u8; MAX_FILENAME],
     filename_len: usize,
-    vdata: Option<VData>,
 }
 
-struct VData {
+/// A memory mapping of a chaperon file
+struct ChaperonFile {
     mmap: memmap::MmapMut,
-    delayed_commit: HashMap<ThreadId, Vec<unsafe fn() -> ()>>,
-    delayed_rollback: HashMap<ThreadId, Vec<unsafe fn() -> ()>>,
-    delayed_clear: HashMap<ThreadId, Vec<unsafe fn() -> ()>>,
-    mutex: u8,
 }
 
-impl VData {
-    pub fn new(mmap: memmap::MmapMut) -> Self {
-        Self {
-            mmap,
-            delayed_commit: HashMap::new(),
-            delayed_rollback: HashMap::new(),
-            delayed_clear: HashMap::new(),
-            mutex: 0,
+impl ChaperonFile {
+    fn open(filename: &str) -> io::Result<Self> {
+        let file = OpenOptions::new().read(true).write(true).open(filename)?;
+        if (file.metadata()?.len() as usize) < mem::size_of::<Chaperon>() {
+            return Err(Error::new(ErrorKind::InvalidData,
+                format!("`{}` is not a chaperon file", filename)));
         }
+        let mmap = unsafe { memmap::MmapOptions::new().map_mut(&file)? };
+        let slf = Self { mmap };
+        if !slf.chaperon().is_valid() {
+            return Err(Error::new(ErrorKind::InvalidData,
+                format!("`{}` is not a chaperon file", filename)));
+        }
+        Ok(slf)
+    }
+
+    fn chaperon(&self) -> &'static mut Chaperon {
+        unsafe { &mut *utils::read(self.mmap.as_ptr() as *mut u8) }
     }
 }
 
-impl !TxOutSafe for Chaperon {}
-impl UnwindSafe for Chaperon {}
-impl RefUnwindSafe for Chaperon {}
-unsafe impl TxInSafe for Chaperon {}
-unsafe impl Send for Chaperon {}
-unsafe impl Sync for Chaperon {}
+/// The volatile state of a running session
+struct Session {
+    file: ChaperonFile,
+    delayed: Vec<Delayed>,
+}
 
-struct SyncBox<T: ?Sized> {
-    data: *mut T
+/// The postponed finalization functions of a pool attached to a session
+struct Delayed {
+    prepare: unsafe fn(),
+    commit: unsafe fn(),
+    rollback: unsafe fn(),
+    clear: unsafe fn(),
 }
 
-impl<T: ?Sized> SyncBox<T> {
-    fn new(data: *mut T) -> Self {
-        Self { data }
+impl Session {
+    fn commit(self) {
+        // Phase 1: make the updates of all pools durable
+        for d in &self.delayed {
+            unsafe { (d.prepare)(); }
+        }
+        crate::may_crash!("chaperon::prepare");
+
+        // The commit point
+        let c = self.file.chaperon();
+        c.committed = true;
+        persist_obj(&c.committed, true);
+        crate::may_crash!("chaperon::commit_point");
+
+        // Phase 2: commit all pools
+        for d in &self.delayed {
+            unsafe { (d.commit)(); }
+            crate::may_crash!("chaperon::commit");
+        }
+        self.clear();
     }
 
-    fn get(&self) -> *mut T {
-        self.data
+    fn rollback(self) {
+        for d in &self.delayed {
+            unsafe { (d.rollback)(); }
+            crate::may_crash!("chaperon::rollback");
+        }
+        self.clear();
+    }
+
+    fn clear(self) {
+        for d in &self.delayed {
+            unsafe { (d.clear)(); }
+            crate::may_crash!("chaperon::clear");
+        }
+        let c = self.file.chaperon();
+        if c.completed() {
+            let _ = fs::remove_file(c.filename());
+        }
     }
 }
 
-unsafe impl<T:?Sized> Sync for SyncBox<T> {}
-unsafe impl<T:?Sized> Send for SyncBox<T> {}
+impl !TxOutSafe for Chaperon {}
+impl UnwindSafe for Chaperon {}
+impl RefUnwindSafe for Chaperon {}
+unsafe impl TxInSafe for Chaperon {}
+unsafe impl Send for Chaperon {}
+unsafe impl Sync for Chaperon {}
 
-static mut CLIST: LazyCell<Mutex<HashMap<ThreadId, SyncBox<Chaperon>>>> = 
+static mut CLIST: LazyCell<Mutex<HashMap<ThreadId, Session>>> =
     LazyCell::new(|| Mutex::new(HashMap::new()));
 
-fn new_chaperon(filename: &str) -> Result<*mut Chaperon> {
+fn with_clist<T, F: FnOnce(&mut HashMap<ThreadId, Session>) -> T>(f: F) -> T {
     let mut clist = match unsafe { CLIST.lock() } {
         Ok(g) => g,
         Err(p) => p.into_inner()
     };
+    f(&mut clist)
+}
+
+fn new_chaperon(filename: &str) -> Result<()> {
     let tid = thread::current().id();
-    if clist.contains_key(&tid) {
-        return Err("Another chaperoned transaction is open".to_string());
-    }
-    let c = Chaperon::new(filename.to_string())
-        .expect(&format!("could not create chaperon file `{}`", filename));
-    clist.entry(tid).or_insert(SyncBox::new(c));
-    Ok(clist.get(&tid).unwrap().get())
+    with_clist(|clist| {
+        if clist.contains_key(&tid) {
+            return Err(crate::Error::ChaperonBusy);
+        }
+        if filename.is_empty() || filename.len() > MAX_FILENAME {
+            return Err(crate::Error::InvalidChaperon(format!(
+                "The chaperon filename should have 1 to {} bytes", MAX_FILENAME)));
+        }
+        let file = Chaperon::create(filename)
+            .map_err(|e| crate::Error::Chaperon { path: filename.to_string(), source: e })?;
+        clist.insert(tid, Session { file, delayed: vec![] });
+        Ok(())
+    })
 }
 
-fn drop_chaperon() {
-    let mut clist = match unsafe { CLIST.lock() } {
-        Ok(g) => g,
-        Err(p) => p.into_inner()
-    };
+fn take_session() -> Option<Session> {
     let tid = thread::current().id();
-    clist.remove(&tid);
+    with_clist(|clist| clist.remove(&tid))
 }
 
 fn current_chaperon() -> Option<*mut Chaperon> {
-    let clist = match unsafe { CLIST.lock() } {
-        Ok(g) => g,
-        Err(p) => p.into_inner()
-    };
     let tid = thread::current().id();
-    if clist.contains_key(&tid) {
-        Some(clist.get(&tid).unwrap().get())
-    } else {
-        None
-    }
+    with_clist(|clist| {
+        clist.get(&tid).map(|s| s.file.chaperon() as *mut Chaperon)
+    })
 }
 
 impl Chaperon {
-    pub(crate) fn new(filename: String) -> io::Result<&'static mut Self> {
+    fn create(filename: &str) -> io::Result<ChaperonFile> {
+        if Path::new(filename).exists() {
+            // The file may belong to a session which is not finished yet. Its
+            // decision is required for recovering the involved pools, so it
+            // cannot be overwritten. Otherwise, it is either a complete
+            // session which was not removed due to a crash, or it is not a
+            // chaperon file at all.
+            match ChaperonFile::open(filename) {
+                Ok(f) if !f.chaperon().completed() => {
+                    return Err(Error::new(ErrorKind::AlreadyExists,
+                        "the file belongs to an incomplete session; open the \
+                        involved pools to recover it"));
+                }
+                Ok(_) => {}
+                Err(_) => {
+                    return Err(Error::new(ErrorKind::AlreadyExists,
+                        "the file exists and is not a chaperon file"));
+                }
+            }
+        }
         let mut file = OpenOptions::new()
             .read(true)
             .write(true)
             .create(true)
             .truncate(true)
-            // Note: If file already exists, it may reflect three cases
-            //   1. A crash happened after creating the file but before assigning
-            //      it to a journal.
-            //   2. User mistakenly specified a filename which already exists
-            //   3. Crash after assigning the journals
-            // In the first case, it is safe to delete the file, but we can't do
-            // it here because the second case is more common and we don't want
-            // to delete a file when it might be required by another chaperoned
-            // session.
-            .open(&filename)?;
-        file.set_len(1024 * 1024 * 1)?;
+            .open(filename)?;
+        file.set_len(1024 * 1024)?;
+
+        // The journals refer to the file by this name; so, it should not
+        // depend on the working directory
+        let path = fs::canonicalize(filename).ok()
+            .and_then(|p| p.to_str().map(str::to_string))
+            .filter(|p| p.len() <= MAX_FILENAME);
+        let filename = match &path {
+            Some(path) => path.as_str(),
+            None => {
+                let _ = fs::remove_file(filename);
+                return Err(Error::new(ErrorKind::InvalidInput,
+                    "the absolute path of the file is too long or not valid UTF-8"));
+            }
+        };
         let mut a = Self {
+            magic: CHAPERON_MAGIC,
+            id: utils::rand() as u32,
             len: 0,
+            committed: false,
             completed: false,
             done: [true; MAX_TRANS],
-            filename: [0; 4096],
+            filename: [0; MAX_FILENAME],
             filename_len: filename.len(),
-            vdata: None,
         };
-        let bytes = filename.as_bytes();
-        for i in 0..4096.min(filename.len()) {
-            a.filename[i] = bytes[i];
-        }
-        file.write_all(a.as_bytes()).unwrap();
-        unsafe { Self::load(&filename) }
-    }
-
-    fn deref(raw: *mut u8) -> &'static mut Self {
-        unsafe { &mut *utils::read(raw) }
+        a.filename[..filename.len()].copy_from_slice(filename.as_bytes());
+        file.write_all(a.as_bytes())?;
+        file.sync_all()?;
+        ChaperonFile::open(filename)
     }
 
     fn as_bytes(&self) -> &[u8] {
@@ -164,50 +245,65 @@ impl Chaperon {
         unsafe { std::slice::from_raw_parts(ptr, std::mem::size_of::<Self>()) }
     }
 
+    fn is_valid(&self) -> bool {
+        self.magic == CHAPERON_MAGIC
+            && self.len <= MAX_TRANS
+            && self.filename_len <= MAX_FILENAME
+    }
+
     /// Loads a chaperon file
+    ///
+    /// The file stays mapped until the program ends.
     pub unsafe fn load(filename: &str) -> io::Result<&'static mut Self> {
-        if Path::new(&filename).exists() {
-            let file = OpenOptions::new().read(true).write(true).open(&filename)?;
-            let mut mmap = memmap::MmapOptions::new().map_mut(&file).unwrap();
-            let slf = Self::deref(mmap.get_mut(0).unwrap());
-            mem::forget(ptr::replace(&mut slf.vdata, Some(VData::new(mmap))));
-            Ok(slf)
-        } else {
-            Err(Error::last_os_error())
-        }
+        let file = ChaperonFile::open(filename)?;
+        let slf = file.chaperon();
+        mem::forget(file);
+        Ok(slf)
     }
 
     pub(crate) fn current() -> Option<*mut Chaperon> {
         current_chaperon()
     }
 
-    pub(crate) fn new_section(&mut self) -> usize {
-        use crate::ll::persist_obj;
-
+    /// Adds a new section to the session, and passes its identifier to
+    /// `attach` to be stored in the journal of the attaching pool
+    ///
+    /// The journal refers to the section before the section is registered;
+    /// so, a crash in between cannot leave a section behind which is never
+    /// finished.
+    pub(crate) fn new_section<F: FnOnce(u64)>(&mut self, attach: F) {
         assert!(self.len < MAX_TRANS, "reached max number of attachments");
+        attach(((self.id as u64) << 32) | (self.len as u64 + 1));
+        self.done[self.len] = false;
+        persist_obj(&self.done[self.len], false);
         self.len += 1;
-        self.done[self.len - 1] = false;
-        persist_obj(self, true);
-        self.len
+        persist_obj(&self.len, true);
     }
 
-    #[inline]
-    pub(crate) fn is_done(&self, id: usize) -> bool {
-        let id = id - 1;
-        assert!(id < self.len, "index out of range");
-        self.done[id]
+    /// Returns the index of a section, if it belongs to this session
+    fn index(&self, sec_id: u64) -> Option<usize> {
+        let idx = (sec_id & u32::MAX as u64) as usize;
+        if (sec_id >> 32) as u32 == self.id && idx > 0 && idx <= self.len {
+            Some(idx - 1)
+        } else {
+            None
+        }
     }
 
     #[inline]
-    pub(crate) fn finish(&mut self, id: usize) {
-        let id = id - 1;
-        assert!(id < self.len, "index out of range");
-        self.done[id] = true;
-        if self.completed() {
-            self.close();
+    pub(crate) fn finish(&mut self, sec_id: u64) {
+        if let Some(i) = self.index(sec_id) {
+            self.done[i] = true;
+            persist_obj(&self.done[i], true);
         }
     }
 
+    /// Returns true if the session reached its commit point
+    pub fn is_committed(&self) -> bool {
+        self.committed
+    }
+
+    /// Returns true if all sections of the session are finished
     pub(crate) fn completed(&mut self) -> bool {
         if self.completed {
             true
@@ -218,12 +314,33 @@ impl Chaperon {
                 }
             }
             self.completed = true;
+            persist_obj(&self.completed, true);
             true
         }
     }
 
-    fn close(&self) {
-        // std::fs::remove_file(self.filename()).unwrap();
+    /// Returns true if section `sec_id` of the chaperon file `filename`
+    /// belongs to a session which reached its commit point
+    ///
+    /// If the file belongs to another session, the session has never
+    /// committed. It fails if the file cannot be opened, because the decision
+    /// is unknown.
+    pub(crate) fn session_committed(filename: &str, sec_id: u64) -> io::Result<bool> {
+        let f = ChaperonFile::open(filename)?;
+        let c = f.chaperon();
+        Ok(c.index(sec_id).is_some() && c.committed)
+    }
+
+    /// Marks section `sec_id` of the chaperon file `filename` as finished, and
+    /// removes the file if it was the last unfinished section
+    pub(crate) fn finish_section(filename: &str, sec_id: u64) {
+        if let Ok(f) = ChaperonFile::open(filename) {
+            let c = f.chaperon();
+            c.finish(sec_id);
+            if c.completed() {
+                let _ = fs::remove_file(filename);
+            }
+        }
     }
 
     /// Returns the chaperon filename
@@ -235,73 +352,38 @@ impl Chaperon {
     }
 
     pub(crate) fn postpone(
-        &mut self,
+        prepare: unsafe fn()->(),
         commit: unsafe fn()->(),
         rollback: unsafe fn()->(),
         clear: unsafe fn()->(),
     ) {
-        if let Some(vdata) = self.vdata.as_mut() {
-            let tid = thread::current().id();
-            let commits = vdata.delayed_commit.entry(tid).or_insert(Vec::new());
-            let rollbacks = vdata.delayed_rollback.entry(tid).or_insert(Vec::new());
-            let clears = vdata.delayed_clear.entry(tid).or_insert(Vec::new());
-            commits.push(commit);
-            rollbacks.push(rollback);
-            clears.push(clear);
-        }
-    }
-
-    fn execute_delayed_commits(&mut self) {
-        if let Some(vdata) = self.vdata.as_mut() {
-            let tid = thread::current().id();
-            let commits = vdata.delayed_commit.entry(tid).or_insert(Vec::new());
-            let clears = vdata.delayed_clear.entry(tid).or_insert(Vec::new());
-            for commit in commits {
-                unsafe { commit(); }
+        let tid = thread::current().id();
+        with_clist(|clist| {
+            if let Some(session) = clist.get_mut(&tid) {
+                session.delayed.push(Delayed { prepare, commit, rollback, clear });
             }
-            self.completed = true;
-            for clear in clears {
-                unsafe { clear(); }
-            }
-            vdata.delayed_commit.remove(&tid);
-            vdata.delayed_clear.remove(&tid);
-        }
-        // self.close();
-    }
-
-    fn execute_delayed_rollbacks(&mut self) {
-        if let Some(vdata) = self.vdata.as_mut() {
-            let tid = thread::current().id();
-            let rollbacks = vdata.delayed_rollback.entry(tid).or_insert(Vec::new());
-            let clears = vdata.delayed_clear.entry(tid).or_insert(Vec::new());
-            for rollback in rollbacks {
-                unsafe { rollback(); }
-            }
-            self.completed = true;
-            for clear in clears {
-                unsafe { clear(); }
-            }
-            vdata.delayed_rollback.remove(&tid);
-            vdata.delayed_clear.remove(&tid);
-        }
-        // self.close();
+        })
     }
 
     #[inline]
     /// Starts a chaperoned session
-    /// 
+    ///
     /// It creates a chaperoned session in which multiple pools can start a
     /// [`transaction`]. The transactions won't be finalized until the session
     /// ends. A chaperon file keeps the necessary information for recovering the
     /// involved pools. If the operation is successful, it returns a value of
     /// type `T`.
-    /// 
+    ///
+    /// It fails if `filename` exists and belongs to a session which is not
+    /// finished yet. In that case, the involved pools should be opened first
+    /// to recover the session.
+    ///
     /// # Safety
-    /// 
+    ///
     /// * In case of a crash, the involved pools are not individually
-    /// recoverable on the absence of the chaperon file.
+    ///   recoverable on the absence of the chaperon file.
     /// * Chaperoned sessions cannot be nested.
-    /// 
+    ///
     /// # Examples
     ///
     /// ```
@@ -355,38 +437,33 @@ impl Chaperon {
     ///     }
     /// }
     /// ```
-    /// 
+    ///
     /// [`transaction`]: ./fn.transaction.html
-    pub fn session<T, F: FnOnce() -> T>(filename: &str, body: F) -> Result<T>
+    pub fn session<T, F>(filename: &str, body: F) -> Result<T>
     where
-        F: panic::UnwindSafe,
+        F: FnOnce() -> T + panic::UnwindSafe,
         T: panic::UnwindSafe + TxOutSafe,
     {
-        let chaperon = unsafe { &mut *new_chaperon(filename)? };
-        let res = panic::catch_unwind(|| body());
-        if let Ok(res) = res {
-            chaperon.execute_delayed_commits();
-            drop_chaperon();
-            Ok(res)
-        } else {
-            chaperon.execute_delayed_rollbacks();
-            drop_chaperon();
-            Err("Unsuccessful chaperoned transaction".to_string())
-        }
-    }
-}
-
-impl Drop for Chaperon {
-    fn drop(&mut self) {
-        if let Some(vdata) = self.vdata.as_ref() {
-            vdata.mmap.flush().unwrap();
+        new_chaperon(filename)?;
+        let res = panic::catch_unwind(body);
+        let session = take_session().unwrap();
+        match res {
+            Ok(res) => {
+                session.commit();
+                Ok(res)
+            }
+            Err(payload) => {
+                session.rollback();
+                Err(crate::Error::aborted(payload))
+            }
         }
     }
 }
 
 impl Debug for Chaperon {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{{ filename: {}, len: {}, [", self.filename(), self.len)?;
+        write!(f, "{{ filename: {}, committed: {}, len: {}, [",
+            self.filename(), self.committed, self.len)?;
         for i in 0..self.len {
             write!(f, "{}{}", if i == 0 { "" } else { ", " }, self.done[i])?;
         }
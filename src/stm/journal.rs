@@ -3,6 +3,7 @@ use crate::alloc::MemPool;
 use crate::ll::*;
 use crate::ptr::Ptr;
 use crate::stm::*;
+use crate::stm::redo::{RedoLog, FLAG_LOGGED};
 use crate::*;
 use std::collections::HashMap;
 use std::fmt::{self, Debug, Formatter};
@@ -22,6 +23,18 @@ use crate::stm::vspd::Scratchpad;
 /// Determines that the changes are committed
 pub const JOURNAL_COMMITTED: u64 = 0x0000_0001;
 
+/// Determines that the transaction uses redo logging
+pub const JOURNAL_REDO: u64 = 0x0000_0002;
+
+/// Determines that the journal has the fields appended to its original layout
+/// (i.e. the redo log). The journals left by older versions do not have them.
+pub const JOURNAL_EXTENDED: u64 = 0x0000_0004;
+
+/// The first byte of the chaperon filename field of a journal if the filename
+/// is stored out of line. It cannot start a filename stored inline by older
+/// versions, as it is not valid in UTF-8.
+const CHAPERON_OUT_OF_LINE: u8 = 0xff;
+
 /// A Journal object to be used for writing logs onto
 ///
 /// Each transaction, hence each thread, may have only one journal for every
@@ -56,7 +69,16 @@ pub const JOURNAL_COMMITTED: u64 = 0x0000_0001;
 /// 
 /// [`transaction()`]: ./fn.transaction.html
 /// 
+#[repr(C)]
 pub struct Journal<A: MemPool> {
+    /// The chaperon filename of the attached session. Older versions keep it
+    /// inline. It is now allocated separately, because paths can be long, and
+    /// the field keeps `CHAPERON_OUT_OF_LINE`, the offset, and the length of
+    /// the filename in its first three words.
+    ///
+    /// The order of the fields up to `gen` is the layout of the journals
+    /// written by older versions, so that they can be recovered.
+    chaperon: [u8; 64],
     pages: Ptr<Page<A>, A>,
 
     #[cfg(feature = "pin_journals")]
@@ -65,12 +87,14 @@ pub struct Journal<A: MemPool> {
     #[cfg(any(feature = "use_pspd", feature = "use_vspd"))]
     spd: Scratchpad<A>,
 
-    gen: u32,
     flags: u64,
     sec_id: u64,
     prev_off: u64,
     next_off: u64,
-    chaperon: [u8;64],
+    gen: u32,
+
+    /// Valid only if `JOURNAL_EXTENDED` is set
+    redo: RedoLog<A>,
 }
 
 impl<A: MemPool> !PSafe for Journal<A> {}
@@ -124,6 +148,27 @@ impl<A: MemPool> Page<A> {
         }
     }
 
+    unsafe fn stamp(&mut self, ts: u64) {
+        for i in 0..self.len {
+            self.logs[i].stamp(ts);
+        }
+    }
+
+    fn has_stamps(&self) -> bool {
+        self.logs[self.head..self.len].iter().any(|l| matches!(l.inner(), LogEnum::StampOnCommit(_)))
+    }
+
+    /// Collects the memory blocks that keep the data of the undo logs
+    fn blocks(&self, res: &mut Vec<(u64, usize)>) {
+        for log in &self.logs[self.head..self.len] {
+            if let LogEnum::DataLog(_, log, len) = log.inner() {
+                if log != u64::MAX {
+                    res.push((log, len));
+                }
+            }
+        }
+    }
+
     unsafe fn commit_dealloc(&mut self, 
         #[cfg(feature = "check_double_free")]
         check_double_free: &mut HashSet<u64>
@@ -191,7 +236,7 @@ impl<A: MemPool> Page<A> {
         }
     }
 
-    fn into_iter(&self) -> std::vec::IntoIter<Log<A>> {
+    fn logs(&self) -> std::vec::IntoIter<Log<A>> {
         Vec::from(self.logs).into_iter()
     }
 }
@@ -218,8 +263,10 @@ impl<A: MemPool> Journal<A> {
             #[cfg(any(feature = "use_pspd", feature = "use_vspd"))]
             spd: Scratchpad::new(),
 
+            redo: RedoLog::new(),
+
             gen,
-            flags: 0,
+            flags: JOURNAL_EXTENDED,
             sec_id: 0,
             next_off: u64::MAX,
             prev_off: u64::MAX,
@@ -253,6 +300,87 @@ impl<A: MemPool> Journal<A> {
         self.flags & flag == flag
     }
 
+    /// Returns the redo log, if the journal has one
+    #[inline]
+    fn redo(&mut self) -> Option<&mut RedoLog<A>> {
+        if self.is_set(JOURNAL_EXTENDED) {
+            Some(&mut self.redo)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the logging mode of the transaction
+    pub fn log_mode(&self) -> LogMode {
+        if self.is_set(JOURNAL_REDO) {
+            LogMode::Redo
+        } else {
+            LogMode::Undo
+        }
+    }
+
+    /// Sets the logging mode of the transaction
+    ///
+    /// It should be called before the transaction takes any log. The mode is
+    /// not persisted, as the recovery does not depend on it.
+    pub(crate) unsafe fn set_log_mode(&mut self, mode: LogMode) {
+        match mode {
+            LogMode::Undo => self.unset(JOURNAL_REDO),
+            LogMode::Redo => self.flags |= JOURNAL_REDO,
+        }
+    }
+
+    /// Returns a pointer to the draft of `val` in the redo log, and drafts it
+    /// if it is not drafted yet
+    ///
+    /// `flag` is the log flag of the container which is set to
+    /// `FLAG_DRAFTED`. It returns `None` if the transaction does not use
+    /// redo logging.
+    #[inline]
+    pub(crate) fn draft_redo<T: ?Sized>(&self, val: &T, flag: &mut u8) -> Option<*mut T> {
+        if self.is_set(JOURNAL_REDO) {
+            unsafe { Some(self.redo.draft(val, flag)) }
+        } else {
+            None
+        }
+    }
+
+    /// Returns true if `val` is in a draft of the redo log
+    #[inline]
+    pub(crate) fn in_draft<T: ?Sized>(&self, val: &T) -> bool {
+        self.is_set(JOURNAL_REDO) && !A::valid(val) && self.redo.home(val).is_some()
+    }
+
+    /// Returns a notifier for the log flag `flag` of a nested object
+    ///
+    /// If the owner of the flag is in a draft of the redo log, the notifier
+    /// updates the original location of the flag, which is reset after the
+    /// draft is applied or discarded. The draft of the flag is set as well,
+    /// so the owner does not log the same data again. It returns
+    /// `Notifier::None` if `flag` is neither in the pool nor in a draft.
+    pub(crate) fn notifier(&self, flag: &u8) -> Notifier<A> {
+        if A::valid(flag) {
+            return Notifier::NonAtomic(Ptr::from_ref(flag));
+        }
+        if self.is_set(JOURNAL_REDO) {
+            unsafe {
+                if let Some(off) = self.redo.home_flag(flag) {
+                    *utils::as_mut(flag) = FLAG_LOGGED;
+                    return Notifier::NonAtomic(Ptr::from_off_unchecked(off));
+                }
+            }
+        }
+        Notifier::None
+    }
+
+    /// Returns a pointer to the draft of `val` in the redo log of the running
+    /// transaction of the current thread, if it is drafted
+    #[inline]
+    pub(crate) fn drafted<T: ?Sized>(val: &T) -> Option<*mut T> {
+        let (j, _) = Self::try_current()?;
+        unsafe { (*j).redo.lookup(val) }
+    }
+
     /// Atomically enters into the list journals of the owner pool
     pub unsafe fn enter_into(&mut self, head_off: &u64, zone: usize) {
         let me = A::off_unchecked(self);
@@ -316,9 +444,7 @@ impl<A: MemPool> Journal<A> {
     /// Writes a new log to the journal
     #[cfg(not(feature = "pin_journals"))]
     pub(crate) fn write(&self, log: LogEnum, notifier: Notifier<A>) -> Ptr<Log<A>, A> {
-        let mut page = if self.pages.is_dangling() {
-            self.new_page()
-        } else if self.pages.is_full() {
+        let mut page = if self.pages.is_dangling() || self.pages.is_full() {
             self.new_page()
         } else {
             self.pages
@@ -337,6 +463,17 @@ impl<A: MemPool> Journal<A> {
         }
         self.current = Ptr::dangling();
         self.pages = Ptr::dangling();
+
+        #[cfg(feature = "use_pspd")] {
+            self.spd.release();
+        }
+    }
+
+    /// Sets the size of the scratchpad pages for the rest of the transaction
+    #[cfg(any(feature = "use_pspd", feature = "use_vspd"))]
+    #[inline]
+    pub(crate) fn set_scratchpad_size(&mut self, size: usize) {
+        self.spd.set_size(size);
     }
 
     /// Writes a new log to the journal
@@ -370,7 +507,7 @@ impl<A: MemPool> Journal<A> {
                 }
             }
 
-            for log in page.into_iter() {
+            for log in page.logs() {
                 let entry = log_cnt.entry(log.kind()).or_default();
                 *entry += 1;
                 if info_level > 3 && log != LogEnum::None {
@@ -396,7 +533,7 @@ impl<A: MemPool> Journal<A> {
         let mut res = format!("Committed: {}\n", 
             if self.is_committed() { "Yes" } else { "No" });
         res += &format!("Chaperoned session id: {}\n", self.sec_id);
-        res += &format!("Chaperone file: {}\n", String::from_utf8(self.chaperon.to_vec()).unwrap_or("".to_string()));
+        res += &format!("Chaperone file: {}\n", self.session_file().unwrap_or_default());
         res += &format!("Number of pages: {}\n", i-1);
 
         #[cfg(feature = "pin_journals")] {
@@ -420,6 +557,25 @@ impl<A: MemPool> Journal<A> {
         res
     }
 
+    /// Makes the updates durable without committing them
+    ///
+    /// It is the first phase of the two-phase commit of a chaperoned session.
+    /// The journal can still be rolled back afterwards.
+    pub unsafe fn prepare(&mut self) {
+        let mut curr = self.pages;
+        while let Some(page) = curr.as_option() {
+            page.commit_data();
+            curr = page.next;
+        }
+        #[cfg(any(feature = "use_pspd", feature = "use_vspd"))] {
+            self.spd.seal();
+        }
+        if let Some(redo) = self.redo() {
+            redo.persist();
+        }
+        sfence();
+    }
+
     /// Commits all logs in the journal
     pub unsafe fn commit(&mut self, 
         #[cfg(feature = "check_double_free")]
@@ -438,6 +594,23 @@ impl<A: MemPool> Journal<A> {
             page.commit_data();
             curr = page.next;
         }
+        sfence();
+        if let Some(redo) = self.redo() {
+            redo.persist(); // The commit point of a redo transaction
+        }
+        if self.has_stamps() {
+            crate::stm::snapshot::stamp(|ts| {
+                let mut curr = self.pages;
+                while let Some(page) = curr.as_option() {
+                    page.stamp(ts);
+                    curr = page.next;
+                }
+                sfence();
+            });
+        }
+        if let Some(redo) = self.redo() {
+            redo.apply();
+        }
         let mut curr = self.pages;
         while let Some(page) = curr.as_option() {
             page.commit_dealloc(
@@ -458,6 +631,9 @@ impl<A: MemPool> Journal<A> {
         #[cfg(any(feature = "use_pspd", feature = "use_vspd"))] {
             self.spd.rollback();
         }
+        if let Some(redo) = self.redo() {
+            redo.rollback();
+        }
         let mut curr = self.pages;
         while let Some(page) = curr.as_option() {
             page.notify();
@@ -481,19 +657,27 @@ impl<A: MemPool> Journal<A> {
     }
 
     /// Recovers from a crash or power failure
+    ///
+    /// It fails without changing anything if the journal is attached to a
+    /// chaperoned session whose chaperon file cannot be opened.
     pub unsafe fn recover(&mut self, 
         #[cfg(feature = "check_double_free")]
         check_double_free: &mut HashSet<u64>
-    ) {
+    ) -> Result<()> {
+        let resume = self.resume()?;
         let mut curr = self.pages;
         while let Some(page) = curr.as_option() {
             page.notify();
             curr = page.next;
         }
-        let mut curr = self.pages;
-        let resume = self.resume();
-        if !self.is_set(JOURNAL_COMMITTED) || resume {
-            let rollback = !resume || !self.is_set(JOURNAL_COMMITTED);
+        if resume && !self.is_set(JOURNAL_COMMITTED) {
+            // The chaperoned session has committed, but this journal has not
+            self.commit(
+                #[cfg(feature = "check_double_free")]
+                check_double_free
+            );
+        } else {
+            let rollback = !resume;
             #[cfg(any(feature = "use_pspd", feature = "use_vspd"))] {
                 if rollback {
                     self.spd.rollback();
@@ -501,6 +685,7 @@ impl<A: MemPool> Journal<A> {
                     self.spd.recover();
                 }
             }
+            let mut curr = self.pages;
             while let Some(page) = curr.as_option() {
                 page.recover(rollback, 
                     #[cfg(feature = "check_double_free")]
@@ -510,6 +695,7 @@ impl<A: MemPool> Journal<A> {
             }
             self.set(JOURNAL_COMMITTED);
         }
+        Ok(())
     }
 
     /// Clears all logs and drops itself from the memory pool
@@ -520,6 +706,10 @@ impl<A: MemPool> Journal<A> {
         #[cfg(any(feature = "use_pspd", feature = "use_vspd"))] {
             self.spd.clear();
         }
+        if let Some(redo) = self.redo() {
+            redo.clear();
+        }
+        self.unset(JOURNAL_REDO);
         #[cfg(feature = "pin_journals")]
         {
             let mut page = self.pages.as_option();
@@ -531,6 +721,9 @@ impl<A: MemPool> Journal<A> {
                 page = p.next.as_option();
             }
             self.current = self.pages;
+
+            // The volatile data of the transaction (`TCell`) expires
+            self.gen = A::tx_gen();
         }
 
         #[cfg(not(feature = "pin_journals"))] {
@@ -568,79 +761,150 @@ impl<A: MemPool> Journal<A> {
     /// on recovery according to the following table:
     ///
     /// ```text
-    ///  ┌───────────┬────────────┬──────────┬─────┐
-    ///  │ Committed │ Chaperoned │ Complete │  FF │
-    ///  ╞═══════════╪════════════╪══════════╪═════╡
-    ///  │    TRUE   │    FALSE   │     X    │ YES │
-    ///  │    TRUE   │    TRUE    │   TRUE   │ YES │
-    ///  │    TRUE   │    TRUE    │   FALSE  │  NO │
-    ///  │   FALSE   │      X     │     X    │  NO │
-    ///  └───────────┴────────────┴──────────┴─────┘
+    ///  ┌───────────┬────────────┬───────────────────┬──────────────┬─────┐
+    ///  │ Committed │ Chaperoned │ Session Committed │ Redo Record  │  FF │
+    ///  ╞═══════════╪════════════╪═══════════════════╪══════════════╪═════╡
+    ///  │    TRUE   │      X     │         X         │       X      │ YES │
+    ///  │   FALSE   │    FALSE   │         X         │     FALSE    │  NO │
+    ///  │   FALSE   │    FALSE   │         X         │     TRUE     │ YES │
+    ///  │   FALSE   │    TRUE    │        TRUE       │       X      │ YES │
+    ///  │   FALSE   │    TRUE    │       FALSE       │       X      │  NO │
+    ///  └───────────┴────────────┴───────────────────┴──────────────┴─────┘
     /// ```
     ///
-    /// Fast-forward means that no matter the transaction is committed or not,
-    /// if there exist logs, discard them all without rolling back.
+    /// Fast-forward means that the transaction is committed, if it is not
+    /// already, and the logs are discarded without rolling back.
     ///
     /// States:
-    ///  * **Committed**: Transaction is already committed but not complete
-    ///               (Logs still exist).
-    ///  * **Chaperoned**: The transaction was attached to a [`Chaperon::transaction`].
-    ///  * **Complete**: The [`Chaperon::transaction`] is complete.
+    ///  * **Committed**: Transaction is already committed (or rolled back) but
+    ///    not complete (Logs still exist).
+    ///  * **Chaperoned**: The transaction was attached to a [`Chaperon::session`].
+    ///  * **Session Committed**: The [`Chaperon::session`] has reached its
+    ///    commit point. If the chaperon file cannot be opened, the decision is
+    ///    unknown and it returns an error.
+    ///  * **Redo Record**: The redo log is persisted or the scratchpad is
+    ///    sealed, which is the commit point of a transaction in the redo mode
+    ///    or with the `use_pspd`/`use_vspd` features. In a chaperoned
+    ///    transaction, it happens before the session commits.
     ///
-    /// [`Chaperon::transaction`]: ../chaperon/struct.Chaperon.html#method.transaction
+    /// [`Chaperon::session`]: ../chaperon/struct.Chaperon.html#method.session
     ///
-    pub fn resume(&self) -> bool {
-        if !self.is_set(JOURNAL_COMMITTED) {
-            false
+    pub fn resume(&self) -> Result<bool> {
+        if self.is_set(JOURNAL_COMMITTED) {
+            Ok(true)
+        } else if let Some(s) = self.session_file() {
+            Chaperon::session_committed(&s, self.sec_id)
+                .map_err(|e| Error::Chaperon { path: s, source: e })
         } else {
-            if self.sec_id != 0 && !self.chaperon.is_empty() {
-                let s = String::from_utf8(self.chaperon.to_vec()).unwrap();
-                let c = unsafe { Chaperon::load(&s)
-                    .expect(&format!("Missing chaperon file `{}`", s)) };
-                c.completed()
-            } else {
-                true
+            #[cfg(any(feature = "use_pspd", feature = "use_vspd"))] {
+                if self.spd.is_sealed() {
+                    return Ok(true);
+                }
             }
+            Ok(self.is_set(JOURNAL_EXTENDED) && self.redo.is_persisted())
         }
     }
 
-    pub(crate) fn start_session(&mut self, chaperon: &mut Chaperon) {
-        let mut filename = [0u8; 64]; 
-        let s = chaperon.filename().as_bytes();
-        for i in 0..usize::min(64,s.len()) {
-            filename[i] = s[i];
+    /// Returns the offset and the length of the chaperon filename, if it is
+    /// stored out of line
+    fn chaperon_block(&self) -> Option<(u64, usize)> {
+        if self.chaperon[0] == CHAPERON_OUT_OF_LINE {
+            let word = |i: usize| {
+                let mut w = [0u8; 8];
+                w.copy_from_slice(&self.chaperon[i * 8..(i + 1) * 8]);
+                u64::from_ne_bytes(w)
+            };
+            Some((word(1), word(2) as usize))
+        } else {
+            None
         }
+    }
+
+    /// Returns the chaperon filename of the session that this journal is
+    /// attached to, if any
+    fn session_file(&self) -> Option<String> {
+        if self.sec_id == 0 {
+            None
+        } else if let Some((off, len)) = self.chaperon_block() {
+            let name = unsafe { A::deref_slice_unchecked::<u8>(off, len) };
+            String::from_utf8(name.to_vec()).ok()
+        } else {
+            let len = self.chaperon.iter().position(|b| *b == 0)
+                .unwrap_or(self.chaperon.len());
+            String::from_utf8(self.chaperon[..len].to_vec()).ok()
+        }
+    }
+
+    pub(crate) fn start_session(&mut self, chaperon: &mut Chaperon) {
+        let filename = chaperon.filename().to_string();
         if self.sec_id != 0 {
-            if self.chaperon != filename {
+            if self.session_file().as_ref() != Some(&filename) {
                 panic!("Cannot attach to another chaperoned session");
             }
             return;
         }
-        self.chaperon = filename;
-        self.sec_id = chaperon.new_section() as u64;
+        chaperon.new_section(|sec_id| unsafe {
+            let len = filename.len();
+            let (p, off, _, z) = A::pre_alloc(len);
+            if p.is_null() {
+                panic!("Memory exhausted");
+            }
+            std::ptr::copy_nonoverlapping(filename.as_ptr(), p, len);
+            persist_with_log::<_,A>(p, len, false);
+            let field = A::off_unchecked(&self.chaperon);
+            let mut marker = [0u8; 8];
+            marker[0] = CHAPERON_OUT_OF_LINE;
+            A::log64(field, u64::from_ne_bytes(marker), z);
+            A::log64(field + 8, off, z);
+            A::log64(field + 16, len as u64, z);
+            A::log64(A::off_unchecked(&self.sec_id), sec_id, z);
+            A::perform(z);
+        });
     }
 
     pub(crate) fn complete(&mut self) {
-        if self.sec_id != 0 && !self.chaperon.is_empty() {
-            unsafe {
-                let s = String::from_utf8(self.chaperon.to_vec()).unwrap();
-                if let Ok(c) = Chaperon::load(&s) {
-                    // If file not exists, it is on the normal path on the first
-                    // execution. The existence of the file is already checked
-                    // earlier in the recovery procedure.
-                    let id = self.sec_id;
-                    self.chaperon = [0; 64];
-                    self.sec_id = 0;
-                    persist_obj_with_log::<_,A>(&self.sec_id, true);
-                    c.finish(id as usize);
-                } else {
-                    self.chaperon = [0; 64];
-                    self.sec_id = 0;
+        if let Some(s) = self.session_file() {
+            Chaperon::finish_section(&s, self.sec_id);
+            if let Some((off, len)) = self.chaperon_block() {
+                unsafe {
+                    let name = A::get_mut_unchecked::<u8>(off);
+                    let z = A::pre_dealloc(name, len);
+                    A::log64(A::off_unchecked(&self.chaperon), 0, z);
+                    A::log64(A::off_unchecked(&self.sec_id), 0, z);
+                    A::perform(z);
                 }
+            } else {
+                // Written inline by an older version
+                self.chaperon = [0; 64];
+                self.sec_id = 0;
+                persist_obj_with_log::<_,A>(&self.chaperon, false);
+                persist_obj_with_log::<_,A>(&self.sec_id, true);
             }
         }
     }
 
+    /// Collects the memory blocks allocated for the logs of this journal
+    pub(crate) fn blocks(&self, res: &mut Vec<(u64, usize)>) {
+        let mut curr = self.pages;
+        while !curr.is_dangling() && res.iter().all(|(off, _)| *off != curr.off()) {
+            res.push((curr.off(), std::mem::size_of::<Page<A>>()));
+            curr.as_ref().blocks(res);
+            curr = curr.as_ref().next;
+        }
+
+        if let Some(block) = self.chaperon_block() {
+            res.push(block);
+        }
+
+        #[cfg(any(feature = "use_pspd", feature = "use_vspd"))] {
+            self.spd.blocks(res);
+        }
+
+        if self.is_set(JOURNAL_EXTENDED) {
+            self.redo.blocks(res);
+        }
+    }
+
     /// Returns the next journal for another transaction
     pub(crate) fn next(&self) -> Ptr<Journal<A>, A> {
         unsafe { Ptr::from_off_unchecked(self.next_off) }
@@ -723,6 +987,50 @@ impl<A: MemPool> Journal<A> {
         }
     }
 
+    /// Disarms the logs in the journal which are equal to `log` so that they
+    /// take no effect when the journal is cleared, and returns their number
+    pub(crate) fn disarm_logs(&self, log: LogEnum) -> usize {
+        let mut cnt = 0;
+        let mut curr = self.pages;
+        while let Some(page) = curr.as_option() {
+            let (head, len) = (page.head, page.len);
+            for l in &mut page.logs[head..len] {
+                if l.inner() == log {
+                    l.disarm();
+                    cnt += 1;
+                }
+            }
+            curr = page.next;
+        }
+        cnt
+    }
+
+    /// Returns true if the journal has versions to stamp on commit
+    fn has_stamps(&self) -> bool {
+        let mut curr = self.pages;
+        while let Some(page) = curr.as_option() {
+            if page.has_stamps() {
+                return true;
+            }
+            curr = page.next;
+        }
+        false
+    }
+
+    /// Returns true if the journal is not committed, or it has logs which
+    /// are not cleared yet (e.g. the logs of dropping an object outside a
+    /// transaction)
+    pub(crate) fn is_pending(&self) -> bool {
+        let mut curr = self.pages;
+        while let Some(page) = curr.as_option() {
+            if page.head < page.len {
+                return true;
+            }
+            curr = page.next;
+        }
+        !self.is_committed()
+    }
+
     /// Ignores all logs
     /// 
     /// This function is only for measuring some properties such as log latency.
@@ -738,7 +1046,7 @@ impl<A: MemPool> Journal<A> {
 impl<A: MemPool> Debug for Journal<A> {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         writeln!(f, "LOGS:")?;
-        let mut curr = self.pages.clone();
+        let mut curr = self.pages;
         while let Some(page) = curr.as_option() {
             page.fmt(f)?;
             curr = page.next;
@@ -43,6 +43,18 @@ pub enum LogEnum {
     /// Unlocks a [`PMutex`](../sync/struct.PMutex.html) on transaction commit.
     UnlockOnCommit(u64),
     None,
+
+    // The kinds below are added after `None`, so that the logs left by older
+    // versions keep their meaning
+
+    /// `(cv, all)`: Wakes up one or all waiters of a
+    /// [`PCondvar`](../sync/struct.PCondvar.html) on transaction commit.
+    NotifyOnCommit(u64, bool),
+
+    /// `(ts)`: Writes the commit timestamp at offset `ts` of a
+    /// [`VersionedCell`](../cell/struct.VersionedCell.html) version on
+    /// transaction commit.
+    StampOnCommit(u64),
 }
 
 fn offset_to_str(off: u64) -> String {
@@ -62,6 +74,8 @@ impl Debug for LogEnum {
             DropOnFailure(off, _)    => write!(f, "DropOnFailure   ({})", offset_to_str(off)),
             RecountOnFailure(off, _) => write!(f, "RecountOnFailure({})", offset_to_str(off)),
             UnlockOnCommit(off)      => write!(f, "UnlockOnCommit  ({})", offset_to_str(off)),
+            NotifyOnCommit(off, _)   => write!(f, "NotifyOnCommit  ({})", offset_to_str(off)),
+            StampOnCommit(off)       => write!(f, "StampOnCommit   ({})", offset_to_str(off)),
             None                     => write!(f, "None"),
         }
     }
@@ -89,12 +103,7 @@ impl<A: MemPool> Copy for Notifier<A> {}
 
 impl<A: MemPool> Clone for Notifier<A> {
     fn clone(&self) -> Self {
-        use Notifier::*;
-        match self {
-            Atomic(c) => Atomic(c.clone()),
-            NonAtomic(c) => NonAtomic(c.clone()),
-            None => None,
-        }
+        *self
     }
 }
 
@@ -107,7 +116,7 @@ impl<A: MemPool> Notifier<A> {
         match self {
             Atomic(n) => {
                 if let Some(n) = n.as_option() {
-                    unsafe { std::intrinsics::atomic_store_relaxed(n.as_mut_ptr(), v) }
+                    unsafe { std::ptr::write_volatile(n.as_mut_ptr(), v) }
                 }
             }
             NonAtomic(n) => {
@@ -158,7 +167,7 @@ impl<A: MemPool> Copy for Log<A> {}
 
 impl<A: MemPool> Clone for Log<A> {
     fn clone(&self) -> Self {
-        Self(self.0, self.1)
+        *self
     }
 }
 
@@ -192,7 +201,7 @@ impl<A: MemPool> Log<A> {
     /// 1. Create a neutral drop log (`off = u64::MAX`) in a log slot in the [`Journal`]
     /// 2. Prepare allocation using [`pre_alloc()`]
     /// 3. Add a low-level log for updating the `off` and `len` of the drop log
-    /// using `set` function
+    ///    using `set` function
     /// 4. Perform the prepared changes to the allocator
     /// 
     /// Note that the deallocation of owned objects are handled through RAII.
@@ -282,6 +291,8 @@ impl<A: MemPool> Log<A> {
             DropOnFailure(_, _) => "DropOnFailure",
             RecountOnFailure(_, _) => "RecountOnFailure",
             UnlockOnCommit(_) => "UnlockOnCommit",
+            NotifyOnCommit(_, _) => "NotifyOnCommit",
+            StampOnCommit(_) => "StampOnCommit",
             None => "None"
         }.to_string()
     }
@@ -290,6 +301,14 @@ impl<A: MemPool> Log<A> {
     pub fn inner(&self) -> LogEnum {
         self.0
     }
+
+    /// Disarms an [`UnlockOnCommit`](./enum.LogEnum.html#variant.UnlockOnCommit)
+    /// log so that clearing it does not unlock the mutex
+    pub(crate) fn disarm(&mut self) {
+        if let UnlockOnCommit(src) = &mut self.0 {
+            *src = u64::MAX;
+        }
+    }
 }
 
 use LogEnum::*;
@@ -344,7 +363,8 @@ impl<A: MemPool> Log<A> {
         let _perf = crate::stat::Measure::<A>::DataLog(std::time::Instant::now());
 
         let len = std::mem::size_of_val(x);
-        if len == 0 {
+        if len == 0 || journal.in_draft(x) {
+            // A draft is discarded if the transaction aborts; it needs no log
             notifier.update(1);
             Ptr::dangling()
         } else {
@@ -357,6 +377,7 @@ impl<A: MemPool> Log<A> {
             #[cfg(feature = "verbose")] {
                 dump_data::<A>("DATA", pointer.off(), len);
             }
+            crate::__trace_event!(A, "log", kind = "DataLog", off = pointer.off(), len = len);
 
             let log = unsafe { pointer.dup() };
 
@@ -387,7 +408,7 @@ impl<A: MemPool> Log<A> {
         let _perf = crate::stat::Measure::<A>::DataLog(std::time::Instant::now());
 
         let len = std::mem::size_of_val(x);
-        if len == 0 {
+        if len == 0 || journal.in_draft(x) {
             notifier.update(1);
             Ptr::dangling()
         } else {
@@ -400,6 +421,7 @@ impl<A: MemPool> Log<A> {
             #[cfg(feature = "verbose")] {
                 dump_data::<A>("DATA", slice.off(), len);
             }
+            crate::__trace_event!(A, "log", kind = "DataLog", off = slice.off(), len = len);
 
             let log = unsafe { slice.dup() };
 
@@ -415,7 +437,7 @@ impl<A: MemPool> Log<A> {
         journal: &Journal<A>,
         mut notifier: Notifier<A>,
     ) -> Ptr<Log<A>, A> {
-        let log = journal.write(log, notifier.clone());
+        let log = journal.write(log, notifier);
         notifier.update(1);
         sfence();
         log
@@ -436,6 +458,7 @@ impl<A: MemPool> Log<A> {
             offset_to_str((offset as usize + (len - 1)) as u64),
             len
         );
+        crate::__trace_event!(A, "log", kind = "DropOnCommit", off = offset, len = len);
         Self::write_on_journal(DropOnCommit(offset, len), journal, Notifier::None)
     }
 
@@ -454,6 +477,7 @@ impl<A: MemPool> Log<A> {
             offset_to_str((offset as usize + (len - 1)) as u64),
             len
         );
+        crate::__trace_event!(A, "log", kind = "DropOnAbort", off = offset, len = len);
 
         Self::write_on_journal(DropOnAbort(offset, len), journal, Notifier::None)
     }
@@ -473,6 +497,7 @@ impl<A: MemPool> Log<A> {
             offset_to_str((offset as usize + (len - 1)) as u64),
             len
         );
+        crate::__trace_event!(A, "log", kind = "DropOnFailure", off = offset, len = len);
 
         Self::write_on_journal(DropOnFailure(offset, len), journal, Notifier::None)
     }
@@ -489,6 +514,7 @@ impl<A: MemPool> Log<A> {
         let _perf = crate::stat::Measure::<A>::MutexLog(std::time::Instant::now());
 
         log!(A, Yellow, "NEW LOG", "FOR:         v@{:<18} UnlockOnCommit", virt_addr);
+        crate::__trace_event!(A, "log", kind = "UnlockOnCommit", addr = virt_addr);
         
         #[cfg(any(feature = "no_pthread", windows))] {
             let b = &mut *(virt_addr as *mut (bool, u64));
@@ -503,6 +529,33 @@ impl<A: MemPool> Log<A> {
         Self::write_on_journal(UnlockOnCommit(virt_addr), journal, Notifier::None);
     }
 
+    /// Creates a new [`NotifyOnCommit`](./enum.LogEnum.html#variant.NotifyOnCommit)
+    /// for waking up the waiters of a condition variable after commit
+    #[inline]
+    #[track_caller]
+    pub unsafe fn notify_on_commit(
+        virt_addr: u64,
+        all: bool,
+        journal: &Journal<A>,
+    ) {
+        log!(A, Yellow, "NEW LOG", "FOR:         v@{:<18} NotifyOnCommit", virt_addr);
+        crate::__trace_event!(A, "log", kind = "NotifyOnCommit", addr = virt_addr);
+        Self::write_on_journal(NotifyOnCommit(virt_addr, all), journal, Notifier::None);
+    }
+
+    /// Creates a new [`StampOnCommit`](./enum.LogEnum.html#variant.StampOnCommit)
+    /// for setting the commit timestamp of a version
+    #[inline]
+    #[track_caller]
+    pub unsafe fn stamp_on_commit(offset: u64, journal: &Journal<A>) {
+        log!(A, Yellow, "NEW LOG", "FOR:         ({:>6}:{:<6}) = {:<6} StampOnCommit",
+            offset_to_str(offset),
+            offset_to_str(offset),
+            8
+        );
+        Self::write_on_journal(StampOnCommit(offset), journal, Notifier::None);
+    }
+
     /// Creates a new [`RecountOnFailure`](./enum.LogEnum.html#variant.RecountOnFailure)
     /// log and writes it on `journal`
     #[inline]
@@ -552,6 +605,9 @@ impl<A: MemPool> Log<A> {
                 #[cfg(feature = "check_allocator_cyclic_links")]
                 debug_assert!(A::verify());
             }
+            NotifyOnCommit(src, _) => {
+                *src = u64::MAX;
+            }
             _ => {}
         }
     }
@@ -563,24 +619,21 @@ impl<A: MemPool> Log<A> {
         #[cfg(feature = "stat_perf")]
         let _perf = crate::stat::Measure::<A>::RollbackLog(std::time::Instant::now());
 
-        match &mut self.0 {
-            DropOnAbort(src, len) => {
-                if *src != u64::MAX {
-                    #[cfg(feature = "check_double_free")] {
-                        if check_double_free.contains(&*src) {
-                            return;
-                        }
-                        check_double_free.insert(*src);
+        if let DropOnAbort(src, len) = &mut self.0 {
+            if *src != u64::MAX {
+                #[cfg(feature = "check_double_free")] {
+                    if check_double_free.contains(&*src) {
+                        return;
                     }
-                    let z = A::pre_dealloc(A::get_mut_unchecked(*src), *len);
-                    A::log64(A::off_unchecked(src), u64::MAX, z);
-                    A::perform(z);
-                    
-                    #[cfg(feature = "check_allocator_cyclic_links")]
-                    debug_assert!(A::verify());
+                    check_double_free.insert(*src);
                 }
+                let z = A::pre_dealloc(A::get_mut_unchecked(*src), *len);
+                A::log64(A::off_unchecked(src), u64::MAX, z);
+                A::perform(z);
+                
+                #[cfg(feature = "check_allocator_cyclic_links")]
+                debug_assert!(A::verify());
             }
-            _ => {}
         }
     }
 
@@ -603,22 +656,20 @@ impl<A: MemPool> Log<A> {
                 }
             }
             DropOnFailure(src, len) => {
-                if rollback {
-                    if *src != u64::MAX {
-                        #[cfg(feature = "check_double_free")] {
-                            if check_double_free.contains(&*src) {
-                                return;
-                            }
-                            check_double_free.insert(*src);
+                if rollback && *src != u64::MAX {
+                    #[cfg(feature = "check_double_free")] {
+                        if check_double_free.contains(&*src) {
+                            return;
                         }
-                        debug_assert!(A::allocated(*src, 1), "Access Violation (0x{:x})", *src);
-                        let z = A::pre_dealloc(A::get_mut_unchecked(*src), *len);
-                        A::log64(A::off_unchecked(src), u64::MAX, z);
-                        A::perform(z);
-                    
-                        #[cfg(feature = "check_allocator_cyclic_links")]
-                        debug_assert!(A::verify());
+                        check_double_free.insert(*src);
                     }
+                    debug_assert!(A::allocated(*src, 1), "Access Violation (0x{:x})", *src);
+                    let z = A::pre_dealloc(A::get_mut_unchecked(*src), *len);
+                    A::log64(A::off_unchecked(src), u64::MAX, z);
+                    A::perform(z);
+                
+                    #[cfg(feature = "check_allocator_cyclic_links")]
+                    debug_assert!(A::verify());
                 }
             }
             RecountOnFailure(src, inc) => {
@@ -630,9 +681,9 @@ impl<A: MemPool> Log<A> {
                     A::prepare(z);
                     if *c != u64::MAX {
                         if *inc {
-                            A::log64(off, *c as u64 + 1, z);
+                            A::log64(off, *c + 1, z);
                         } else {
-                            A::log64(off, *c as u64 - 1, z);
+                            A::log64(off, *c - 1, z);
                         }
                     }
                     A::log64(A::off_unchecked(src), u64::MAX, z);
@@ -642,7 +693,7 @@ impl<A: MemPool> Log<A> {
                     debug_assert!(A::verify());
                 }
             }
-            UnlockOnCommit(src) => {
+            UnlockOnCommit(src) | NotifyOnCommit(src, _) => {
                 *src = u64::MAX;
             }
             _ => {}
@@ -654,16 +705,13 @@ impl<A: MemPool> Log<A> {
         #[cfg(feature = "stat_perf")]
         let _perf = crate::stat::Measure::<A>::CommitLog(std::time::Instant::now());
 
-        match &mut self.0 {
-            DataLog(_src, _log, _len) => {
-                debug_assert!(A::allocated(*_src, 1), "Access Violation at address 0x{:x}", *_src);
+        if let DataLog(_src, _log, _len) = &mut self.0 {
+            debug_assert!(A::allocated(*_src, 1), "Access Violation at address 0x{:x}", *_src);
 
-                #[cfg(all(not(feature = "no_flush_updates"), not(feature = "replace_with_log")))]
-                unsafe {
-                    persist_with_log::<u8,A>(A::get_mut_unchecked(*_src), *_len, false);
-                }
+            #[cfg(all(not(feature = "no_flush_updates"), not(feature = "replace_with_log")))]
+            unsafe {
+                persist_with_log::<u8,A>(A::get_mut_unchecked(*_src), *_len, false);
             }
-            _ => {}
         }
     }
 
@@ -675,35 +723,32 @@ impl<A: MemPool> Log<A> {
         #[cfg(feature = "stat_perf")]
         let _perf = crate::stat::Measure::<A>::CommitLog(std::time::Instant::now());
 
-        match &mut self.0 {
-            DropOnCommit(src, len) => {
-                if *src != u64::MAX {
-                    unsafe {
-                        #[cfg(feature = "check_double_free")] {
-                            if check_double_free.contains(&*src) {
-                                return;
-                            }
-                            check_double_free.insert(*src);
+        if let DropOnCommit(src, len) = &mut self.0 {
+            if *src != u64::MAX {
+                unsafe {
+                    #[cfg(feature = "check_double_free")] {
+                        if check_double_free.contains(&*src) {
+                            return;
                         }
-                        let z = A::pre_dealloc(A::get_mut_unchecked(*src), *len);
-                        A::log64(A::off_unchecked(src), u64::MAX, z);
-                        A::perform(z);
-                    
-                        #[cfg(feature = "check_allocator_cyclic_links")]
-                        debug_assert!(A::verify());
+                        check_double_free.insert(*src);
                     }
+                    let z = A::pre_dealloc(A::get_mut_unchecked(*src), *len);
+                    A::log64(A::off_unchecked(src), u64::MAX, z);
+                    A::perform(z);
+                
+                    #[cfg(feature = "check_allocator_cyclic_links")]
+                    debug_assert!(A::verify());
                 }
             }
-            _ => {}
         }
     }
 
     /// Clears this log and notifies the owner
     /// 
     /// * If it is a [`DataLog`](./enum.LogEnum.html#variant.DataLog), it reclaims
-    /// the allocation for the log.
+    ///   the allocation for the log.
     /// * If it is a [`UnlockOnCommit`](./enum.LogEnum.html#variant.UnlockOnCommit),
-    /// it unlocks the mutex.
+    ///   it unlocks the mutex.
     /// 
     pub unsafe fn clear(&mut self, 
         #[cfg(feature = "check_double_free")]
@@ -740,6 +785,10 @@ impl<A: MemPool> Log<A> {
             UnlockOnCommit(src) => {
                 if *src != u64::MAX {
                     log!(A, Magenta, "UNLOCK", "FOR:          v@{}", *src);
+
+                    #[cfg(feature = "check_deadlock")]
+                    crate::sync::deadlock::released(*src);
+
                     #[cfg(not(any(feature = "no_pthread", windows)))] {
                         let b = &mut *(*src as *mut (bool, libc::pthread_mutex_t, libc::pthread_mutexattr_t));
                         b.0 = false;
@@ -754,16 +803,33 @@ impl<A: MemPool> Log<A> {
                         let b = &mut *(*src as *mut (bool, u64));
                         b.0 = false;
                         let lock = &mut b.1;
-                        std::intrinsics::atomic_store_rel(lock, 0);
+                        crate::sync::spin_release(lock);
                     }
 
                     *src = u64::MAX;
                 }
             }
+            NotifyOnCommit(src, all) if *src != u64::MAX => {
+                log!(A, Magenta, "NOTIFY", "FOR:          v@{}", *src);
+                crate::sync::notify_condvar(*src, *all);
+                *src = u64::MAX;
+            }
             _ => {}
         }
     }
 
+    /// Writes the commit timestamp `ts` if it is a
+    /// [`StampOnCommit`](./enum.LogEnum.html#variant.StampOnCommit) log
+    pub(crate) unsafe fn stamp(&mut self, ts: u64) {
+        if let StampOnCommit(off) = self.0 {
+            if off != u64::MAX {
+                let p = A::get_mut_unchecked::<u64>(off);
+                std::sync::atomic::AtomicU64::from_ptr(p).store(ts, std::sync::atomic::Ordering::Release);
+                persist_obj(&*p, false);
+            }
+        }
+    }
+
     /// Notify the owner that the log is created/cleared according to `v`
     #[inline]
     pub unsafe fn notify(&mut self, v: u8) {
@@ -3,26 +3,59 @@
 mod chaperon;
 mod journal;
 mod log;
+mod options;
+mod redo;
+mod snapshot;
 pub mod pspd;
 pub mod vspd;
 
 use crate::alloc::MemPool;
 use crate::result::Result;
 use crate::{TxInSafe,TxOutSafe};
-use std::panic::UnwindSafe;
+use std::panic::{RefUnwindSafe, UnwindSafe};
 
 pub use chaperon::*;
 pub use journal::*;
 pub use log::*;
+pub use options::*;
+pub use snapshot::Snapshot;
+
+#[cfg(not(any(feature = "use_pspd", feature = "use_vspd")))]
+pub(crate) use redo::{FLAG_DRAFTED, FLAG_LOGGED};
+pub(crate) use snapshot::oldest_snapshot;
 
 /// Atomically executes commands
 /// 
 /// See [`MemPool::transaction()`](../alloc/trait.MemPool.html#method.transaction)
 /// for more details.
-pub fn transaction<T, F: FnOnce(&'static Journal<A>) -> T, A: MemPool>(body: F) -> Result<T>
+pub fn transaction<T, F, A: MemPool>(body: F) -> Result<T>
 where
-    F: TxInSafe + UnwindSafe,
+    F: FnOnce(&'static Journal<A>) -> T + TxInSafe + UnwindSafe,
     T: TxOutSafe,
 {
     A::transaction(body)
 }
+
+/// Atomically executes commands with timed locking and retries
+/// 
+/// See [`MemPool::transaction_with()`](../alloc/trait.MemPool.html#method.transaction_with)
+/// for more details.
+pub fn transaction_with<T, F, A: MemPool>(opts: TxOptions, body: F) -> Result<T>
+where
+    F: Fn(&'static Journal<A>) -> T + TxInSafe + RefUnwindSafe,
+    T: TxOutSafe,
+{
+    A::transaction_with(opts, body)
+}
+
+/// Executes a read-only transaction on a consistent snapshot
+/// 
+/// See [`MemPool::read_transaction()`](../alloc/trait.MemPool.html#method.read_transaction)
+/// for more details.
+pub fn read_transaction<T, F, A: MemPool>(body: F) -> Result<T>
+where
+    F: FnOnce(&Snapshot<A>) -> T + TxInSafe + UnwindSafe,
+    T: TxOutSafe,
+{
+    A::read_transaction(body)
+}
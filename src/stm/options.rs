@@ -0,0 +1,197 @@
+//! Options for transactions with timed locking, the logging modes, and the
+//! scratchpad size
+
+use std::cell::Cell;
+use std::time::{Duration, Instant};
+
+/// The logging scheme of a transaction
+///
+/// The default mode of the transactions of a pool is [`Undo`], unless the
+/// pool is opened with the [`O_REDO`] flag. It can be overridden for a single
+/// transaction using [`TxOptions::log_mode`].
+///
+/// [`Undo`]: #variant.Undo
+/// [`O_REDO`]: ../alloc/open_flags/constant.O_REDO.html
+/// [`TxOptions::log_mode`]: ./struct.TxOptions.html#method.log_mode
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LogMode {
+    /// Takes a persistent copy of the old data before its first update in the
+    /// transaction, and updates the data in place
+    Undo,
+
+    /// Buffers the updates of [`PCell`] and [`PRefCell`] in the volatile
+    /// memory, and applies them when the transaction commits
+    ///
+    /// The updates are not flushed one by one; they are persisted all at
+    /// once at the commit point. It benefits the transactions that update
+    /// the same locations many times. Other containers keep taking undo logs
+    /// in the same transaction.
+    ///
+    /// [`PCell`]: ../cell/struct.PCell.html
+    /// [`PRefCell`]: ../cell/struct.PRefCell.html
+    Redo,
+}
+
+/// The default size of the scratchpad pages in bytes
+///
+/// It only applies when one of the `use_pspd` or `use_vspd` features is
+/// enabled. See [`TxOptions::scratchpad_size`].
+///
+/// [`TxOptions::scratchpad_size`]: ./struct.TxOptions.html#method.scratchpad_size
+pub const DEFAULT_SCRATCHPAD_SIZE: usize = 1024;
+
+/// Options of a retrying transaction
+///
+/// It is used with [`MemPool::transaction_with`] to bound the time spent
+/// waiting for transaction-wide locks, to re-run the transaction body if
+/// a lock could not be acquired in time, and to choose the logging mode.
+///
+/// # Examples
+///
+/// ```
+/// use corundum::stm::TxOptions;
+/// use std::time::Duration;
+///
+/// let opts = TxOptions::new()
+///     .retries(5)
+///     .backoff(Duration::from_millis(1))
+///     .lock_timeout(Duration::from_millis(50));
+///
+/// assert_eq!(opts.max_retries(), 5);
+/// ```
+///
+/// [`MemPool::transaction_with`]: ../alloc/trait.MemPool.html#method.transaction_with
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TxOptions {
+    retries: usize,
+    backoff: Duration,
+    lock_timeout: Option<Duration>,
+    log_mode: Option<LogMode>,
+    scratchpad_size: Option<usize>,
+}
+
+impl Default for TxOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TxOptions {
+    /// Creates a new option set with no retry, no lock timeout, and the
+    /// pool's logging mode, which behaves the same as a normal transaction
+    pub const fn new() -> Self {
+        Self {
+            retries: 0,
+            backoff: Duration::from_millis(0),
+            lock_timeout: None,
+            log_mode: None,
+            scratchpad_size: None,
+        }
+    }
+
+    /// Sets the number of times that the body is re-run after the first
+    /// attempt
+    pub const fn retries(mut self, retries: usize) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    /// Sets the delay before the first retry; the delay doubles for every
+    /// further retry
+    pub const fn backoff(mut self, backoff: Duration) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Sets the maximum time that each lock acquisition may block
+    pub const fn lock_timeout(mut self, timeout: Duration) -> Self {
+        self.lock_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the logging mode of the transaction
+    ///
+    /// It has no effect if the transaction is nested in another transaction
+    /// of the same pool; the nested transaction keeps the mode of the outer
+    /// one.
+    pub const fn log_mode(mut self, mode: LogMode) -> Self {
+        self.log_mode = Some(mode);
+        self
+    }
+
+    /// Sets the size of the scratchpad pages in bytes
+    ///
+    /// With the `use_pspd` or `use_vspd` features, the transaction copies the
+    /// data of the cells into scratchpad pages of this size, and allocates
+    /// more pages as needed. Larger pages suit the transactions that update
+    /// many objects. The default is [`DEFAULT_SCRATCHPAD_SIZE`]. Without the
+    /// features, or in a nested transaction, it has no effect.
+    ///
+    /// [`DEFAULT_SCRATCHPAD_SIZE`]: ./constant.DEFAULT_SCRATCHPAD_SIZE.html
+    pub const fn scratchpad_size(mut self, size: usize) -> Self {
+        self.scratchpad_size = Some(size);
+        self
+    }
+
+    /// Returns the number of retries
+    pub const fn max_retries(&self) -> usize {
+        self.retries
+    }
+
+    /// Returns the delay before the `n`-th retry (starting from 1)
+    pub fn delay(&self, n: usize) -> Duration {
+        let shift = (n.max(1) - 1).min(16) as u32;
+        self.backoff.saturating_mul(1 << shift)
+    }
+
+    /// Returns the lock timeout, if any
+    pub const fn timeout(&self) -> Option<Duration> {
+        self.lock_timeout
+    }
+
+    /// Returns the logging mode, if it is set
+    pub const fn mode(&self) -> Option<LogMode> {
+        self.log_mode
+    }
+
+    /// Returns the size of the scratchpad pages, if it is set
+    pub const fn scratchpad(&self) -> Option<usize> {
+        self.scratchpad_size
+    }
+}
+
+/// The panic payload of a lock acquisition which timed out
+///
+/// The lock unwinds the transaction with this payload, and
+/// [`MemPool::transaction_with`] re-runs the body if there are retries left.
+///
+/// [`MemPool::transaction_with`]: ../alloc/trait.MemPool.html#method.transaction_with
+#[derive(Debug)]
+pub struct LockTimeout;
+
+thread_local! {
+    static LOCK_TIMEOUT: Cell<Option<Duration>> = const { Cell::new(None) };
+    static TIMED_OUT: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Sets the lock timeout of the current thread, and returns the previous one
+pub(crate) fn set_lock_timeout(timeout: Option<Duration>) -> Option<Duration> {
+    LOCK_TIMEOUT.with(|t| t.replace(timeout))
+}
+
+/// Returns the time until which a lock acquisition in the current thread may
+/// block, if there is a timeout
+pub(crate) fn lock_deadline() -> Option<Instant> {
+    LOCK_TIMEOUT.with(|t| t.get()).map(|t| Instant::now() + t)
+}
+
+/// Unwinds the current transaction because a lock was not acquired in time
+pub(crate) fn lock_timed_out() -> ! {
+    TIMED_OUT.with(|t| t.set(true));
+    std::panic::resume_unwind(Box::new(LockTimeout))
+}
+
+/// Returns true if a lock timed out since the last call
+pub(crate) fn take_timed_out() -> bool {
+    TIMED_OUT.with(|t| t.replace(false))
+}
@@ -1,22 +1,27 @@
 //! Persistent Scratchpad Memory
-//! 
-//! This features is still under development. To enable it, you need to add
-//! `"use_pspd"` to the feature list.
-//! 
+//!
+//! To enable it, you need to add `"use_pspd"` to the feature list. With the
+//! scratchpad, [`PCell`], [`PRefCell`], and [`Prc`] do not take undo logs.
+//! Instead, the first update in a transaction copies the data into a
+//! scratchpad page in the pool, and the rest of the transaction works on the
+//! copy. At commit, the pages are flushed and the scratchpad is sealed, which
+//! is the commit point; then, the copies are written back to the original
+//! locations. The pages are released when the journal is cleared.
+//!
+//! The size of the pages can be set per transaction using
+//! [`TxOptions::scratchpad_size`].
+//!
+//! [`PCell`]: ../../cell/struct.PCell.html
+//! [`PRefCell`]: ../../cell/struct.PRefCell.html
+//! [`Prc`]: ../../prc/struct.Prc.html
+//! [`TxOptions::scratchpad_size`]: ../struct.TxOptions.html#method.scratchpad_size
 
-use crate::cell::LazyCell;
 use crate::alloc::MemPool;
 use crate::ptr::Ptr;
+use crate::stm::DEFAULT_SCRATCHPAD_SIZE;
 use crate::{utils, ll};
 use std::{mem, ptr};
 
-static SCRATCHPAD_SIZE: LazyCell<usize> = LazyCell::new(|| {
-    utils::nearest_pow2(std::env::var("SPD_SIZE")
-        .unwrap_or("1024".to_string())
-        .parse::<u64>()
-        .expect("RECOVERY_INFO should be an unsigned integer")) as usize
-});
-
 struct Page<A: MemPool> {
     len: usize,
     cap: usize,
@@ -24,154 +29,225 @@ struct Page<A: MemPool> {
 }
 
 impl<A: MemPool> Page<A> {
-    unsafe fn write<T: ?Sized>(&mut self, val: &T, org_off: u64) -> *mut T {
+    #[inline]
+    unsafe fn data(&self) -> *mut u8 {
+        (self as *const Self as *mut u8).add(mem::size_of::<Self>())
+    }
+
+    /// Allocates a new page of at least `size` bytes, and links it to `link`
+    /// atomically
+    unsafe fn alloc(size: usize, link: &Ptr<Page<A>, A>) {
+        let size = utils::nearest_pow2((size + mem::size_of::<Self>()) as u64) as usize;
+        let (p, off, len, z) = A::pre_alloc(size);
+        if p.is_null() {
+            panic!("Memory exhausted");
+        }
+        A::drop_on_failure(off, len, z);
+        let pg = utils::read::<Page<A>>(p);
+        pg.cap = size - mem::size_of::<Self>();
+        pg.len = 0;
+        pg.next = Ptr::dangling();
+        ll::persist_obj(pg, false);
+        A::log64(A::off_unchecked(link.off_ref()), off, z);
+        A::perform(z);
+    }
+
+    unsafe fn write<T: ?Sized>(&mut self, val: &T, org_off: u64, page_size: usize) -> *mut T {
         let size = mem::size_of_val(val);
 
         // Data Layout:
         //   * org_off                           (u64)
-        //   * relative distance from next item  (u64)
-        //   * data                              (T)
-        let dist = 8 + 8 + size;
+        //   * data size                         (u64)
+        //   * data                              (T, padded to 8 bytes)
+        let dist = 8 + 8 + pad(size);
         if self.len + dist > self.cap {
-            if let Some(next) = self.next.as_option() {
-                next.write(val, org_off)
-            } else {
-                let cap = *SCRATCHPAD_SIZE;
-                let cap = utils::nearest_pow2(usize::max(cap, dist) as u64) as usize;
-                // FIXME: Memory leak
-                let (p, off, _, z) = A::pre_alloc(cap);
-                let pg = utils::read::<Page<A>>(p);
-                pg.cap = cap - mem::size_of::<Page<A>>();
-                pg.len = 0;
-                pg.next = self.next;
-                A::log64(A::off_unchecked(self.next.off_mut()), off, z);
-                A::perform(z);
-                pg.write(val, org_off)
+            if self.next.is_dangling() {
+                Self::alloc(usize::max(page_size, dist), &self.next);
             }
+            self.next.get_mut().write(val, org_off, page_size)
         } else {
-            let p = self as *mut Self as *mut u8;
-            let p = p.add(mem::size_of::<Self>());
-            
             // First 8 bytes is org_off
-            let p = p.add(self.len);
+            let p = self.data().add(self.len);
             *utils::read::<u64>(p) = org_off;
-            
-            // Second 8 bytes is the relative distance
+
+            // Second 8 bytes is the data size
             let p = p.add(8);
-            *utils::read::<usize>(p) = dist;
-            
+            *utils::read::<usize>(p) = size;
+
             // The last bytes contain data
             let p = p.add(8);
             ptr::copy_nonoverlapping(val as *const _ as *const u8, p, size);
-    
+
             self.len += dist;
-            utils::read(p)
+            (val as *const T as *mut T).wrapping_byte_offset(
+                (p as isize).wrapping_sub(val as *const T as *const u8 as isize))
         }
     }
 
-    unsafe fn commit(&mut self) {
-        ll::persist(self, self.len, true);
-        let off = A::off_unchecked(self) + mem::size_of::<Page<A>>() as u64;
+    /// Flushes the page and the data in it
+    unsafe fn persist(&self) {
+        ll::persist(self as *const Self as *const u8, mem::size_of::<Self>() + self.len, false);
+    }
+
+    /// Writes back the data to the original locations
+    unsafe fn apply(&self) {
         let mut cur = 0;
-        while cur < self.len as u64 {
-            let p = utils::read_addr::<u8>(off + cur + A::start()) as *mut u8;
+        while cur < self.len {
+            let p = self.data().add(cur);
             let org_off = *utils::read::<u64>(p);
 
             let p = p.add(8);
-            let dist = *utils::read::<usize>(p);
+            let len = *utils::read::<usize>(p);
 
             let p = p.add(8);
-            let len = dist - 16;
             let org = utils::read_addr::<u8>(org_off + A::start());
             ptr::copy_nonoverlapping(p, org, len);
             ll::persist(org, len, false);
+            crate::may_crash!("spd::apply");
 
-            cur += dist as u64;
-        }
-
-        if let Some(next) = self.next.as_option() {
-            next.commit();
-        }
-    }
-
-    unsafe fn release(&mut self, org_off: u64) {
-        let next_off = A::off_unchecked(self.next.off_mut());
-        if let Some(next) = self.next.as_option() {
-            next.release(next_off);
-        }
-
-        if self.cap != 0 {
-            let z = A::pre_dealloc(self as *mut _ as *mut u8, mem::size_of::<Page<A>>() + self.cap);
-            A::log64(org_off, u64::MAX, z);
-            A::perform(z);
+            cur += 8 + 8 + pad(len);
         }
     }
 }
 
+/// The scratchpad of a journal
 pub struct Scratchpad<A: MemPool> {
-    pages: Ptr<Page<A>, A>
+    pages: Ptr<Page<A>, A>,
+    sealed: u64,
+
+    /// The size of the new pages, which is not persistent
+    size: usize,
 }
 
 impl<A: MemPool> Scratchpad<A> {
     pub(crate) fn new() -> Self {
-        unsafe {
-            let cap = *SCRATCHPAD_SIZE - mem::size_of::<Page<A>>();
-            // FIXME: Memory leak
-            let (p, _off, _) = A::alloc(mem::size_of::<Page<A>>() + cap);
-            let pg = utils::read::<Page<A>>(p);
-            pg.cap = cap;
-            pg.len = 0;
-            pg.next = Ptr::dangling();
-            Self {
-                pages: Ptr::from_raw(pg)
-            }
+        Self {
+            pages: Ptr::dangling(),
+            sealed: 0,
+            size: DEFAULT_SCRATCHPAD_SIZE,
+        }
+    }
+
+    /// Collects the memory blocks of the pages
+    pub(crate) fn blocks(&self, res: &mut Vec<(u64, usize)>) {
+        let mut curr = self.pages;
+        while !curr.is_dangling() && res.iter().all(|(off, _)| *off != curr.off()) {
+            let page = curr.as_ref();
+            res.push((curr.off(), mem::size_of::<Page<A>>() + page.cap));
+            curr = page.next;
         }
     }
 
+    /// Sets the size of the new pages
     #[inline]
+    pub(crate) fn set_size(&mut self, size: usize) {
+        self.size = size;
+    }
+
+    /// Returns true if the scratchpad is sealed, which means that the
+    /// transaction has reached its commit point
+    #[inline]
+    pub(crate) fn is_sealed(&self) -> bool {
+        self.sealed != 0
+    }
+
+    /// Copies `val` into the scratchpad, and returns a pointer to the copy
     pub(crate) unsafe fn write<T: ?Sized>(&mut self, val: &T, off: u64) -> *mut T {
-        self.pages.write(val, off)
+        let size = usize::max(self.size, 64);
+        if self.pages.is_dangling() {
+            Page::alloc(size, &self.pages);
+        }
+        self.pages.get_mut().write(val, off, size)
+    }
+
+    /// Persists the pages and seals the scratchpad
+    pub(crate) unsafe fn seal(&mut self) {
+        if self.is_sealed() || self.pages.is_dangling() {
+            return;
+        }
+        let mut curr = self.pages;
+        while let Some(page) = curr.as_option() {
+            page.persist();
+            curr = page.next;
+        }
+        ll::sfence();
+        crate::may_crash!("spd::seal");
+        self.sealed = 1;
+        ll::persist_obj(&self.sealed, true);
+    }
+
+    /// Writes back the data of a sealed scratchpad to the original locations
+    unsafe fn apply(&self) {
+        if !self.is_sealed() {
+            return;
+        }
+        let mut curr = self.pages;
+        while let Some(page) = curr.as_option() {
+            page.apply();
+            curr = page.next;
+        }
+        ll::sfence();
     }
 
     #[inline]
     pub(crate) unsafe fn recover(&mut self) {
-        self.commit();
+        self.apply();
     }
 
     #[inline]
     pub(crate) unsafe fn commit(&mut self) {
-        self.pages.commit();
+        self.seal();
+        self.apply();
     }
 
     #[inline]
     pub(crate) unsafe fn rollback(&mut self) {
-        // Do nothing
+        // The copies are discarded when the scratchpad is cleared
     }
 
-    #[inline]
+    /// Unseals the scratchpad and releases its pages
+    ///
+    /// With `pin_journals`, the first page is kept for the next transactions.
     pub(crate) unsafe fn clear(&mut self) {
+        if self.is_sealed() {
+            self.sealed = 0;
+            ll::persist_obj(&self.sealed, true);
+        }
         #[cfg(not(feature = "pin_journals"))] {
-            let org_off = A::off_unchecked(self.pages.off_mut());
-            self.pages.release(org_off);
+            self.release();
         }
         #[cfg(feature = "pin_journals")] {
-            let next_off = A::off_unchecked(self.pages.next.off_mut());
-            if let Some(next) = self.pages.next.as_option() {
-                next.release(next_off);
+            if !self.pages.is_dangling() {
+                let first = self.pages.get_mut();
+                while !first.next.is_dangling() {
+                    Self::release_first(&first.next);
+                }
+                first.len = 0;
             }
-            self.pages.len = 0;
         }
+        self.size = DEFAULT_SCRATCHPAD_SIZE;
     }
-}
 
-impl<A: MemPool> Drop for Scratchpad<A> {
-    fn drop(&mut self) {
-        unsafe {
-            self.clear();
-            if !self.pages.is_dangling() {
-                let next_off = A::off_unchecked(self.pages.off_mut());
-                self.pages.release(next_off);
-            }
+    /// Releases all pages
+    pub(crate) unsafe fn release(&mut self) {
+        while !self.pages.is_dangling() {
+            Self::release_first(&self.pages);
         }
     }
-}
\ No newline at end of file
+
+    /// Releases the page that `link` points to, and atomically links the
+    /// next page instead
+    unsafe fn release_first(link: &Ptr<Page<A>, A>) {
+        let page = link.get_mut();
+        let next = page.next.off();
+        let z = A::pre_dealloc(page as *mut Page<A> as *mut u8,
+            mem::size_of::<Page<A>>() + page.cap);
+        A::log64(A::off_unchecked(link.off_ref()), next, z);
+        A::perform(z);
+    }
+}
+
+#[inline]
+fn pad(len: usize) -> usize {
+    (len + 7) & !7
+}
@@ -0,0 +1,325 @@
+//! Redo logging
+//!
+//! In a transaction with the [`Redo`] mode, the containers that support it
+//! do not take an undo log before updating their data. Instead, the first
+//! update copies the data into a volatile *draft*, and the container reads
+//! and writes the draft for the rest of the transaction. At commit, all
+//! drafts are written into a persistent redo record, which is the commit
+//! point of the transaction, and then they are applied to their original
+//! locations. An aborted transaction simply discards its drafts.
+//!
+//! The log flag of a container is set to [`FLAG_DRAFTED`] when its data is
+//! drafted. The flag is only a hint; the draft is looked up in the redo log
+//! of the running transaction, so a stale flag left by a crash does not
+//! affect the correctness.
+//!
+//! The data that a drafted object points to, such as the buffer of a `PVec`
+//! or the value of a `Pbox`, is not drafted with it; it is logged in the
+//! undo log as usual. The log flag of such a nested object is in the draft,
+//! so its log notifies the original location of the flag instead, which is
+//! reset together with the flags of the draft.
+//!
+//! [`Redo`]: ../enum.LogMode.html#variant.Redo
+
+use crate::alloc::MemPool;
+use crate::cell::VCell;
+use crate::ll;
+use crate::VSafe;
+use std::alloc::{alloc, dealloc, Layout};
+use std::collections::BTreeMap;
+use std::{mem, ptr};
+
+/// The value of a log flag when the data is logged in an undo log
+pub(crate) const FLAG_LOGGED: u8 = 1;
+
+/// The value of a log flag when the data is drafted in a redo log
+pub(crate) const FLAG_DRAFTED: u8 = 2;
+
+/// A volatile copy of a persistent object
+struct Draft {
+    /// The offset of the original object
+    home: u64,
+    ptr: *mut u8,
+    layout: Layout,
+
+    /// The offsets of the log flags to reset after applying the draft
+    flags: Vec<u64>,
+}
+
+impl Drop for Draft {
+    fn drop(&mut self) {
+        unsafe { dealloc(self.ptr, self.layout); }
+    }
+}
+
+/// The drafts of a transaction sorted by their original virtual addresses
+#[derive(Default)]
+struct Drafts {
+    map: BTreeMap<usize, Draft>,
+}
+
+unsafe impl VSafe for Drafts {}
+
+impl Drafts {
+    /// Finds the draft which contains `len` bytes at address `addr`
+    fn find(&self, addr: usize, len: usize) -> Option<(usize, &Draft)> {
+        let (start, draft) = self.map.range(..=addr).next_back()?;
+        if addr + len <= start + draft.layout.size() {
+            Some((*start, draft))
+        } else {
+            assert!(addr >= start + draft.layout.size(), "Overlapping drafts");
+            None
+        }
+    }
+}
+
+/// Returns a pointer to `val` relocated to address `to`
+#[inline]
+fn relocate<T: ?Sized>(val: &T, to: *mut u8) -> *mut T {
+    let from = val as *const T as *const u8;
+    (val as *const T as *mut T).wrapping_byte_offset((to as isize).wrapping_sub(from as isize))
+}
+
+/// The redo log of a journal
+///
+/// The drafts are kept in the volatile memory. The persistent part is the
+/// location of the redo record, which is only set when the transaction
+/// commits.
+///
+/// Record Layout (per draft, all fields are 8-byte aligned):
+///   * home offset                                (u64)
+///   * data length                                (u64)
+///   * number of flags                            (u64)
+///   * flag offsets                               (u64 * number of flags)
+///   * data                                       (data length, padded)
+pub(crate) struct RedoLog<A: MemPool> {
+    off: u64,
+    len: u64,
+    drafts: VCell<Drafts, A>,
+}
+
+impl<A: MemPool> RedoLog<A> {
+    pub(crate) fn new() -> Self {
+        Self {
+            off: u64::MAX,
+            len: 0,
+            drafts: VCell::new(Drafts::default()),
+        }
+    }
+
+    /// Returns true if the redo record is persisted
+    #[inline]
+    pub(crate) fn is_persisted(&self) -> bool {
+        self.off != u64::MAX
+    }
+
+    /// Collects the memory block of the redo record, if any
+    pub(crate) fn blocks(&self, res: &mut Vec<(u64, usize)>) {
+        if self.off != u64::MAX {
+            res.push((self.off, self.len as usize));
+        }
+    }
+
+    /// Returns true if there is no draft
+    #[inline]
+    pub(crate) fn is_empty(&self) -> bool {
+        self.drafts.map.is_empty()
+    }
+
+    /// Returns the number of drafts
+    #[inline]
+    pub(crate) fn len(&self) -> usize {
+        self.drafts.map.len()
+    }
+
+    /// Returns a pointer to the draft of `val`, and creates it if it is not
+    /// drafted yet
+    ///
+    /// `flag` is the log flag of the container. If `val` is not in the pool,
+    /// e.g., if it is itself in a draft, it is updated in place.
+    pub(crate) unsafe fn draft<T: ?Sized>(&self, val: &T, flag: &mut u8) -> *mut T {
+        let len = mem::size_of_val(val);
+        let flag_off = match A::off(&*flag) {
+            Ok(off) => off,
+            Err(_) => return val as *const T as *mut T,
+        };
+        if len == 0 || A::off(val).is_err() {
+            return val as *const T as *mut T;
+        }
+        let addr = val as *const T as *const u8 as usize;
+        let drafts = self.drafts.as_mut();
+        let res = if let Some((start, draft)) = drafts.find(addr, len) {
+            let p = relocate(val, draft.ptr.add(addr - start));
+            if *flag != FLAG_DRAFTED {
+                drafts.map.get_mut(&start).unwrap().flags.push(flag_off);
+            }
+            p
+        } else {
+            let layout = Layout::for_value(val);
+            let p = alloc(layout);
+            if p.is_null() {
+                std::alloc::handle_alloc_error(layout);
+            }
+            ptr::copy_nonoverlapping(addr as *const u8, p, len);
+
+            // Drafts of the enclosed objects are merged into the new draft
+            let mut flags = vec![flag_off];
+            let inner: Vec<usize> = drafts.map.range(addr..addr + len).map(|(k, _)| *k).collect();
+            for k in inner {
+                let d = drafts.map.remove(&k).unwrap();
+                assert!(k + d.layout.size() <= addr + len, "Overlapping drafts");
+                ptr::copy_nonoverlapping(d.ptr, p.add(k - addr), d.layout.size());
+                flags.extend_from_slice(&d.flags);
+            }
+            drafts.map.insert(addr, Draft {
+                home: A::off_unchecked(val),
+                ptr: p,
+                layout,
+                flags,
+            });
+            relocate(val, p)
+        };
+        *flag = FLAG_DRAFTED;
+        res
+    }
+
+    /// Returns the offset of the original location of `val`, if `val` is in
+    /// a draft
+    pub(crate) fn home<T: ?Sized>(&self, val: &T) -> Option<u64> {
+        let addr = val as *const T as *const u8 as usize;
+        let len = mem::size_of_val(val);
+        self.drafts.map.values().find_map(|d| {
+            let start = d.ptr as usize;
+            if addr >= start && addr + len <= start + d.layout.size() {
+                Some(d.home + (addr - start) as u64)
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Returns the offset of the original location of the log flag `flag`
+    /// of a nested object, if `flag` is in a draft
+    ///
+    /// The flag is added to the flags of the draft, so that its original
+    /// location is reset after the draft is applied or discarded.
+    pub(crate) unsafe fn home_flag(&self, flag: &u8) -> Option<u64> {
+        let addr = flag as *const u8 as usize;
+        let drafts = self.drafts.as_mut();
+        let draft = drafts.map.values_mut().find(|d| {
+            let start = d.ptr as usize;
+            addr >= start && addr < start + d.layout.size()
+        })?;
+        let off = draft.home + (addr - draft.ptr as usize) as u64;
+        if !draft.flags.contains(&off) {
+            draft.flags.push(off);
+        }
+        Some(off)
+    }
+
+    /// Returns a pointer to the draft of `val`, if it is drafted
+    pub(crate) fn lookup<T: ?Sized>(&self, val: &T) -> Option<*mut T> {
+        let addr = val as *const T as *const u8 as usize;
+        let (start, draft) = self.drafts.find(addr, mem::size_of_val(val))?;
+        Some(relocate(val, unsafe { draft.ptr.add(addr - start) }))
+    }
+
+    /// Writes the drafts into a new persistent redo record
+    ///
+    /// Once it returns, the record survives a crash, and recovery applies
+    /// it again.
+    pub(crate) unsafe fn persist(&mut self) {
+        if self.is_persisted() || self.is_empty() {
+            return;
+        }
+        let drafts = &*self.drafts;
+        let size: usize = drafts.map.values()
+            .map(|d| 24 + 8 * d.flags.len() + pad(d.layout.size()))
+            .sum();
+        let (p, off, len, z) = A::pre_alloc(size);
+        if p.is_null() {
+            panic!("Memory exhausted");
+        }
+        A::drop_on_failure(off, len, z);
+        let mut cur = p;
+        for d in drafts.map.values() {
+            *(cur as *mut u64) = d.home;
+            *(cur.add(8) as *mut u64) = d.layout.size() as u64;
+            *(cur.add(16) as *mut u64) = d.flags.len() as u64;
+            cur = cur.add(24);
+            for f in &d.flags {
+                *(cur as *mut u64) = *f;
+                cur = cur.add(8);
+            }
+            ptr::copy_nonoverlapping(d.ptr, cur, d.layout.size());
+            cur = cur.add(pad(d.layout.size()));
+        }
+        ll::persist(p, size, false);
+        crate::may_crash!("redo::persist");
+        A::log64(A::off_unchecked(&self.len), size as u64, z);
+        A::log64(A::off_unchecked(&self.off), off, z);
+        A::perform(z);
+    }
+
+    /// Applies the persistent redo record to the original locations
+    ///
+    /// It is idempotent, so it can be repeated in recovery.
+    pub(crate) unsafe fn apply(&self) {
+        if !self.is_persisted() {
+            return;
+        }
+        let base = A::get_mut_unchecked::<u8>(self.off) as *mut u8;
+        let end = self.len as usize;
+        let mut flags = vec![];
+        let mut cur = 0;
+        while cur < end {
+            let p = base.add(cur);
+            let home = *(p as *const u64);
+            let len = *(p.add(8) as *const u64) as usize;
+            let n = *(p.add(16) as *const u64) as usize;
+            let p = p.add(24);
+            for i in 0..n {
+                flags.push(*(p.add(8 * i) as *const u64));
+            }
+            let p = p.add(8 * n);
+            let org = A::get_mut_unchecked::<u8>(home) as *mut u8;
+            ptr::copy_nonoverlapping(p, org, len);
+            ll::persist(org, len, false);
+            crate::may_crash!("redo::apply");
+            cur += 24 + 8 * n + pad(len);
+        }
+        for f in flags {
+            let flag = A::get_mut_unchecked::<u8>(f);
+            *flag = 0;
+            ll::persist_obj(flag, false);
+        }
+        ll::sfence();
+    }
+
+    /// Discards the drafts of an aborted transaction
+    pub(crate) unsafe fn rollback(&mut self) {
+        for d in self.drafts.map.values() {
+            for f in &d.flags {
+                *A::get_mut_unchecked::<u8>(*f) = 0;
+            }
+        }
+        self.drafts.as_mut().map.clear();
+    }
+
+    /// Releases the redo record and the drafts
+    pub(crate) unsafe fn clear(&mut self) {
+        if self.is_persisted() {
+            let p = A::get_mut_unchecked::<u8>(self.off);
+            let z = A::pre_dealloc(p, self.len as usize);
+            A::log64(A::off_unchecked(&self.off), u64::MAX, z);
+            A::log64(A::off_unchecked(&self.len), 0, z);
+            A::perform(z);
+        }
+        mem::take(self.drafts.as_mut());
+    }
+}
+
+#[inline]
+fn pad(len: usize) -> usize {
+    (len + 7) & !7
+}
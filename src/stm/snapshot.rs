@@ -0,0 +1,90 @@
+//! Read-only snapshots of versioned data
+
+use crate::alloc::MemPool;
+use crate::TxOutSafe;
+use std::collections::BTreeMap;
+use std::marker::PhantomData;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// The timestamp of the last transaction whose versions are stamped
+static CLOCK: AtomicU64 = AtomicU64::new(0);
+
+/// Serializes stamping the versions of committing transactions
+static STAMP: Mutex<()> = Mutex::new(());
+
+/// The number of active snapshots at each timestamp
+static ACTIVE: Mutex<BTreeMap<u64, usize>> = Mutex::new(BTreeMap::new());
+
+/// A consistent view of the versioned data as of the start of a
+/// [`read_transaction`]
+///
+/// A snapshot observes every change committed before it was taken, and none
+/// of the changes committed afterwards or still in progress, no matter how
+/// long it lives. It is only available within the body of a
+/// [`read_transaction`], and it is passed to the read functions of the
+/// versioned containers such as [`VersionedCell::read`].
+///
+/// [`read_transaction`]: ../alloc/trait.MemPool.html#method.read_transaction
+/// [`VersionedCell::read`]: ../cell/struct.VersionedCell.html#method.read
+pub struct Snapshot<A: MemPool> {
+    ts: u64,
+    phantom: PhantomData<A>,
+}
+
+impl<A: MemPool> !TxOutSafe for Snapshot<A> {}
+impl<A: MemPool> !Send for Snapshot<A> {}
+impl<A: MemPool> !Sync for Snapshot<A> {}
+
+impl<A: MemPool> Snapshot<A> {
+    /// Takes a new snapshot
+    pub(crate) fn new() -> Self {
+        let mut active = ACTIVE.lock().unwrap_or_else(|e| e.into_inner());
+        let ts = CLOCK.load(Ordering::Acquire);
+        *active.entry(ts).or_default() += 1;
+        Self { ts, phantom: PhantomData }
+    }
+
+    /// Returns the timestamp of the snapshot
+    #[inline]
+    pub fn timestamp(&self) -> u64 {
+        self.ts
+    }
+}
+
+impl<A: MemPool> Drop for Snapshot<A> {
+    fn drop(&mut self) {
+        let mut active = ACTIVE.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(cnt) = active.get_mut(&self.ts) {
+            *cnt -= 1;
+            if *cnt == 0 {
+                active.remove(&self.ts);
+            }
+        }
+    }
+}
+
+/// Returns the timestamp of the oldest active snapshot, or the current time
+/// if there is none
+///
+/// Every snapshot taken afterwards has a timestamp not smaller than the
+/// returned value.
+pub(crate) fn oldest_snapshot() -> u64 {
+    let active = ACTIVE.lock().unwrap_or_else(|e| e.into_inner());
+    match active.keys().next() {
+        Some(ts) => *ts,
+        None => CLOCK.load(Ordering::Acquire),
+    }
+}
+
+/// Assigns a new timestamp to a committing transaction
+///
+/// `stamp` writes the timestamp into the versions that the transaction
+/// created. The new versions become visible to the snapshots taken after
+/// `stamp` returns, all at once.
+pub(crate) fn stamp<F: FnOnce(u64)>(stamp: F) {
+    let _guard = STAMP.lock().unwrap_or_else(|e| e.into_inner());
+    let ts = CLOCK.load(Ordering::Relaxed) + 1;
+    stamp(ts);
+    CLOCK.store(ts, Ordering::Release);
+}
@@ -1,149 +1,230 @@
 //! Volatile Scratchpad Memory
-//! 
-//! This features is still under development. To enable it, you need to add
-//! `"use_pspd"` to the feature list.
-//! 
-
-use crate::cell::{LazyCell,VCell};
+//!
+//! To enable it, you need to add `"use_vspd"` to the feature list. It works
+//! the same as the [persistent scratchpad](../pspd/index.html), except that
+//! the copies are kept in the volatile memory during the transaction. At
+//! commit, they are written into a persistent record in one go, and the
+//! record is sealed, which is the commit point; then, the copies are written
+//! back to the original locations. The record and the volatile buffer are
+//! released when the journal is cleared.
+//!
+//! The size of the buffer chunks can be set per transaction using
+//! [`TxOptions::scratchpad_size`].
+//!
+//! [`TxOptions::scratchpad_size`]: ../struct.TxOptions.html#method.scratchpad_size
+
+use crate::cell::VCell;
 use crate::alloc::MemPool;
-use crate::{utils, ll};
+use crate::stm::DEFAULT_SCRATCHPAD_SIZE;
+use crate::{utils, ll, VSafe};
 use std::{mem, ptr, alloc::*};
 
-static SCRATCHPAD_SIZE: LazyCell<usize> = LazyCell::new(|| {
-    std::env::var("SPD_SIZE")
-        .unwrap_or("1024".to_string())
-        .parse::<usize>()
-        .expect("RECOVERY_INFO should be an unsigned integer")
-});
+/// A volatile chunk of the scratchpad buffer
+///
+/// Chunks never move, so the pointers to the copies remain valid until the
+/// buffer is released.
+struct Chunk {
+    ptr: *mut u8,
+    cap: usize,
+    len: usize,
+}
+
+impl Drop for Chunk {
+    fn drop(&mut self) {
+        unsafe { dealloc(self.ptr, Layout::from_size_align_unchecked(self.cap, 8)); }
+    }
+}
+
+#[derive(Default)]
+struct Buffer {
+    chunks: Vec<Chunk>,
+}
 
-struct RawPtr(*mut u8);
+unsafe impl VSafe for Buffer {}
 
-impl Default for RawPtr {
-    fn default() -> Self {
-        Self(ptr::null_mut())
+impl Buffer {
+    /// Returns the total length of the data in the chunks
+    fn len(&self) -> usize {
+        self.chunks.iter().map(|c| c.len).sum()
     }
 }
 
+/// The scratchpad of a journal
 pub struct Scratchpad<A: MemPool> {
-    base: VCell<RawPtr, A>,
-    cap: usize,
+    buf: VCell<Buffer, A>,
+    off: u64,
     len: usize,
-    off: u64
+    sealed: u64,
+
+    /// The size of the new chunks, which is not persistent
+    size: usize,
 }
 
 impl<A: MemPool> Scratchpad<A> {
     pub(crate) fn new() -> Self {
-        let size = *SCRATCHPAD_SIZE;
         Self {
-            base: VCell::new(RawPtr(unsafe {
-                alloc(Layout::from_size_align_unchecked(size, 2))
-            })),
-            cap: size,
+            buf: VCell::new(Buffer::default()),
+            off: u64::MAX,
             len: 0,
-            off: u64::MAX
+            sealed: 0,
+            size: DEFAULT_SCRATCHPAD_SIZE,
         }
     }
 
+    /// Collects the memory block of the persistent record, if any
+    pub(crate) fn blocks(&self, res: &mut Vec<(u64, usize)>) {
+        if self.off != u64::MAX {
+            res.push((self.off, self.len));
+        }
+    }
+
+    /// Sets the size of the new chunks
+    #[inline]
+    pub(crate) fn set_size(&mut self, size: usize) {
+        self.size = size;
+    }
+
+    /// Returns true if the scratchpad is sealed, which means that the
+    /// transaction has reached its commit point
+    #[inline]
+    pub(crate) fn is_sealed(&self) -> bool {
+        self.sealed != 0
+    }
+
+    /// Copies `val` into the scratchpad, and returns a pointer to the copy
     pub(crate) unsafe fn write<T: ?Sized>(&mut self, val: &T, off: u64) -> *mut T {
         let size = mem::size_of_val(val);
 
         // Data Layout:
         //   * org_off                           (u64)
-        //   * relative distance from next item  (u64)
-        //   * data                              (T)
-        let len = 8 + 8 + size;
-        if self.len + len > self.cap {
-            let new_cap = self.cap + *SCRATCHPAD_SIZE;
-            self.base = VCell::new(RawPtr(realloc(self.base.0,
-                Layout::from_size_align_unchecked(self.cap, 2),
-                new_cap)));
-            self.cap = new_cap;
+        //   * data size                         (u64)
+        //   * data                              (T, padded to 8 bytes)
+        let len = 8 + 8 + pad(size);
+        let buf = self.buf.as_mut();
+        if buf.chunks.last().is_none_or(|c| c.len + len > c.cap) {
+            let cap = pad(usize::max(self.size, len));
+            let layout = Layout::from_size_align_unchecked(cap, 8);
+            let ptr = alloc(layout);
+            if ptr.is_null() {
+                handle_alloc_error(layout);
+            }
+            buf.chunks.push(Chunk { ptr, cap, len: 0 });
         }
+        let chunk = buf.chunks.last_mut().unwrap();
+
         // First 8 bytes is org_off
-        let p = self.base.0.add(self.len);
+        let p = chunk.ptr.add(chunk.len);
         *utils::read::<u64>(p) = off;
 
-        // Second 8 bytes is the relative distance
+        // Second 8 bytes is the data size
         let p = p.add(8);
-        *utils::read::<usize>(p) = len;
+        *utils::read::<usize>(p) = size;
 
         // The last bytes contain data
         let p = p.add(8);
         ptr::copy_nonoverlapping(val as *const _ as *const u8, p, size);
 
-        self.len += len;
-        utils::read(p)
+        chunk.len += len;
+        (val as *const T as *mut T).wrapping_byte_offset(
+            (p as isize).wrapping_sub(val as *const T as *const u8 as isize))
     }
 
-    unsafe fn apply(&mut self) {
-        if self.off != u64::MAX {
-            let mut cur = 0;
-            while cur < self.len as u64 {
-                let p = utils::read_addr::<u8>(cur + self.off + A::start()) as *mut u8;
-
-                // First 8 bytes is org_off
-                let org_off = *utils::read::<u64>(p);
-    
-                // Second 8 bytes is the relative distance
-                let p = p.add(8);
-                let dist = *utils::read::<usize>(p);
-    
-                // The last bytes contain data
-                let p = p.add(8);
-                let len = dist - 16;
-                let org = utils::read_addr::<u8>(org_off + A::start());
-
-                ptr::copy_nonoverlapping(p, org, len);
-                ll::persist(org, len, false);
-    
-                cur += dist as u64;
+    /// Writes the buffer into a persistent record and seals the scratchpad
+    pub(crate) unsafe fn seal(&mut self) {
+        let size = self.buf.len();
+        if self.is_sealed() || size == 0 {
+            return;
+        }
+        if self.off == u64::MAX {
+            let (p, off, len, z) = A::pre_alloc(size);
+            if p.is_null() {
+                panic!("Memory exhausted");
             }
+            A::drop_on_failure(off, len, z);
+            A::log64(A::off_unchecked(&self.len), size as u64, z);
+            A::log64(A::off_unchecked(&self.off), off, z);
+            A::perform(z);
+        }
+        let p = utils::read_addr::<u8>(self.off + A::start()) as *mut u8;
+        let mut cur = p;
+        for c in &self.buf.chunks {
+            ptr::copy_nonoverlapping(c.ptr, cur, c.len);
+            cur = cur.add(c.len);
+        }
+        ll::persist(p, self.len, false);
+        ll::sfence();
+        crate::may_crash!("spd::seal");
+        self.sealed = 1;
+        ll::persist_obj(&self.sealed, true);
+    }
+
+    /// Writes back the data of the persistent record to the original
+    /// locations
+    unsafe fn apply(&self) {
+        if !self.is_sealed() {
+            return;
+        }
+        let base = utils::read_addr::<u8>(self.off + A::start()) as *mut u8;
+        let mut cur = 0;
+        while cur < self.len {
+            let p = base.add(cur);
+
+            // First 8 bytes is org_off
+            let org_off = *utils::read::<u64>(p);
+
+            // Second 8 bytes is the data size
+            let p = p.add(8);
+            let len = *utils::read::<usize>(p);
+
+            // The last bytes contain data
+            let p = p.add(8);
+            let org = utils::read_addr::<u8>(org_off + A::start());
+
+            ptr::copy_nonoverlapping(p, org, len);
+            ll::persist(org, len, false);
+            crate::may_crash!("spd::apply");
+
+            cur += 8 + 8 + pad(len);
         }
+        ll::sfence();
     }
 
+    #[inline]
     pub(crate) unsafe fn recover(&mut self) {
-        // Redo
         self.apply();
     }
 
+    #[inline]
     pub(crate) unsafe fn commit(&mut self) {
-        if self.len > 0 {
-            let size = mem::size_of::<Self>();
-            let (p, off, len, z) = A::pre_alloc(self.len);
-            ptr::copy_nonoverlapping(self.base.0, p, self.len);
-            A::drop_on_failure(off, len, z);
-            ll::persist(&*p, size, false);
-            A::log64(A::off_unchecked(&self.off), off, z);
-            A::perform(z);
-    
-            self.apply();
-        }
+        self.seal();
+        self.apply();
     }
 
+    #[inline]
     pub(crate) unsafe fn rollback(&mut self) {
-        // Do nothing
+        // The copies are discarded when the scratchpad is cleared
     }
 
+    /// Unseals the scratchpad, and releases the persistent record and the
+    /// volatile buffer
     pub(crate) unsafe fn clear(&mut self) {
+        if self.is_sealed() {
+            self.sealed = 0;
+            ll::persist_obj(&self.sealed, true);
+        }
         if self.off != u64::MAX {
-            let p = A::deref_mut(self.off).unwrap();
+            let p = utils::read_addr::<u8>(self.off + A::start());
             let z = A::pre_dealloc(p, self.len);
             A::log64(A::off_unchecked(&self.off), u64::MAX, z);
             A::log64(A::off_unchecked(&self.len), 0, z);
             A::perform(z);
         }
-        self.len = 0;
+        mem::take(self.buf.as_mut());
+        self.size = DEFAULT_SCRATCHPAD_SIZE;
     }
 }
 
-impl<A: MemPool> Drop for Scratchpad<A> {
-    fn drop(&mut self) {
-        unsafe {
-            self.clear();
-            if !self.base.0.is_null() {
-                dealloc(self.base.0, Layout::from_size_align_unchecked(self.cap, 2));
-                VCell::invalidate(&mut self.base);
-            }
-        }
-    }
-}
\ No newline at end of file
+#[inline]
+fn pad(len: usize) -> usize {
+    (len + 7) & !7
+}
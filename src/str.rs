@@ -2,12 +2,12 @@
 
 use crate::RootObj;
 use crate::convert::PFrom;
-use crate::alloc::MemPool;
+use crate::alloc::{MemPool, Trace, Visitor};
 use crate::clone::PClone;
 use crate::stm::*;
 use crate::vec::Vec;
 use std::string::FromUtf8Error;
-use std::borrow::{Cow, ToOwned};
+use std::borrow::Cow;
 use std::char::decode_utf16;
 use std::ops::{self, Index, IndexMut, RangeBounds};
 use std::str::pattern::Pattern;
@@ -367,7 +367,7 @@ impl<A: MemPool> String<A> {
     ///     assert_eq!("Hello �World", output);
     /// }).unwrap();
     /// ```
-    pub fn from_utf8_lossy<'a>(v: &'a [u8], j: &Journal<A>) -> String<A> {
+    pub fn from_utf8_lossy(v: &[u8], j: &Journal<A>) -> String<A> {
         Self::from_str(&StdString::from_utf8_lossy(v), j)
     }
 
@@ -810,7 +810,7 @@ impl<A: MemPool> String<A> {
         if self.vec.is_empty() {
             None
         } else {
-            let ch = self.as_str().chars().rev().next()?;
+            let ch = self.as_str().chars().next_back()?;
             let newlen = self.len() - ch.len_utf8();
             unsafe {
                 self.vec.set_len(newlen);
@@ -1228,6 +1228,13 @@ impl<A: MemPool> PClone<A> for String<A> {
     }
 }
 
+impl<A: MemPool> Trace<A> for String<A> {
+    #[inline]
+    fn trace<V: Visitor>(&self, v: &mut V) {
+        self.vec.trace(v);
+    }
+}
+
 // impl<A: MemPool> Clone for String<A> {
 //     fn clone(&self) -> Self {
 //         let journal = &Journal::try_current().expect("This function should be called only inside a transaction").0;
@@ -1323,32 +1330,18 @@ impl<A: MemPool> PClone<A> for String<A> {
 // }
 
 /// A convenience impl that delegates to the impl for `&str`
-impl<'a, 'b, A: MemPool> Pattern<'a> for &'b String<A> {
-    type Searcher = <&'b str as Pattern<'a>>::Searcher;
+impl<'b, A: MemPool> Pattern for &'b String<A> {
+    type Searcher<'a> = <&'b str as Pattern>::Searcher<'a>;
 
-    fn into_searcher(self, haystack: &'a str) -> <&'b str as Pattern<'a>>::Searcher {
+    fn into_searcher<'a>(self, haystack: &'a str) -> <&'b str as Pattern>::Searcher<'a> {
         self.as_str()[..].into_searcher(haystack)
     }
-
-    #[inline]
-    fn is_contained_in(self, haystack: &'a str) -> bool {
-        self.as_str()[..].is_contained_in(haystack)
-    }
-
-    #[inline]
-    fn is_prefix_of(self, haystack: &'a str) -> bool {
-        self.as_str()[..].is_prefix_of(haystack)
-    }
 }
 
 impl<A: MemPool> PartialEq for String<A> {
     #[inline]
     fn eq(&self, other: &String<A>) -> bool {
-        PartialEq::eq(&self.as_str()[..], &other.as_str()[..])
-    }
-    #[inline]
-    fn ne(&self, other: &String<A>) -> bool {
-        PartialEq::ne(&self[..], &other[..])
+        PartialEq::eq(self.as_str(), other.as_str())
     }
 }
 
@@ -1360,10 +1353,6 @@ macro_rules! impl_eq {
             fn eq(&self, other: &$rhs) -> bool {
                 PartialEq::eq(&self[..], &other[..])
             }
-            #[inline]
-            fn ne(&self, other: &$rhs) -> bool {
-                PartialEq::ne(&self[..], &other[..])
-            }
         }
         #[allow(unused_lifetimes)]
         impl<'a, 'b, A: MemPool> PartialEq<$lhs> for $rhs {
@@ -1371,10 +1360,6 @@ macro_rules! impl_eq {
             fn eq(&self, other: &$lhs) -> bool {
                 PartialEq::eq(&self[..], &other[..])
             }
-            #[inline]
-            fn ne(&self, other: &$lhs) -> bool {
-                PartialEq::ne(&self[..], &other[..])
-            }
         }
     };
 }
@@ -1689,7 +1674,7 @@ impl<A: MemPool> ToPString<A> for str {
 impl<A: MemPool> ToPString<A> for Cow<'_, str> {
     #[inline]
     fn to_pstring(&self, journal: &Journal<A>) -> String<A> {
-        String::from_str(&self[..].to_owned(), journal)
+        String::from_str(&self[..], journal)
     }
 }
 
@@ -1712,12 +1697,7 @@ impl<A: MemPool> ToPString<A> for StdString {
 //         self[..].to_owned()
 //     }
 // }
-impl<A: MemPool> StdToString for String<A> {
-    #[inline]
-    fn to_string(&self) -> StdString {
-        self.as_str().to_owned()
-    }
-}
+
 impl<A: MemPool> AsRef<str> for String<A> {
     #[inline]
     fn as_ref(&self) -> &str {
@@ -0,0 +1,347 @@
+use crate::alloc::MemPool;
+use crate::clone::PClone;
+use crate::ll::persist_obj;
+use crate::stm::Journal;
+use crate::*;
+use std::fmt;
+use std::marker::PhantomData;
+use std::panic::{RefUnwindSafe, UnwindSafe};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// A persistent integer type which can be safely shared between threads and
+/// modified without a transaction
+///
+/// Every modification is flushed to the persistent memory followed by a
+/// fence before the function returns, so it is durable as soon as it is
+/// visible to the caller. None of the operations requires a [`Journal`].
+///
+/// # Visibility before durability
+///
+/// A modification is visible to other threads as soon as it is made, which
+/// is before the modifying thread flushes it. Hence, another thread may
+/// [`load`] a value that is lost if a crash happens before the flush. If the
+/// thread persists anything that depends on the loaded value, it should use
+/// [`load_durable`] instead, which flushes the value before returning it.
+/// Then, whatever the caller observes has reached the persistent memory.
+///
+/// # Interplay with transactions
+///
+/// The operations are never logged. If they are used inside a transaction,
+/// they take effect immediately, and they are *not* rolled back if the
+/// transaction aborts. Other threads may observe and modify the value before
+/// the transaction ends. Therefore, a `PAtomicU64` should not be used to keep
+/// data that must be consistent with the data that a transaction modifies.
+///
+/// # Examples
+///
+/// ```
+/// use corundum::default::*;
+/// use std::sync::atomic::Ordering;
+/// use std::thread;
+///
+/// type P = Allocator;
+///
+/// let counter = P::open::<PAtomicU64>("foo.pool", O_CF).unwrap();
+/// let old = counter.load(Ordering::Acquire);
+///
+/// let threads: Vec<_> = (0..4).map(|_| {
+///     let counter = counter.clone();
+///     thread::spawn(move || {
+///         for _ in 0..100 {
+///             counter.fetch_add(1, Ordering::AcqRel);
+///         }
+///     })
+/// }).collect();
+///
+/// for t in threads {
+///     t.join().unwrap();
+/// }
+///
+/// assert_eq!(counter.load(Ordering::Acquire), old + 400);
+/// ```
+///
+/// [`Journal`]: ../stm/struct.Journal.html
+/// [`load`]: #method.load
+/// [`load_durable`]: #method.load_durable
+pub struct PAtomicU64<A: MemPool> {
+    heap: PhantomData<A>,
+    value: AtomicU64,
+}
+
+impl<A: MemPool> !TxOutSafe for PAtomicU64<A> {}
+impl<A: MemPool> UnwindSafe for PAtomicU64<A> {}
+impl<A: MemPool> RefUnwindSafe for PAtomicU64<A> {}
+
+unsafe impl<A: MemPool> TxInSafe for PAtomicU64<A> {}
+unsafe impl<A: MemPool> PSafe for PAtomicU64<A> {}
+unsafe impl<A: MemPool> Send for PAtomicU64<A> {}
+unsafe impl<A: MemPool> Sync for PAtomicU64<A> {}
+unsafe impl<A: MemPool> PSend for PAtomicU64<A> {}
+
+impl<A: MemPool> PAtomicU64<A> {
+    /// Creates a new atomic integer
+    pub const fn new(v: u64) -> Self {
+        Self {
+            heap: PhantomData,
+            value: AtomicU64::new(v),
+        }
+    }
+
+    #[inline]
+    fn persist(&self) {
+        persist_obj(&self.value, true);
+    }
+
+    /// Loads the value
+    ///
+    /// The value may not be durable yet, if the thread that wrote it has not
+    /// flushed it.
+    #[inline]
+    pub fn load(&self, order: Ordering) -> u64 {
+        self.value.load(order)
+    }
+
+    /// Loads the value and makes it durable before returning
+    ///
+    /// It flushes the value even if it is written by another thread which
+    /// has not flushed it yet, so that the returned value survives a crash.
+    #[inline]
+    pub fn load_durable(&self, order: Ordering) -> u64 {
+        let res = self.value.load(order);
+        self.persist();
+        res
+    }
+
+    /// Stores `v` and makes it durable
+    #[inline]
+    pub fn store(&self, v: u64, order: Ordering) {
+        self.value.store(v, order);
+        self.persist();
+    }
+
+    /// Stores `v`, makes it durable, and returns the previous value
+    #[inline]
+    pub fn swap(&self, v: u64, order: Ordering) -> u64 {
+        let res = self.value.swap(v, order);
+        self.persist();
+        res
+    }
+
+    /// Adds `v` to the current value, makes it durable, and returns the
+    /// previous value
+    ///
+    /// This operation wraps around on overflow.
+    #[inline]
+    pub fn fetch_add(&self, v: u64, order: Ordering) -> u64 {
+        let res = self.value.fetch_add(v, order);
+        self.persist();
+        res
+    }
+
+    /// Subtracts `v` from the current value, makes it durable, and returns
+    /// the previous value
+    ///
+    /// This operation wraps around on overflow.
+    #[inline]
+    pub fn fetch_sub(&self, v: u64, order: Ordering) -> u64 {
+        let res = self.value.fetch_sub(v, order);
+        self.persist();
+        res
+    }
+
+    /// Stores `new` if the current value is the same as `current`
+    ///
+    /// The return value is a result indicating whether the new value was
+    /// written and containing the previous value. On success, the new value
+    /// is durable before returning.
+    #[inline]
+    pub fn compare_exchange(&self, current: u64, new: u64, success: Ordering,
+        failure: Ordering) -> std::result::Result<u64, u64>
+    {
+        let res = self.value.compare_exchange(current, new, success, failure);
+        if res.is_ok() {
+            self.persist();
+        }
+        res
+    }
+}
+
+impl<A: MemPool> Default for PAtomicU64<A> {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+impl<A: MemPool> RootObj<A> for PAtomicU64<A> {
+    fn init(_: &Journal<A>) -> Self {
+        Self::default()
+    }
+}
+
+impl<A: MemPool> Trace<A> for PAtomicU64<A> {
+    #[inline]
+    fn trace<V: Visitor>(&self, _: &mut V) {}
+}
+
+impl<A: MemPool> PClone<A> for PAtomicU64<A> {
+    fn pclone(&self, _: &Journal<A>) -> Self {
+        Self::new(self.load_durable(Ordering::Acquire))
+    }
+}
+
+impl<A: MemPool> fmt::Debug for PAtomicU64<A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.load(Ordering::Relaxed), f)
+    }
+}
+
+/// A persistent raw pointer type which can be safely shared between threads
+/// and modified without a transaction
+///
+/// The pointer is kept as an offset in pool `A`, so it remains valid after
+/// restart. A null pointer is allowed, but any other pointer should point to
+/// an object in the same pool. Similar to `std::sync::atomic::AtomicPtr`,
+/// it does not own the pointee; hence, the pointee is neither traversed nor
+/// dropped with it. However, the memory that it points to is never reclaimed
+/// as a leak.
+///
+/// The durability guarantees, including the visibility of a pointer before it
+/// is flushed, and the interplay with transactions are the same as
+/// [`PAtomicU64`].
+///
+/// [`PAtomicU64`]: ./struct.PAtomicU64.html
+pub struct PAtomicPtr<T, A: MemPool> {
+    off: PAtomicU64<A>,
+    phantom: PhantomData<*mut T>,
+}
+
+impl<T, A: MemPool> !TxOutSafe for PAtomicPtr<T, A> {}
+impl<T, A: MemPool> UnwindSafe for PAtomicPtr<T, A> {}
+impl<T, A: MemPool> RefUnwindSafe for PAtomicPtr<T, A> {}
+
+unsafe impl<T, A: MemPool> TxInSafe for PAtomicPtr<T, A> {}
+unsafe impl<T, A: MemPool> PSafe for PAtomicPtr<T, A> {}
+unsafe impl<T, A: MemPool> Send for PAtomicPtr<T, A> {}
+unsafe impl<T, A: MemPool> Sync for PAtomicPtr<T, A> {}
+unsafe impl<T, A: MemPool> PSend for PAtomicPtr<T, A> {}
+
+impl<T, A: MemPool> PAtomicPtr<T, A> {
+    /// Creates a null pointer
+    pub const fn null() -> Self {
+        Self {
+            off: PAtomicU64::new(u64::MAX),
+            phantom: PhantomData,
+        }
+    }
+
+    /// Creates a new atomic pointer
+    ///
+    /// # Panics
+    ///
+    /// It panics if `p` is neither null nor pointing to an object in the pool.
+    pub fn new(p: *mut T) -> Self {
+        Self {
+            off: PAtomicU64::new(Self::to_off(p)),
+            phantom: PhantomData,
+        }
+    }
+
+    #[inline]
+    #[track_caller]
+    fn to_off(p: *mut T) -> u64 {
+        if p.is_null() {
+            u64::MAX
+        } else {
+            A::off(p).expect("The pointer is not in the pool")
+        }
+    }
+
+    #[inline]
+    fn to_ptr(off: u64) -> *mut T {
+        if off == u64::MAX {
+            std::ptr::null_mut()
+        } else {
+            unsafe { A::get_mut_unchecked::<T>(off) }
+        }
+    }
+
+    /// Loads the pointer
+    ///
+    /// Similar to [`PAtomicU64::load`], the pointer may not be durable yet.
+    ///
+    /// [`PAtomicU64::load`]: ./struct.PAtomicU64.html#method.load
+    #[inline]
+    pub fn load(&self, order: Ordering) -> *mut T {
+        Self::to_ptr(self.off.load(order))
+    }
+
+    /// Loads the pointer and makes it durable before returning
+    ///
+    /// It should be used before publishing the pointee in another persistent
+    /// object, so that the link is not lost if the thread that stored the
+    /// pointer crashes before flushing it.
+    #[inline]
+    pub fn load_durable(&self, order: Ordering) -> *mut T {
+        Self::to_ptr(self.off.load_durable(order))
+    }
+
+    /// Returns `true` if the pointer is null
+    #[inline]
+    pub fn is_null(&self, order: Ordering) -> bool {
+        self.off.load(order) == u64::MAX
+    }
+
+    /// Stores `p` and makes it durable
+    #[inline]
+    #[track_caller]
+    pub fn store(&self, p: *mut T, order: Ordering) {
+        self.off.store(Self::to_off(p), order)
+    }
+
+    /// Stores `p`, makes it durable, and returns the previous pointer
+    #[inline]
+    #[track_caller]
+    pub fn swap(&self, p: *mut T, order: Ordering) -> *mut T {
+        Self::to_ptr(self.off.swap(Self::to_off(p), order))
+    }
+
+    /// Stores `new` if the current pointer is the same as `current`
+    ///
+    /// The return value is a result indicating whether the new pointer was
+    /// written and containing the previous pointer. On success, the new
+    /// pointer is durable before returning.
+    #[inline]
+    #[track_caller]
+    pub fn compare_exchange(&self, current: *mut T, new: *mut T, success: Ordering,
+        failure: Ordering) -> std::result::Result<*mut T, *mut T>
+    {
+        self.off.compare_exchange(Self::to_off(current), Self::to_off(new), success, failure)
+            .map(Self::to_ptr)
+            .map_err(Self::to_ptr)
+    }
+}
+
+impl<T, A: MemPool> Default for PAtomicPtr<T, A> {
+    fn default() -> Self {
+        Self::null()
+    }
+}
+
+impl<T, A: MemPool> RootObj<A> for PAtomicPtr<T, A> {
+    fn init(_: &Journal<A>) -> Self {
+        Self::null()
+    }
+}
+
+impl<T, A: MemPool> Trace<A> for PAtomicPtr<T, A> {
+    #[inline]
+    fn trace<V: Visitor>(&self, v: &mut V) {
+        v.visit_raw(unsafe { &*self.off.value.as_ptr() }, std::mem::size_of::<T>());
+    }
+}
+
+impl<T, A: MemPool> fmt::Debug for PAtomicPtr<T, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Pointer::fmt(&self.load(Ordering::Relaxed), f)
+    }
+}
@@ -0,0 +1,202 @@
+use crate::alloc::MemPool;
+use crate::cell::VCell;
+use crate::stm::{Journal, Log};
+use crate::*;
+use std::marker::PhantomData;
+use std::panic::{RefUnwindSafe, UnwindSafe};
+use std::sync::{Condvar, Mutex};
+use std::time::{Duration, Instant};
+use std::fmt;
+
+use super::MutexGuard;
+
+/// A transaction-aware condition variable
+///
+/// Condition variables represent the ability to block a thread such that it
+/// consumes no CPU time while waiting for an event to occur. A `PCondvar` is
+/// used together with a [`PMutex`]: [`wait`] releases the lock that the
+/// running transaction holds, blocks until it is notified, and then locks the
+/// mutex again. Unlike `std::sync::Condvar`, [`notify_one`] and [`notify_all`]
+/// take a journal, and the waiters are woken up only after the notifying
+/// transaction commits. If the notifying transaction rolls back, no thread is
+/// notified.
+///
+/// The waiting state is kept in a [`VCell`], so it resets on restart.
+///
+/// Spurious wakeups are possible, so the condition should always be checked
+/// in a loop.
+///
+/// # Examples
+///
+/// ```
+/// use corundum::default::*;
+/// use std::thread;
+///
+/// type P = Allocator;
+///
+/// #[derive(Root)]
+/// struct Counter {
+///     value: PMutex<i32>,
+///     ready: PCondvar,
+/// }
+///
+/// let root = P::open::<Counter>("foo.pool", O_CF).unwrap();
+///
+/// let consumer = {
+///     let root = root.clone();
+///     thread::spawn(move || {
+///         transaction(|j| {
+///             let mut value = root.value.lock(j);
+///             while *value == 0 {
+///                 value = root.ready.wait(value);
+///             }
+///             std::mem::replace(&mut *value, 0)
+///         }).unwrap()
+///     })
+/// };
+///
+/// transaction(|j| {
+///     *root.value.lock(j) = 10;
+///     root.ready.notify_one(j);
+/// }).unwrap(); // <-- the consumer is notified here
+///
+/// assert_eq!(consumer.join().unwrap(), 10);
+/// ```
+///
+/// [`PMutex`]: ./struct.PMutex.html
+/// [`VCell`]: ../cell/struct.VCell.html
+/// [`wait`]: #method.wait
+/// [`notify_one`]: #method.notify_one
+/// [`notify_all`]: #method.notify_all
+pub struct PCondvar<A: MemPool> {
+    heap: PhantomData<A>,
+    inner: VCell<CondvarInner, A>,
+}
+
+#[derive(Default)]
+struct CondvarInner {
+    /// The number of notifications so far
+    seq: Mutex<u64>,
+    cv: Condvar,
+}
+
+impl<A: MemPool> !TxOutSafe for PCondvar<A> {}
+impl<A: MemPool> UnwindSafe for PCondvar<A> {}
+impl<A: MemPool> RefUnwindSafe for PCondvar<A> {}
+
+unsafe impl<A: MemPool> TxInSafe for PCondvar<A> {}
+unsafe impl<A: MemPool> PSafe for PCondvar<A> {}
+unsafe impl<A: MemPool> Send for PCondvar<A> {}
+unsafe impl<A: MemPool> Sync for PCondvar<A> {}
+unsafe impl<A: MemPool> PSend for PCondvar<A> {}
+
+impl<A: MemPool> PCondvar<A> {
+    /// Creates a new condition variable
+    pub fn new() -> Self {
+        Self {
+            heap: PhantomData,
+            inner: VCell::new(CondvarInner::default()),
+        }
+    }
+
+    /// Blocks the current thread until this condition variable receives a
+    /// notification
+    ///
+    /// This function atomically releases the mutex specified by `guard` and
+    /// blocks the current thread. Since a [`PMutex`] is held until the end of
+    /// the transaction, all acquisitions of the mutex by the running
+    /// transaction are released. When the thread is notified, the mutex is
+    /// locked again as before, and the guard is returned.
+    ///
+    /// # Panics
+    ///
+    /// It panics if the data protected by the mutex is already modified in
+    /// the running transaction. Waiting would otherwise expose uncommitted
+    /// changes to the notifying transaction.
+    ///
+    /// [`PMutex`]: ./struct.PMutex.html
+    pub fn wait<'a, T>(&self, guard: MutexGuard<'a, T, A>) -> MutexGuard<'a, T, A> {
+        let inner = &*self.inner;
+        let seq = inner.seq.lock().unwrap();
+        let ticket = *seq;
+        guard.unlocked(move || {
+            let _seq = inner.cv.wait_while(seq, |s| *s == ticket).unwrap();
+        });
+        guard
+    }
+
+    /// Waits on this condition variable for a notification, timing out after
+    /// the specified duration
+    ///
+    /// It is similar to [`wait`], except that it returns `true` as the second
+    /// value of the pair if the timeout elapsed without a notification.
+    ///
+    /// [`wait`]: #method.wait
+    pub fn wait_timeout<'a, T>(&self, guard: MutexGuard<'a, T, A>, dur: Duration)
+        -> (MutexGuard<'a, T, A>, bool)
+    {
+        let inner = &*self.inner;
+        let seq = inner.seq.lock().unwrap();
+        let ticket = *seq;
+        let deadline = Instant::now() + dur;
+        let timed_out = guard.unlocked(move || {
+            let (_seq, res) = inner.cv.wait_timeout_while(seq,
+                deadline.saturating_duration_since(Instant::now()),
+                |s| *s == ticket).unwrap();
+            res.timed_out()
+        });
+        (guard, timed_out)
+    }
+
+    /// Wakes up one blocked thread on this condition variable after the
+    /// transaction commits
+    pub fn notify_one(&self, journal: &Journal<A>) {
+        unsafe { Log::notify_on_commit(self.addr(), false, journal); }
+    }
+
+    /// Wakes up all blocked threads on this condition variable after the
+    /// transaction commits
+    pub fn notify_all(&self, journal: &Journal<A>) {
+        unsafe { Log::notify_on_commit(self.addr(), true, journal); }
+    }
+
+    #[inline]
+    fn addr(&self) -> u64 {
+        &*self.inner as *const CondvarInner as u64
+    }
+}
+
+/// Notifies the waiters of the condition variable whose volatile state is at
+/// virtual address `addr`
+pub(crate) unsafe fn notify_condvar(addr: u64, all: bool) {
+    let inner = &*(addr as *const CondvarInner);
+    *inner.seq.lock().unwrap() += 1;
+    if all {
+        inner.cv.notify_all();
+    } else {
+        inner.cv.notify_one();
+    }
+}
+
+impl<A: MemPool> Default for PCondvar<A> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<A: MemPool> RootObj<A> for PCondvar<A> {
+    fn init(_: &Journal<A>) -> Self {
+        Self::new()
+    }
+}
+
+impl<A: MemPool> Trace<A> for PCondvar<A> {
+    #[inline]
+    fn trace<V: Visitor>(&self, _: &mut V) {}
+}
+
+impl<A: MemPool> fmt::Debug for PCondvar<A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad("PCondvar { .. }")
+    }
+}
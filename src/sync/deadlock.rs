@@ -0,0 +1,130 @@
+//! Lock-ordering diagnostics for transaction-wide locks
+//!
+//! With the `check_deadlock` feature, every [`PMutex`] acquisition is
+//! recorded in a wait-for graph. A thread that is about to block on a lock
+//! follows the chain of owners of the lock; if the chain reaches back to the
+//! thread itself, waiting would never end. In that case, the locking call
+//! panics with a report of the lock sites involved in the cycle, so that the
+//! transaction rolls back and releases its locks instead of hanging.
+//!
+//! The ownership entries are removed when the [`UnlockOnCommit`] logs of the
+//! owner's journal are cleared.
+//!
+//! [`PMutex`]: ../struct.PMutex.html
+//! [`UnlockOnCommit`]: ../../stm/enum.LogEnum.html#variant.UnlockOnCommit
+
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::panic::Location;
+use std::sync::Mutex;
+use std::thread::{self, ThreadId};
+
+struct Owner {
+    thread: ThreadId,
+    name: String,
+    site: &'static Location<'static>,
+    depth: usize,
+}
+
+#[derive(Default)]
+struct Registry {
+    /// Owners of the locks indexed by the address of the lock
+    owners: HashMap<u64, Owner>,
+
+    /// The lock that each blocked thread is waiting for, and the waiting site
+    waiting: HashMap<ThreadId, (u64, &'static Location<'static>)>,
+}
+
+static REGISTRY: Mutex<Option<Registry>> = Mutex::new(None);
+
+fn with_registry<T, F: FnOnce(&mut Registry) -> T>(f: F) -> T {
+    let mut reg = REGISTRY.lock().unwrap_or_else(|e| e.into_inner());
+    f(reg.get_or_insert_with(Default::default))
+}
+
+fn thread_name() -> String {
+    let t = thread::current();
+    match t.name() {
+        Some(name) => format!("`{}`", name),
+        None => format!("{:?}", t.id()),
+    }
+}
+
+/// Registers that the current thread is about to wait for `lock` at `site`
+///
+/// # Panics
+///
+/// It panics with a report of the lock sites, if waiting closes a cycle in
+/// the wait-for graph.
+pub(crate) fn wait(lock: u64, site: &'static Location<'static>) {
+    let me = thread::current().id();
+    let report = with_registry(|reg| {
+        let mut report = String::new();
+        let mut curr = lock;
+        let mut at = site;
+        let mut name = thread_name();
+        let mut steps = reg.waiting.len() + 1;
+        while let Some(owner) = reg.owners.get(&curr) {
+            if owner.thread == me && curr == lock {
+                // Reentrant locking does not block
+                return None;
+            }
+            let _ = writeln!(report,
+                "  thread {} waits at {} for a lock held by thread {} since {}",
+                name, at, owner.name, owner.site);
+            if owner.thread == me {
+                return Some(report);
+            }
+            steps -= 1;
+            match reg.waiting.get(&owner.thread) {
+                Some((next, next_at)) if steps > 0 => {
+                    curr = *next;
+                    at = *next_at;
+                    name = owner.name.clone();
+                }
+                _ => break,
+            }
+        }
+        reg.waiting.insert(me, (lock, site));
+        None
+    });
+    if let Some(report) = report {
+        panic!("Deadlock detected; the transaction is rolled back:\n{}", report);
+    }
+}
+
+/// Registers that the current thread stopped waiting without acquiring the
+/// lock
+pub(crate) fn cancel() {
+    let me = thread::current().id();
+    with_registry(|reg| {
+        reg.waiting.remove(&me);
+    })
+}
+
+/// Registers that the current thread acquired `lock` at `site`
+pub(crate) fn acquired(lock: u64, site: &'static Location<'static>) {
+    let me = thread::current().id();
+    with_registry(|reg| {
+        reg.waiting.remove(&me);
+        let owner = reg.owners.entry(lock).or_insert_with(|| Owner {
+            thread: me,
+            name: thread_name(),
+            site,
+            depth: 0,
+        });
+        owner.depth += 1;
+    })
+}
+
+/// Removes one acquisition of `lock` from the graph
+pub(crate) fn released(lock: u64) {
+    with_registry(|reg| {
+        if let Some(owner) = reg.owners.get_mut(&lock) {
+            owner.depth -= 1;
+            if owner.depth == 0 {
+                reg.owners.remove(&lock);
+            }
+        }
+    })
+}
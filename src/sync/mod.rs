@@ -1,7 +1,16 @@
 //! Useful synchronization primitives
 
+mod atomic;
+mod condvar;
 mod mutex;
 mod parc;
+mod rwlock;
 
+#[cfg(feature = "check_deadlock")]
+pub(crate) mod deadlock;
+
+pub use atomic::*;
+pub use condvar::*;
 pub use mutex::*;
 pub use parc::*;
+pub use rwlock::*;
@@ -1,16 +1,17 @@
 use crate::alloc::MemPool;
 use crate::cell::VCell;
 use crate::ptr::Ptr;
-use crate::stm::{Journal, Log, Notifier, Logger};
+use crate::stm::{Journal, Log, LogEnum, Notifier, Logger};
 use crate::*;
 use std::cell::UnsafeCell;
 use std::marker::PhantomData;
 use std::ops::{Deref, DerefMut};
 use std::panic::{RefUnwindSafe, UnwindSafe};
 use std::sync::{TryLockError, TryLockResult};
+use std::time::{Duration, Instant};
 
 #[allow(unused_imports)]
-use std::{fmt, intrinsics};
+use std::fmt;
 
 /// A transaction-wide recursive mutual exclusion primitive useful for
 /// protecting shared data while transaction is open. Further locking in the
@@ -181,6 +182,7 @@ impl<T: PSafe, A: MemPool> PMutex<T, A> {
 
     #[inline]
     #[allow(clippy::mut_from_ref)]
+    #[allow(invalid_reference_casting)]
     fn self_mut(&self) -> &mut Self {
         unsafe {
             let ptr: *const Self = self;
@@ -190,32 +192,108 @@ impl<T: PSafe, A: MemPool> PMutex<T, A> {
 }
 
 impl<T, A: MemPool> PMutex<T, A> {
+    /// Blocks until the underlying mutex is locked, or the lock timeout of
+    /// the transaction passes
     #[inline]
-    fn raw_lock(&self, journal: &Journal<A>) {
-        unsafe {
-            // Log::unlock_on_failure(self.inner.get(), journal);
-            let lock = &self.inner.lock.1 as *const _ as *mut _;
+    #[cfg_attr(feature = "check_deadlock", track_caller)]
+    unsafe fn raw_lock_once(&self) {
+        let lock = &self.inner.lock.1 as *const _ as *mut _;
+
+        #[cfg(feature = "check_deadlock")]
+        super::deadlock::wait(self.lock_id(), std::panic::Location::caller());
+
+        if let Some(deadline) = crate::stm::lock_deadline() {
+            #[cfg(all(target_os = "linux", not(feature = "no_pthread")))]
+            let locked = timed_lock(lock, deadline);
+
+            #[cfg(not(all(target_os = "linux", not(feature = "no_pthread"))))]
+            let locked = wait_until(Some(deadline), || self.raw_trylock_once());
+
+            if !locked {
+                #[cfg(feature = "check_deadlock")]
+                super::deadlock::cancel();
+
+                crate::stm::lock_timed_out();
+            }
+        } else {
             #[cfg(not(any(feature = "no_pthread", windows)))] {
                 libc::pthread_mutex_lock(lock);
             }
             #[cfg(any(feature = "no_pthread", windows))] {
                 let tid = std::thread::current().id().as_u64().get();
-                while intrinsics::atomic_cxchg_acqrel(lock, 0, tid).0 != tid {}
+                while spin_cxchg(lock, tid) != tid {}
             }
+        }
+
+        #[cfg(feature = "check_deadlock")]
+        super::deadlock::acquired(self.lock_id(), std::panic::Location::caller());
+    }
+
+    /// Unlocks the underlying mutex once
+    #[inline]
+    unsafe fn raw_unlock_once(&self) {
+        let lock = &self.inner.lock.1 as *const _ as *mut _;
+
+        #[cfg(feature = "check_deadlock")]
+        super::deadlock::released(self.lock_id());
+
+        #[cfg(not(any(feature = "no_pthread", windows)))]
+        libc::pthread_mutex_unlock(lock);
+
+        #[cfg(any(feature = "no_pthread", windows))] 
+        spin_release(lock);
+    }
+
+    #[inline]
+    #[cfg_attr(feature = "check_deadlock", track_caller)]
+    fn raw_lock(&self, journal: &Journal<A>) {
+        unsafe {
+            // Log::unlock_on_failure(self.inner.get(), journal);
+            self.raw_lock_once();
             if self.inner.acquire() {
                 Log::unlock_on_commit(&self.inner.lock as *const _ as u64, journal);
             } else {
-                #[cfg(not(any(feature = "no_pthread", windows)))]
-                libc::pthread_mutex_unlock(lock);
-
-                #[cfg(any(feature = "no_pthread", windows))] 
-                intrinsics::atomic_store_rel(lock, 0);
-
+                self.raw_unlock_once();
                 panic!("Cannot have multiple instances of MutexGuard");
             }
         }
     }
 
+    /// Temporarily releases all acquisitions of the lock by the transaction,
+    /// and returns their count to be given to [`reacquire`](#method.reacquire)
+    ///
+    /// The [`UnlockOnCommit`] logs of the released acquisitions are disarmed
+    /// first, so that if the transaction unwinds before reacquiring the lock,
+    /// rolling it back does not unlock a mutex that it no longer holds.
+    ///
+    /// [`UnlockOnCommit`]: ../stm/enum.LogEnum.html#variant.UnlockOnCommit
+    ///
+    /// # Panics
+    ///
+    /// It panics if the protected data is modified in the transaction, as
+    /// unlocking would expose the uncommitted changes to other transactions.
+    unsafe fn release_all(&self, journal: &Journal<A>) -> usize {
+        assert!((*self.data.get()).0 == 0,
+            "Cannot release a mutex whose data is modified in the running transaction");
+        let n = journal.disarm_logs(LogEnum::UnlockOnCommit(&self.inner.lock as *const _ as u64));
+        self.inner.release();
+        for _ in 0..n {
+            self.raw_unlock_once();
+        }
+        n
+    }
+
+    /// Acquires the lock `n` times to restore the state before
+    /// [`release_all`](#method.release_all). Every acquisition is logged as
+    /// soon as it is taken, so that the journal only unlocks what it holds.
+    unsafe fn reacquire(&self, n: usize, journal: &Journal<A>) {
+        for _ in 0..n {
+            self.raw_lock_once();
+            Log::unlock_on_commit(&self.inner.lock as *const _ as u64, journal);
+        }
+        self.inner.acquire();
+    }
+
     /// Acquires a mutex, blocking the current thread until it is able to do so.
     /// 
     /// This function will block the local thread until it is available to
@@ -228,6 +306,17 @@ impl<T, A: MemPool> PMutex<T, A> {
     /// The mutex remains locked until the transaction is committed. 
     /// Alternatively, [`PMutex`] can be used as a compact form of `Mutex`.
     /// 
+    /// In a transaction started by [`transaction_with`] with a lock timeout,
+    /// it blocks at most for the given time. Then, it unwinds the transaction
+    /// so that it is rolled back and retried.
+    /// 
+    /// # Panics
+    ///
+    /// With the `check_deadlock` feature, it panics if waiting for the lock
+    /// would never end because the owner thread is, directly or indirectly,
+    /// waiting for a lock held by the current thread. The panic message lists
+    /// the lock sites in the cycle, and the transaction is rolled back.
+    ///
     /// # Examples
     /// 
     /// ```
@@ -254,37 +343,53 @@ impl<T, A: MemPool> PMutex<T, A> {
     /// 
     /// [`PMutex`]: ../default/type.PMutex.html
     /// [`UnlockOnCommit`]: ../stm/enum.LogEnum.html#variant.UnlockOnCommit
+    /// [`transaction_with`]: ../alloc/trait.MemPool.html#method.transaction_with
     /// 
+    #[cfg_attr(feature = "check_deadlock", track_caller)]
     pub fn lock<'a>(&'a self, journal: &'a Journal<A>) -> MutexGuard<'a, T, A> {
         self.raw_lock(journal);
         unsafe { MutexGuard::new(self, journal) }
     }
 
+    /// Returns the identifier of the lock which is the address of its
+    /// `UnlockOnCommit` target
+    #[cfg(feature = "check_deadlock")]
     #[inline]
-    fn raw_trylock(&self, journal: &Journal<A>) -> bool {
-        unsafe {
-            let lock = &self.inner.lock.1 as *const _ as *mut _;
+    fn lock_id(&self) -> u64 {
+        &self.inner.lock as *const _ as u64
+    }
+
+    /// Tries to lock the underlying mutex without taking a log
+    #[inline]
+    unsafe fn raw_trylock_once(&self) -> bool {
+        let lock = &self.inner.lock.1 as *const _ as *mut _;
 
-            #[cfg(not(any(feature = "no_pthread", windows)))]
-            let result = libc::pthread_mutex_trylock(lock) == 0;
+        #[cfg(not(any(feature = "no_pthread", windows)))]
+        let result = libc::pthread_mutex_trylock(lock) == 0;
 
-            #[cfg(any(feature = "no_pthread", windows))]
-            let result = {
-                let tid = std::thread::current().id().as_u64().get();
-                intrinsics::atomic_cxchg_acqrel(lock, 0, tid).0 == tid
-            };
+        #[cfg(any(feature = "no_pthread", windows))]
+        let result = {
+            let tid = std::thread::current().id().as_u64().get();
+            let old = spin_cxchg(lock, tid);
+            old == 0 || old == tid
+        };
+
+        result
+    }
+
+    #[inline]
+    #[cfg_attr(feature = "check_deadlock", track_caller)]
+    fn raw_trylock(&self, journal: &Journal<A>) -> bool {
+        unsafe {
+            if self.raw_trylock_once() {
+                #[cfg(feature = "check_deadlock")]
+                super::deadlock::acquired(self.lock_id(), std::panic::Location::caller());
 
-            if result {
                 if self.inner.acquire() {
                     Log::unlock_on_commit(&self.inner.lock as *const _ as u64, journal);
                     true
                 } else {
-                    #[cfg(not(any(feature = "no_pthread", windows)))] 
-                    libc::pthread_mutex_unlock(lock);
-
-                    #[cfg(any(feature = "no_pthread", windows))] 
-                    intrinsics::atomic_store_rel(lock, 0);
-
+                    self.raw_unlock_once();
                     panic!("Cannot have multiple instances of MutexGuard");
                 }
             } else {
@@ -337,6 +442,7 @@ impl<T, A: MemPool> PMutex<T, A> {
     /// ```
     /// 
     /// [`PMutex`]: ../default/type.PMutex.html
+    #[cfg_attr(feature = "check_deadlock", track_caller)]
     pub fn try_lock<'a>(&'a self, journal: &'a Journal<A>) -> TryLockResult<MutexGuard<'a, T, A>> {
         if self.raw_trylock(journal) {
             unsafe { Ok(MutexGuard::new(self, journal)) }
@@ -352,6 +458,13 @@ impl<T: RootObj<A>, A: MemPool> RootObj<A> for PMutex<T, A> {
     }
 }
 
+impl<T: Trace<A>, A: MemPool> Trace<A> for PMutex<T, A> {
+    #[inline]
+    fn trace<V: Visitor>(&self, v: &mut V) {
+        unsafe { (*self.data.get()).1.trace(v) }
+    }
+}
+
 impl<T: fmt::Debug, A: MemPool> fmt::Debug for PMutex<T, A> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         self.data.fmt(f)
@@ -388,6 +501,18 @@ impl<'mutex, T, A: MemPool> MutexGuard<'mutex, T, A> {
     }
 }
 
+impl<T, A: MemPool> MutexGuard<'_, T, A> {
+    /// Runs `f` while the mutex is unlocked, and locks it again afterwards
+    pub(crate) fn unlocked<R>(&self, f: impl FnOnce() -> R) -> R {
+        unsafe {
+            let n = self.lock.release_all(&*self.journal);
+            let res = f();
+            self.lock.reacquire(n, &*self.journal);
+            res
+        }
+    }
+}
+
 impl<T, A: MemPool> Deref for MutexGuard<'_, T, A> {
     type Target = T;
 
@@ -422,3 +547,69 @@ pub unsafe fn init_lock(mutex: *mut libc::pthread_mutex_t, attr: *mut libc::pthr
     let result = libc::pthread_mutexattr_destroy(attr);
     debug_assert_eq!(result, 0);
 }
+
+/// Blocks until the pthread mutex `lock` is locked or `deadline` passes, and
+/// returns true if it is locked
+#[cfg(all(target_os = "linux", not(feature = "no_pthread")))]
+pub(crate) unsafe fn timed_lock(lock: *mut libc::pthread_mutex_t, deadline: Instant) -> bool {
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    // The timeout is an absolute time of the realtime clock
+    let left = deadline.saturating_duration_since(Instant::now());
+    let abs = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default() + left;
+    let ts = libc::timespec {
+        tv_sec: abs.as_secs() as libc::time_t,
+        tv_nsec: abs.subsec_nanos() as _,
+    };
+    libc::pthread_mutex_timedlock(lock, &ts) == 0
+}
+
+/// The longest time that [`wait_until`] parks the thread between two tries
+const MAX_BACKOFF: Duration = Duration::from_millis(1);
+
+/// Tries `ready` until it returns true or `deadline` passes, and returns
+/// false if it timed out. The thread is parked between the tries with an
+/// exponential backoff instead of spinning.
+pub(crate) fn wait_until<F: FnMut() -> bool>(deadline: Option<Instant>, mut ready: F) -> bool {
+    let mut backoff = Duration::from_micros(1);
+    loop {
+        if ready() {
+            return true;
+        }
+        let mut park = backoff;
+        if let Some(deadline) = deadline {
+            let now = Instant::now();
+            if now >= deadline {
+                return false;
+            }
+            park = park.min(deadline - now);
+        }
+        std::thread::park_timeout(park);
+        backoff = (backoff * 2).min(MAX_BACKOFF);
+    }
+}
+
+/// Tries to take the spin lock `lock` for thread `tid`, and returns the
+/// previous owner
+#[cfg(any(feature = "no_pthread", windows))]
+#[inline]
+pub(crate) unsafe fn spin_cxchg(lock: *mut u64, tid: u64) -> u64 {
+    use std::sync::atomic::{AtomicU64, Ordering};
+    match AtomicU64::from_ptr(lock).compare_exchange(0, tid, Ordering::AcqRel, Ordering::Acquire) {
+        Ok(old) | Err(old) => old
+    }
+}
+
+/// Returns the owner of the spin lock `lock`
+#[cfg(any(feature = "no_pthread", windows))]
+#[inline]
+pub(crate) unsafe fn spin_owner(lock: *mut u64) -> u64 {
+    std::sync::atomic::AtomicU64::from_ptr(lock).load(std::sync::atomic::Ordering::Acquire)
+}
+
+/// Releases the spin lock `lock`
+#[cfg(any(feature = "no_pthread", windows))]
+#[inline]
+pub(crate) unsafe fn spin_release(lock: *mut u64) {
+    std::sync::atomic::AtomicU64::from_ptr(lock).store(0, std::sync::atomic::Ordering::Release)
+}
@@ -7,7 +7,6 @@ use crate::clone::*;
 use crate::ptr::Ptr;
 use crate::stm::*;
 use crate::*;
-use std::clone::Clone as StdClone;
 use std::cmp::Ordering;
 use std::hash::Hash;
 use std::hash::Hasher;
@@ -360,8 +359,6 @@ impl<T: PSafe, A: MemPool> Parc<mem::MaybeUninit<T>, A> {
 }
 
 impl<T: PSafe, A: MemPool> Parc<MaybeUninit<T>, A> {
-    #[inline]
-
     /// Returns a mutable reference into the given `Parc`, if there are
     /// no other [`Parc`] or [`Weak`] pointers to the same allocation.
     ///
@@ -453,11 +450,11 @@ impl<T: PSafe + ?Sized, A: MemPool> Parc<T, A> {
     /// [`upgrade`]: ./struct.Weak.html#method.upgrade
     pub fn downgrade(this: &Self, j: &Journal<A>) -> Weak<T, A> {
         let inner = this.inner();
-        let _lock = SpinLock::acquire(inner.counter.lock.as_mut());
+        let _lock = unsafe { SpinLock::acquire(inner.counter.lock.as_mut()) };
 
         lock_free_fetch_inc(&mut inner.counter.weak, j);
         Weak {
-            ptr: this.ptr.clone(),
+            ptr: this.ptr,
         }
     }
 
@@ -590,12 +587,25 @@ impl<T: PSafe, A: MemPool> PmemUsage for Parc<T, A> {
     }
 }
 
-impl<T: PSafe + PmemUsage + ?Sized, A: MemPool> PmemUsage for Parc<T, A> {
+impl<T: PSafe + PmemUsage, A: MemPool> PmemUsage for Parc<T, A> {
     fn size_of() -> usize {
         Ptr::<ParcInner<T, A>, A>::size_of() + T::size_of()
     }
 }
 
+impl<T: PSafe + Trace<A>, A: MemPool> Trace<A> for Parc<T, A> {
+    #[inline]
+    fn trace<V: Visitor>(&self, v: &mut V) {
+        let counters = || {
+            let counter = &self.inner().counter;
+            (&counter.strong, &counter.weak)
+        };
+        if v.visit_strong(self.ptr.off_ref(), mem::size_of::<ParcInner<T, A>>(), counters) {
+            self.inner().value.trace(v);
+        }
+    }
+}
+
 impl<T: PSafe + ?Sized, A: MemPool> Deref for Parc<T, A> {
     type Target = T;
 
@@ -631,12 +641,11 @@ impl<T: PSafe, A: MemPool> Parc<T, A> {
     /// ```
     ///
     pub fn initialize(arc: &Option<Parc<T, A>>, value: T) -> crate::result::Result<()> {
-        assert!(
-            !Journal::<A>::is_running(),
-            "Parc::initialize() cannot be used inside a transaction"
-        );
+        if Journal::<A>::is_running() {
+            return Err(crate::Error::TransactionRunning);
+        }
         match arc {
-            Some(_) => Err("already initialized".to_string()),
+            Some(_) => Err(crate::Error::AlreadyInitialized),
             None => if A::valid(arc) {
                 unsafe {
                     let new = A::atomic_new(
@@ -664,7 +673,7 @@ impl<T: PSafe, A: MemPool> Parc<T, A> {
                 }
                 Ok(())
             } else {
-                Err("The object is not in the PM".to_string())
+                Err(crate::Error::NotInPool)
             }
         }
     }
@@ -951,7 +960,7 @@ impl<T: PSafe + ?Sized, A: MemPool> Weak<T, A> {
     pub fn upgrade(&self, j: &Journal<A>) -> Option<Parc<T, A>> {
         let inner = self.inner()?;
 
-        let _lock = SpinLock::acquire(inner.counter.lock.as_mut());
+        let _lock = unsafe { SpinLock::acquire(inner.counter.lock.as_mut()) };
         let n = inner.counter.strong;
 
         if n == 0 {
@@ -1062,6 +1071,21 @@ impl<T: PSafe + ?Sized, A: MemPool> Drop for Weak<T, A> {
     }
 }
 
+impl<T: PSafe, A: MemPool> Trace<A> for Weak<T, A> {
+    /// Visits the allocation without traversing the value. The value is
+    /// either dropped or reachable through a strong reference.
+    #[inline]
+    fn trace<V: Visitor>(&self, v: &mut V) {
+        if !self.ptr.is_dangling() {
+            let counters = || {
+                let counter = &self.ptr.as_ref().counter;
+                (&counter.strong, &counter.weak)
+            };
+            v.visit_weak(self.ptr.off_ref(), mem::size_of::<ParcInner<T, A>>(), counters);
+        }
+    }
+}
+
 impl<T: PSafe + ?Sized, A: MemPool> PClone<A> for Weak<T, A> {
     #[inline]
     fn pclone(&self, j: &Journal<A>) -> Weak<T, A> {
@@ -1105,7 +1129,7 @@ trait ParcBoxPtr<T: PSafe + ?Sized, A: MemPool> {
 
 #[inline]
 fn load(lock: *mut u8, cnt: &usize) -> usize {
-    let _lock = SpinLock::acquire(lock);
+    let _lock = unsafe { SpinLock::acquire(lock) };
     *cnt
 }
 
@@ -1238,7 +1262,7 @@ fn data_offset_sized<T, A: MemPool>() -> isize {
 #[inline]
 fn data_offset_align<A: MemPool>(align: usize) -> isize {
     let layout = std::alloc::Layout::new::<ParcInner<(), A>>();
-    (layout.size() + layout.padding_needed_for(align)) as isize
+    (layout.size().div_ceil(align) * align) as isize
 }
 
 /// `VWeak` is a version of [`Parc`] that holds a non-owning thread-safe 
@@ -1322,7 +1346,7 @@ impl<T: PSafe + ?Sized, A: MemPool> VWeak<T, A> {
     pub fn promote(&self, j: &Journal<A>) -> Option<Parc<T, A>> {
         let inner = self.inner()?;
 
-        let _lock = SpinLock::acquire(inner.counter.lock.as_mut());
+        let _lock = unsafe { SpinLock::acquire(inner.counter.lock.as_mut()) };
         let n = inner.counter.strong;
 
         if n == 0 {
@@ -0,0 +1,400 @@
+use crate::alloc::MemPool;
+use crate::cell::VCell;
+use crate::ptr::Ptr;
+use crate::stm::{Journal, Log, Notifier, Logger};
+use crate::*;
+use std::cell::UnsafeCell;
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+use std::panic::{RefUnwindSafe, UnwindSafe};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+#[allow(unused_imports)]
+use std::fmt;
+
+/// A transaction-wide reader-writer lock
+///
+/// This type of lock allows a number of readers or at most one writer at any
+/// point in time. Readers do not need a journal; a [`ReadGuard`] is released
+/// as soon as it is dropped. The write portion of this lock behaves like a
+/// [`PMutex`]: it is recursive within the owner thread, and it is held until
+/// the transaction commits or rolls back. Therefore, readers in other threads
+/// never observe uncommitted data.
+///
+/// The lock state is kept in a [`VCell`], so a lock that was held at the time
+/// of a crash is free again after restart.
+///
+/// Taking a write lock in a thread which already holds a read guard of the
+/// same lock deadlocks, and taking a read lock while a write guard is alive in
+/// the same thread panics.
+///
+/// # Examples
+///
+/// ```
+/// use corundum::default::*;
+/// use std::thread;
+///
+/// type P = Allocator;
+///
+/// // PRwLock<T> = corundum::sync::PRwLock<T,P>
+/// let obj = P::open::<Parc<PRwLock<i32>>>("foo.pool", O_CF).unwrap();
+///
+/// let a = Parc::demote(&obj);
+/// thread::spawn(move || {
+///     transaction(|j| {
+///         if let Some(obj) = a.promote(j) {
+///             *obj.write(j) = 10;
+///         }
+///     }).unwrap();
+/// }).join().expect("thread::spawn failed");
+///
+/// // No transaction is required for reading
+/// assert_eq!(*obj.read(), 10);
+/// ```
+///
+/// [`PMutex`]: ./struct.PMutex.html
+/// [`VCell`]: ../cell/struct.VCell.html
+/// [`ReadGuard`]: ./struct.ReadGuard.html
+pub struct PRwLock<T, A: MemPool> {
+    heap: PhantomData<A>,
+    inner: VCell<RwLockInner, A>,
+    data: UnsafeCell<(u8, T)>,
+}
+
+struct RwLockInner {
+    borrowed: bool,
+    readers: AtomicUsize,
+
+    #[cfg(not(any(feature = "no_pthread", windows)))]
+    lock: (bool, libc::pthread_mutex_t, libc::pthread_mutexattr_t),
+
+    #[cfg(any(feature = "no_pthread", windows))]
+    lock: (bool, u64)
+}
+
+impl Default for RwLockInner {
+
+    #[cfg(not(any(feature = "no_pthread", windows)))]
+    fn default() -> Self {
+        use std::mem::MaybeUninit;
+        let mut attr = MaybeUninit::<libc::pthread_mutexattr_t>::uninit();
+        let mut lock = libc::PTHREAD_MUTEX_INITIALIZER;
+        unsafe { super::init_lock(&mut lock, attr.as_mut_ptr()); }
+        RwLockInner {
+            borrowed: false,
+            readers: AtomicUsize::new(0),
+            lock: (false, lock, unsafe { attr.assume_init() })
+        }
+    }
+
+    #[cfg(any(feature = "no_pthread", windows))]
+    fn default() -> Self {
+        RwLockInner { borrowed: false, readers: AtomicUsize::new(0), lock: (false, 0) }
+    }
+}
+
+impl RwLockInner {
+    /// Blocks until the lock is taken or `deadline` passes, and returns true
+    /// if it is taken
+    #[inline]
+    unsafe fn raw_lock_until(&self, deadline: Option<Instant>) -> bool {
+        let deadline = match deadline {
+            Some(deadline) => deadline,
+            None => {
+                self.raw_lock();
+                return true;
+            }
+        };
+        let lock = &self.lock.1 as *const _ as *mut _;
+
+        #[cfg(all(target_os = "linux", not(feature = "no_pthread")))]
+        let locked = super::timed_lock(lock, deadline);
+
+        #[cfg(all(not(target_os = "linux"), not(any(feature = "no_pthread", windows))))]
+        let locked = super::wait_until(Some(deadline), || libc::pthread_mutex_trylock(lock) == 0);
+
+        #[cfg(any(feature = "no_pthread", windows))]
+        let locked = {
+            let tid = std::thread::current().id().as_u64().get();
+            super::wait_until(Some(deadline), || {
+                let old = super::spin_cxchg(lock, tid);
+                old == 0 || old == tid
+            })
+        };
+
+        locked
+    }
+
+    #[inline]
+    unsafe fn raw_lock(&self) {
+        let lock = &self.lock.1 as *const _ as *mut _;
+        #[cfg(not(any(feature = "no_pthread", windows)))] {
+            libc::pthread_mutex_lock(lock);
+        }
+        #[cfg(any(feature = "no_pthread", windows))] {
+            let tid = std::thread::current().id().as_u64().get();
+            while super::spin_cxchg(lock, tid) != tid {}
+        }
+    }
+
+    #[inline]
+    unsafe fn raw_unlock(&self) {
+        let lock = &self.lock.1 as *const _ as *mut _;
+        #[cfg(not(any(feature = "no_pthread", windows)))]
+        libc::pthread_mutex_unlock(lock);
+
+        #[cfg(any(feature = "no_pthread", windows))]
+        super::spin_release(lock);
+    }
+
+    fn acquire(&self) -> bool {
+        if self.borrowed {
+            false
+        } else {
+            unsafe { utils::as_mut(self).borrowed = true; }
+            true
+        }
+    }
+
+    fn release(&self) {
+        unsafe { utils::as_mut(self).borrowed = false; }
+    }
+
+    /// Registers a new reader. It waits for the writer, if there is any in
+    /// another thread, to finish its transaction.
+    fn add_reader(&self) {
+        unsafe {
+            #[cfg(any(feature = "no_pthread", windows))] {
+                // The lock is not reentrant in this mode, so the owner thread
+                // should not take it again.
+                let tid = std::thread::current().id().as_u64().get();
+                let lock = &self.lock.1 as *const _ as *mut u64;
+                if super::spin_owner(lock) == tid {
+                    assert!(!self.borrowed, "Cannot read while the lock is mutably borrowed");
+                    self.readers.fetch_add(1, Ordering::AcqRel);
+                    return;
+                }
+            }
+            self.raw_lock();
+            if self.borrowed {
+                self.raw_unlock();
+                panic!("Cannot read while the lock is mutably borrowed");
+            }
+            self.readers.fetch_add(1, Ordering::AcqRel);
+            self.raw_unlock();
+        }
+    }
+
+    fn remove_reader(&self) {
+        self.readers.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+impl<T: ?Sized, A: MemPool> !TxOutSafe for PRwLock<T, A> {}
+impl<T, A: MemPool> UnwindSafe for PRwLock<T, A> {}
+impl<T, A: MemPool> RefUnwindSafe for PRwLock<T, A> {}
+
+unsafe impl<T, A: MemPool> TxInSafe for PRwLock<T, A> {}
+unsafe impl<T, A: MemPool> PSafe for PRwLock<T, A> {}
+unsafe impl<T: Send, A: MemPool> Send for PRwLock<T, A> {}
+unsafe impl<T: Send + Sync, A: MemPool> Sync for PRwLock<T, A> {}
+unsafe impl<T, A: MemPool> PSend for PRwLock<T, A> {}
+
+impl<T, A: MemPool> PRwLock<T, A> {
+    /// Creates a new `PRwLock`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use corundum::alloc::heap::*;
+    ///
+    /// Heap::transaction(|j| {
+    ///     let p = Parc::new(PRwLock::new(10), j);
+    /// }).unwrap();
+    /// ```
+    pub fn new(data: T) -> PRwLock<T, A> {
+        PRwLock {
+            heap: PhantomData,
+            inner: VCell::new(RwLockInner::default()),
+            data: UnsafeCell::new((0, data)),
+        }
+    }
+
+    /// Locks this lock with shared read access, blocking the current thread
+    /// until it can be acquired.
+    ///
+    /// If another transaction holds the write lock, this function waits for
+    /// it to commit or roll back. Multiple readers may hold the lock at the
+    /// same time. Unlike [`write`], it does not require a journal, and the
+    /// lock is released as soon as the returned guard is dropped.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the current thread holds a write guard of
+    /// this lock.
+    ///
+    /// [`write`]: #method.write
+    pub fn read(&self) -> ReadGuard<'_, T, A> {
+        self.inner.add_reader();
+        ReadGuard { lock: self }
+    }
+
+    /// Locks this lock with exclusive write access, blocking the current
+    /// thread until it can be acquired.
+    ///
+    /// It waits for the active readers to drop their guards and for the
+    /// writers of other transactions to finish. Similar to [`PMutex::lock`],
+    /// an [`UnlockOnCommit`] log is created to keep the lock until the
+    /// transaction is done. Further locking in the same transaction does not
+    /// block. If the transaction has a lock timeout, and the lock is not
+    /// acquired in time, the transaction is unwound the same way as
+    /// [`PMutex::lock`].
+    ///
+    /// # Panics
+    ///
+    /// This function panics if another write guard of this lock is alive.
+    ///
+    /// [`PMutex::lock`]: ./struct.PMutex.html#method.lock
+    /// [`UnlockOnCommit`]: ../stm/enum.LogEnum.html#variant.UnlockOnCommit
+    pub fn write<'a>(&'a self, journal: &'a Journal<A>) -> WriteGuard<'a, T, A> {
+        unsafe {
+            let deadline = crate::stm::lock_deadline();
+            if !self.inner.raw_lock_until(deadline) {
+                crate::stm::lock_timed_out();
+            }
+            if !self.inner.acquire() {
+                self.inner.raw_unlock();
+                panic!("Cannot have multiple instances of WriteGuard");
+            }
+            let readers = &self.inner.readers;
+            if !super::wait_until(deadline, || readers.load(Ordering::Acquire) == 0) {
+                self.inner.release();
+                self.inner.raw_unlock();
+                crate::stm::lock_timed_out();
+            }
+            Log::unlock_on_commit(&self.inner.lock as *const _ as u64, journal);
+            WriteGuard { lock: self, journal }
+        }
+    }
+}
+
+impl<T: PSafe, A: MemPool> PRwLock<T, A> {
+    #[inline]
+    #[allow(clippy::mut_from_ref)]
+    #[track_caller]
+    /// Takes a log and returns a `&mut T` for interior mutability
+    fn get_mut(&self, journal: &Journal<A>) -> &mut T {
+        unsafe {
+            let inner = &mut *self.data.get();
+            if inner.0 == 0 {
+                assert!(A::valid(inner), "The object is not in the pool's valid range");
+                inner.1.create_log(journal, Notifier::NonAtomic(Ptr::from_ref(&inner.0)));
+            }
+            &mut inner.1
+        }
+    }
+}
+
+impl<T: RootObj<A>, A: MemPool> RootObj<A> for PRwLock<T, A> {
+    fn init(journal: &Journal<A>) -> Self {
+        PRwLock::new(T::init(journal))
+    }
+}
+
+impl<T: Trace<A>, A: MemPool> Trace<A> for PRwLock<T, A> {
+    #[inline]
+    fn trace<V: Visitor>(&self, v: &mut V) {
+        unsafe { (*self.data.get()).1.trace(v) }
+    }
+}
+
+impl<T: fmt::Debug, A: MemPool> fmt::Debug for PRwLock<T, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.data.fmt(f)
+    }
+}
+
+/// RAII structure used to release the shared read access of a lock when
+/// dropped
+pub struct ReadGuard<'a, T: 'a, A: MemPool> {
+    lock: &'a PRwLock<T, A>,
+}
+
+impl<T: ?Sized, A: MemPool> !TxOutSafe for ReadGuard<'_, T, A> {}
+impl<T: ?Sized, A: MemPool> !Send for ReadGuard<'_, T, A> {}
+unsafe impl<T: Sync, A: MemPool> Sync for ReadGuard<'_, T, A> {}
+
+impl<T, A: MemPool> Deref for ReadGuard<'_, T, A> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &(*self.lock.data.get()).1 }
+    }
+}
+
+impl<T, A: MemPool> Drop for ReadGuard<'_, T, A> {
+    fn drop(&mut self) {
+        self.lock.inner.remove_reader()
+    }
+}
+
+impl<T: fmt::Debug, A: MemPool> fmt::Debug for ReadGuard<'_, T, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<T: fmt::Display, A: MemPool> fmt::Display for ReadGuard<'_, T, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        (**self).fmt(f)
+    }
+}
+
+/// RAII structure used to borrow the data of a write-locked [`PRwLock`]
+///
+/// Dropping the guard does not release the lock; it remains locked until
+/// the transaction is done.
+///
+/// [`PRwLock`]: ./struct.PRwLock.html
+pub struct WriteGuard<'a, T: 'a, A: MemPool> {
+    lock: &'a PRwLock<T, A>,
+    journal: *const Journal<A>,
+}
+
+impl<T: ?Sized, A: MemPool> !TxOutSafe for WriteGuard<'_, T, A> {}
+impl<T: ?Sized, A: MemPool> !Send for WriteGuard<'_, T, A> {}
+unsafe impl<T: Sync, A: MemPool> Sync for WriteGuard<'_, T, A> {}
+
+impl<T, A: MemPool> Deref for WriteGuard<'_, T, A> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &(*self.lock.data.get()).1 }
+    }
+}
+
+impl<T: PSafe, A: MemPool> DerefMut for WriteGuard<'_, T, A> {
+    #[track_caller]
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { self.lock.get_mut(&*self.journal) }
+    }
+}
+
+impl<T, A: MemPool> Drop for WriteGuard<'_, T, A> {
+    fn drop(&mut self) {
+        self.lock.inner.release()
+    }
+}
+
+impl<T: fmt::Debug, A: MemPool> fmt::Debug for WriteGuard<'_, T, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<T: fmt::Display, A: MemPool> fmt::Display for WriteGuard<'_, T, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        (**self).fmt(f)
+    }
+}
@@ -129,7 +129,7 @@ pub(crate) mod problems {
         }
 
         for thread in threads {
-            let _ = thread.join().unwrap();
+            thread.join().unwrap();
         }
         println!("Memory usage = {}", P::used());
     }
@@ -165,9 +165,8 @@ pub(crate) mod problems {
         fn print(n: &Node) {
             let t = n.next.borrow();
             print!("{} ", n.val);
-            match &*t {
-                Some(succ) => print(succ),
-                None => {}
+            if let Some(succ) = &*t {
+                print(succ);
             }
         }
 
@@ -195,7 +194,7 @@ pub(crate) mod problems {
             let a = Parc::new(42, j);
             let b = a.demote();
             std::thread::spawn(move || {
-                let _ = P::transaction(|j| {
+                P::transaction(|j| {
                     std::thread::sleep(Duration::from_millis(900));
                     if let Some(b) = b.promote(j) {
                         println!("Exit {}", *b);
@@ -650,6 +649,965 @@ pub(crate) mod test {
         t2.join().unwrap();
     }
 
+    #[test]
+    #[cfg(feature = "check_deadlock")]
+    fn test_mutex_deadlock_detection() {
+        use crate::cell::LazyCell;
+        use std::sync::Barrier;
+
+        // A static is not captured, so it can be used inside a transaction
+        static BARRIER: LazyCell<Barrier> = LazyCell::new(|| Barrier::new(2));
+
+        struct Root {
+            a: PMutex<u32, A>,
+            b: PMutex<u32, A>,
+        }
+
+        impl RootObj<A> for Root {
+            fn init(_j: &Journal<A>) -> Self {
+                Self {
+                    a: PMutex::new(0),
+                    b: PMutex::new(0),
+                }
+            }
+        }
+
+        let root = A::open::<Root>("test_mutex_deadlock_detection.pool", O_CF).unwrap();
+
+        let spawn = |first: bool| {
+            let root = root.clone();
+            thread::spawn(move || {
+                A::transaction(|j| {
+                    let (x, y) = if first { (&root.a, &root.b) } else { (&root.b, &root.a) };
+                    *x.lock(j) += 1;
+                    // Both threads hold their first lock before either
+                    // takes the second one
+                    BARRIER.wait();
+                    *y.lock(j) += 1;
+                }).is_ok()
+            })
+        };
+
+        let t1 = spawn(true);
+        let t2 = spawn(false);
+        let r1 = t1.join().unwrap();
+        let r2 = t2.join().unwrap();
+
+        // Exactly one of the transactions is rolled back
+        assert!(r1 ^ r2);
+        A::transaction(|j| {
+            assert_eq!(*root.a.lock(j), 1);
+            assert_eq!(*root.b.lock(j), 1);
+        }).unwrap();
+    }
+
+    #[test]
+    fn test_transaction_with_timeout() {
+        use crate::stm::TxOptions;
+        use std::time::Duration;
+
+        let root = A::open::<PMutex<u32, A>>("test_transaction_with.pool", O_CF).unwrap();
+        let init = A::transaction(|j| *root.lock(j)).unwrap();
+
+        let hold = |ms: u64| {
+            let root = root.clone();
+            let t = thread::spawn(move || {
+                A::transaction(|j| {
+                    *root.lock(j) += 1;
+                    thread::sleep(Duration::from_millis(ms));
+                }).unwrap()
+            });
+            thread::sleep(Duration::from_millis(50));
+            t
+        };
+
+        // The lock is busy during all attempts
+        let t = hold(500);
+        let opts = TxOptions::new()
+            .retries(2)
+            .backoff(Duration::from_millis(10))
+            .lock_timeout(Duration::from_millis(20));
+        assert!(A::transaction_with(opts, |j| *root.lock(j) += 10).is_err());
+        t.join().unwrap();
+
+        // A retry succeeds after the other transaction commits
+        let t = hold(200);
+        let opts = opts.retries(20);
+        let v = A::transaction_with(opts, |j| {
+            let mut v = root.lock(j);
+            *v += 10;
+            *v
+        }).unwrap();
+        t.join().unwrap();
+        assert_eq!(v, init + 12);
+
+        // Other failures are not retried
+        assert!(A::transaction_with(opts, |j| {
+            *root.lock(j) += 100;
+            panic!("abort");
+        }).is_err());
+        assert_eq!(A::transaction(|j| *root.lock(j)).unwrap(), init + 12);
+    }
+
+    #[test]
+    fn test_rwlock_write_timeout() {
+        use crate::stm::TxOptions;
+        use crate::sync::PRwLock;
+        use std::sync::mpsc::channel;
+        use std::time::{Duration, Instant};
+
+        let root = A::open::<PRwLock<u32, A>>("test_rwlock_timeout.pool", O_CF).unwrap();
+        let opts = TxOptions::new()
+            .retries(1)
+            .lock_timeout(Duration::from_millis(20));
+
+        // Another transaction holds the write lock
+        let (locked, wait) = channel();
+        let t = {
+            let root = root.clone();
+            let locked = AssertTxInSafe(locked);
+            thread::spawn(move || {
+                A::transaction(|j| {
+                    *root.write(j) += 1;
+                    locked.send(()).unwrap();
+                    thread::sleep(Duration::from_millis(300));
+                }).unwrap()
+            })
+        };
+        wait.recv().unwrap();
+        let start = Instant::now();
+        assert!(A::transaction_with(opts, |j| *root.write(j) += 10).is_err());
+        assert!(start.elapsed() < Duration::from_millis(250));
+        t.join().unwrap();
+
+        // A reader in another thread keeps the writers waiting
+        let (locked, wait) = channel();
+        let (done, release) = channel::<()>();
+        let t = {
+            let root = root.clone();
+            thread::spawn(move || {
+                let _r = root.read();
+                locked.send(()).unwrap();
+                release.recv().unwrap();
+            })
+        };
+        wait.recv().unwrap();
+        assert!(A::transaction_with(opts, |j| *root.write(j) += 10).is_err());
+        done.send(()).unwrap();
+        t.join().unwrap();
+
+        // The lock is not left taken after the timeouts
+        A::transaction_with(opts, |j| *root.write(j) += 10).unwrap();
+        assert_eq!(*root.read(), 11);
+    }
+
+    #[test]
+    fn test_condvar() {
+        use crate::sync::PCondvar;
+        use std::time::Duration;
+
+        struct Root {
+            items: PMutex<u64, A>,
+            ready: PCondvar<A>,
+        }
+
+        impl RootObj<A> for Root {
+            fn init(_j: &Journal<A>) -> Self {
+                Self {
+                    items: PMutex::new(0),
+                    ready: PCondvar::new(),
+                }
+            }
+        }
+
+        let root = A::open::<Root>("test_condvar.pool", O_CF).unwrap();
+
+        let consumer = {
+            let root = root.clone();
+            thread::spawn(move || {
+                let mut consumed = 0;
+                while consumed < 10 {
+                    consumed += A::transaction(|j| {
+                        let mut items = root.items.lock(j);
+                        while *items == 0 {
+                            items = root.ready.wait(items);
+                        }
+                        std::mem::replace(&mut *items, 0)
+                    }).unwrap();
+                }
+                consumed
+            })
+        };
+
+        for _ in 0..10 {
+            A::transaction(|j| {
+                *root.items.lock(j) += 1;
+                root.ready.notify_one(j);
+            }).unwrap();
+        }
+        assert_eq!(consumer.join().unwrap(), 10);
+
+        // Waiters are notified only if the notifying transaction commits
+        for commit in [false, true] {
+            let waiter = {
+                let root = root.clone();
+                thread::spawn(move || {
+                    A::transaction(|j| {
+                        let items = root.items.lock(j);
+                        root.ready.wait_timeout(items, Duration::from_millis(500)).1
+                    }).unwrap()
+                })
+            };
+            thread::sleep(Duration::from_millis(100));
+            let _ = A::transaction(|j| {
+                root.ready.notify_all(j);
+                if !commit {
+                    panic!("abort");
+                }
+            });
+            assert_eq!(waiter.join().unwrap(), !commit);
+        }
+    }
+
+    #[test]
+    fn test_condvar_unwind() {
+        use crate::stm::TxOptions;
+        use crate::sync::PCondvar;
+        use std::sync::mpsc::channel;
+        use std::time::Duration;
+
+        struct Root {
+            items: PMutex<u64, A>,
+            ready: PCondvar<A>,
+        }
+
+        impl RootObj<A> for Root {
+            fn init(_j: &Journal<A>) -> Self {
+                Self {
+                    items: PMutex::new(0),
+                    ready: PCondvar::new(),
+                }
+            }
+        }
+
+        let root = A::open::<Root>("test_condvar_unwind.pool", O_CF).unwrap();
+        let opts = TxOptions::new()
+            .retries(0)
+            .lock_timeout(Duration::from_millis(50));
+
+        // The waiter times out on reacquiring the mutex and unwinds
+        let (locked, wait) = channel();
+        let waiter = {
+            let root = root.clone();
+            let locked = AssertTxInSafe(locked);
+            thread::spawn(move || {
+                A::transaction_with(opts, |j| {
+                    let items = root.items.lock(j);
+                    locked.send(()).unwrap();
+                    root.ready.wait_timeout(items, Duration::from_millis(100));
+                }).is_err()
+            })
+        };
+        wait.recv().unwrap();
+        thread::sleep(Duration::from_millis(20));
+
+        let (locked, wait) = channel();
+        let holder = {
+            let root = root.clone();
+            let locked = AssertTxInSafe(locked);
+            thread::spawn(move || {
+                A::transaction(|j| {
+                    *root.items.lock(j) += 1;
+                    locked.send(()).unwrap();
+                    thread::sleep(Duration::from_millis(400));
+                }).unwrap()
+            })
+        };
+        wait.recv().unwrap();
+        assert!(waiter.join().unwrap());
+
+        // Rolling back the waiter does not unlock the mutex of the holder
+        assert!(A::transaction_with(opts, |j| *root.items.lock(j) += 10).is_err());
+        holder.join().unwrap();
+        A::transaction_with(opts, |j| *root.items.lock(j) += 10).unwrap();
+        assert_eq!(A::transaction(|j| *root.items.lock(j)).unwrap(), 11);
+    }
+
+    #[test]
+    fn test_rwlock() {
+        use crate::sync::PRwLock;
+
+        let root = A::open::<PRwLock<(u64, u64), A>>("test_rwlock.pool", O_CF).unwrap();
+
+        let mut threads = vec![];
+        for _ in 0..4 {
+            let root = root.clone();
+            threads.push(thread::spawn(move || {
+                for _ in 0..50 {
+                    A::transaction(|j| {
+                        let mut w = root.write(j);
+                        w.0 += 1;
+                        w.1 += 1;
+                    }).unwrap();
+                }
+            }));
+        }
+        for _ in 0..4 {
+            let root = root.clone();
+            threads.push(thread::spawn(move || {
+                for _ in 0..200 {
+                    let r = root.read();
+                    assert_eq!(r.0, r.1);
+                }
+            }));
+        }
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        let (a, b) = *root.read();
+        assert_eq!(a, b);
+
+        // The write lock is released and the data is restored on rollback
+        let _ = A::transaction(|j| {
+            let mut w = root.write(j);
+            *w = (0, 1);
+            panic!("abort");
+        });
+        assert_eq!(*root.read(), (a, b));
+
+        A::transaction(|j| {
+            root.write(j).0 += 1;
+            assert_eq!(root.read().0, a + 1);
+            root.write(j).1 += 1;
+        }).unwrap();
+        assert_eq!(*root.read(), (a + 1, b + 1));
+    }
+
+    #[test]
+    fn test_atomic() {
+        use crate::sync::{PAtomicU64, PAtomicPtr};
+        use std::sync::atomic::Ordering::*;
+
+        struct Root {
+            count: PAtomicU64<A>,
+            ptr: PAtomicPtr<PAtomicU64<A>, A>,
+        }
+
+        impl RootObj<A> for Root {
+            fn init(_j: &Journal<A>) -> Self {
+                Self {
+                    count: PAtomicU64::default(),
+                    ptr: PAtomicPtr::null(),
+                }
+            }
+        }
+
+        let root = A::open::<Root>("test_atomic.pool", O_CF).unwrap();
+        let init = root.count.load(Acquire);
+
+        let mut threads = vec![];
+        for _ in 0..4 {
+            let root = root.clone();
+            threads.push(thread::spawn(move || {
+                for _ in 0..100 {
+                    root.count.fetch_add(1, AcqRel);
+                }
+            }));
+        }
+        for thread in threads {
+            thread.join().unwrap();
+        }
+        assert_eq!(root.count.load(Acquire), init + 400);
+        assert_eq!(root.count.load_durable(Acquire), init + 400);
+
+        assert_eq!(root.count.compare_exchange(init, 0, AcqRel, Acquire), Err(init + 400));
+        assert_eq!(root.count.compare_exchange(init + 400, init + 1, AcqRel, Acquire), Ok(init + 400));
+
+        // Atomic updates are not rolled back with the transaction
+        let _ = A::transaction(|_| {
+            root.count.fetch_sub(1, AcqRel);
+            panic!("abort");
+        });
+        assert_eq!(root.count.load(Acquire), init);
+
+        let p = &root.count as *const _ as *mut PAtomicU64<A>;
+        if !root.ptr.is_null(Acquire) {
+            assert_eq!(root.ptr.load(Acquire), p);
+        }
+        root.ptr.store(std::ptr::null_mut(), Release);
+        assert!(root.ptr.load(Acquire).is_null());
+        assert_eq!(root.ptr.compare_exchange(std::ptr::null_mut(), p, AcqRel, Acquire),
+            Ok(std::ptr::null_mut()));
+        assert_eq!(root.ptr.load_durable(Acquire), p);
+        assert_eq!(unsafe { (*root.ptr.load(Acquire)).load(Acquire) }, init);
+    }
+
+    #[test]
+    fn test_read_transaction() {
+        use crate::cell::VersionedCell;
+
+        struct Root {
+            a: VersionedCell<i32, A>,
+            b: VersionedCell<i32, A>,
+        }
+
+        impl RootObj<A> for Root {
+            fn init(j: &Journal<A>) -> Self {
+                Self {
+                    a: VersionedCell::new(0, j),
+                    b: VersionedCell::new(0, j),
+                }
+            }
+        }
+
+        let root = A::open::<Root>("test_read_transaction.pool", O_CF).unwrap();
+        let init = A::read_transaction(|s| *root.a.read(s).unwrap()).unwrap();
+
+        A::transaction(|j| {
+            root.a.set(init + 1, j);
+            root.b.set(init + 1, j);
+        }).unwrap();
+
+        A::read_transaction(|s| {
+            assert_eq!(*root.a.read(s).unwrap(), init + 1);
+            assert_eq!(*root.b.read(s).unwrap(), init + 1);
+
+            let writer = root.clone();
+            thread::spawn(move || A::transaction(|j| {
+                writer.a.update(|v| v + 1, j);
+                writer.b.update(|v| v + 1, j);
+            }).unwrap()).join().unwrap();
+
+            // The snapshot does not observe the later commits
+            assert_eq!(*root.a.read(s).unwrap(), init + 1);
+            assert_eq!(*root.b.read(s).unwrap(), init + 1);
+        }).unwrap();
+
+        A::read_transaction(|s| {
+            assert_eq!(*root.a.read(s).unwrap(), init + 2);
+            assert_eq!(*root.b.read(s).unwrap(), init + 2);
+        }).unwrap();
+
+        // Aborted writes are never visible
+        let _ = A::transaction(|j| {
+            root.a.set(-1, j);
+            panic!("abort");
+        });
+        A::read_transaction(|s| assert_eq!(*root.a.read(s).unwrap(), init + 2)).unwrap();
+
+        // Without active snapshots, old versions are reclaimed
+        for _ in 0..10 {
+            A::transaction(|j| root.a.update(|v| v + 1, j)).unwrap();
+        }
+        assert!(root.a.versions() <= 2);
+        A::read_transaction(|s| assert_eq!(*root.a.read(s).unwrap(), init + 12)).unwrap();
+    }
+
+    #[test]
+    fn test_read_transaction_vec() {
+        use crate::cell::VersionedCell;
+        use crate::clone::PClone;
+        use crate::vec::Vec as PVec;
+
+        type Root = VersionedCell<PVec<u64, A>, A>;
+
+        let root = A::open::<Root>("test_read_transaction_vec.pool", O_CF).unwrap();
+        let push = |n: u64| A::transaction(|j| {
+            root.update(|v| {
+                let mut v = v.pclone(j);
+                for i in 0..n {
+                    v.push(i, j);
+                }
+                v
+            }, j)
+        }).unwrap();
+        push(10);
+
+        // A scan of the vector is not affected by the concurrent commits
+        A::read_transaction(|s| {
+            let v = root.read(s).unwrap();
+            let len = v.len();
+            let sum: u64 = v.iter().sum();
+
+            A::transaction(|j| {
+                root.update(|v| {
+                    let mut v = v.pclone(j);
+                    v.clear();
+                    v
+                }, j)
+            }).unwrap();
+
+            let v = root.read(s).unwrap();
+            assert_eq!(v.len(), len);
+            assert_eq!(v.iter().sum::<u64>(), sum);
+        }).unwrap();
+
+        A::read_transaction(|s| assert!(root.read(s).unwrap().is_empty())).unwrap();
+        push(4);
+        A::read_transaction(|s| assert_eq!(root.read(s).unwrap().as_slice(), &[0, 1, 2, 3])).unwrap();
+    }
+
+    #[test]
+    fn test_redo_log() {
+        use crate::cell::{PCell, PRefCell};
+
+        struct Root {
+            a: PCell<i32, A>,
+            b: PRefCell<[i32; 4], A>,
+        }
+
+        impl RootObj<A> for Root {
+            fn init(_j: &Journal<A>) -> Self {
+                Self {
+                    a: PCell::new(0),
+                    b: PRefCell::new([0; 4]),
+                }
+            }
+        }
+
+        let root = A::open::<Root>("test_redo_log.pool", O_CF).unwrap();
+        let redo = || TxOptions::new().log_mode(LogMode::Redo);
+
+        A::transaction_with(redo(), |j| {
+            assert_eq!(j.log_mode(), LogMode::Redo);
+            for i in 1..=10 {
+                root.a.set(i, j);
+                assert_eq!(root.a.get(), i);
+            }
+            let mut b = root.b.borrow_mut(j);
+            b[0] = 1;
+            b[3] = 4;
+            b[0] += 1;
+        }).unwrap();
+        assert_eq!(root.a.get(), 10);
+        assert_eq!(*root.b.borrow(), [2, 0, 0, 4]);
+
+        // Aborted drafts are discarded
+        let _ = A::transaction_with(redo(), |j| {
+            root.a.set(-1, j);
+            root.b.borrow_mut(j)[1] = -1;
+            panic!("abort");
+        });
+        assert_eq!(root.a.get(), 10);
+        assert_eq!(*root.b.borrow(), [2, 0, 0, 4]);
+
+        // The undo mode works on the same data afterwards
+        let _ = A::transaction(|j| {
+            assert_eq!(j.log_mode(), LogMode::Undo);
+            root.a.set(-1, j);
+            panic!("abort");
+        });
+        assert_eq!(root.a.get(), 10);
+
+        // A nested transaction follows the mode of the outer one
+        A::transaction(|j| {
+            A::transaction_with(redo(), |j| {
+                assert_eq!(j.log_mode(), LogMode::Undo);
+            }).unwrap();
+            root.a.set(11, j);
+        }).unwrap();
+        assert_eq!(root.a.get(), 11);
+    }
+
+    #[test]
+    #[cfg(not(any(feature = "use_pspd", feature = "use_vspd")))]
+    fn test_redo_nested() {
+        use crate::boxed::Pbox;
+        use crate::cell::PRefCell;
+        use crate::stl::HashMap;
+        use crate::vec::Vec as PVec;
+
+        struct Root {
+            vec: PRefCell<PVec<u64, A>, A>,
+            boxed: PRefCell<Pbox<u64, A>, A>,
+            map: PRefCell<HashMap<u64, u64, A>, A>,
+        }
+
+        impl RootObj<A> for Root {
+            fn init(j: &Journal<A>) -> Self {
+                Self {
+                    vec: PRefCell::new(PVec::new()),
+                    boxed: PRefCell::new(Pbox::new(0, j)),
+                    map: PRefCell::new(HashMap::new(j)),
+                }
+            }
+        }
+
+        let root = A::open::<Root>("test_redo_nested.pool", O_CF | O_REDO).unwrap();
+        let check = |n: u64| {
+            let vec = root.vec.borrow();
+            assert_eq!(vec.len() as u64, n);
+            assert!(vec.iter().enumerate().all(|(i, v)| *v == i as u64 * 10));
+            assert_eq!(**root.boxed.borrow(), n);
+            let map = root.map.borrow();
+            assert_eq!(map.len() as u64, n);
+            assert!((0..n).all(|i| map.get(&i) == Some(&(i * 10))));
+        };
+
+        A::transaction(|j| {
+            assert_eq!(j.log_mode(), LogMode::Redo);
+            let mut vec = root.vec.borrow_mut(j);
+            vec.clear();
+            for i in 0..20 {
+                vec.push(i, j);
+            }
+            for v in vec.as_slice_mut(j) {
+                *v *= 10;
+            }
+            let mut boxed = root.boxed.borrow_mut(j);
+            **boxed = 0;
+            for _ in 0..20 {
+                **boxed += 1;
+            }
+            let mut map = root.map.borrow_mut(j);
+            map.clear(j);
+            for i in 0..20 {
+                map.insert(i, i * 10, j);
+            }
+        }).unwrap();
+        check(20);
+
+        // The nested objects are restored with the aborted drafts
+        let _ = A::transaction(|j| {
+            let mut vec = root.vec.borrow_mut(j);
+            vec.as_slice_mut(j)[0] = 1;
+            for i in 20..40 {
+                vec.push(i, j);
+            }
+            **root.boxed.borrow_mut(j) = 100;
+            let mut map = root.map.borrow_mut(j);
+            for i in 0..40 {
+                map.insert(i, 0, j);
+            }
+            panic!("abort");
+        });
+        check(20);
+
+        // The log flags are not left set for the undo transactions
+        let _ = A::transaction_with(TxOptions::new().log_mode(LogMode::Undo), |j| {
+            root.vec.borrow_mut(j).as_slice_mut(j)[0] = 1;
+            **root.boxed.borrow_mut(j) = 100;
+            root.map.borrow_mut(j).insert(0, 1, j);
+            panic!("abort");
+        });
+        check(20);
+    }
+
+    /// Runs the ignored test `name` of this module in a child process with
+    /// the environment variables `envs`, and returns its standard error
+    ///
+    /// The child runs a phase of a crash test, which is selected by `envs`.
+    /// It panics if the child fails.
+    fn run_child(name: &str, envs: &[(&str, &str)]) -> String {
+        let out = std::process::Command::new(std::env::current_exe().unwrap())
+            .args([&format!("tests::test::{}", name), "--exact", "--ignored", "--nocapture"])
+            .env_remove("CRASH_AT")
+            .envs(envs.iter().cloned())
+            .output()
+            .unwrap();
+        let stderr = String::from_utf8_lossy(&out.stderr).to_string();
+        assert!(out.status.success(), "{}", stderr);
+        stderr
+    }
+
+    /// Runs the ignored test `name` in a child process which crashes at the
+    /// persist point `point`, and checks that it crashed there
+    fn crash_child(name: &str, point: &str, envs: &[(&str, &str)]) {
+        let mut envs = envs.to_vec();
+        envs.push(("CRASH_AT", point));
+        let stderr = run_child(name, &envs);
+        assert!(stderr.contains(&format!("Crashed at `{}`", point)), "{}", stderr);
+    }
+
+    /// Runs a redo transaction that crashes at the point given in `CRASH_AT`
+    /// in the `run` phase, and checks the recovered data in the `check` phase
+    ///
+    /// It is executed in a child process by `redo_crash_recovery`.
+    #[test]
+    #[ignore]
+    #[cfg(not(any(feature = "use_pspd", feature = "use_vspd")))]
+    fn redo_crash_child() {
+        crate::pool!(pool1, P1);
+        type P1 = pool1::P1;
+
+        struct Root {
+            a: pool1::PCell<i32>,
+            b: pool1::PRefCell<[i32; 4]>,
+        }
+
+        impl RootObj<P1> for Root {
+            fn init(_j: &Journal<P1>) -> Self {
+                Self {
+                    a: pool1::PCell::new(0),
+                    b: pool1::PRefCell::new([0; 4]),
+                }
+            }
+        }
+
+        let phase = match std::env::var("REDO_PHASE") {
+            Ok(phase) => phase,
+            Err(_) => return,
+        };
+
+        if phase == "run" {
+            let root = P1::open::<Root>("redo_crash.pool", O_CF | O_REDO).unwrap();
+            let undo = TxOptions::new().log_mode(LogMode::Undo);
+            P1::transaction_with(undo, |j| {
+                assert_eq!(j.log_mode(), LogMode::Undo);
+                root.a.set(1, j);
+                *root.b.borrow_mut(j) = [1; 4];
+            }).unwrap();
+            let _ = P1::transaction(|j| {
+                assert_eq!(j.log_mode(), LogMode::Redo);
+                root.a.set(2, j);
+                *root.b.borrow_mut(j) = [2; 4];
+            });
+            panic!("the transaction did not crash");
+        } else {
+            let expected = match std::env::var("REDO_EXPECT").as_deref() {
+                Ok("commit") => 2,
+                _ => 1,
+            };
+            let root = P1::open::<Root>("redo_crash.pool", O_CFNE).unwrap();
+            assert_eq!(root.a.get(), expected);
+            assert_eq!(*root.b.borrow(), [expected; 4]);
+
+            // The log flags are reset by the recovery
+            let _ = P1::transaction(|j| {
+                root.a.set(0, j);
+                root.b.borrow_mut(j)[0] = 0;
+                panic!("abort");
+            });
+            assert_eq!(root.a.get(), expected);
+            assert_eq!(*root.b.borrow(), [expected; 4]);
+        }
+    }
+
+    #[test]
+    #[cfg(not(any(feature = "use_pspd", feature = "use_vspd")))]
+    fn redo_crash_recovery() {
+        for (point, expect) in [
+            ("redo::persist", "abort"),
+            ("redo::apply", "commit"),
+        ] {
+            crash_child("redo_crash_child", point, &[("REDO_PHASE", "run")]);
+            run_child("redo_crash_child", &[("REDO_PHASE", "check"), ("REDO_EXPECT", expect)]);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "crash_test")]
+    fn test_crash_injection() {
+        use crate::crash::CrashTest;
+
+        crate::pool!(pool1, P1);
+        type P1 = pool1::P1;
+
+        struct Root {
+            checking: pool1::PCell<i32>,
+            savings: pool1::PCell<i32>,
+        }
+
+        impl RootObj<P1> for Root {
+            fn init(_j: &Journal<P1>) -> Self {
+                Self {
+                    checking: pool1::PCell::new(100),
+                    savings: pool1::PCell::new(0),
+                }
+            }
+        }
+
+        let check = |root: &Root| {
+            assert_eq!(root.checking.get() + root.savings.get(), 100);
+        };
+
+        // A transfer in a single transaction is crash-consistent
+        let points = CrashTest::new("crash_injection.pool").run::<P1, Root, _, _>(|root| {
+            P1::transaction(|j| {
+                root.checking.set(root.checking.get() - 10, j);
+                root.savings.set(root.savings.get() + 10, j);
+            }).unwrap();
+        }, check).unwrap();
+        assert!(points > 0);
+
+        // A transfer in two transactions is not
+        let split = |root: &Root| {
+            P1::transaction(|j| root.checking.set(root.checking.get() - 10, j)).unwrap();
+            P1::transaction(|j| root.savings.set(root.savings.get() + 10, j)).unwrap();
+        };
+        let point = match CrashTest::new("crash_injection.pool")
+            .run::<P1, Root, _, _>(split, check) {
+            Err(crate::Error::Inconsistent { point, .. }) => point,
+            res => panic!("unexpected result: {:?}", res),
+        };
+        let n = point.expect("the final state should be consistent");
+
+        // The failing point is reproducible
+        assert!(CrashTest::new("crash_injection.pool").at(n)
+            .run::<P1, Root, _, _>(split, check).is_err());
+        if n > 1 {
+            assert!(CrashTest::new("crash_injection.pool").at(n - 1)
+                .run::<P1, Root, _, _>(split, check).is_ok());
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "crash_test")]
+    fn test_crash_grow() {
+        use crate::crash::CrashTest;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        crate::pool!(pool1, P1);
+        type P1 = pool1::P1;
+
+        static SIZE: AtomicUsize = AtomicUsize::new(0);
+
+        // The pool either keeps its size or reaches the new one
+        let points = CrashTest::new("crash_grow.pool").run::<P1, pool1::PCell<i32>, _, _>(|_| {
+            SIZE.store(P1::size(), Ordering::Release);
+            P1::grow(P1::size() + 3 * 1024 * 1024 + 88).unwrap();
+        }, |_| {
+            let size = SIZE.load(Ordering::Acquire);
+            assert!(P1::size() == size || P1::size() == size + 3 * 1024 * 1024 + 88,
+                "unexpected size {} (initial size = {})", P1::size(), size);
+            assert!(P1::verify());
+        }).unwrap();
+        assert!(points > 0);
+    }
+
+    #[test]
+    #[cfg(any(feature = "use_pspd", feature = "use_vspd"))]
+    fn test_scratchpad() {
+        crate::pool!(pool2, P2);
+        type P2 = pool2::P2;
+
+        struct Root {
+            a: pool2::PCell<i32>,
+            b: pool2::PRefCell<[u64; 32]>,
+            c: pool2::PVec<pool2::PCell<i32>>,
+        }
+
+        impl RootObj<P2> for Root {
+            fn init(j: &Journal<P2>) -> Self {
+                let mut c = pool2::PVec::with_capacity(16, j);
+                for _ in 0..16 {
+                    c.push(pool2::PCell::new(0), j);
+                }
+                Self {
+                    a: pool2::PCell::new(0),
+                    b: pool2::PRefCell::new([0; 32]),
+                    c,
+                }
+            }
+        }
+
+        let root = P2::open::<Root>("test_scratchpad.pool", O_CF).unwrap();
+        let used = P2::used();
+
+        P2::transaction(|j| {
+            for i in 1..=10 {
+                root.a.set(i, j);
+                assert_eq!(root.a.get(), i);
+            }
+            let mut b = root.b.borrow_mut(j);
+            b[0] = 1;
+            b[31] = 31;
+        }).unwrap();
+        assert_eq!(root.a.get(), 10);
+        assert_eq!(root.b.borrow()[0], 1);
+        assert_eq!(root.b.borrow()[31], 31);
+        assert_eq!(P2::used(), used);
+
+        // Aborted copies are discarded
+        let _ = P2::transaction(|j| {
+            root.a.set(-1, j);
+            root.b.borrow_mut(j)[1] = 1;
+            panic!("abort");
+        });
+        assert_eq!(root.a.get(), 10);
+        assert_eq!(root.b.borrow()[1], 0);
+        assert_eq!(P2::used(), used);
+
+        // Small pages make the scratchpad grow
+        P2::transaction_with(TxOptions::new().scratchpad_size(64), |j| {
+            for (i, c) in root.c.iter().enumerate() {
+                c.set(i as i32, j);
+            }
+            root.b.borrow_mut(j)[2] = 2;
+        }).unwrap();
+        for (i, c) in root.c.iter().enumerate() {
+            assert_eq!(c.get(), i as i32);
+        }
+        assert_eq!(root.b.borrow()[2], 2);
+        assert_eq!(P2::used(), used);
+    }
+
+    /// Runs a transaction that crashes at the point given in `CRASH_AT` in
+    /// the `run` phase, and checks the recovered data in the `check` phase
+    ///
+    /// It is executed in a child process by `spd_crash_recovery`.
+    #[test]
+    #[ignore]
+    #[cfg(any(feature = "use_pspd", feature = "use_vspd"))]
+    fn spd_crash_child() {
+        crate::pool!(pool1, P1);
+        type P1 = pool1::P1;
+
+        struct Root {
+            a: pool1::PCell<i32>,
+            b: pool1::PRefCell<[i32; 4]>,
+        }
+
+        impl RootObj<P1> for Root {
+            fn init(_j: &Journal<P1>) -> Self {
+                Self {
+                    a: pool1::PCell::new(1),
+                    b: pool1::PRefCell::new([1; 4]),
+                }
+            }
+        }
+
+        let phase = match std::env::var("SPD_PHASE") {
+            Ok(phase) => phase,
+            Err(_) => return,
+        };
+
+        if phase == "run" {
+            let root = P1::open::<Root>("spd_crash.pool", O_CF).unwrap();
+            let _ = P1::transaction(|j| {
+                root.a.set(2, j);
+                *root.b.borrow_mut(j) = [2; 4];
+            });
+            panic!("the transaction did not crash");
+        } else {
+            let expected = match std::env::var("SPD_EXPECT").as_deref() {
+                Ok("commit") => 2,
+                _ => 1,
+            };
+            let root = P1::open::<Root>("spd_crash.pool", O_CFNE).unwrap();
+            assert_eq!(root.a.get(), expected);
+            assert_eq!(*root.b.borrow(), [expected; 4]);
+
+            // The scratchpad is released by the recovery
+            P1::transaction(|j| root.a.set(3, j)).unwrap();
+            assert_eq!(root.a.get(), 3);
+            assert_eq!(*root.b.borrow(), [expected; 4]);
+        }
+    }
+
+    #[test]
+    #[cfg(any(feature = "use_pspd", feature = "use_vspd"))]
+    fn spd_crash_recovery() {
+        for (point, expect) in [
+            ("spd::seal", "abort"),
+            ("spd::apply", "commit"),
+        ] {
+            crash_child("spd_crash_child", point, &[("SPD_PHASE", "run")]);
+            run_child("spd_crash_child", &[("SPD_PHASE", "check"), ("SPD_EXPECT", expect)]);
+        }
+    }
+
     #[test]
     // #[ignore]
     fn test_string_mt() {
@@ -823,6 +1781,159 @@ pub(crate) mod test {
         }
     }
 
+    #[test]
+    fn test_read_info() {
+        crate::pool!(pool1, P1);
+        crate::pool!(pool2, P2);
+
+        type P1 = pool1::P1;
+        type P2 = pool2::P2;
+
+        let used = {
+            let root = P1::open::<pool1::PCell<i32>>("read_info.pool", O_CF).unwrap();
+            P1::transaction(|j| root.set(1, j)).unwrap();
+            P1::used()
+        };
+        let image = std::fs::read("read_info.pool").unwrap();
+
+        // The recovery releases the pinned journals, if any. The usage after
+        // that is obtained by recovering the image with its own pool type.
+        let recovered = {
+            let _pool = P1::open_no_root("read_info.pool", 0).unwrap();
+            P1::used()
+        };
+        #[cfg(not(feature = "pin_journals"))]
+        assert_eq!(recovered, used);
+        std::fs::write("read_info.pool", &image).unwrap();
+
+        // Another pool type can only open the file to read info or to
+        // recover it knowing the pool type which created it
+        assert!(P2::open_no_root("read_info.pool", 0).is_err());
+        assert!(P2::open_no_root("read_info.pool", O_REPAIR).is_err());
+        {
+            let _pool = P2::open_no_root("read_info.pool", O_READINFO).unwrap();
+            assert_eq!(P2::used(), used);
+            assert!(P2::verify());
+            assert!(P2::grow(P2::size() * 2).is_err());
+        }
+        assert!(std::fs::read("read_info.pool").unwrap() == image);
+
+        let pool1 = std::any::type_name::<P1>().rsplit_once("::").unwrap().0;
+        assert!(matches!(P2::open_repair("read_info.pool", "corundum::default"),
+            Err(crate::Error::InvalidPool(_))));
+
+        // The header should be within the bounds of the file
+        let mut corrupted = image.clone();
+        corrupted[48..56].copy_from_slice(&u64::MAX.to_ne_bytes());
+        std::fs::write("read_info.pool", &corrupted).unwrap();
+        assert!(matches!(P2::open_repair("read_info.pool", pool1),
+            Err(crate::Error::InvalidPool(_))));
+        assert!(std::fs::read("read_info.pool").unwrap() == corrupted);
+        std::fs::write("read_info.pool", &image).unwrap();
+
+        let _pool = P2::open_repair("read_info.pool", pool1).unwrap();
+        assert_eq!(P2::used(), recovered);
+        assert!(P2::verify());
+    }
+
+    /// Swaps the values of two pools in a chaperoned session which crashes at
+    /// `CRASH_AT`, or checks the state of the pools after recovery
+    ///
+    /// It is executed in a child process by `chaperon_crash_recovery`.
+    #[test]
+    #[ignore]
+    fn chaperon_crash_child() {
+        use std::path::Path;
+
+        crate::pool!(pool1, P1);
+        crate::pool!(pool2, P2);
+
+        type P1 = pool1::P1;
+        type P2 = pool2::P2;
+
+        let phase = match std::env::var("CHAPERON_PHASE") {
+            Ok(phase) => phase,
+            Err(_) => return,
+        };
+
+        if phase == "run" {
+            let _ = std::fs::remove_file("crash.chaperon");
+            let root1 = P1::open::<pool1::PCell<i32>>("crash1.pool", O_CF).unwrap();
+            let root2 = P2::open::<pool2::PCell<i32>>("crash2.pool", O_CF).unwrap();
+            P1::transaction(|j| root1.set(10, j)).unwrap();
+            P2::transaction(|j| root2.set(20, j)).unwrap();
+
+            let abort = std::env::var("CHAPERON_ABORT").is_ok();
+            let _ = Chaperon::session("crash.chaperon", || {
+                let old = P1::transaction(|j| {
+                    let old = root1.get();
+                    root1.set(root2.get(), j);
+                    old
+                }).unwrap();
+                P2::transaction(|j| root2.set(old, j)).unwrap();
+                if abort {
+                    panic!("abort");
+                }
+            });
+            panic!("the session did not crash");
+        } else if phase == "missing" {
+            // The decision is lost with the chaperon file; the pool should not
+            // be opened with an unknown state
+            std::fs::remove_file("crash.chaperon").unwrap();
+            match P1::open::<pool1::PCell<i32>>("crash1.pool", O_CFNE) {
+                Err(crate::Error::Chaperon { .. }) => {}
+                Err(e) => panic!("unexpected error: {}", e),
+                Ok(_) => panic!("the pool was opened without the chaperon file"),
+            }
+        } else {
+            let expected = match std::env::var("CHAPERON_EXPECT").as_deref() {
+                Ok("commit") => (20, 10),
+                _ => (10, 20),
+            };
+
+            let root1 = P1::open::<pool1::PCell<i32>>("crash1.pool", O_CFNE).unwrap();
+            assert_eq!(root1.get(), expected.0);
+
+            // The second pool is not recovered yet
+            assert!(Path::new("crash.chaperon").exists());
+            assert!(Chaperon::session("crash.chaperon", || {}).is_err());
+
+            let root2 = P2::open::<pool2::PCell<i32>>("crash2.pool", O_CFNE).unwrap();
+            assert_eq!(root2.get(), expected.1);
+            assert!(!Path::new("crash.chaperon").exists());
+
+            Chaperon::session("crash.chaperon", || {
+                P1::transaction(|j| root1.set(expected.1, j)).unwrap();
+                P2::transaction(|j| root2.set(expected.0, j)).unwrap();
+            }).unwrap();
+            assert_eq!((root1.get(), root2.get()), (expected.1, expected.0));
+            assert!(!Path::new("crash.chaperon").exists());
+        }
+    }
+
+    #[test]
+    fn chaperon_crash_recovery() {
+        let child = "chaperon_crash_child";
+        for (point, abort, expect) in [
+            ("chaperon::prepare", false, "abort"),
+            ("chaperon::commit_point", false, "commit"),
+            ("chaperon::commit", false, "commit"),
+            ("chaperon::clear", false, "commit"),
+            ("chaperon::rollback", true, "abort"),
+            ("chaperon::clear", true, "abort"),
+        ] {
+            let mut envs = vec![("CHAPERON_PHASE", "run")];
+            if abort {
+                envs.push(("CHAPERON_ABORT", "1"));
+            }
+            crash_child(child, point, &envs);
+            run_child(child, &[("CHAPERON_PHASE", "check"), ("CHAPERON_EXPECT", expect)]);
+        }
+
+        crash_child(child, "chaperon::commit_point", &[("CHAPERON_PHASE", "run")]);
+        run_child(child, &[("CHAPERON_PHASE", "missing")]);
+    }
+
     #[test]
     fn concat_test() {
         use crate::default::*;
@@ -879,9 +1990,9 @@ pub(crate) mod test {
                     self.next = Some(Prc::new(PRefCell::new(Cell::new(v)), j));
                 }
             }
-            pub fn sum(&self, j: &Journal) -> i32 {
+            pub fn sum(&self, _j: &Journal) -> i32 {
                 if let Some(next) = &self.next {
-                    self.k + next.borrow().sum(j)
+                    self.k + next.borrow().sum(_j)
                 } else {
                     self.k
                 }
@@ -946,7 +2057,7 @@ pub(crate) mod test {
                 }
                 *self.next.borrow_mut(j) = Some(Prc::new(Cell::new(v), j));
             }
-            pub fn sum(&self, j: &Journal<A>) -> i32 {
+            pub fn sum(&self, _j: &Journal<A>) -> i32 {
                 let mut k = if let Some(g) = &*self.k.borrow() {
                     **g
                 } else {
@@ -959,7 +2070,7 @@ pub(crate) mod test {
                     k = 0;
                 };
                 if let Some(next) = &*self.next.borrow() {
-                    k + next.sum(j)
+                    k + next.sum(_j)
                 } else {
                     k
                 }
@@ -1225,7 +2336,7 @@ pub(crate) mod test {
                     println!("adding new element {}", value);
                     let new = Prc::new(PRefCell::new(Node::new(value)),j);
                     if let Some(root) = &sb.next {
-                        let mut curr = Prc::downgrade(&root, j);
+                        let mut curr = Prc::downgrade(root, j);
                         let mut last = PWeak::<PRefCell<Node<i32>>>::new();
                         let mut added = false;
                         while let Some(pnode) = curr.upgrade(j) {
@@ -1233,7 +2344,7 @@ pub(crate) mod test {
                             if node.value > value {
                                 let mut new_node = (*new).borrow_mut(j);
                                 let mut element = node;
-                                if rand() % 2 == 0 {
+                                if rand().is_multiple_of(2) {
                                     print_usage(2);
                                     println!("############################################################################################# CRASHED 1");
                                     // std::process::exit(0); // 0 to make the test ok
@@ -1255,7 +2366,7 @@ pub(crate) mod test {
                             }
                             last = Prc::downgrade(&pnode, j);
                             if let Some(next) = node.next.as_ref() {
-                                curr = Prc::downgrade(&next, j);
+                                curr = Prc::downgrade(next, j);
                             } else {
                                 curr = Weak::new();
                             }
@@ -1275,7 +2386,7 @@ pub(crate) mod test {
                 DelRndNum => {
                     println!("deleting element {}", value);
                     if let Some(root) = &sb.next {
-                        let mut curr = Prc::downgrade(&root, j);
+                        let mut curr = Prc::downgrade(root, j);
                         while let Some(pnode) = curr.upgrade(j) {
                             let node = pnode.borrow();
                             if node.value == value {
@@ -1288,7 +2399,7 @@ pub(crate) mod test {
                                 break;
                             }
                             if let Some(next) = &node.next {
-                                curr = Prc::downgrade(&next, j);
+                                curr = Prc::downgrade(next, j);
                             } else {
                                 curr = Weak::new();
                             }
@@ -1306,14 +2417,14 @@ pub(crate) mod test {
         let counter = transaction(|j| {
             let mut counter = 0;
             if let Some(root) = &sb.root.borrow().next {
-                let mut curr = Prc::downgrade(&root, j);
+                let mut curr = Prc::downgrade(root, j);
                 print!("[ ");
                 while let Some(node) = curr.upgrade(j) {
                     let node = node.borrow();
                     counter += 1;
                     print!("{} ", node.value);
                     if let Some(next) = &node.next {
-                        curr = Prc::downgrade(&next, j);
+                        curr = Prc::downgrade(next, j);
                     } else {
                         break;
                     }
@@ -1382,6 +2493,7 @@ pub(crate) mod test {
         }
 
         impl<'a> std::ops::AddAssign<&'a str> for MyString<'a> {
+            #[allow(invalid_reference_casting)]
             fn add_assign(&mut self, other: &'a str) {
                 unsafe {
                     let m: *const [u8] = self.value;
@@ -1443,14 +2555,10 @@ pub(crate) mod test {
     fn test_logs() {
         use crate::default::*;
 
+        #[derive(Default)]
         struct Root {
             head: Option<PRefCell<i32>>,
         }
-        impl Default for Root {
-            fn default() -> Self {
-                Root { head: None }
-            }
-        }
         let root = A::open::<Pbox<PRefCell<Root>>>("sb7.pool", O_CFNE).unwrap();
         let data = A::transaction(|j| {
             let mut root = root.borrow_mut(j);
@@ -1578,14 +2686,10 @@ pub(crate) mod test {
     fn test_mutex() {
         use crate::default::*;
 
+        #[derive(Default)]
         struct SB {
             root: Option<Prc<PMutex<i32>>>,
         }
-        impl Default for SB {
-            fn default() -> Self {
-                SB { root: None }
-            }
-        }
         let sb = A::open::<Pbox<PRefCell<SB>>>("sb9.pool", O_CFNE).unwrap();
         transaction(|j| {
             let mut sb = sb.borrow_mut(j);
@@ -1941,6 +3045,7 @@ mod test_btree {
     }
 
     #[derive(Clone)]
+    #[allow(clippy::type_complexity)]
     pub struct IntNode<K, V> {
         len: usize,
         left: Weak<RefCell<BTreeNode<K, V>>>,
@@ -2147,7 +3252,7 @@ mod test_btree {
         fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
             match self {
                 Leaf(this) => {
-                    for i in 0..N - 1 as usize {
+                    for i in 0..N - 1 {
                         if let Some(value) = &this.values[i] {
                             value.fmt(f)?;
                         } else {
@@ -2156,7 +3261,7 @@ mod test_btree {
                     }
                 }
                 Internal(this) => {
-                    for i in 0..N - 1 as usize {
+                    for i in 0..N - 1 {
                         if let Some(child) = &this.slots[i] {
                             child.borrow().fmt(f)?;
                         } else {
@@ -1,6 +1,7 @@
 use std::fmt::{Debug, Error, Formatter};
 use std::fs::File;
 use std::io::Read;
+use std::sync::atomic::{AtomicU8, Ordering};
 
 #[cfg(not(feature = "no_flush_alloc"))]
 use crate::ll::*;
@@ -13,6 +14,8 @@ pub fn rand() -> i64 {
 }
 
 static mut CRASH_PROB: Option<u64> = None;
+static CRASH_AT: crate::cell::LazyCell<Option<String>> =
+    crate::cell::LazyCell::new(|| std::env::var("CRASH_AT").ok());
 
 #[macro_export]
 macro_rules! may_crash {
@@ -22,6 +25,13 @@ macro_rules! may_crash {
             std::process::exit(0);
         }
     };
+    ($point:expr) => {
+        if $crate::utils::crash_at($point) {
+            eprintln!("\nCrashed at `{}` ({}:{})", $point, file!(), line!());
+            std::process::exit(0);
+        }
+        $crate::may_crash!();
+    };
 }
 
 #[inline]
@@ -32,7 +42,7 @@ pub fn can_crash() -> bool {
                 return false;
             } else {
                 let r: u64 = rand::random();
-                return r % 10000 == 0;
+                return r.is_multiple_of(10000);
             }
         } else {
             let p = std::env::var("CRASH_PROB")
@@ -45,6 +55,11 @@ pub fn can_crash() -> bool {
     }
 }
 
+/// Returns true if the `CRASH_AT` environment variable names the crash point
+pub fn crash_at(point: &str) -> bool {
+    CRASH_AT.as_deref() == Some(point)
+}
+
 #[inline]
 #[doc(hidden)]
 pub unsafe fn as_mut<'a, T: ?Sized>(v: *const T) -> &'a mut T {
@@ -199,7 +214,7 @@ impl<T: Copy, const N: usize> Ring<T, N> {
     }
 
     #[inline]
-    pub fn foreach<F: FnMut(T) -> ()>(&self, mut f: F) {
+    pub fn foreach<F: FnMut(T)>(&self, mut f: F) {
         let mut head = self.head;
         while head != self.tail {
             f(self.data[head]);
@@ -217,7 +232,7 @@ impl<T: Copy, const N: usize> Ring<T, N> {
     }
 
     #[inline]
-    pub fn foreach_reverse<F: FnMut(T) -> ()>(&self, mut f: F) {
+    pub fn foreach_reverse<F: FnMut(T)>(&self, mut f: F) {
         let mut tail = self.tail;
         while tail != self.head {
             let d = self.data[tail];
@@ -279,15 +294,19 @@ pub struct SpinLock {
 }
 
 impl SpinLock {
-    pub fn acquire(lock: *mut u8) -> Self {
-        unsafe { while std::intrinsics::atomic_cxchg_acqrel_acquire(lock, 0, 1).0 == 1 {} }
+    pub unsafe fn acquire(lock: *mut u8) -> Self {
+        let atomic = &*(lock as *const AtomicU8);
+        while atomic.compare_exchange(0, 1, Ordering::AcqRel, Ordering::Acquire).is_err() {}
         Self { lock }
     }
 }
 
 impl Drop for SpinLock {
     fn drop(&mut self) {
-        unsafe { std::intrinsics::atomic_store_relaxed(self.lock, 0); }
+        unsafe {
+            let atomic = &*(self.lock as *const AtomicU8);
+            atomic.store(0, Ordering::Release);
+        }
     }
 }
 
@@ -325,6 +344,45 @@ macro_rules! log {
     };
 }
 
+/// Emits a `tracing` event tagged with the name of pool `$p`, if the
+/// `tracing` feature is enabled
+#[cfg(feature = "tracing")]
+#[macro_export]
+#[doc(hidden)]
+macro_rules! __trace_event {
+    ($p:ty, $msg:expr $(, $k:ident = $v:expr)* $(,)?) => {
+        $crate::__tracing::trace!(target: "corundum",
+            pool = <$p as $crate::MemPoolTraits>::name(), $($k = $v,)* $msg)
+    };
+}
+
+#[cfg(not(feature = "tracing"))]
+#[macro_export]
+#[doc(hidden)]
+macro_rules! __trace_event {
+    ($p:ty, $msg:expr $(, $k:ident = $v:expr)* $(,)?) => { };
+}
+
+/// Enters a `tracing` span tagged with the name of pool `$p`, if the
+/// `tracing` feature is enabled. The span is exited when the returned guard
+/// is dropped.
+#[cfg(feature = "tracing")]
+#[macro_export]
+#[doc(hidden)]
+macro_rules! __trace_span {
+    ($p:ty, $name:expr $(, $k:ident = $v:expr)* $(,)?) => {
+        $crate::__tracing::debug_span!(target: "corundum", $name,
+            pool = <$p as $crate::MemPoolTraits>::name(), $($k = $v,)*).entered()
+    };
+}
+
+#[cfg(not(feature = "tracing"))]
+#[macro_export]
+#[doc(hidden)]
+macro_rules! __trace_span {
+    ($p:ty, $name:expr $(, $k:ident = $v:expr)* $(,)?) => { () };
+}
+
 pub const fn nearest_pow2(mut v: u64) -> u64 {
     v -= 1;
     v |= v >> 1;
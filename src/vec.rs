@@ -92,7 +92,7 @@ impl<T: PSafe, A: MemPool> Vec<T, A> {
     /// }).unwrap();
     /// ```
     pub fn from_slice(x: &[T], journal: &Journal<A>) -> Self {
-        if x.len() == 0 {
+        if x.is_empty() {
             Self::empty()
         } else {
             let buf = unsafe { A::new_slice(x, journal) };
@@ -102,7 +102,7 @@ impl<T: PSafe, A: MemPool> Vec<T, A> {
     }
 
     pub(crate) unsafe fn from_slice_nolog(x: &[T]) -> (Self, usize) {
-        if x.len() == 0 {
+        if x.is_empty() {
             (Self::empty(), 0)
         } else {
             let (buf, off, _, z) = A::atomic_new_slice(x);
@@ -292,7 +292,7 @@ impl<T: PSafe, A: MemPool> Vec<T, A> {
     }
 
     /// Returns raw parts in form of (buf: *mut u8, length: usize, capacity: usize)
-    pub(crate) unsafe fn into_raw_parts(&self) -> (*mut T, usize, usize) {
+    pub(crate) unsafe fn as_raw_parts(&self) -> (*mut T, usize, usize) {
         (
             A::get_mut_unchecked(self.buf.off()),
             self.len,
@@ -396,7 +396,7 @@ impl<T: PSafe, A: MemPool> Vec<T, A> {
         let res = Self::__to_slice_mut(self.off(), self.len());
         if self.has_log == 0 {
             unsafe {
-                res.create_log(j, Notifier::NonAtomic(Ptr::from_ref(&self.has_log)));
+                res.create_log(j, j.notifier(&self.has_log));
             }
         }
         self.to_slice_mut()
@@ -442,7 +442,7 @@ impl<T: PSafe, A: MemPool> Vec<T, A> {
     /// }).unwrap();
     /// ```
     pub fn extend_from_slice(&mut self, other: &[T], j: &Journal<A>) {
-        if other.len() != 0 {
+        if !other.is_empty() {
             unsafe {
                 let len = self.len;
                 let new_cap = self.capacity().max(len + other.len());
@@ -810,7 +810,7 @@ impl<T: PSafe, A: MemPool> Vec<T, A> {
     // /// ```
     // /// # use corundum::vec::Vec;
     // /// # use corundum::alloc::heap::*;
-/// Heap::transaction(|j| {
+    // /// Heap::transaction(|j| {
     // ///     let mut vec = Vec::from_slice(&[10, 20, 21, 30, 20], j);
     // ///
     // ///     vec.dedup_by_key(|i| *i / 10);
@@ -842,7 +842,7 @@ impl<T: PSafe, A: MemPool> Vec<T, A> {
     // /// # use corundum::vec::Vec;
     // /// # use corundum::str::*;
     // /// # use corundum::alloc::heap::*;
-/// Heap::transaction(|j| {
+    // /// Heap::transaction(|j| {
     // ///     let mut vec = Vec::from_slice(&["foo", "bar", "Bar", "baz", "bar"], j);
     // ///
     // ///     vec.dedup_by(|a, b| a.eq_ignore_ascii_case(b));
@@ -1077,8 +1077,6 @@ macro_rules! __impl_slice_eq1 {
         {
             #[inline]
             fn eq(&self, other: &$rhs) -> bool { self[..] == other[..] }
-            #[inline]
-            fn ne(&self, other: &$rhs) -> bool { self[..] != other[..] }
         }
     }
 }
@@ -1113,6 +1111,15 @@ impl<A: MemPool, T: PSafe + PClone<A>> PClone<A> for Vec<T, A> {
     }
 }
 
+impl<A: MemPool, T: PSafe + Trace<A>> Trace<A> for Vec<T, A> {
+    fn trace<V: Visitor>(&self, v: &mut V) {
+        let cap = self.capacity();
+        if cap > 0 && v.visit(&self.buf.off, cap * mem::size_of::<T>()) {
+            self.as_slice().trace(v);
+        }
+    }
+}
+
 impl<A: MemPool, T: PSafe + Eq> Eq for Vec<T, A> {}
 
 /// Implements ordering of vectors, lexicographically.
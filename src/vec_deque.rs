@@ -0,0 +1,476 @@
+//! A double-ended queue implemented with a growable ring buffer
+
+use crate::alloc::MemPool;
+use crate::clone::PClone;
+use crate::ptr::*;
+use crate::stm::*;
+use crate::*;
+use std::alloc::Layout;
+use std::fmt::{Debug, Formatter};
+use std::marker::PhantomData;
+use std::ops::Index;
+use std::{mem, ptr};
+
+/// A double-ended persistent queue implemented with a growable ring buffer,
+/// written `VecDeque<T>`
+///
+/// [`PVecDeque`] is an alias name in the pool module for `VecDeque`.
+///
+/// Pushing and popping at either end are O(1). Like [`Vec`], the methods
+/// that change the length take `&mut self`, so the deque should be placed in
+/// a cell that logs it before modification. Besides its header, only the
+/// slot that receives a new element is logged; the elements are never
+/// shifted, unless the buffer grows.
+///
+/// [`PVecDeque`]: ../alloc/default/type.PVecDeque.html
+/// [`Vec`]: ../vec/struct.Vec.html
+///
+/// # Examples
+///
+/// ```
+/// # use corundum::vec_deque::VecDeque;
+/// # use corundum::alloc::heap::*;
+/// Heap::transaction(|j| {
+///     let mut deque = VecDeque::new();
+///     deque.push_back(2, j);
+///     deque.push_back(3, j);
+///     deque.push_front(1, j);
+///
+///     assert_eq!(deque.len(), 3);
+///     assert_eq!(deque[0], 1);
+///     assert_eq!(deque.pop_back(), Some(3));
+///     assert_eq!(deque.pop_front(), Some(1));
+///     assert_eq!(deque.front(), Some(&2));
+/// }).unwrap();
+/// ```
+pub struct VecDeque<T: PSafe, A: MemPool> {
+    buf: Slice<T, A>,
+    head: usize,
+    len: usize,
+    marker: PhantomData<[T]>,
+}
+
+unsafe impl<T: PSafe, A: MemPool> PSafe for VecDeque<T, A> {}
+impl<T, A: MemPool> !Send for VecDeque<T, A> {}
+impl<T, A: MemPool> !Sync for VecDeque<T, A> {}
+impl<T, A: MemPool> !VSafe for VecDeque<T, A> {}
+
+impl<T: PSafe, A: MemPool> VecDeque<T, A> {
+    /// Creates an empty deque with zero capacity
+    pub const fn new() -> Self {
+        Self {
+            buf: Slice::from_off_cap(u64::MAX, 0),
+            head: 0,
+            len: 0,
+            marker: PhantomData,
+        }
+    }
+
+    /// Creates an empty deque with space for at least `cap` elements
+    pub fn with_capacity(cap: usize, j: &Journal<A>) -> Self {
+        let mut res = Self::new();
+        if cap > 0 {
+            unsafe { res.buf = Self::alloc_buf(cap, j); }
+        }
+        res
+    }
+
+    unsafe fn alloc_buf(cap: usize, j: &Journal<A>) -> Slice<T, A> {
+        let layout = Layout::array::<T>(cap).unwrap();
+        let buf = A::new_uninit_for_layout(layout.size(), j);
+        Slice::from_off_cap(A::off_unchecked(buf), cap)
+    }
+
+    /// Returns the number of elements
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the deque contains no elements
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the number of elements the deque can hold without
+    /// reallocating
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.buf.capacity()
+    }
+
+    /// Returns the index of the `i`-th element in the buffer
+    #[inline]
+    fn wrap(&self, i: usize) -> usize {
+        let i = self.head + i;
+        if i >= self.capacity() {
+            i - self.capacity()
+        } else {
+            i
+        }
+    }
+
+    /// Doubles the capacity if the buffer is full, and makes the elements
+    /// contiguous in the new buffer
+    fn grow(&mut self, j: &Journal<A>) {
+        if self.len < self.capacity() {
+            return;
+        }
+        let cap = (self.capacity() * 2).max(4);
+        unsafe {
+            let new = Self::alloc_buf(cap, j);
+            let (front, back) = self.as_slices();
+            ptr::copy_nonoverlapping(front.as_ptr(), new.as_mut_ptr(), front.len());
+            ptr::copy_nonoverlapping(back.as_ptr(), new.as_mut_ptr().add(front.len()), back.len());
+            if !self.buf.is_empty() {
+                A::free_slice(self.buf.as_slice());
+            }
+            self.buf = new;
+        }
+        self.head = 0;
+    }
+
+    /// Writes `value` in the slot `i` of the buffer after logging it
+    ///
+    /// The slot might have held an element that was popped in the same
+    /// transaction, so it is logged to be restored upon a failure.
+    #[inline]
+    unsafe fn write(&mut self, i: usize, value: T, j: &Journal<A>) {
+        let slot = self.buf.get_unchecked(i);
+        slot.create_log(j, Notifier::None);
+        ptr::write(slot, value);
+    }
+
+    /// Appends an element to the back of the deque
+    pub fn push_back(&mut self, value: T, j: &Journal<A>) {
+        self.grow(j);
+        unsafe { self.write(self.wrap(self.len), value, j); }
+        self.len += 1;
+    }
+
+    /// Prepends an element to the front of the deque
+    pub fn push_front(&mut self, value: T, j: &Journal<A>) {
+        self.grow(j);
+        self.head = self.wrap(self.capacity() - 1);
+        unsafe { self.write(self.head, value, j); }
+        self.len += 1;
+    }
+
+    /// Removes the last element and returns it, or `None` if the deque is
+    /// empty
+    pub fn pop_back(&mut self) -> Option<T> {
+        if self.len == 0 {
+            None
+        } else {
+            self.len -= 1;
+            unsafe { Some(ptr::read(self.buf.get_unchecked(self.wrap(self.len)))) }
+        }
+    }
+
+    /// Removes the first element and returns it, or `None` if the deque is
+    /// empty
+    pub fn pop_front(&mut self) -> Option<T> {
+        if self.len == 0 {
+            None
+        } else {
+            let head = self.head;
+            self.head = self.wrap(1);
+            self.len -= 1;
+            unsafe { Some(ptr::read(self.buf.get_unchecked(head))) }
+        }
+    }
+
+    /// Returns a reference to the element at index `i`
+    pub fn get(&self, i: usize) -> Option<&T> {
+        if i < self.len {
+            unsafe { Some(self.buf.get_unchecked(self.wrap(i))) }
+        } else {
+            None
+        }
+    }
+
+    /// Returns a mutable reference to the element at index `i` after taking
+    /// a log of it
+    pub fn get_mut(&mut self, i: usize, j: &Journal<A>) -> Option<&mut T> {
+        if i < self.len {
+            unsafe {
+                let e = self.buf.get_unchecked(self.wrap(i));
+                e.create_log(j, Notifier::None);
+                Some(e)
+            }
+        } else {
+            None
+        }
+    }
+
+    /// Returns a reference to the first element
+    pub fn front(&self) -> Option<&T> {
+        self.get(0)
+    }
+
+    /// Returns a reference to the last element
+    pub fn back(&self) -> Option<&T> {
+        if self.len == 0 {
+            None
+        } else {
+            self.get(self.len - 1)
+        }
+    }
+
+    /// Returns the contents in order as two slices; the second one is empty
+    /// if the elements do not wrap around
+    pub fn as_slices(&self) -> (&[T], &[T]) {
+        if self.len == 0 {
+            return (&[], &[]);
+        }
+        let all = self.buf.as_slice();
+        let end = self.head + self.len;
+        if end <= all.len() {
+            (&all[self.head..end], &[])
+        } else {
+            (&all[self.head..], &all[..end - all.len()])
+        }
+    }
+
+    /// Returns a front-to-back iterator
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &T> {
+        let (front, back) = self.as_slices();
+        front.iter().chain(back.iter())
+    }
+
+    /// Removes all elements; the capacity remains the same
+    pub fn clear(&mut self) {
+        while let Some(e) = self.pop_back() {
+            mem::drop(e);
+        }
+        self.head = 0;
+    }
+}
+
+impl<T: PSafe, A: MemPool> Drop for VecDeque<T, A> {
+    fn drop(&mut self) {
+        self.clear();
+        if !self.buf.is_empty() {
+            unsafe { A::free_slice(self.buf.as_slice()); }
+        }
+    }
+}
+
+impl<T: PSafe, A: MemPool> Index<usize> for VecDeque<T, A> {
+    type Output = T;
+
+    fn index(&self, i: usize) -> &T {
+        self.get(i).expect("index out of bounds")
+    }
+}
+
+impl<T: PSafe + Debug, A: MemPool> Debug for VecDeque<T, A> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: PSafe + PartialEq, A: MemPool> PartialEq for VecDeque<T, A> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len && self.iter().eq(other.iter())
+    }
+}
+
+impl<T: PSafe + PartialEq, A: MemPool> PartialEq<[T]> for VecDeque<T, A> {
+    fn eq(&self, other: &[T]) -> bool {
+        self.len == other.len() && self.iter().eq(other.iter())
+    }
+}
+
+impl<T: PSafe + PClone<A>, A: MemPool> PClone<A> for VecDeque<T, A> {
+    fn pclone(&self, j: &Journal<A>) -> Self {
+        let mut res = Self::with_capacity(self.len, j);
+        for e in self.iter() {
+            res.push_back(e.pclone(j), j);
+        }
+        res
+    }
+}
+
+impl<T: PSafe + Trace<A>, A: MemPool> Trace<A> for VecDeque<T, A> {
+    fn trace<V: Visitor>(&self, v: &mut V) {
+        let cap = self.capacity();
+        if cap > 0 && v.visit(&self.buf.off, cap * mem::size_of::<T>()) {
+            for e in self.iter() {
+                e.trace(v);
+            }
+        }
+    }
+}
+
+impl<T: PSafe, A: MemPool> Default for VecDeque<T, A> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: PSafe, A: MemPool> RootObj<A> for VecDeque<T, A> {
+    fn init(_: &Journal<A>) -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::default::*;
+    use crate::open_flags::*;
+
+    type A = Allocator;
+
+    #[test]
+    fn test_vec_deque() {
+        let root = A::open::<PRefCell<PVecDeque<u64>>>("vec_deque.pool", O_CF).unwrap();
+
+        A::transaction(|j| {
+            let mut q = root.borrow_mut(j);
+            q.clear();
+            for i in 0..10 {
+                q.push_back(i, j);
+            }
+            for _ in 0..5 {
+                q.pop_front();
+            }
+            for i in 10..13 {
+                q.push_back(i, j);
+            }
+        }).unwrap();
+
+        // The elements wrap around the end of the buffer
+        assert_eq!(root.borrow().capacity(), 16);
+        assert!(root.borrow().iter().cloned().eq(5..13));
+
+        let _ = A::transaction(|j| {
+            let mut q = root.borrow_mut(j);
+            while q.pop_front().is_some() {}
+            for i in 100..116 {
+                q.push_front(i, j);
+            }
+            panic!("abort");
+        });
+
+        assert_eq!(root.borrow().capacity(), 16);
+        assert!(root.borrow().iter().cloned().eq(5..13));
+
+        A::transaction(|j| {
+            let mut q = root.borrow_mut(j);
+            for i in (0..5).rev() {
+                q.push_front(i, j);
+            }
+            for i in 13..20 {
+                q.push_back(i, j);
+            }
+        }).unwrap();
+
+        let q = root.borrow();
+        assert_eq!(q.len(), 20);
+        assert!(q.iter().cloned().eq(0..20));
+        assert!(q.iter().rev().cloned().eq((0..20).rev()));
+        assert_eq!((q.front(), q.back()), (Some(&0), Some(&19)));
+    }
+
+    #[test]
+    fn test_vec_deque_wrap() {
+        let root = A::open::<PRefCell<PVecDeque<u64>>>("vec_deque_wrap.pool", O_CF).unwrap();
+
+        A::transaction(|j| {
+            let mut q = root.borrow_mut(j);
+            for i in 0..12 {
+                q.push_back(i, j);
+            }
+            for _ in 0..10 {
+                q.pop_front();
+            }
+            for i in 12..20 {
+                q.push_back(i, j);
+            }
+        }).unwrap();
+
+        {
+            let q = root.borrow();
+            assert_eq!(q.capacity(), 16);
+            assert!(q.head + q.len() > q.capacity());
+            assert_eq!(q.as_slices(), (&[10, 11, 12, 13, 14, 15][..], &[16, 17, 18, 19][..]));
+            assert!(q.iter().cloned().eq(10..20));
+            assert!(q.iter().rev().cloned().eq((10..20).rev()));
+            assert_eq!((q[5], q.get(6)), (15, Some(&16)));
+        }
+
+        // Pops and pushes on both ends of a wrapped buffer are rolled back
+        let _ = A::transaction(|j| {
+            let mut q = root.borrow_mut(j);
+            for _ in 0..6 {
+                q.pop_back();
+                q.pop_front();
+            }
+            for i in 100..106 {
+                q.push_front(i, j);
+                q.push_back(i, j);
+            }
+            *q.get_mut(0, j).unwrap() = 0;
+            panic!("abort");
+        });
+
+        let q = root.borrow();
+        assert_eq!(q.capacity(), 16);
+        assert!(q.head + q.len() > q.capacity());
+        assert!(q.iter().cloned().eq(10..20));
+    }
+
+    #[test]
+    fn test_vec_deque_grow_abort() {
+        let root = A::open::<PRefCell<PVecDeque<u64>>>("vec_deque_grow.pool", O_CF).unwrap();
+
+        // A full buffer that wraps around
+        A::transaction(|j| {
+            let mut q = root.borrow_mut(j);
+            for i in 0..8 {
+                q.push_back(i, j);
+            }
+            for _ in 0..4 {
+                q.pop_front();
+            }
+            for i in 8..12 {
+                q.push_back(i, j);
+            }
+        }).unwrap();
+
+        {
+            let q = root.borrow();
+            assert_eq!((q.len(), q.capacity()), (8, 8));
+            assert!(q.head + q.len() > q.capacity());
+        }
+
+        let _ = A::transaction(|j| {
+            let mut q = root.borrow_mut(j);
+            for i in 12..40 {
+                q.push_back(i, j);
+            }
+            assert_eq!(q.capacity(), 64);
+            assert_eq!(q.head, 0);
+            panic!("abort");
+        });
+
+        {
+            let q = root.borrow();
+            assert_eq!((q.len(), q.capacity()), (8, 8));
+            assert!(q.head + q.len() > q.capacity());
+            assert!(q.iter().cloned().eq(4..12));
+        }
+
+        // The old buffer is still in use after the aborted growth
+        A::transaction(|j| {
+            let mut q = root.borrow_mut(j);
+            q.push_front(3, j);
+            q.push_back(12, j);
+        }).unwrap();
+
+        assert_eq!(root.borrow().capacity(), 16);
+        assert!(root.borrow().iter().cloned().eq(3..13));
+    }
+}
@@ -0,0 +1,104 @@
+use corundum::default::*;
+use corundum::open_flags::*;
+use corundum::{Migrate, Visitor};
+
+type P = Allocator;
+
+#[derive(Root, Trace)]
+struct Node {
+    value: PCell<i32>,
+    next: PRefCell<Option<Pbox<Node>>>,
+}
+
+#[derive(Trace)]
+enum Shape {
+    Empty,
+    Single(Pbox<Node>),
+    Pair { first: Pbox<Node>, second: Option<Pbox<Node>> },
+}
+
+#[derive(Default)]
+struct Counter {
+    pointers: usize,
+}
+
+impl Visitor for Counter {
+    fn visit(&mut self, _off: &u64, _len: usize) -> bool {
+        self.pointers += 1;
+        true
+    }
+}
+
+fn count<T: Trace<P>>(obj: &T) -> usize {
+    let mut counter = Counter::default();
+    obj.trace(&mut counter);
+    counter.pointers
+}
+
+#[test]
+fn derive_root_and_trace() {
+    let head = P::open::<Node>("derive_trace.pool", O_CF).unwrap();
+    assert_eq!(head.value.get(), 0);
+    assert_eq!(count(&*head), 0);
+
+    P::transaction(|j| {
+        for i in 1..=3 {
+            let next = head.next.replace(None, j);
+            let node = Node { value: PCell::new(i), next: PRefCell::new(next) };
+            *head.next.borrow_mut(j) = Some(Pbox::new(node, j));
+        }
+    }).unwrap();
+    assert_eq!(count(&*head), 3);
+}
+
+#[test]
+fn derive_trace_enum() {
+    let _pool = P::open_no_root("derive_enum.pool", O_CF).unwrap();
+
+    P::transaction(|j| {
+        let node = || Pbox::new(Node { value: PCell::new(1), next: PRefCell::new(None) }, j);
+        assert_eq!(count(&Shape::Empty), 0);
+        assert_eq!(count(&Shape::Single(node())), 1);
+        assert_eq!(count(&Shape::Pair { first: node(), second: None }), 1);
+        assert_eq!(count(&Shape::Pair { first: node(), second: Some(node()) }), 2);
+    }).unwrap();
+}
+
+#[derive(Root)]
+#[schema(name = "Tally", version = 1)]
+struct TallyV1 {
+    count: PCell<i32>,
+}
+
+#[derive(Root)]
+#[schema(name = "Tally", version = 2)]
+#[migrate(TallyV1)]
+struct Tally {
+    count: PCell<i64>,
+    label: PRefCell<PString>,
+}
+
+impl Migrate<TallyV1, P> for Tally {
+    fn migrate(old: TallyV1, j: &Journal) -> Self {
+        Self {
+            count: PCell::new(old.count.get() as i64),
+            label: PRefCell::new("migrated".to_pstring(j)),
+        }
+    }
+}
+
+#[test]
+fn derive_root_migrate() {
+    {
+        let root = P::open::<TallyV1>("derive_migrate.pool", O_CF).unwrap();
+        P::transaction(|j| root.count.set(5, j)).unwrap();
+    }
+    {
+        let root = P::open::<Tally>("derive_migrate.pool", 0).unwrap();
+        assert_eq!(root.count.get(), 5);
+        assert_eq!(root.label.borrow().as_str(), "migrated");
+    }
+    assert!(P::open::<TallyV1>("derive_migrate.pool", 0).is_err());
+    let root = P::open::<Tally>("derive_migrate.pool", 0).unwrap();
+    assert_eq!(root.count.get(), 5);
+}